@@ -0,0 +1,257 @@
+//! The cycle browser: one whole cycle shown as a list, for players who would
+//! rather see everything and pick than tap through a cycle blind. This is the
+//! state machine only. The renderer asks for a view of it every frame and
+//! draws whatever it's told.
+//!
+//! While the browser is open, the browsed slot's cycle key moves the selection
+//! instead of cycling, as do the arrow keys and the dpad. Enter or gamepad A
+//! picks the selection; backspace or gamepad B closes the browser. A browser
+//! left alone closes itself after a while.
+
+use std::time::{Duration, Instant};
+
+use super::keys::CycleSlot;
+use crate::plugin::Action;
+
+/// How long the browser stays open without any input.
+pub const BROWSER_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many entries the renderer is given at once.
+pub const BROWSER_PAGE_SIZE: usize = 8;
+
+// Keyboard scan codes and the gamepad codes from keycodes.cpp.
+const KEY_UP_ARROW: u32 = 200;
+const KEY_DOWN_ARROW: u32 = 208;
+const KEY_ENTER: u32 = 28;
+const KEY_BACKSPACE: u32 = 14;
+const GAMEPAD_DPAD_UP: u32 = 266;
+const GAMEPAD_DPAD_DOWN: u32 = 267;
+const GAMEPAD_A: u32 = 276;
+const GAMEPAD_B: u32 = 277;
+
+/// The keys that mean something to an open browser whatever the bindings are.
+pub const BROWSE_KEYS: [u32; 8] = [
+    KEY_UP_ARROW,
+    KEY_DOWN_ARROW,
+    KEY_ENTER,
+    KEY_BACKSPACE,
+    GAMEPAD_DPAD_UP,
+    GAMEPAD_DPAD_DOWN,
+    GAMEPAD_A,
+    GAMEPAD_B,
+];
+
+/// Which browser action this key stands for, given the key for the browsed
+/// slot's cycle and the activate key. `Action::None` if it isn't one.
+pub fn browse_action_for(key: u32, cycle_key: u32, activate_key: u32) -> Action {
+    match key {
+        KEY_UP_ARROW | GAMEPAD_DPAD_UP => Action::BrowseUp,
+        KEY_DOWN_ARROW | GAMEPAD_DPAD_DOWN => Action::BrowseDown,
+        KEY_ENTER | GAMEPAD_A => Action::BrowseConfirm,
+        KEY_BACKSPACE | GAMEPAD_B => Action::BrowseCancel,
+        _ if key == cycle_key => Action::BrowseDown,
+        _ if key == activate_key => Action::BrowseConfirm,
+        _ => Action::None,
+    }
+}
+
+/// What came of a browser input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowserOutcome {
+    /// The selection moved, or the input meant nothing; keep browsing.
+    Browsing,
+    /// The player picked the entry with this form spec. The browser is done.
+    Chosen(String),
+    /// The player closed the browser without picking anything.
+    Cancelled,
+}
+
+/// A browser open on one cycle.
+#[derive(Debug, Clone)]
+pub struct CycleBrowser {
+    slot: CycleSlot,
+    /// Form specs, in cycle order as of when the browser opened.
+    entries: Vec<String>,
+    selected: usize,
+    last_input: Instant,
+}
+
+impl CycleBrowser {
+    /// Open a browser on these entries with the current entry selected.
+    /// There's nothing to browse in an empty cycle, so that gets `None`.
+    pub fn open(
+        slot: CycleSlot,
+        entries: Vec<String>,
+        current: Option<&str>,
+        now: Instant,
+    ) -> Option<Self> {
+        if entries.is_empty() {
+            return None;
+        }
+        let selected = current
+            .and_then(|spec| entries.iter().position(|xs| xs == spec))
+            .unwrap_or_default();
+        Some(Self {
+            slot,
+            entries,
+            selected,
+            last_input: now,
+        })
+    }
+
+    pub fn slot(&self) -> &CycleSlot {
+        &self.slot
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// The index of the selected entry in the whole cycle.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Has the browser been left alone too long?
+    pub fn expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_input) >= BROWSER_TIMEOUT
+    }
+
+    /// Act on a browser action. Moving off either end of the list wraps around.
+    pub fn handle(&mut self, action: &Action, now: Instant) -> BrowserOutcome {
+        self.last_input = now;
+        let count = self.entries.len();
+        match *action {
+            Action::BrowseUp => {
+                self.selected = (self.selected + count - 1) % count;
+                BrowserOutcome::Browsing
+            }
+            Action::BrowseDown => {
+                self.selected = (self.selected + 1) % count;
+                BrowserOutcome::Browsing
+            }
+            Action::BrowseConfirm => BrowserOutcome::Chosen(self.entries[self.selected].clone()),
+            Action::BrowseCancel => BrowserOutcome::Cancelled,
+            _ => BrowserOutcome::Browsing,
+        }
+    }
+
+    /// The page the selection is on, counting from 0.
+    pub fn page(&self) -> usize {
+        self.selected / BROWSER_PAGE_SIZE
+    }
+
+    pub fn page_count(&self) -> usize {
+        (self.entries.len() + BROWSER_PAGE_SIZE - 1) / BROWSER_PAGE_SIZE
+    }
+
+    /// The entries on the selection's page.
+    pub fn page_entries(&self) -> &[String] {
+        let start = self.page() * BROWSER_PAGE_SIZE;
+        let end = (start + BROWSER_PAGE_SIZE).min(self.entries.len());
+        &self.entries[start..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn specs(count: usize) -> Vec<String> {
+        (1..=count)
+            .map(|i| format!("Skyrim.esm|0x{:x}", 0x1000 + i))
+            .collect()
+    }
+
+    #[test]
+    fn browsing_pages_and_wraps() {
+        let now = Instant::now();
+        let entries = specs(19);
+        let mut browser = CycleBrowser::open(
+            CycleSlot::Right,
+            entries.clone(),
+            Some(entries[9].as_str()),
+            now,
+        )
+        .expect("a cycle with entries can be browsed");
+        assert_eq!(browser.selected(), 9);
+        assert_eq!(browser.page(), 1);
+        assert_eq!(browser.page_count(), 3);
+        assert_eq!(browser.page_entries(), &entries[8..16]);
+
+        assert_eq!(
+            browser.handle(&Action::BrowseUp, now),
+            BrowserOutcome::Browsing
+        );
+        assert_eq!(
+            browser.handle(&Action::BrowseUp, now),
+            BrowserOutcome::Browsing
+        );
+        assert_eq!(browser.selected(), 7);
+        assert_eq!(browser.page(), 0);
+
+        for _ in 0..12 {
+            browser.handle(&Action::BrowseDown, now);
+        }
+        assert_eq!(browser.selected(), 0);
+        browser.handle(&Action::BrowseUp, now);
+        assert_eq!(browser.selected(), 18);
+        assert_eq!(browser.page_entries(), &entries[16..]);
+
+        assert_eq!(
+            browser.handle(&Action::BrowseConfirm, now),
+            BrowserOutcome::Chosen(entries[18].clone())
+        );
+        assert_eq!(
+            browser.handle(&Action::BrowseCancel, now),
+            BrowserOutcome::Cancelled
+        );
+    }
+
+    #[test]
+    fn browsers_open_only_on_something_and_time_out() {
+        let now = Instant::now();
+        assert!(CycleBrowser::open(CycleSlot::Power, Vec::new(), None, now).is_none());
+
+        // An entry that's gone from the cycle selects the top.
+        let mut browser = CycleBrowser::open(CycleSlot::Power, specs(3), Some("Gone.esp|0x1"), now)
+            .expect("a cycle with entries can be browsed");
+        assert_eq!(browser.selected(), 0);
+
+        let later = now + BROWSER_TIMEOUT - Duration::from_millis(1);
+        assert!(!browser.expired(later));
+        browser.handle(&Action::BrowseDown, later);
+        // Input keeps it open.
+        assert!(!browser.expired(now + BROWSER_TIMEOUT));
+        assert!(browser.expired(later + BROWSER_TIMEOUT));
+    }
+
+    #[test]
+    fn keys_route_to_browser_actions() {
+        let (cycle_key, activate_key) = (5, 9);
+        assert_eq!(
+            browse_action_for(200, cycle_key, activate_key),
+            Action::BrowseUp
+        );
+        assert_eq!(
+            browse_action_for(267, cycle_key, activate_key),
+            Action::BrowseDown
+        );
+        assert_eq!(
+            browse_action_for(5, cycle_key, activate_key),
+            Action::BrowseDown
+        );
+        assert_eq!(
+            browse_action_for(9, cycle_key, activate_key),
+            Action::BrowseConfirm
+        );
+        assert_eq!(
+            browse_action_for(276, cycle_key, activate_key),
+            Action::BrowseConfirm
+        );
+        assert_eq!(
+            browse_action_for(277, cycle_key, activate_key),
+            Action::BrowseCancel
+        );
+        assert_eq!(browse_action_for(6, cycle_key, activate_key), Action::None);
+    }
+}
@@ -0,0 +1,62 @@
+//! Spell-preparation mods make the player prepare a known spell before it can
+//! be cast. Such spells stay in the cycles, but the HUD shows them as locked
+//! and won't equip them. Whether a spell is castable is asked of the game once
+//! and remembered until the preparation mod tells us something changed.
+
+use std::collections::HashMap;
+
+/// What we've learned about which spells can be cast right now.
+#[derive(Debug, Clone, Default)]
+pub struct SpellCastability {
+    known: HashMap<String, bool>,
+}
+
+impl SpellCastability {
+    /// Is the spell with this form spec castable? Asks `query` only if we
+    /// haven't asked about this spell since the last invalidation.
+    pub fn is_castable<F>(&mut self, form_spec: &str, query: F) -> bool
+    where
+        F: FnOnce(&str) -> bool,
+    {
+        if let Some(castable) = self.known.get(form_spec) {
+            return *castable;
+        }
+        let castable = query(form_spec);
+        self.known.insert(form_spec.to_string(), castable);
+        castable
+    }
+
+    /// Forget everything; the next lookup of each spell asks again.
+    pub fn invalidate(&mut self) {
+        self.known.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn asks_once_until_invalidated() {
+        let asked = Cell::new(0);
+        let prepared = Cell::new(false);
+        let query = |_: &str| {
+            asked.set(asked.get() + 1);
+            prepared.get()
+        };
+
+        let mut castability = SpellCastability::default();
+        assert!(!castability.is_castable("Skyrim.esm|0x12fcd", query));
+        assert!(!castability.is_castable("Skyrim.esm|0x12fcd", query));
+        assert_eq!(asked.get(), 1);
+
+        // The player prepares the spell; nothing changes until we're told.
+        prepared.set(true);
+        assert!(!castability.is_castable("Skyrim.esm|0x12fcd", query));
+        castability.invalidate();
+        assert!(castability.is_castable("Skyrim.esm|0x12fcd", query));
+        assert_eq!(asked.get(), 2);
+    }
+}
@@ -0,0 +1,118 @@
+//! Two-stage destructive actions. The MCM asks for a destructive action and
+//! gets back a token and a summary of what would happen; nothing changes yet.
+//! Only handing the token back with `confirm()` does the deed, and only once
+//! and only if it comes back soon enough. A forgotten confirmation dialog in
+//! an MCM script can't destroy anything this way.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a token stays good. Long enough to read a summary and click.
+pub const CONFIRMATION_EXPIRY: Duration = Duration::from_secs(60);
+
+/// A destructive action waiting for the player to confirm it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingAction {
+    /// Empty every cycle.
+    ClearCycles,
+    /// Remove entries unused for more than this many game days.
+    Prune { days: u32 },
+}
+
+/// Actions waiting on confirmation, by token.
+#[derive(Debug, Clone)]
+pub struct Confirmations {
+    pending: HashMap<u32, (PendingAction, Instant)>,
+    next_token: u32,
+}
+
+impl Default for Confirmations {
+    fn default() -> Self {
+        Self {
+            pending: HashMap::new(),
+            next_token: 1,
+        }
+    }
+}
+
+impl Confirmations {
+    /// Hold this action until it's confirmed. Returns the token to confirm it with.
+    /// Tokens are never 0, so 0 is safe for Papyrus to use as "no token".
+    pub fn request(&mut self, action: PendingAction, now: Instant) -> u32 {
+        self.expire(now);
+        let token = self.next_token;
+        self.next_token = self.next_token.checked_add(1).unwrap_or(1);
+        self.pending.insert(token, (action, now));
+        token
+    }
+
+    /// Hand over the action for this token, if it's known and not expired.
+    /// Each token works once.
+    pub fn take(&mut self, token: u32, now: Instant) -> Option<PendingAction> {
+        self.expire(now);
+        self.pending.remove(&token).map(|(action, _)| action)
+    }
+
+    /// Forget actions whose tokens are too old to confirm.
+    pub fn expire(&mut self, now: Instant) {
+        self.pending
+            .retain(|_, (_, issued)| now.saturating_duration_since(*issued) < CONFIRMATION_EXPIRY);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirmed_actions_come_back() {
+        let now = Instant::now();
+        let mut confirmations = Confirmations::default();
+
+        let clear = confirmations.request(PendingAction::ClearCycles, now);
+        let prune = confirmations.request(PendingAction::Prune { days: 30 }, now);
+        assert_ne!(clear, 0);
+        assert_ne!(clear, prune);
+
+        let later = now + Duration::from_secs(5);
+        assert_eq!(
+            confirmations.take(prune, later),
+            Some(PendingAction::Prune { days: 30 })
+        );
+        assert_eq!(
+            confirmations.take(clear, later),
+            Some(PendingAction::ClearCycles)
+        );
+        assert!(confirmations.is_empty());
+    }
+
+    #[test]
+    fn tokens_work_once() {
+        let now = Instant::now();
+        let mut confirmations = Confirmations::default();
+        let token = confirmations.request(PendingAction::ClearCycles, now);
+        assert!(confirmations.take(token, now).is_some());
+        assert!(confirmations.take(token, now).is_none());
+        assert!(confirmations.take(0, now).is_none());
+        assert!(confirmations.take(token + 1, now).is_none());
+    }
+
+    #[test]
+    fn tokens_expire() {
+        let now = Instant::now();
+        let mut confirmations = Confirmations::default();
+        let token = confirmations.request(PendingAction::Prune { days: 7 }, now);
+        let stale = confirmations.request(PendingAction::ClearCycles, now);
+
+        let almost = now + CONFIRMATION_EXPIRY - Duration::from_millis(1);
+        assert!(confirmations.take(token, almost).is_some());
+        assert!(confirmations
+            .take(stale, now + CONFIRMATION_EXPIRY)
+            .is_none());
+        assert!(confirmations.is_empty());
+    }
+}
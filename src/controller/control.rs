@@ -2,8 +2,10 @@
 //!
 //! This struct holds all runtime data for the HUD and its implementation
 //! along with associated types manage what happens when the user presses keys.
-//! It tracks key states, cycle contents (via CycleData), what needs to be drawn
-//! in the HUD, and manages a cache for items in use by the HUD.
+//! It owns the cycles (`CycleData`) and a cache for items in use by the HUD,
+//! and coordinates three smaller pieces of state that don't talk to the game:
+//! key presses (`InputState`), what's equipped (`EquippedState`), and what
+//! the HUD is showing (`VisualState`).
 //! This is by far the most complex logic in the entire application.
 //!
 //! Functions of note: `handle_key_event()`, `handle_item_equipped()`, and
@@ -13,29 +15,110 @@
 //! set is itself complex.
 
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::time::Instant;
 
 use cxx::let_cxx_string;
 use once_cell::sync::Lazy;
 use strfmt::strfmt;
 
+use super::browser::{browse_action_for, BrowserOutcome, CycleBrowser};
+use super::castable::SpellCastability;
+use super::confirm::{Confirmations, PendingAction};
+use super::cyclefile::CycleFile;
 use super::cycles::*;
+use super::encumbrance::{check_load, LoadCheck};
+use super::equipstate::EquippedState;
+use super::flurry::is_flurry_noise;
+use super::gate::Gate;
+use super::inputstate::{InputState, LongPressTimer};
 use super::keys::*;
-use super::settings::{settings, ActivationMethod, UnarmedMethod};
+use super::limits::daily_limit;
+use super::loadgate::{DeferredEvent, LoadGate};
+use super::lockhint::LockLevel;
+use super::overrides::{
+    activation_override, disable_activation_override, ActivationAction, OverrideHand,
+};
+use super::rebind::{tear_down, SlotBindings, Teardown};
+use super::settings::{key_action, settings, ActivationMethod, CycleAutoSort, UnarmedMethod};
+use super::shutdown::accepting_events;
+use super::slotage::NEVER_CHANGED;
+use super::swaps::SwapTracker;
+use super::timers::{cancel_all_timers, running_timers, timer_running};
+use super::undo::{CycleEdit, UndoStack};
+use super::visualstate::VisualState;
+use super::vitals::VitalsChange;
 use crate::cycleentries::*;
 use crate::data::item_cache::ItemCache;
 use crate::data::potion::PotionType;
 use crate::data::*;
-use crate::layouts::Layout;
+use crate::layouts::{hud_layout, Layout};
 use crate::plugin::*;
 
 /// There can be only one. Not public because we want access managed.
-static CONTROLLER: Lazy<Mutex<Controller>> = Lazy::new(|| Mutex::new(Controller::new()));
+static CONTROLLER: Lazy<Gate<Controller>> = Lazy::new(|| Gate::new(Controller::new()));
+
+/// Hand the controller something to do. If we're already inside the
+/// controller on this thread, because the game called back into us, the work
+/// runs as soon as the current call is done. After shutdown, nothing runs.
+pub fn dispatch<F>(work: F)
+where
+    F: FnOnce(&mut Controller) + Send + 'static,
+{
+    if !accepting_events() {
+        return;
+    }
+    CONTROLLER.dispatch(work);
+}
+
+/// Ask the controller something. A question that comes back around while the
+/// controller is busy on this thread, or that arrives after shutdown, gets the
+/// default answer.
+pub fn query<R, F>(work: F) -> R
+where
+    R: Default,
+    F: FnOnce(&mut Controller) -> R,
+{
+    if !accepting_events() {
+        return R::default();
+    }
+    CONTROLLER.query(work)
+}
+
+/// Look at the controller without waiting. None if it's busy or we've shut down.
+pub fn peek<R, F>(work: F) -> Option<R>
+where
+    F: FnOnce(&Controller) -> R,
+{
+    if !accepting_events() {
+        return None;
+    }
+    CONTROLLER.try_peek(work)
+}
 
-pub fn get() -> std::sync::MutexGuard<'static, Controller> {
-    CONTROLLER
-        .lock()
-        .expect("Unrecoverable runtime problem: cannot acquire controller lock. Exiting.")
+/// A line about the controller for the shutdown log. Doesn't create the
+/// controller if it was never used, and doesn't wait on it if it's busy.
+pub fn shutdown_summary() -> String {
+    let Some(controller) = Lazy::get(&CONTROLLER) else {
+        return "the HUD was never set up".to_string();
+    };
+    controller
+        .try_peek(|ctrl| {
+            let lengths: Vec<String> = [
+                CycleSlot::Power,
+                CycleSlot::Utility,
+                CycleSlot::Left,
+                CycleSlot::Right,
+            ]
+            .iter()
+            .map(|which| format!("{which}={}", ctrl.cycles.formids(which).len()))
+            .collect();
+            format!(
+                "cycle lengths: {}; cached items: {}",
+                lengths.join(", "),
+                ctrl.cache.len()
+            )
+        })
+        .unwrap_or_else(|| "the controller was busy".to_string())
 }
 
 /// What, model/view/controller? In my UI application? oh no
@@ -45,18 +128,22 @@ pub struct Controller {
     pub cycles: CycleData,
     /// The hud item cache.
     pub cache: ItemCache,
-    /// The items the HUD should show right now.
-    visible: HashMap<HudElement, HudItem>,
-    /// True if we've got a two-handed weapon equipped right now.
-    two_hander_equipped: bool,
-    /// We cache the form spec of any left-hand item we were holding before a two-hander was equipped.
-    left_hand_cached: String,
-    /// We cache a right-hand form spec string similarly.
-    right_hand_cached: String,
-    /// We need to track keystate to implement modifier keys.
-    tracked_keys: HashMap<u32, TrackedKey>,
-    /// True if we're using CGO's alternative grip.
-    cgo_alt_grip: bool,
+    /// Key presses and menu toggles in flight.
+    input: InputState,
+    /// What the player has equipped, had equipped, and is able to equip.
+    equipped: EquippedState,
+    /// What the HUD is showing.
+    visual: VisualState,
+    /// Holds equip and inventory events that arrive while a save is loading.
+    load_gate: LoadGate,
+    /// Recent cycle edits the player can take back.
+    undo: UndoStack,
+    /// Which known spells a spell-preparation mod lets the player cast.
+    castability: SpellCastability,
+    /// Destructive MCM actions waiting for the player to confirm them.
+    confirmations: Confirmations,
+    /// Reusable items that just turned into other forms, or might have.
+    swaps: SwapTracker,
 }
 
 impl Controller {
@@ -65,25 +152,161 @@ impl Controller {
         Controller {
             cycles: CycleData::default(),
             cache: ItemCache::new(),
-            visible: HashMap::new(),
-            two_hander_equipped: false,
-            left_hand_cached: "".to_string(),
-            right_hand_cached: "".to_string(),
-            tracked_keys: HashMap::new(),
-            cgo_alt_grip: false,
+            input: InputState::default(),
+            equipped: EquippedState::default(),
+            visual: VisualState::default(),
+            load_gate: LoadGate::default(),
+            undo: UndoStack::default(),
+            castability: SpellCastability::default(),
+            confirmations: Confirmations::default(),
+            swaps: SwapTracker::default(),
         }
     }
 
     /// Called after a save load to initialize state. The validate function logs out cycles.
     pub fn refresh_after_load(&mut self) {
-        self.cycles.validate(&mut self.cache);
+        self.quarantine_missing_plugins();
+        self.cycles
+            .validate(&mut self.cache, settings().prune_missing_items());
+        self.cycles.reconcile_usage_dates(game_day());
+        self.migrate_utility_ammo();
+        self.sort_cycles_by_usage();
         self.update_hud();
     }
 
+    /// Set aside cycle entries whose plugins aren't loaded, and bring back any
+    /// set aside earlier whose plugins are loaded again.
+    fn quarantine_missing_plugins(&mut self) {
+        let restored = self.cycles.restore_quarantined(form_exists);
+        for (slot, spec) in restored.iter() {
+            log::info!("Its plugin is back, so {spec} returns to the {slot} cycle.");
+        }
+        let removed = self.cycles.quarantine_missing(form_exists);
+        if removed.is_empty() {
+            return;
+        }
+        for (slot, spec) in removed.iter() {
+            log::info!(
+                "Removed {spec} from the {slot} cycle because the game can't find it; its plugin is probably not loaded."
+            );
+        }
+        let format = translated_key(FMT_QUARANTINED);
+        let mut vars = HashMap::new();
+        vars.insert("count".to_string(), removed.len().to_string());
+        notify(&strfmt(&format, &vars).unwrap_or_default());
+    }
+
+    /// If the player asked for it, let each cycle's most-used entries drift
+    /// toward the top. Cycles sort at most once a game day.
+    fn sort_cycles_by_usage(&mut self) {
+        if !matches!(settings().cycle_auto_sort(), CycleAutoSort::Usage) {
+            return;
+        }
+        let changed = self.cycles.sort_by_usage(game_day(), &mut self.cache);
+        if !changed.is_empty() {
+            log::debug!("Usage sorting changed these cycles: {changed:?}");
+        }
+    }
+
+    /// Older configs can have ammo in the utility cycle. If the player cycles
+    /// ammo with its own hotkey, those entries move out of the utility cycle.
+    /// Otherwise they stay, and activating one equips it.
+    fn migrate_utility_ammo(&mut self) {
+        if settings().cycle_ammo() {
+            let relocated = self.cycles.relocate_utility_ammo(&mut self.cache);
+            if relocated.is_empty() {
+                return;
+            }
+            log::info!(
+                "Moved {} ammo entries out of the utility cycle and into ammo cycling:",
+                relocated.len()
+            );
+            for spec in relocated.iter() {
+                log::info!("    {}", self.cache.get(spec));
+            }
+            let format = translated_key(FMT_AMMO_RELOCATED);
+            let mut vars = HashMap::new();
+            vars.insert("count".to_string(), relocated.len().to_string());
+            if let Ok(msg) = strfmt(&format, &vars) {
+                notify(&msg);
+            }
+        } else {
+            let ammo = self.cycles.utility_ammo(&mut self.cache);
+            if !ammo.is_empty() {
+                log::info!(
+                    "Your utility cycle has {} ammo entries. Activating one equips it.",
+                    ammo.len()
+                );
+            }
+        }
+    }
+
+    /// Called when we start restoring state from a save. Equip and inventory
+    /// events are held until the game tells us loading is done.
+    pub fn begin_load_grace_period(&mut self) {
+        log::debug!("Holding equip and inventory events until the load completes.");
+        self.load_gate.open(Instant::now());
+        self.undo.clear();
+        self.equipped.slot_ages.begin_settling();
+    }
+
+    /// The game has finished loading. Replay anything we held onto.
+    pub fn handle_load_complete(&mut self) {
+        self.replay_deferred_events();
+    }
+
+    /// Returns true if this event arrived during the post-load grace period
+    /// and has been set aside for later. If the grace period timed out without
+    /// anyone telling us loading finished, we replay what we held first.
+    fn defer_during_load(&mut self, event: DeferredEvent) -> bool {
+        if self.load_gate.is_buffering(Instant::now()) {
+            self.load_gate.defer(event);
+            return true;
+        }
+        if self.load_gate.has_pending() {
+            log::debug!("Load grace period timed out; replaying held events.");
+            self.replay_deferred_events();
+        }
+        false
+    }
+
+    fn replay_deferred_events(&mut self) {
+        let events = self.load_gate.close();
+        if !events.is_empty() {
+            log::debug!("Replaying {} events held during load.", events.len());
+        }
+        for event in events {
+            match event {
+                DeferredEvent::Equip {
+                    equipped,
+                    form_spec,
+                    right,
+                    left,
+                } => {
+                    self.process_item_equipped(equipped, &form_spec, &right, &left);
+                }
+                DeferredEvent::Inventory { form_spec, count } => {
+                    self.process_inventory_changed(&form_spec, count);
+                }
+            }
+        }
+        self.equipped.slot_ages.settled();
+    }
+
+    /// The game is about to load a save or start a new one, so nothing we know
+    /// belongs to it any more. Go back to a fresh controller; a cosave with
+    /// cycles in it replaces them in a moment, and a new game starts from these.
+    pub fn revert_to_defaults(&mut self) {
+        log::info!("Game reverting; returning to default cycles and a fresh controller.");
+        *self = Controller::new();
+        self.apply_settings();
+    }
+
     /// Called by the MCM cycle clear button.
     pub fn clear_cycles(&mut self) {
         log::info!("Clearing all cycles. Turning off targeting computer.");
         self.cycles.clear();
+        self.undo.clear();
     }
 
     /// Get the names of all items in the given cycle. Papyrus support.
@@ -98,6 +321,20 @@ impl Controller {
         }
     }
 
+    /// Get the descriptions of all items in the given cycle, in the same order
+    /// as `cycle_names()`. Papyrus support.
+    pub fn cycle_descriptions(&mut self, which: i32) -> Vec<String> {
+        match which {
+            0 => self.cycles.descriptions(&CycleSlot::Power, &mut self.cache),
+            1 => self
+                .cycles
+                .descriptions(&CycleSlot::Utility, &mut self.cache),
+            2 => self.cycles.descriptions(&CycleSlot::Left, &mut self.cache),
+            3 => self.cycles.descriptions(&CycleSlot::Right, &mut self.cache),
+            _ => Vec::new(),
+        }
+    }
+
     /// Get form IDs for all items in the given cycle. Papyrus support.
     pub fn cycle_formids(&self, which: i32) -> Vec<String> {
         match which {
@@ -109,23 +346,103 @@ impl Controller {
         }
     }
 
+    /// Everything in a cycle, in its current order with the entry on top first.
+    /// Anything that isn't a cycle slot has nothing in it.
+    pub fn cycle_contents(&mut self, slot: Action) -> Vec<CycleEntryView> {
+        let Ok(which) = CycleSlot::try_from(slot) else {
+            return Vec::new();
+        };
+        let shown = self
+            .visual
+            .showing(&HudElement::from(&which))
+            .map(|xs| xs.form_string())
+            .unwrap_or_default();
+        self.cycles
+            .formids(&which)
+            .into_iter()
+            .map(|form_spec| {
+                let item = self.cache.get(&form_spec);
+                CycleEntryView {
+                    showing: form_spec == shown,
+                    name: item.name(),
+                    description: item.description(),
+                    icon_key: item.icon_key(),
+                    count: if item.count_matters() {
+                        item.count()
+                    } else {
+                        0
+                    },
+                    form_spec,
+                }
+            })
+            .collect()
+    }
+
+    /// Flip the player's tool flag for an entry in the given cycle. Papyrus support.
+    pub fn toggle_tool_flag(&mut self, which: i32, index: i32) -> bool {
+        let Ok(index) = usize::try_from(index) else {
+            return false;
+        };
+        let Some(slot) = cycle_slot_for_papyrus(which) else {
+            return false;
+        };
+        let is_tool = self.cycles.toggle_tool_flag(&slot, index);
+        log::info!("Tool flag set to {is_tool} for {slot} cycle entry {index}.");
+        is_tool
+    }
+
+    /// Move a cycle entry from one position to another, for the MCM. Returns
+    /// where the entry ended up, or -1 if nothing moved.
+    pub fn move_cycle_entry(&mut self, which: i32, from: i32, to: i32) -> i32 {
+        let (Ok(from), Ok(to)) = (usize::try_from(from), usize::try_from(to)) else {
+            return -1;
+        };
+        let Some(slot) = cycle_slot_for_papyrus(which) else {
+            return -1;
+        };
+        let Some(moved_to) = self.cycles.move_entry(&slot, from, to) else {
+            return -1;
+        };
+        log::info!("Moved {slot} cycle entry {from} to {to}; it is now at {moved_to}.");
+        i32::try_from(moved_to).unwrap_or(-1)
+    }
+
+    /// Take the entry at this position out of a cycle, for the MCM. The edit
+    /// can be undone like any other.
+    pub fn remove_cycle_entry(&mut self, which: i32, index: i32) -> bool {
+        let Ok(index) = usize::try_from(index) else {
+            return false;
+        };
+        let Some(slot) = cycle_slot_for_papyrus(which) else {
+            return false;
+        };
+        let Some(form_spec) = self.cycles.formids(&slot).get(index).cloned() else {
+            return false;
+        };
+        let item = self.cache.get(&form_spec);
+        let removed = self.remove_from_cycle(slot.clone(), &item);
+        if removed {
+            log::info!("Removed {item} from the {slot} cycle.");
+            if matches!(slot, CycleSlot::Utility) {
+                self.show_utility_top();
+            }
+        }
+        removed
+    }
+
     /// Called after any settings file read to enforce them.
     pub fn apply_settings(&mut self) {
         let settings = settings();
 
-        match settings.unequip_method() {
-            UnarmedMethod::AddToCycles => {
+        for hand in [CycleSlot::Left, CycleSlot::Right] {
+            if settings.unarmed_in_cycle(&hand) {
                 let h2h = HudItem::make_unarmed_proxy();
                 self.cache.record(h2h.clone());
-                self.cycles.add_item(CycleSlot::Left, &h2h);
-                self.cycles.add_item(CycleSlot::Right, &h2h);
-            }
-            _ => {
-                // remove any item with h2h type from cycles
-                self.cycles
-                    .filter_kind(&CycleSlot::Left, &BaseType::HandToHand, &mut self.cache);
+                self.cycles.add_item(hand, &h2h);
+            } else {
+                // remove any item with h2h type from the cycle
                 self.cycles
-                    .filter_kind(&CycleSlot::Right, &BaseType::HandToHand, &mut self.cache);
+                    .filter_kind(&hand, &BaseType::HandToHand, &mut self.cache);
             }
         }
 
@@ -177,46 +494,149 @@ impl Controller {
         }
 
         // Apply any new anchor relocations to the current layout.
-        Layout::refresh();
+        self.refresh_layout();
 
         self.cache.introspect();
     }
 
+    /// Called after a settings refresh. Any slot whose key changed or went
+    /// away loses whatever a press on its old key started: the tracked key,
+    /// its timers, an open browser, and the not-yet-equipped item the HUD was
+    /// showing. Cycles are not touched. Returns what was cancelled.
+    pub fn cancel_rebound_input(&mut self, before: &SlotBindings) -> Teardown {
+        let after = SlotBindings::from_settings(&settings());
+        let teardown = tear_down(before, &after, &mut self.input.tracked_keys, |which| {
+            let running = timer_running(which);
+            if running {
+                stopTimer(*which);
+            }
+            running
+        });
+
+        for slot in teardown.slots.iter() {
+            let Ok(which) = CycleSlot::try_from(*slot) else {
+                continue;
+            };
+            self.visual.close_browser_on(&which);
+            let equipped = match which {
+                CycleSlot::Power => specEquippedPower(),
+                CycleSlot::Left => specEquippedLeft(),
+                CycleSlot::Right => specEquippedRight(),
+                CycleSlot::Utility => continue,
+            };
+            let item = self.cache.get(&equipped);
+            self.update_slot(HudElement::from(&which), &item);
+        }
+        teardown
+    }
+
+    /// Re-read the layout file and pick up anything we cache from it.
+    pub fn refresh_layout(&mut self) {
+        Layout::refresh();
+        self.visual.cooldown_format = hud_layout().cooldown_format;
+    }
+
     /// For all visible items, refresh data used by the renderer that might
     /// have changed in the last N draw cycles, where N is a count controlled
     /// by the renderer itself.
     pub fn refresh_hud_items(&mut self) {
-        // The only relevant items are shouts, left, and right hand.
-        if let Some(power) = self.visible.get_mut(&HudElement::Power) {
-            power.refresh_extra_data();
+        let drained = self.visual.refresh_items();
+        if !settings().warn_drained_charge() {
+            return;
         }
-        if let Some(left) = self.visible.get_mut(&HudElement::Left) {
-            left.refresh_extra_data();
+        for hand in drained {
+            let Some(item) = self.visual.showing(&hand) else {
+                continue;
+            };
+            let format = translated_key(FMT_CHARGE_DRAINED);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
         }
-        if let Some(right) = self.visible.get_mut(&HudElement::Right) {
-            right.refresh_extra_data();
+    }
+
+    /// Is anything waiting on the clock? The renderer asks every frame, so
+    /// this has to stay cheap.
+    pub fn has_clock_work(&self) -> bool {
+        self.visual.has_clock_work() || !self.confirmations.is_empty() || self.load_gate.is_open()
+    }
+
+    /// Let go of whatever has been waiting too long: a lock hint, an idle
+    /// cycle browser, destructive actions nobody confirmed, and events held
+    /// for a load that never told us it finished.
+    pub fn tick(&mut self, now: Instant) {
+        self.visual.expire(now);
+        self.confirmations.expire(now);
+        if self.load_gate.timed_out(now) {
+            log::debug!("Load grace period timed out; replaying held events.");
+            self.replay_deferred_events();
         }
     }
 
     /// The player's inventory changed! Act on it if we need to.
-    pub fn handle_inventory_changed(&mut self, form_spec: &String, new_count: u32) {
-        let Some(item) = self.cache.update_count(form_spec.as_str(), new_count) else {
+    pub fn handle_inventory_changed(
+        &mut self,
+        form_spec: &String,
+        new_count: u32,
+        weight: f32,
+        quest_item: bool,
+    ) {
+        // Weights can change under us when survival mods adjust them, and
+        // quests take back the items they hand out. Every inventory event
+        // refreshes both.
+        self.cache.update_weight(form_spec, weight);
+        self.cache.update_quest_item(form_spec, quest_item);
+        let mut touched = Vec::new();
+        for (slot, item) in self.visual.visible.iter_mut() {
+            if item.form_string() == *form_spec {
+                item.set_quest_item(quest_item);
+                touched.push(*slot);
+            }
+        }
+        for slot in touched {
+            self.visual.touch(slot);
+        }
+        let event = DeferredEvent::Inventory {
+            form_spec: form_spec.clone(),
+            count: new_count,
+        };
+        if self.defer_during_load(event) {
             return;
+        }
+        self.process_inventory_changed(form_spec, new_count);
+    }
+
+    fn process_inventory_changed(&mut self, form_spec: &String, new_count: u32) {
+        let previous_count = self.cache.peek_count(form_spec).unwrap_or_default();
+        let (kind, name) = match self.cache.update_count(form_spec.as_str(), new_count) {
+            Some(item) => (item.kind().clone(), item.name()),
+            None => {
+                // A reusable item can swap itself for a form we've never seen.
+                // That only matters if there's one in the utility cycle.
+                if new_count == 0 || !self.utility_holds_reusables() {
+                    return;
+                }
+                let item = self.cache.get(form_spec);
+                if item.kind().reusable_family().is_none() {
+                    return;
+                }
+                (item.kind().clone(), item.name())
+            }
         };
 
         // If I were smart enough to have the hud use the same object that the cache
         // holds this would be a lot less work, but I am stupid.
 
-        let kind = item.kind().clone();
-        log::trace!(
-            "inventory count update: name='{}'; count={new_count}",
-            item.name()
-        );
+        log::trace!("inventory count update: name='{name}'; count={new_count}");
 
         if kind.is_ammo() {
-            if let Some(candidate) = self.visible.get_mut(&HudElement::Ammo) {
+            if let Some(candidate) = self.visual.visible.get_mut(&HudElement::Ammo) {
                 if candidate.form_string() == *form_spec {
                     candidate.set_count(new_count);
+                    self.visual.touch(HudElement::Ammo);
+                    if new_count == 0 && settings().cycle_ammo() {
+                        self.replace_spent_ammo(form_spec);
+                    }
                 }
             }
         } else if kind.is_utility() {
@@ -233,7 +653,7 @@ impl Controller {
                 }
             }
 
-            if let Some(candidate) = self.visible.get_mut(&HudElement::Utility) {
+            if let Some(candidate) = self.visual.visible.get_mut(&HudElement::Utility) {
                 let visible_spec = candidate.form_string();
                 if visible_spec == *form_spec {
                     candidate.set_count(new_count);
@@ -244,83 +664,187 @@ impl Controller {
                 } else if visible_spec == "stamina_proxy" {
                     candidate.set_count(staminaPotionCount());
                 }
+                self.visual.touch(HudElement::Utility);
             }
+        } else if matches!(kind, BaseType::Light(base::LightType::Torch)) && new_count > 0 {
+            self.relight_torch(form_spec, new_count);
         } else {
             // This entire code block is unlikely to execute because we are
             // consistently getting the unequip message first. Unfortunately
             // we have no idea at that time *why* the unequip event happened.
-            if let Some(candidate) = self.visible.get_mut(&HudElement::Left) {
+            if let Some(candidate) = self.visual.visible.get_mut(&HudElement::Left) {
                 if candidate.form_string() == *form_spec {
                     candidate.set_count(new_count);
+                    self.visual.touch(HudElement::Left);
                     if new_count == 0 {
                         self.advance_hand_cycle(&CycleSlot::Left);
                     }
                 }
             }
-            if let Some(candidate) = self.visible.get_mut(&HudElement::Right) {
+            if let Some(candidate) = self.visual.visible.get_mut(&HudElement::Right) {
                 if candidate.form_string() == *form_spec {
                     candidate.set_count(new_count);
+                    self.visual.touch(HudElement::Right);
                     if new_count == 0 {
                         self.advance_hand_cycle(&CycleSlot::Right);
                     }
                 }
             }
         }
+        if let Some(family) = kind.reusable_family() {
+            // Using one of these never uses it up, so it isn't pruned. It
+            // might have turned into another form, though.
+            if new_count == 0 || previous_count == 0 {
+                self.follow_reusable_swap(family, form_spec, new_count);
+            }
+            return;
+        }
         if new_count > 0 {
             return;
         }
 
-        self.cycles
-            .remove_zero_count_items(form_spec.as_str(), &kind);
+        // Ask before pruning, which can take the entry off the top of its cycle.
+        let left_ran_out = kind.left_hand_ok()
+            && self.cycles.hand_ran_out(
+                &CycleSlot::Left,
+                form_spec,
+                &kind,
+                self.visual.showing(&HudElement::Left),
+            );
+        let right_ran_out = kind.right_hand_ok()
+            && self.cycles.hand_ran_out(
+                &CycleSlot::Right,
+                form_spec,
+                &kind,
+                self.visual.showing(&HudElement::Right),
+            );
+
+        if settings().prune_missing_items()
+            && self
+                .cycles
+                .remove_zero_count_items(form_spec.as_str(), &kind)
+        {
+            let format = translated_key(FMT_PRUNED_EMPTY);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), name);
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+        }
 
         // The count of the inventory item went to zero. We need to check
         // if we must equip/ready something else now.
 
         if kind.is_utility() {
-            if let Some(vis) = self.visible.get(&HudElement::Utility) {
+            if let Some(vis) = self.visual.showing(&HudElement::Utility) {
                 if vis.form_string() == *form_spec {
-                    if let Some(formspec) = self.cycles.get_top(&CycleSlot::Utility) {
+                    if let Some(formspec) = self.replacement_for(&CycleSlot::Utility, form_spec) {
                         let item = self.cache.get_with_refresh(&formspec);
                         self.update_slot(HudElement::Utility, &item);
                     }
                 }
             }
         }
-        if kind.left_hand_ok() {
-            if let Some(vis) = self.visible.get(&HudElement::Left) {
-                if vis.form_string() == *form_spec {
-                    if let Some(formspec) = self.cycles.get_top(&CycleSlot::Left) {
-                        let item = self.cache.get(&formspec);
-                        self.equip_item(&item, Action::Left);
-                    }
-                }
+        if left_ran_out {
+            if let Some(formspec) = self.replacement_for(&CycleSlot::Left, form_spec) {
+                let item = self.cache.get(&formspec);
+                self.equip_item(&item, Action::Left);
             }
         }
-        if kind.right_hand_ok() {
-            if let Some(vis) = self.visible.get(&HudElement::Right) {
-                if vis.form_string() == *form_spec {
-                    if let Some(formspec) = self.cycles.get_top(&CycleSlot::Right) {
-                        let item = self.cache.get(&formspec);
-                        self.equip_item(&item, Action::Right);
-                        // this might race with the left hand. IDEK.
-                    }
-                }
+        if right_ran_out {
+            if let Some(formspec) = self.replacement_for(&CycleSlot::Right, form_spec) {
+                let item = self.cache.get(&formspec);
+                self.equip_item(&item, Action::Right);
+                // this might race with the left hand. IDEK.
+            }
+        }
+    }
+
+    /// What should take the place of an entry the player just ran out of. If
+    /// missing entries stay in their cycles, the cycle moves on past it.
+    /// Does the utility cycle have any reusable items that could swap forms?
+    fn utility_holds_reusables(&mut self) -> bool {
+        self.cycles
+            .formids(&CycleSlot::Utility)
+            .iter()
+            .any(|spec| self.cache.get(spec).kind().reusable_family().is_some())
+    }
+
+    /// Pair this loss or arrival of a reusable item with its other half. When
+    /// the pair is complete, the new form takes the old one's place in the
+    /// utility cycle and on the HUD.
+    fn follow_reusable_swap(&mut self, family: &str, form_spec: &str, new_count: u32) {
+        let now = Instant::now();
+        let (old, new) = if new_count == 0 {
+            match self.swaps.lost(family, form_spec, now) {
+                Some(new) => (form_spec.to_string(), new),
+                None => return,
+            }
+        } else {
+            match self.swaps.found(family, form_spec, now) {
+                Some(old) => (old, form_spec.to_string()),
+                None => return,
             }
+        };
+        if !self.cycles.swap_entry(&CycleSlot::Utility, &old, &new) {
+            return;
+        }
+        log::info!("A reusable item changed forms; following it in the utility cycle: old='{old}'; new='{new}';");
+        if self
+            .visual
+            .showing(&HudElement::Utility)
+            .is_some_and(|vis| vis.form_string() == old)
+        {
+            let item = self.cache.get_with_refresh(&new);
+            self.update_slot(HudElement::Utility, &item);
         }
     }
 
+    fn replacement_for(&mut self, which: &CycleSlot, lost: &str) -> Option<String> {
+        let top = self.cycles.get_top(which)?;
+        if top != lost {
+            return Some(top);
+        }
+        let next = self.cycles.advance(which, 1);
+        self.skip_unusable(which, next)
+    }
+
     /// Handle a gameplay key-press event that the event system decided we need to know about.
     ///
     /// Returns an enum indicating what we did in response, so that the C++ layer can
     /// start a tick timer for cycle delay.
-    pub fn handle_key_event(&mut self, key: u32, button: &ButtonEvent) -> KeyEventResponse {
+    pub fn handle_key_event(
+        &mut self,
+        key: u32,
+        button: &ButtonEvent,
+        sequence: u64,
+    ) -> KeyEventResponse {
+        let state = KeyState::from(button);
+        let response = self.respond_to_key(key, &state, sequence);
+        self.input.recent.record(key, state, &response);
+        response
+    }
+
+    fn respond_to_key(&mut self, key: u32, state: &KeyState, sequence: u64) -> KeyEventResponse {
+        // The cycle browser gets first look, because the keys it uses aren't
+        // necessarily hotkeys.
+        if let Some(response) = self.route_to_browser(key, state) {
+            return response;
+        }
+
         if matches!(Hotkey::from(key), Hotkey::None) {
             return KeyEventResponse::default();
         }
 
+        // Events that started life in a menu are not gameplay input. We still
+        // track key state so modifiers don't look stuck, but we don't act.
+        if !self.input.context.accept_world_event(key, sequence, state) {
+            log::debug!("dropping menu-originated key event; key={key}; sequence={sequence};");
+            self.input.note_state(key, state.clone());
+            return KeyEventResponse::default();
+        }
+
         // This call starts and stops long-press timers as well.
         // It returns nothing if the handler should take no further action.
-        let Some(tracked) = self.create_or_update_tracked(key, button, false) else {
+        let Some(tracked) = self.create_or_update_tracked(key, state.clone(), false) else {
             return KeyEventResponse::default();
         };
 
@@ -328,17 +852,39 @@ impl Controller {
             return KeyEventResponse::default();
         }
 
+        // While the game has the player's controls, hotkeys that equip or use
+        // things are the game's business. Showing and hiding the HUD isn't.
+        if self.equipped.vitals.controls_disabled()
+            && !matches!(tracked.action(), Action::ShowHide | Action::RefreshLayout)
+        {
+            log::trace!("Player controls are disabled; passing on key {key}.");
+            return KeyEventResponse::default();
+        }
+
         log::trace!("handling keypress={tracked}");
 
+        if let Some(response) = self.auto_repeat(&tracked) {
+            return response;
+        }
+
         // From here on, we only care if the key has gone up.
         if tracked.state != KeyState::Up {
             return KeyEventResponse::handled();
         }
 
+        // Hotbar keys act right away, even if the HUD was hidden.
+        if let Some(slot) = tracked.hotbar_slot() {
+            return self.activate_hotbar_slot(slot);
+        }
+
         let options = settings();
         let requested_action = tracked.action();
-        // if the hud is NOT visible, we show it briefly before cycling.
-        if !matches!(requested_action, Action::RefreshLayout | Action::ShowHide) && showBriefly() {
+        // If the hud is NOT visible, we show it briefly. The press goes on to
+        // cycle as well, unless the player wants it to only wake the HUD.
+        if !matches!(requested_action, Action::RefreshLayout | Action::ShowHide)
+            && showBriefly()
+            && options.fade_wakeup_only()
+        {
             return KeyEventResponse::handled();
         }
 
@@ -364,20 +910,139 @@ impl Controller {
                     KeyEventResponse::default()
                 }
             }
+            Action::Ammo => self.advance_ammo(),
+            Action::LeftPrevious => self.swap_to_previous(CycleSlot::Left),
+            Action::RightPrevious => self.swap_to_previous(CycleSlot::Right),
             Action::RefreshLayout => {
-                Layout::refresh();
+                self.refresh_layout();
                 KeyEventResponse::handled()
             }
             Action::ShowHide => {
-                if !options.autofade() {
-                    self.cycles.toggle_hud();
+                if options.autofade() {
+                    return KeyEventResponse::handled();
+                }
+                self.cycles.toggle_hud();
+                KeyEventResponse {
+                    handled: true,
+                    outcome: KeyOutcome::HudToggled,
+                    ..Default::default()
                 }
-                KeyEventResponse::handled()
             }
             _ => KeyEventResponse::default(),
         }
     }
 
+    /// Holding a cycle key down cycles again every so often, for slots set to
+    /// repeat. Returns a response if this event was a repeat, or the release
+    /// that ends a press that repeated. That release restarts the slot's equip
+    /// timer if one is running.
+    fn auto_repeat(&mut self, tracked: &TrackedKey) -> Option<KeyEventResponse> {
+        let action = tracked.action();
+        if !matches!(
+            action,
+            Action::Power | Action::Utility | Action::Left | Action::Right
+        ) {
+            return None;
+        }
+        let options = settings();
+        if !matches!(options.cycle_advance_method(), ActivationMethod::Hotkey)
+            || options.should_start_long_press_timer(tracked.key)
+        {
+            return None;
+        }
+
+        match tracked.state {
+            KeyState::Down => {
+                self.input.repeats.release(tracked.key);
+                None
+            }
+            KeyState::Up => {
+                if !self.input.repeats.release(tracked.key) {
+                    return None;
+                }
+                // The equip delay counts from when the player lets go, not from
+                // the last repeat, so they get the full delay to change their mind.
+                let start_timer = if timer_running(&action) {
+                    action
+                } else {
+                    Action::None
+                };
+                Some(KeyEventResponse {
+                    handled: true,
+                    start_timer,
+                    ..Default::default()
+                })
+            }
+            KeyState::Pressed => {
+                let held = tracked.held_for()?;
+                let timings = options.timings_for(&action);
+                if !self.input.repeats.poll(tracked.key, held, &timings) {
+                    return None;
+                }
+                if showBriefly() && options.fade_wakeup_only() {
+                    return Some(KeyEventResponse::handled());
+                }
+                log::trace!("repeating held cycle key; action={action:?};");
+                let response = match action {
+                    Action::Power => self.advance_cycle_power(),
+                    Action::Utility => self.advance_cycle_utilities(),
+                    Action::Left if hasRangedEquipped() && options.cycle_ammo() => {
+                        self.advance_ammo()
+                    }
+                    Action::Left => self.advance_hand_cycle(&CycleSlot::Left),
+                    _ => self.advance_hand_cycle(&CycleSlot::Right),
+                };
+                Some(response)
+            }
+        }
+    }
+
+    /// Equip or use the entry at this position in the hotbar's cycle, without
+    /// cycling. Positions count from the cycle's hotbar anchor, so a key keeps
+    /// picking the same entry however the cycle turns. The cycle turns to the
+    /// pick, so what the HUD shows is the cycle's top, as it is after cycling.
+    /// Holding the hotbar modifier picks from the utility cycle instead.
+    fn activate_hotbar_slot(&mut self, position: usize) -> KeyEventResponse {
+        let which = if self.tracked_modifier(&Modifier::Hotbar).is_pressed() {
+            CycleSlot::Utility
+        } else {
+            settings().hotbar_cycle()
+        };
+        let cycle = self.cycles.formids(&which);
+        let Some(form_spec) = self.input.hotbar.resolve(&which, &cycle, position) else {
+            log::debug!(
+                "Nothing at hotbar position {} in the {which} cycle.",
+                position + 1
+            );
+            honk();
+            return KeyEventResponse::cycle_empty(HudElement::from(&which));
+        };
+
+        // If the HUD was faded out, this brings it back so the player sees the choice.
+        showBriefly();
+        let item = self.cache.get_with_refresh(&form_spec);
+        log::info!(
+            "Hotbar position {} selects '{}' from the {which} cycle.",
+            position + 1,
+            item.name()
+        );
+        self.cycles.set_top(&which, &form_spec);
+        self.update_slot(HudElement::from(&which), &item);
+        self.visual.flash(HudElement::from(&which), Instant::now());
+        if matches!(which, CycleSlot::Utility) {
+            self.use_item_now(&item)
+        } else {
+            self.timer_expired(Action::from(which.clone()));
+            KeyEventResponse {
+                handled: true,
+                outcome: KeyOutcome::ItemEquipped,
+                slot: HudElement::from(&which),
+                item_name: item.name(),
+                ..Default::default()
+            }
+        }
+    }
+
     /// Handle the power/shouts key being pressed.
     fn handle_cycle_power(&mut self) -> KeyEventResponse {
         let options = settings();
@@ -398,7 +1063,7 @@ impl Controller {
 
     /// The power/shouts keypress resulted in advancing the cycle.
     fn advance_cycle_power(&mut self) -> KeyEventResponse {
-        let current_not_in_cycle = if let Some(visible) = self.visible.get(&HudElement::Power) {
+        let current_not_in_cycle = if let Some(visible) = self.visual.showing(&HudElement::Power) {
             !self.cycles.includes(&CycleSlot::Power, visible)
         } else {
             false
@@ -408,6 +1073,7 @@ impl Controller {
         } else {
             self.cycles.advance(&CycleSlot::Power, 1)
         };
+        let candidate = self.skip_unusable(&CycleSlot::Power, candidate);
 
         if let Some(next) = candidate {
             let item = self.cache.get_with_refresh(&next);
@@ -416,9 +1082,13 @@ impl Controller {
                 handled: true,
                 start_timer: Action::from(CycleSlot::Power),
                 stop_timer: Action::None,
+                wrapped: self.cycles.take_wrapped(&CycleSlot::Power),
+                outcome: KeyOutcome::CycleAdvanced,
+                slot: HudElement::Power,
+                item_name: item.name(),
             }
         } else {
-            KeyEventResponse::handled()
+            KeyEventResponse::cycle_empty(HudElement::Power)
         }
     }
 
@@ -456,7 +1126,8 @@ impl Controller {
 
     /// Advance the utilities/consumables cycle.
     fn advance_cycle_utilities(&mut self) -> KeyEventResponse {
-        let current_not_in_cycle = if let Some(visible) = self.visible.get(&HudElement::Utility) {
+        let current_not_in_cycle = if let Some(visible) = self.visual.showing(&HudElement::Utility)
+        {
             !self.cycles.includes(&CycleSlot::Utility, visible)
         } else {
             false
@@ -466,6 +1137,7 @@ impl Controller {
         } else {
             self.cycles.advance(&CycleSlot::Utility, 1)
         };
+        let candidate = self.skip_unusable(&CycleSlot::Utility, candidate);
 
         if let Some(next) = candidate {
             let item = self.cache.get_with_refresh(&next);
@@ -474,9 +1146,13 @@ impl Controller {
                 handled: true,
                 start_timer: Action::None,
                 stop_timer: Action::None,
+                wrapped: self.cycles.take_wrapped(&CycleSlot::Utility),
+                outcome: KeyOutcome::CycleAdvanced,
+                slot: HudElement::Utility,
+                item_name: item.name(),
             }
         } else {
-            KeyEventResponse::handled()
+            KeyEventResponse::cycle_empty(HudElement::Utility)
         }
     }
 
@@ -553,21 +1229,30 @@ impl Controller {
         slot: CycleSlot,
     ) -> KeyEventResponse {
         match requested {
+            RequestedAction::Unequip if !self.equipped.vitals.permit("unequip a hand") => {
+                KeyEventResponse::handled()
+            }
             RequestedAction::Unequip => {
                 log::info!("unequipping {hand:?} hand by request");
                 let unarmed = HudItem::make_unarmed_proxy();
                 unequipSlot(hand);
-                self.update_slot(HudElement::from(&slot), &unarmed);
+                self.show_empty_hand(&slot);
                 self.cycles.set_top(&slot, &unarmed.form_string());
-                KeyEventResponse {
-                    handled: true,
-                    start_timer: Action::None,
-                    stop_timer: hand,
-                }
+                KeyEventResponse::equipped(hand, HudElement::from(&slot), unarmed.name())
             }
             RequestedAction::Advance => self.advance_hand_cycle(&slot),
             RequestedAction::AdvanceAmmo => self.advance_ammo(), // pretty sure we never hit this
             RequestedAction::Match => self.match_hands(hand),
+            RequestedAction::EquipNow => {
+                self.equip_shown(hand);
+                let hud = HudElement::from(&slot);
+                let name = self
+                    .visual
+                    .showing(&hud)
+                    .map(|item| item.name())
+                    .unwrap_or_default();
+                KeyEventResponse::equipped(hand, hud, name)
+            }
             RequestedAction::Consume => KeyEventResponse::default(),
             RequestedAction::None => KeyEventResponse::default(),
         }
@@ -581,6 +1266,9 @@ impl Controller {
     /// Match hands to each other; that is, dual-wield whatever is in the hand we
     /// were asked to match, if possible.
     fn match_hands(&mut self, action: Action) -> KeyEventResponse {
+        if !self.equipped.vitals.permit("match hands") {
+            return KeyEventResponse::handled();
+        }
         let (equipped, other_hand) = if matches!(action, Action::Left) {
             (specEquippedLeft(), Action::Right)
         } else {
@@ -596,17 +1284,14 @@ impl Controller {
             } else {
                 self.equip_item(&item, other_hand);
             }
-            KeyEventResponse {
-                handled: true,
-                start_timer: Action::None,
-                stop_timer: action,
-            }
+            KeyEventResponse::equipped(action, HudElement::from(other_hand), item.name())
         } else {
             log::info!("Can't dual-wield '{}' item!", item.name());
             KeyEventResponse {
                 handled: true,
                 start_timer: Action::None,
                 stop_timer: action,
+                ..Default::default()
             }
         }
     }
@@ -625,13 +1310,13 @@ impl Controller {
             HudElement::Left
         };
 
-        if self.two_hander_equipped {
+        if self.equipped.two_hander_equipped {
             // Here either hand may cycle, and the other hand must bounce back
             // to what was previously equipped. We update both slots in the HUD.
 
             // this should not be None given the first check, but we need to check anyway
             let Some(form_string) = self.cycles.peek_next(which) else {
-                return KeyEventResponse::handled();
+                return KeyEventResponse::cycle_empty(HudElement::from(which));
             };
 
             let candidate = self.cache.get(&form_string);
@@ -642,11 +1327,12 @@ impl Controller {
             }
 
             // Now we got fun. Do we have something to bounce back to in the other hand?
-            let (other_cached, other_hand) = if matches!(which, CycleSlot::Left) {
-                (self.right_hand_cached.clone(), CycleSlot::Right)
+            let other_hand = if matches!(which, CycleSlot::Left) {
+                CycleSlot::Right
             } else {
-                (self.left_hand_cached.clone(), CycleSlot::Left)
+                CycleSlot::Left
             };
+            let other_cached = self.equipped.remembered_hand(&other_hand).to_string();
 
             if other_cached.is_empty() {
                 // The other hand has no opinions. Advance without fear.
@@ -676,9 +1362,9 @@ impl Controller {
                         if let Some(advance_to) = self.cycles.advance_skipping_twohanders() {
                             let _changed = self.update_slot(other_hud, &advance_to.clone());
                             self.cycles.set_top(&other_hand, &advance_to);
-                            self.right_hand_cached = Some(advance_to);
+                            self.equipped.right_hand_cached = Some(advance_to);
                         } else {
-                            self.right_hand_cached = Some(*hand2hand_HudItem());
+                            self.equipped.right_hand_cached = Some(*hand2hand_HudItem());
                         }
                     }
                     */
@@ -699,7 +1385,7 @@ impl Controller {
             // and in this case it's the hand trying to cycle forward.
             let Some(form_string) = self.cycles.advance_skipping(which, return_to.clone()) else {
                 honk();
-                return KeyEventResponse::handled();
+                return KeyEventResponse::cycle_empty(HudElement::from(which));
             };
 
             let candidate = self.cache.get(&form_string);
@@ -713,25 +1399,34 @@ impl Controller {
             }
         } else {
             // Phew. Okay. Now we're on to the one-handers equipped cases. These are easier.
-            let maybe_candidate = if let Some(other_equipped) = self.visible.get(&other_hud) {
-                // Are we dual-wielding? If so, do we have at least two?
-                if !other_equipped.count_matters() || other_equipped.count() > 1 {
-                    self.cycles.advance(which, 1)
-                } else {
-                    self.cycles.advance_skipping(which, other_equipped.clone())
-                }
+            // Are we dual-wielding? If so, we need at least two of the other hand's item
+            // to land on it here too.
+            let only_one_of = self
+                .visual
+                .showing(&other_hud)
+                .filter(|xs| xs.count_matters() && xs.count() <= 1)
+                .map(|xs| xs.form_string());
+            let skip_tools = matches!(which, CycleSlot::Right) && settings().cycle_skips_tools();
+            let flagged = if skip_tools {
+                self.cycles.flagged_tools()
             } else {
-                self.cycles.advance(which, 1)
+                Vec::new()
             };
+            let first = self.cycles.advance(which, 1);
+            let found = self.skip_unusable_or(which, first, |item| {
+                let spec = item.form_string();
+                only_one_of.as_ref() == Some(&spec)
+                    || (skip_tools && (item.is_tool() || flagged.contains(&spec)))
+            });
 
-            if let Some(candidate) = maybe_candidate {
+            if let Some(candidate) = found {
                 let item = self.cache.get(&candidate);
                 return self.update_and_record(which, &item);
             }
         }
 
         // If we got here, we got nothin'.
-        KeyEventResponse::handled()
+        KeyEventResponse::cycle_empty(HudElement::from(which))
     }
 
     fn advance_ammo(&mut self) -> KeyEventResponse {
@@ -752,7 +1447,13 @@ impl Controller {
             if let Some(next) = maybe_next {
                 let_cxx_string!(form_spec = next);
                 equipAmmo(&form_spec);
-                KeyEventResponse::handled()
+                KeyEventResponse {
+                    handled: true,
+                    outcome: KeyOutcome::CycleAdvanced,
+                    slot: HudElement::Ammo,
+                    item_name: self.cache.get(next).name(),
+                    ..Default::default()
+                }
             } else {
                 log::warn!("Something very strange just happened. Ammo types: {ammotypes:?}");
                 honk();
@@ -774,6 +1475,10 @@ impl Controller {
                 Action::None
             },
             stop_timer: Action::None,
+            wrapped: self.cycles.take_wrapped(which),
+            outcome: KeyOutcome::CycleAdvanced,
+            slot: hud,
+            item_name: next.name(),
         }
     }
 
@@ -781,46 +1486,205 @@ impl Controller {
     fn use_utility_item(&mut self) -> KeyEventResponse {
         if let Some(form_string) = self.cycles.get_top(&CycleSlot::Utility) {
             let item = self.cache.get(&form_string);
-            log::info!("Activating utility item: name='{}';", item.name());
-            if matches!(
-                item.kind(),
-                BaseType::Potion(PotionType::Poison) | BaseType::Food(_)
-            ) {
-                cxx::let_cxx_string!(form_spec = item.form_string());
-                consumePotion(&form_spec);
-            } else if item.form_string() == "health_proxy" {
-                chooseHealthPotion();
-            } else if item.form_string() == "magicka_proxy" {
-                chooseMagickaPotion();
-            } else if item.form_string() == "stamina_proxy" {
-                chooseStaminaPotion();
-            } else if item.is_potion() {
-                cxx::let_cxx_string!(form_spec = item.form_string());
-                consumePotion(&form_spec);
-            } else if item.is_armor() {
-                cxx::let_cxx_string!(form_spec = item.form_string());
-                cxx::let_cxx_string!(name = item.name());
-                toggleArmor(&form_spec, &name);
-            } else if item.is_ammo() {
-                cxx::let_cxx_string!(form_spec = item.form_string());
-                equipAmmo(&form_spec)
-            }
+            self.use_item_now(&item)
         } else {
             log::debug!("No item at top of utility cycle to use.");
-        }
-
-        // No matter what we did, we stop the timer. Not that a timer should exist.
-        KeyEventResponse {
-            handled: true,
-            start_timer: Action::None,
-            stop_timer: Action::Utility,
+            honk();
+            KeyEventResponse {
+                stop_timer: Action::Utility,
+                ..KeyEventResponse::cycle_empty(HudElement::Utility)
+            }
         }
     }
 
-    /// When the equip delay for a cycle expires, equip the item at the top.
-    ///
-    /// This function implements a critical behavior in the mod: equipping
-    /// items. When the delay timer expires, we're notified to act on the
+    /// Use or consume this utility item right now.
+    fn use_item_now(&mut self, item: &HudItem) -> KeyEventResponse {
+        if !self.equipped.vitals.permit("use an item") {
+            return KeyEventResponse::handled();
+        }
+        if item.is_quest_item() && !settings().activate_quest_items() {
+            log::info!("Not using quest item: name='{}';", item.name());
+            honk();
+            let format = translated_key(FMT_QUEST_ITEM);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+            return KeyEventResponse {
+                handled: true,
+                stop_timer: Action::Utility,
+                ..Default::default()
+            };
+        }
+
+        let limit = daily_limit(item);
+        if let Some(limit) = limit.as_ref() {
+            if self.cycles.daily_uses().remaining(limit, game_day()) == 0 {
+                log::info!(
+                    "Daily limit reached; not using item: name='{}'; limit={};",
+                    item.name(),
+                    limit.per_day
+                );
+                honk();
+                let format = translated_key(FMT_DAILY_LIMIT);
+                let mut vars = HashMap::new();
+                vars.insert("item".to_string(), item.name());
+                vars.insert("limit".to_string(), limit.per_day.to_string());
+                notify(&strfmt(&format, &vars).unwrap_or_default());
+                return KeyEventResponse {
+                    handled: true,
+                    stop_timer: Action::Utility,
+                    ..Default::default()
+                };
+            }
+        }
+
+        let poison = matches!(item.kind(), BaseType::Potion(PotionType::Poison));
+        if poison && !self.weapon_ready_for_poison() {
+            log::info!("No weapon to apply poison to: name='{}';", item.name());
+            honk();
+            let format = translated_key(FMT_NOTHING_TO_POISON);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+            return KeyEventResponse {
+                handled: true,
+                stop_timer: Action::Utility,
+                ..Default::default()
+            };
+        }
+
+        log::info!("Activating utility item: name='{}';", item.name());
+        // The inventory event for the last one takes it out of the cycle; we
+        // tell the player why it's gone.
+        let last_one = item.count() == 1
+            && !item.form_string().ends_with("_proxy")
+            && matches!(item.kind(), BaseType::Potion(_) | BaseType::Food(_));
+        self.cycles.mark_used(&item.form_string(), game_day());
+        self.sort_cycles_by_usage();
+        if self.activate_with_override(item) {
+            // The player's override did the work.
+        } else if poison {
+            // The game puts poison on the readied weapons rather than drinking it.
+            cxx::let_cxx_string!(form_spec = item.form_string());
+            consumePotion(&form_spec);
+            let format = translated_key(FMT_POISON_APPLIED);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+        } else if matches!(item.kind(), BaseType::Reusable(_)) {
+            cxx::let_cxx_string!(form_spec = item.form_string());
+            cxx::let_cxx_string!(name = item.raw_name());
+            useReusableItem(&form_spec, &name);
+            let format = translated_key(FMT_ITEM_USED);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+        } else if matches!(item.kind(), BaseType::Food(_)) {
+            cxx::let_cxx_string!(form_spec = item.form_string());
+            consumePotion(&form_spec);
+        } else if item.form_string() == "health_proxy" {
+            chooseHealthPotion();
+        } else if item.form_string() == "magicka_proxy" {
+            chooseMagickaPotion();
+        } else if item.form_string() == "stamina_proxy" {
+            chooseStaminaPotion();
+        } else if item.is_potion() {
+            cxx::let_cxx_string!(form_spec = item.form_string());
+            consumePotion(&form_spec);
+        } else if item.is_armor() {
+            cxx::let_cxx_string!(form_spec = item.form_string());
+            cxx::let_cxx_string!(name = item.raw_name());
+            toggleArmor(&form_spec, &name);
+        } else if item.is_ammo() {
+            cxx::let_cxx_string!(form_spec = item.form_string());
+            equipAmmo(&form_spec)
+        }
+
+        if let Some(limit) = limit.as_ref() {
+            self.cycles.daily_uses().record(limit, game_day());
+            self.show_utility_top();
+        }
+        if last_one {
+            let format = translated_key(FMT_USED_UP);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+        }
+
+        // No matter what we did, we stop the timer. Not that a timer should exist.
+        KeyEventResponse {
+            handled: true,
+            start_timer: Action::None,
+            stop_timer: Action::Utility,
+            wrapped: false,
+            outcome: if item.is_armor() || item.is_ammo() {
+                KeyOutcome::ItemEquipped
+            } else {
+                KeyOutcome::ItemConsumed
+            },
+            slot: HudElement::Utility,
+            item_name: item.name(),
+        }
+    }
+
+    /// Poison goes on a weapon in either hand, so one of them has to hold one.
+    fn weapon_ready_for_poison(&mut self) -> bool {
+        [specEquippedRight(), specEquippedLeft()]
+            .iter()
+            .any(|spec| !spec.is_empty() && self.cache.get(spec).is_weapon())
+    }
+
+    /// Check the player's activation overrides for this item and act on the
+    /// override if there is one. Returns false if default handling should run.
+    fn activate_with_override(&mut self, item: &HudItem) -> bool {
+        let form_string = item.form_string();
+        let Some(action) = activation_override(&form_string) else {
+            return false;
+        };
+
+        match action {
+            ActivationAction::UseAsNormal => false,
+            ActivationAction::CastSpell { spell } => {
+                let target = self.cache.get(&spell);
+                if !target.is_spell() && !target.is_power() {
+                    disable_activation_override(
+                        &form_string,
+                        format!("'{spell}' is not a spell in this game.").as_str(),
+                    );
+                    return false;
+                }
+                log::info!(
+                    "Activation override: casting '{}' instead of using '{}'.",
+                    target.name(),
+                    item.name()
+                );
+                cxx::let_cxx_string!(spell_spec = spell);
+                castSpellOnSelf(&spell_spec);
+                true
+            }
+            ActivationAction::Equip { hand } => {
+                if !item.kind().left_hand_ok() && !item.kind().right_hand_ok() {
+                    disable_activation_override(&form_string, "The item can't be held in a hand.");
+                    return false;
+                }
+                let which = match hand {
+                    OverrideHand::Left => Action::Left,
+                    OverrideHand::Right => Action::Right,
+                };
+                log::info!(
+                    "Activation override: equipping '{}' in {which:?} hand.",
+                    item.name()
+                );
+                self.equip_item(item, which);
+                true
+            }
+        }
+    }
+
+    /// When the equip delay for a cycle expires, equip the item at the top.
+    ///
+    /// This function implements a critical behavior in the mod: equipping
+    /// items. When the delay timer expires, we're notified to act on the
     /// player's changes to the cycle rotation. The delay exists to let the
     /// player tap a hotkey repeatedly to look at the items in a cycle without
     /// equipping each one of them as they go. Instead we wait for a little bit,
@@ -829,6 +1693,9 @@ impl Controller {
     /// We do not act here on cascading changes. Instead, we let the equipped-change
     /// callback decide what to do when, e.g., a two-handed item is equipped.
     pub fn timer_expired(&mut self, which: Action) {
+        if !self.equipped.vitals.permit("act on an expired timer") {
+            return;
+        }
         // Has a long press action timer fired? If so, we do the long press action
         // for this key. We know there's one because we would not have started a
         // timer if there wasn't.
@@ -851,14 +1718,19 @@ impl Controller {
             // again on key up.
             return;
         }
+        self.equip_shown(which);
+    }
 
+    /// Equip whatever the HUD shows in this slot. For the equipment set
+    /// slot, switch to the selected set.
+    fn equip_shown(&mut self, which: Action) {
         let hud = HudElement::from(which);
         if matches!(which, Action::Equipment) {
             self.equip_selected_set();
             return;
         }
 
-        let Some(item) = &self.visible.get(&hud) else {
+        let Some(item) = self.visual.showing(&hud).cloned() else {
             log::warn!(
                 "visible item in hud slot was None, which should not happen; slot={:?};",
                 hud
@@ -871,12 +1743,9 @@ impl Controller {
         let kind = item.kind();
         if matches!(kind, BaseType::HandToHand) {
             log::info!("Melee time! Unequipping slot {which:?} so you can go punch a dragon.");
-            if matches!(which, Action::Left) {
-                // TODO wasteful but better than a magic string?
-                self.left_hand_cached = HudItem::make_unarmed_proxy().form_string();
-            } else {
-                self.right_hand_cached = HudItem::make_unarmed_proxy().form_string();
-            }
+            // TODO wasteful but better than a magic string?
+            self.equipped
+                .remember_hand(&which, HudItem::make_unarmed_proxy().form_string());
             unequipSlot(which);
             return;
         }
@@ -892,13 +1761,9 @@ impl Controller {
         }
 
         if !item.two_handed() {
-            if which == Action::Left {
-                self.left_hand_cached = item.form_string();
-            } else {
-                self.right_hand_cached = item.form_string();
-            }
+            self.equipped.remember_hand(&which, item.form_string());
         }
-        self.equip_item(item, which);
+        self.equip_item(&item, which);
     }
 
     /// Handle a long-press timer firing.
@@ -912,6 +1777,11 @@ impl Controller {
                 }
             }
             Action::LongPressRight => {
+                if let Some(main) = self.main_weapon_to_restore() {
+                    self.return_to_main_weapon(&main);
+                    stopTimer(Action::Right);
+                    return;
+                }
                 let do_this = Hotkey::Right.long_press_action();
                 if !matches!(do_this, RequestedAction::None) {
                     self.do_hand_action(do_this, Action::Right, CycleSlot::Right);
@@ -927,6 +1797,10 @@ impl Controller {
                     unequipSlot(Action::Power);
                     stopTimer(Action::Power);
                 }
+                RequestedAction::EquipNow => {
+                    stopTimer(Action::Power);
+                    self.equip_shown(Action::Power);
+                }
                 _ => {}
             },
             Action::LongPressUtility => match Hotkey::Utility.long_press_action() {
@@ -944,19 +1818,131 @@ impl Controller {
         }
     }
 
+    /// If the right hand is holding a tool, find the most recent non-tool
+    /// weapon it held. Returns None if there's nothing to go back to.
+    fn main_weapon_to_restore(&mut self) -> Option<HudItem> {
+        if !settings().long_press_leaves_tools() {
+            return None;
+        }
+        let holding_tool = self
+            .visual
+            .visible
+            .get(&HudElement::Right)
+            .is_some_and(|xs| self.cycles.is_tool(xs));
+        if !holding_tool {
+            return None;
+        }
+
+        let cycles = &self.cycles;
+        let cache = &mut self.cache;
+        let spec = self
+            .equipped
+            .history
+            .most_recent_matching(&CycleSlot::Right, |xs| !cycles.is_tool(&cache.get(xs)))?;
+        Some(self.cache.get(&spec))
+    }
+
+    /// Jump the right hand straight back to the given weapon, skipping the cycle.
+    fn return_to_main_weapon(&mut self, main: &HudItem) {
+        log::info!("Putting away the tool and returning to '{}'.", main.name());
+        if self.cycles.includes(&CycleSlot::Right, main) {
+            self.cycles.set_top(&CycleSlot::Right, &main.form_string());
+        }
+        self.update_slot(HudElement::Right, main);
+        if !main.two_handed() {
+            self.equipped.right_hand_cached = main.form_string();
+        }
+        self.equip_item(main, Action::Right);
+    }
+
+    /// Put a hand back to what it held before its current item, skipping
+    /// the cycle. The swap goes into the hand's history like any other equip,
+    /// so swapping again goes back to where the player started.
+    fn swap_to_previous(&mut self, which: CycleSlot) -> KeyEventResponse {
+        let Some(spec) = self.equipped.history.previous(&which) else {
+            log::debug!("Nothing earlier to swap back to in the {which} hand.");
+            return KeyEventResponse::handled();
+        };
+        let item = self.cache.get(&spec);
+        let hand = if matches!(which, CycleSlot::Left) {
+            Action::Left
+        } else {
+            Action::Right
+        };
+        let fits = if matches!(hand, Action::Left) {
+            item.left_hand_ok() && !item.two_handed()
+        } else {
+            item.right_hand_ok()
+        };
+        if item.is_spent() || !fits {
+            log::debug!(
+                "Can't swap the {which} hand back to '{}'; count={};",
+                item.name(),
+                item.count()
+            );
+            return KeyEventResponse::handled();
+        }
+
+        log::info!("Swapping the {which} hand back to '{}'.", item.name());
+        if self.cycles.includes(&which, &item) {
+            self.cycles.set_top(&which, &spec);
+        }
+        self.update_slot(HudElement::from(&which), &item);
+        if !item.two_handed() {
+            self.equipped.remember_hand(&hand, spec);
+        }
+        self.equip_item(&item, hand);
+        // A cycle in progress in this hand would undo the swap when it fires.
+        KeyEventResponse {
+            handled: true,
+            stop_timer: hand,
+            ..Default::default()
+        }
+    }
+
     /// Convenience function for equipping any equippable.
-    fn equip_item(&self, item: &HudItem, which: Action) {
+    fn equip_item(&mut self, item: &HudItem, which: Action) {
         if !matches!(which, Action::Right | Action::Left | Action::Utility) {
             return;
         }
+        if !self.equipped.vitals.permit("equip an item") {
+            return;
+        }
+
+        if !self.spell_castable(item) {
+            log::info!("Not equipping unprepared spell '{}'.", item.name());
+            honk();
+            let format = translated_key(FMT_NOT_PREPARED);
+            let mut vars = HashMap::new();
+            vars.insert("item".to_string(), item.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+            return;
+        }
+
+        if !self.carry_allows_equip(item, &which) {
+            return;
+        }
+
+        // Lighting a torch would knock a two-hander out of the player's hands.
+        if matches!(which, Action::Left)
+            && matches!(item.kind(), BaseType::Light(_))
+            && self.equipped.two_hander_equipped
+        {
+            log::info!("Not lighting '{}' with a two-hander out.", item.name());
+            honk();
+            return;
+        }
 
         let kind = item.kind();
         cxx::let_cxx_string!(form_spec = item.form_string());
-        cxx::let_cxx_string!(name = item.name());
+        cxx::let_cxx_string!(name = item.raw_name());
         log::debug!("about to equip this item: slot={:?}; {}", which, item);
 
         if kind.is_magic() || kind.left_hand_ok() || kind.right_hand_ok() {
             equipWeapon(&form_spec, which, &name);
+            if let Some(bolts) = item.shoots_bolts() {
+                self.equip_ammo_for(item, bolts);
+            }
         } else if kind.is_armor() {
             toggleArmor(&form_spec, &name);
         } else if matches!(kind, BaseType::Ammo(_)) {
@@ -969,11 +1955,230 @@ impl Controller {
         }
     }
 
+    /// Check an auto-equip of a weapon or piece of armor against the player's
+    /// carry weight. Whatever is in the hand now stays in the inventory, so it
+    /// frees nothing; armor that's already worn is about to come off, so it
+    /// always passes.
+    fn carry_allows_equip(&mut self, item: &HudItem, which: &Action) -> bool {
+        let kind = item.kind();
+        if kind.is_armor() {
+            if getEquippedItems().items.contains(&item.form_string()) {
+                return true;
+            }
+            return self.carry_allows(std::slice::from_ref(item));
+        }
+        if kind.is_magic() || !(kind.left_hand_ok() || kind.right_hand_ok()) {
+            return true;
+        }
+
+        let held = match *which {
+            Action::Left => specEquippedLeft(),
+            Action::Right => specEquippedRight(),
+            _ => return true,
+        };
+        if held == item.form_string() {
+            return true;
+        }
+        self.carry_allows(std::slice::from_ref(item))
+    }
+
+    /// Would this change put the player over their carry weight? If so, say
+    /// which item did it. Whether we then refuse or go ahead anyway is up to
+    /// the player's settings. Returns true if the equip should go ahead.
+    fn carry_allows(&mut self, going_on: &[HudItem]) -> bool {
+        let carry = getCarryWeight();
+        let LoadCheck::Overweight { item, total, max } = check_load(&carry, going_on) else {
+            return true;
+        };
+
+        let refuse = settings().refuse_overweight();
+        log::info!(
+            "Equipping '{item}' puts the player at {total} of {max} carry weight; refusing={refuse}"
+        );
+        let mut vars = HashMap::new();
+        vars.insert("item".to_string(), item);
+        vars.insert("total".to_string(), format!("{total:.0}"));
+        vars.insert("max".to_string(), format!("{max:.0}"));
+        let format = if refuse {
+            honk();
+            translated_key(FMT_OVERWEIGHT_REFUSED)
+        } else {
+            translated_key(FMT_OVERWEIGHT_WARNING)
+        };
+        notify(&strfmt(&format, &vars).unwrap_or_default());
+        !refuse
+    }
+
+    /// Can the player cast this right now? Anything that isn't a spell can be
+    /// "cast". Spells are checked with the game once, then remembered until a
+    /// spell-preparation mod tells us something changed.
+    fn spell_castable(&mut self, item: &HudItem) -> bool {
+        if !item.is_spell() {
+            return true;
+        }
+        self.castability
+            .is_castable(&item.form_string(), spell_castable_now)
+    }
+
+    /// Keep advancing this cycle past entries the player can't use right now:
+    /// anything no longer in the inventory, and unprepared spells if the
+    /// player wants those skipped. Returns None if nothing in the cycle is
+    /// usable.
+    fn skip_unusable(&mut self, which: &CycleSlot, first: Option<String>) -> Option<String> {
+        self.skip_unusable_or(which, first, |_| false)
+    }
+
+    /// Like `skip_unusable()`, also passing over entries `unwanted` says yes to.
+    fn skip_unusable_or<F>(
+        &mut self,
+        which: &CycleSlot,
+        first: Option<String>,
+        unwanted: F,
+    ) -> Option<String>
+    where
+        F: Fn(&HudItem) -> bool,
+    {
+        let skip_unprepared = settings().skip_unprepared_spells();
+        let cache = &mut self.cache;
+        let castability = &mut self.castability;
+        let found = self.cycles.advance_until(which, first?, |spec| {
+            if !player_has(spec) {
+                log::debug!("Passing over an entry the player doesn't have; spec={spec};");
+                return false;
+            }
+            let item = cache.get(&spec.to_string());
+            if unwanted(&item) {
+                log::debug!("Passing over an entry we can't pick here; spec={spec};");
+                return false;
+            }
+            !skip_unprepared
+                || !item.is_spell()
+                || castability.is_castable(spec, spell_castable_now)
+        });
+        if found.is_none() {
+            log::debug!("Nothing in the {which:?} cycle is usable right now.");
+        }
+        found
+    }
+
+    /// A spell-preparation mod says the player's prepared spells changed.
+    /// Ask again about every spell, and redraw the hands.
+    pub fn handle_spell_preparation_changed(&mut self) {
+        log::debug!("Spell preparation changed; forgetting which spells are castable.");
+        self.castability.invalidate();
+        for slot in [HudElement::Left, HudElement::Right] {
+            if let Some(item) = self.visual.showing(&slot).cloned() {
+                self.update_slot(slot, &item);
+            }
+        }
+    }
+
+    /// The player just equipped a bow or crossbow. If they want it, make sure
+    /// ammo that fits it is equipped too: the ammo already equipped if it fits,
+    /// then the ammo the HUD is showing, then whatever fitting ammo they have
+    /// the most of.
+    fn equip_ammo_for(&mut self, weapon: &HudItem, bolts: bool) {
+        if !settings().auto_equip_ammo() || !self.equipped.vitals.permit("equip ammo") {
+            return;
+        }
+
+        let fitting = getAmmoInventoryFor(bolts);
+        let equipped = specEquippedAmmo();
+        if fitting.contains(&equipped) {
+            return;
+        }
+
+        let shown = self
+            .visual
+            .visible
+            .get(&HudElement::Ammo)
+            .map(|xs| xs.form_string())
+            .filter(|spec| settings().cycle_ammo() && fitting.contains(spec));
+        let Some(spec) = shown.or_else(|| fitting.first().cloned()) else {
+            log::info!(
+                "No {} to go with '{}'.",
+                if bolts { "bolts" } else { "arrows" },
+                weapon.name()
+            );
+            honk();
+            let format = translated_key(FMT_NO_AMMO);
+            let mut vars = HashMap::new();
+            vars.insert(
+                "ammo".to_string(),
+                translated_key(if bolts { FMT_BOLTS } else { FMT_ARROWS }),
+            );
+            vars.insert("weapon".to_string(), weapon.name());
+            notify(&strfmt(&format, &vars).unwrap_or_default());
+            return;
+        };
+
+        let ammo = self.cache.get(&spec);
+        log::info!(
+            "Equipping ammo '{}' to go with '{}'.",
+            ammo.name(),
+            weapon.name()
+        );
+        cxx::let_cxx_string!(form_spec = spec);
+        equipAmmo(&form_spec);
+        self.update_slot(HudElement::Ammo, &ammo);
+    }
+
+    /// The ammo the HUD was showing just ran out. Move on to other ammo that
+    /// fits the bow or crossbow in hand, if the player has any.
+    /// A torch burns out by leaving the inventory. If the player still has one
+    /// of the same torch, and the one that went out was in their hand, light
+    /// the next one.
+    fn relight_torch(&mut self, form_spec: &String, new_count: u32) {
+        if let Some(candidate) = self.visual.visible.get_mut(&HudElement::Left) {
+            if candidate.form_string() == *form_spec {
+                candidate.set_count(new_count);
+                self.visual.touch(HudElement::Left);
+            }
+        }
+        if !settings().relight_torches()
+            || self.equipped.remembered_hand(&CycleSlot::Left) != form_spec.as_str()
+            || !specEquippedLeft().is_empty()
+        {
+            return;
+        }
+        if !self.equipped.vitals.permit("light a fresh torch") {
+            return;
+        }
+        let torch = self.cache.get(form_spec);
+        log::info!("A torch burned out; lighting another. {new_count} left.");
+        self.equip_item(&torch, Action::Left);
+    }
+
+    fn replace_spent_ammo(&mut self, spent: &str) {
+        let Some(bolts) = self
+            .visual
+            .showing(&HudElement::Right)
+            .and_then(|xs| xs.shoots_bolts())
+        else {
+            return;
+        };
+        if !self.equipped.vitals.permit("replace spent ammo") {
+            return;
+        }
+        let Some(next) = getAmmoInventoryFor(bolts)
+            .into_iter()
+            .find(|xs| xs.as_str() != spent)
+        else {
+            log::info!("Out of ammo, with nothing to move on to.");
+            return;
+        };
+        let ammo = self.cache.get(&next);
+        log::info!("Out of that ammo; moving on to '{}'.", ammo.name());
+        cxx::let_cxx_string!(form_spec = next);
+        equipAmmo(&form_spec);
+        self.update_slot(HudElement::Ammo, &ammo);
+    }
+
     /// We get this event when the player is using CGO and has switched grip mode.
     pub fn handle_grip_change(&mut self, using_alt_grip: bool) {
         // Record this in a local var so we can respect it when we equip new things.
         log::info!("CGO grip change observed; alt-grip={using_alt_grip};");
-        self.cgo_alt_grip = using_alt_grip;
+        self.equipped.cgo_alt_grip = using_alt_grip;
 
         let spec = specEquippedRight();
         let item = self.cache.get(&spec);
@@ -989,7 +2194,7 @@ impl Controller {
             // This is weird stuff. CGO will make you hold this in two hands.
             let left = specEquippedLeft();
             self.switch_to_two_hander();
-            self.left_hand_cached = left;
+            self.equipped.left_hand_cached = left;
         } else if item.two_handed() {
             // Alt grip is now OFF and we are holding what is normally a two-hander.
             self.switch_to_two_hander();
@@ -1007,29 +2212,29 @@ impl Controller {
 
     /// Shared logic for handling the switch to a one-handed weapon from a two-hander.
     fn switch_to_one_hander(&mut self) {
-        if !self.left_hand_cached.is_empty() {
+        if !self.equipped.left_hand_cached.is_empty() {
             let unarmed = HudItem::make_unarmed_proxy();
-            let prev_left = self.left_hand_cached.clone();
+            let prev_left = self.equipped.left_hand_cached.clone();
             log::trace!(
                 "re-requipping what we previously had in the LEFT hand; spec={};",
                 prev_left
             );
             if prev_left == unarmed.form_string() {
                 unequipSlot(Action::Left);
-                self.update_slot(HudElement::Left, &unarmed);
+                self.visual.show_empty_hand(HudElement::Left);
             } else {
                 let item = self.cache.get(&prev_left);
                 self.update_slot(HudElement::Left, &item);
                 cxx::let_cxx_string!(form_spec = prev_left.clone());
-                cxx::let_cxx_string!(name = item.name());
+                cxx::let_cxx_string!(name = item.raw_name());
                 reequipHand(Action::Left, &form_spec, &name);
             }
         } else if let Some(left_next) = self.cycles.get_top(&CycleSlot::Left) {
             let item = self.cache.get(&left_next);
-            self.left_hand_cached = left_next.clone();
+            self.equipped.left_hand_cached = left_next.clone();
             self.update_slot(HudElement::Left, &item);
             cxx::let_cxx_string!(form_spec = left_next);
-            cxx::let_cxx_string!(name = item.name());
+            cxx::let_cxx_string!(name = item.raw_name());
             reequipHand(Action::Left, &form_spec, &name);
         }
     }
@@ -1038,7 +2243,67 @@ impl Controller {
     /// not. If you're NOT using CGO, this is the same as asking if an item is
     /// two-handed or not. If you are using CGO, it's more complicated.
     fn treat_as_two_handed(&self, item: &HudItem) -> bool {
-        (self.cgo_alt_grip && !item.two_handed()) || (!self.cgo_alt_grip && item.two_handed())
+        let two_handed = self.equipped.grips.two_handed(&CycleSlot::Right, item);
+        (self.equipped.cgo_alt_grip && !two_handed) || (!self.equipped.cgo_alt_grip && two_handed)
+    }
+
+    /// A grip-switching mod says the weapon in this hand is now held in one
+    /// or both hands. It's the same weapon, so no cycle moves; we remember
+    /// the grip and block or unblock the left hand in the HUD.
+    pub fn handle_hand_grip_change(&mut self, hand: Action, two_handed: bool) {
+        let slot = match hand {
+            Action::Left => CycleSlot::Left,
+            Action::Right => CycleSlot::Right,
+            _ => return,
+        };
+        let hud = HudElement::from(hand);
+        let Some(held) = self.visual.showing(&hud).cloned() else {
+            return;
+        };
+        if !held.is_weapon()
+            || !self
+                .equipped
+                .grips
+                .set(&slot, &held.form_string(), two_handed)
+        {
+            return;
+        }
+        log::info!(
+            "Grip changed; hand={slot}; two-handed={two_handed}; weapon='{}';",
+            held.name()
+        );
+        self.update_slot(hud, &held);
+
+        if matches!(slot, CycleSlot::Right) {
+            self.equipped.two_hander_equipped = two_handed;
+            if two_handed {
+                let left = equipped_spec(&CycleSlot::Left);
+                if !left.is_empty() && left != held.form_string() {
+                    self.equipped.left_hand_cached = left;
+                }
+                self.update_slot(HudElement::Left, &HudItem::default());
+            } else if !self.equipped.left_hand_cached.is_empty() {
+                let left = self.cache.get(&self.equipped.left_hand_cached.clone());
+                self.update_slot(HudElement::Left, &left);
+            }
+        }
+    }
+
+    /// Show an item the player equipped without our help, say from the
+    /// favorites menu or the inventory, and spin the slot's cycle around to it.
+    /// If the item isn't in the cycle, the slot still shows it but the cycle
+    /// stays where it was. Returns true if the slot changed.
+    fn follow_equip(&mut self, slot: &CycleSlot, item: &HudItem) -> bool {
+        let changed = self.update_slot(HudElement::from(slot), item);
+        if changed
+            && self
+                .equipped
+                .rotations
+                .allow(slot, &item.form_string(), Instant::now())
+        {
+            self.cycles.set_top(slot, &item.form_string());
+        }
+        changed
     }
 
     /// An item that was equipped is no longer equipped. Empty out a HUD slot if
@@ -1052,33 +2317,56 @@ impl Controller {
         // Here we only care about updating the HUD. We let the rest fall where may.
         // We ONLY ever empty a visible slot here.
         log::trace!("item UNequipped; right={equipped_right}; left={equipped_left}; unequipped_spec={unequipped_spec};");
-        let right_vis = self.visible.get(&HudElement::Right);
-        let left_vis = self.visible.get(&HudElement::Left);
+        let right_vis = self.visual.showing(&HudElement::Right);
+        let left_vis = self.visual.showing(&HudElement::Left);
         let empty = HudItem::default();
         let item = self.cache.get(unequipped_spec);
 
         match item.kind() {
             BaseType::Ammo(_) => return self.update_slot(HudElement::Ammo, &empty),
-            BaseType::Light(_) => return self.update_slot(HudElement::Left, &empty),
+            BaseType::Light(_) => return self.hand_emptied(&CycleSlot::Left, equipped_left),
             BaseType::Power(_) => return self.update_slot(HudElement::Power, &empty),
             BaseType::Shout(_) => return self.update_slot(HudElement::Power, &empty),
             _ => {}
         }
 
         // This works for scrolls, spells, weapons, torches, and shields.
+        // We leave the cycles alone, so the next press picks up where it was.
         if let Some(visible) = right_vis {
             if (equipped_right != unequipped_spec) && *unequipped_spec == visible.form_string() {
-                return self.update_slot(HudElement::Right, &empty);
+                return self.hand_emptied(&CycleSlot::Right, equipped_right);
             }
         }
         if let Some(visible) = left_vis {
             if (equipped_left != unequipped_spec) && *unequipped_spec == visible.form_string() {
-                return self.update_slot(HudElement::Left, &empty);
+                return self.hand_emptied(&CycleSlot::Left, equipped_left);
             }
         }
         false
     }
 
+    /// Something left this hand. If the hand now holds nothing (unarmed, or a
+    /// bound weapon expired), it's empty. If something else is already in it,
+    /// that item's equip event will fill in the slot, so until then it's blank.
+    /// We leave the cycle alone, so the next press picks up where it was.
+    fn hand_emptied(&mut self, hand: &CycleSlot, now_in_hand: &str) -> bool {
+        if now_in_hand.is_empty() || now_in_hand == HudItem::make_unarmed_proxy().form_string() {
+            self.show_empty_hand(hand)
+        } else {
+            self.update_slot(HudElement::from(hand), &HudItem::default())
+        }
+    }
+
+    /// Show this hand as holding nothing, and tell the player it's unarmed if
+    /// it wasn't empty already.
+    fn show_empty_hand(&mut self, hand: &CycleSlot) -> bool {
+        let newly_empty = self.visual.show_empty_hand(HudElement::from(hand));
+        if newly_empty {
+            notify(&translated_key(MSG_UNARMED));
+        }
+        newly_empty
+    }
+
     /// The game informs us that our equipment has changed. Update.
     ///
     /// The item we're handed was either equipped or UNequipped. There are some
@@ -1092,6 +2380,42 @@ impl Controller {
         equipped_right: &String,
         equipped_left: &String,
     ) -> bool {
+        let shown = |slot: HudElement| {
+            self.visual
+                .visible
+                .get(&slot)
+                .map(|xs| xs.form_string())
+                .unwrap_or_default()
+        };
+        if is_flurry_noise(
+            form_spec,
+            &shown(HudElement::Right),
+            &shown(HudElement::Left),
+            isPlayerAttacking(),
+        ) {
+            log::trace!("Ignoring equip event mid-attack for an item already in hand; equipped={equipped}; form_spec={form_spec};");
+            return false;
+        }
+        let event = DeferredEvent::Equip {
+            equipped,
+            form_spec: form_spec.clone(),
+            right: equipped_right.clone(),
+            left: equipped_left.clone(),
+        };
+        if self.defer_during_load(event) {
+            return false;
+        }
+        self.process_item_equipped(equipped, form_spec, equipped_right, equipped_left)
+    }
+
+    fn process_item_equipped(
+        &mut self,
+        equipped: bool,
+        form_spec: &String,
+        equipped_right: &String,
+        equipped_left: &String,
+    ) -> bool {
+        self.confirm_slot_contents(equipped, form_spec, equipped_right, equipped_left);
         if !equipped {
             return self.handle_item_unequipped(form_spec, equipped_right, equipped_left);
         }
@@ -1113,8 +2437,11 @@ impl Controller {
             item.icon()
         );
 
+        self.cycles.mark_used(form_spec, game_day());
+        self.sort_cycles_by_usage();
+
         if item.is_ammo() {
-            if let Some(visible) = self.visible.get(&HudElement::Ammo) {
+            if let Some(visible) = self.visual.showing(&HudElement::Ammo) {
                 if visible.form_string() != *form_spec {
                     self.update_slot(HudElement::Ammo, &item);
                     return true;
@@ -1133,7 +2460,7 @@ impl Controller {
         }
 
         if item.is_power() {
-            if let Some(visible) = self.visible.get(&HudElement::Power) {
+            if let Some(visible) = self.visual.showing(&HudElement::Power) {
                 if visible.form_string() != *form_spec {
                     self.update_slot(HudElement::Power, &item);
                     self.cycles.set_top(&CycleSlot::Power, &item.form_string());
@@ -1143,6 +2470,7 @@ impl Controller {
                 }
             } else {
                 self.update_slot(HudElement::Power, &item);
+                self.cycles.set_top(&CycleSlot::Power, &item.form_string());
                 return true;
             }
         }
@@ -1151,6 +2479,13 @@ impl Controller {
             return false;
         }
 
+        if right {
+            self.equipped.history.record(&CycleSlot::Right, form_spec);
+        }
+        if left {
+            self.equipped.history.record(&CycleSlot::Left, form_spec);
+        }
+
         // ----------
         // The hard part starts. Earlier, we did our best to set up the HUD to
         // show what we want in each hand. So we look at the item equipped: does
@@ -1164,24 +2499,21 @@ impl Controller {
         // end-of-the-world type stuff.
 
         let treat_as_two_hander = self.treat_as_two_handed(&item);
-        let switching = item.two_handed() != self.two_hander_equipped;
+        let two_handed = self.equipped.grips.two_handed(&CycleSlot::Right, &item);
+        let switching = two_handed != self.equipped.two_hander_equipped;
         log::trace!("weapon grip normally={}; alt-grip={}; we are treating it like: 2-hander={treat_as_two_hander}; switching={switching};",
-            item.two_handed(), self.cgo_alt_grip);
-        self.two_hander_equipped = item.two_handed();
-
-        if treat_as_two_hander && right {
-            let changed = self.update_slot(HudElement::Right, &item);
-            if changed {
-                // Change was out of band. We need to react by spinning the cycle around if possible.
-                self.cycles.set_top(&CycleSlot::Right, &item.form_string());
+            item.two_handed(), self.equipped.cgo_alt_grip);
+        self.equipped.two_hander_equipped = two_handed;
+
+        if treat_as_two_hander {
+            if !right {
+                // The game has only ever reported two-handers against the right
+                // hand, but they occupy both hands no matter what it says.
+                log::debug!("treat_as_two_hander + left detected; item={item}");
             }
+            let changed = self.follow_equip(&CycleSlot::Right, &item);
             self.update_slot(HudElement::Left, &HudItem::default());
             return changed;
-        } else if treat_as_two_hander && left {
-            log::debug!("treat_as_two_hander + left detected; item={item}");
-            // TODO The left hud slot should be cleared and the left hand unequipped.
-            // but I'm also not sure we ever get here.
-            return false;
         }
 
         // It's a one-hander (effectively). Does it match an earlier decision?
@@ -1193,15 +2525,17 @@ impl Controller {
         //     item.form_string(),
         //     rightie,
         //     leftie,
-        //     self.two_hander_equipped,
+        //     self.equipped.two_hander_equipped,
         //     item.two_handed(),
         //     item.name(),
         // );
         let leftvis = self
+            .visual
             .visible
             .get(&HudElement::Left)
             .map_or("".to_string(), |xs| xs.form_string());
         let rightvis = self
+            .visual
             .visible
             .get(&HudElement::Right)
             .map_or("".to_string(), |xs| xs.form_string());
@@ -1210,11 +2544,11 @@ impl Controller {
         let left_unexpected = leftvis != leftie;
 
         if right && right_unexpected {
-            self.right_hand_cached = item.form_string().clone();
-            self.update_slot(HudElement::Right, &item);
+            self.equipped.right_hand_cached = item.form_string().clone();
+            self.follow_equip(&CycleSlot::Right, &item);
         } else if left && left_unexpected {
-            self.left_hand_cached = item.form_string().clone();
-            self.update_slot(HudElement::Left, &item);
+            self.equipped.left_hand_cached = item.form_string().clone();
+            self.follow_equip(&CycleSlot::Left, &item);
         }
 
         // If the player is now a werewolf or a vampire, we do not
@@ -1240,27 +2574,27 @@ impl Controller {
 
         if left {
             // The item is effectively a one-hander, and it's now in our left hand.
-            if !self.right_hand_cached.is_empty() {
-                let prev_right = self.right_hand_cached.clone();
+            if !self.equipped.right_hand_cached.is_empty() {
+                let prev_right = self.equipped.right_hand_cached.clone();
                 log::debug!(
                     "re-requipping what we previously had in the right hand; spec={};",
                     prev_right
                 );
                 if prev_right == unarmed.form_string() {
                     unequipSlot(Action::Right);
-                    self.update_slot(HudElement::Right, &unarmed);
+                    self.visual.show_empty_hand(HudElement::Right);
                 } else {
                     let item = self.cache.get(&prev_right);
                     self.update_slot(HudElement::Right, &item);
                     cxx::let_cxx_string!(form_spec = prev_right);
-                    cxx::let_cxx_string!(name = item.name());
+                    cxx::let_cxx_string!(name = item.raw_name());
                     reequipHand(Action::Right, &form_spec, &name);
                 }
             } else if let Some(right_next) = self.cycles.get_top(&CycleSlot::Right) {
-                self.right_hand_cached = right_next.clone();
+                self.equipped.right_hand_cached = right_next.clone();
                 let item = self.cache.get(&right_next);
                 cxx::let_cxx_string!(form_spec = right_next);
-                cxx::let_cxx_string!(name = item.name());
+                cxx::let_cxx_string!(name = item.raw_name());
                 reequipHand(Action::Right, &form_spec, &name);
                 self.update_slot(HudElement::Right, &item);
             }
@@ -1269,15 +2603,337 @@ impl Controller {
         left_unexpected || right_unexpected
     }
 
-    /// Get the item equipped in a specific slot.
-    /// Called by the HUD rendering loop in the ImGui code.
-    pub fn entry_to_show_in_slot(&self, slot: HudElement) -> Box<HudItem> {
-        let Some(candidate) = self.visible.get(&slot) else {
-            // log::debug!("nothing to draw in slot {slot:?}");
-            return Box::<HudItem>::default();
-        };
+    /// Equip events are the only word on what's really in a slot; everything
+    /// else we show is what we expect the game to do. Note what this event says
+    /// is in the slots it touches, so layouts can time changes from it.
+    fn confirm_slot_contents(
+        &mut self,
+        equipped: bool,
+        form_spec: &String,
+        equipped_right: &str,
+        equipped_left: &str,
+    ) {
+        let now = Instant::now();
+        let item = self.cache.get(form_spec);
+        let spec = if equipped { form_spec.as_str() } else { "" };
+        if item.is_ammo() {
+            self.equipped.slot_ages.confirm(HudElement::Ammo, spec, now);
+        } else if item.is_power() {
+            self.equipped
+                .slot_ages
+                .confirm(HudElement::Power, spec, now);
+        } else {
+            self.equipped
+                .confirm_hands(equipped_right, equipped_left, now);
+        }
+    }
+
+    /// Get the item equipped in a specific slot.
+    /// Called by the HUD rendering loop in the ImGui code, which keeps what it
+    /// gets until `huditem_changed()` says the slot changed. Anything that
+    /// changes how a slot draws has to mark the slot as changed.
+    pub fn entry_to_show_in_slot(&self, slot: HudElement) -> Box<HudItem> {
+        let Some(candidate) = self.visual.showing(&slot) else {
+            // log::debug!("nothing to draw in slot {slot:?}");
+            return Box::<HudItem>::default();
+        };
+
+        let mut item = candidate.clone();
+        let now = Instant::now();
+        let age = self.equipped.slot_ages.age_millis(&slot, now);
+        item.set_change_age((age != NEVER_CHANGED).then_some(age));
+        if slot == HudElement::Utility {
+            if let Some(hint) = self.visual.lock_hints.active(now) {
+                let level = translated_key(format!("$SoulsyHUD_lock_{}", hint.level).as_str());
+                item.set_lock_hint(Some((hint.lockpicks, level)));
+            }
+        }
+        Box::new(item)
+    }
+
+    /// How much of this slot's hotbar flash is left, from 1.0 down to 0.0.
+    pub fn hotbar_flash(&self, slot: Action, now: Instant) -> f32 {
+        self.visual.flash_left(&HudElement::from(slot), now)
+    }
+
+    /// Has the item in this slot changed since the renderer last asked?
+    pub fn huditem_changed(&mut self, slot: HudElement) -> bool {
+        self.visual.take_change(&slot)
+    }
+
+    /// The game opened or closed a menu. Key events reaching gameplay are
+    /// ignored while one is open; menu hotkeys arrive through the menu hook.
+    pub fn handle_menu_changed(&mut self, name: &str, opening: bool) {
+        log::trace!("menu changed; name='{name}'; opening={opening};");
+        self.input.context.menu_changed(name, opening);
+        if opening {
+            self.cancel_equip_timers(name);
+        }
+    }
+
+    /// The player got staggered or knocked into ragdoll. Whatever they were
+    /// about to equip would land at a bad moment, so it doesn't land at all.
+    pub fn handle_player_staggered(&mut self) {
+        self.cancel_equip_timers("stagger");
+    }
+
+    /// Drop every pending equip. The HUD keeps showing what the player cycled
+    /// to, and nothing comes back when the interruption ends; the player
+    /// cycles or waits out a fresh delay if they still want it.
+    fn cancel_equip_timers(&mut self, why: &str) {
+        let cancelled = cancel_all_timers();
+        if !cancelled.is_empty() {
+            log::info!("Cancelled pending equips; why='{why}'; cancelled={cancelled:?};");
+        }
+    }
+
+    /// Everything we know that bears on what the player cycled to and why, as
+    /// one block of text for the log: the cycles, what's equipped and showing,
+    /// the equip timers waiting to fire, and the last few key events.
+    pub fn dump_state(&mut self) -> String {
+        let mut lines = vec!["SoulsyHUD controller state:".to_string()];
+        for which in [
+            CycleSlot::Power,
+            CycleSlot::Utility,
+            CycleSlot::Left,
+            CycleSlot::Right,
+        ] {
+            let names = self.cycles.names(&which, &mut self.cache);
+            let entries: Vec<String> = names
+                .iter()
+                .zip(self.cycles.formids(&which))
+                .map(|(name, spec)| format!("{name} <{spec}>"))
+                .collect();
+            lines.push(format!(
+                "{which} cycle, selection first: [{}]",
+                entries.join(", ")
+            ));
+        }
+        lines.push(format!(
+            "equipped: power='{}'; ammo='{}'; left='{}'; right='{}';",
+            specEquippedPower(),
+            specEquippedAmmo(),
+            specEquippedLeft(),
+            specEquippedRight()
+        ));
+        for slot in [
+            HudElement::Power,
+            HudElement::Utility,
+            HudElement::Left,
+            HudElement::Right,
+            HudElement::Ammo,
+        ] {
+            if let Some(item) = self.visual.showing(&slot) {
+                lines.push(format!(
+                    "showing in {slot:?}: {} <{}>",
+                    item.name(),
+                    item.form_string()
+                ));
+            }
+        }
+        lines.push(format!(
+            "equip timers running, and their slots highlighted: {:?}",
+            running_timers()
+        ));
+        lines.push("last key events, oldest first:".to_string());
+        lines.extend(self.input.recent.entries().map(|xs| format!("    {xs}")));
+        lines.join("\n")
+    }
+
+    /// The crosshair found a lock. If the player wants lock hints, count their
+    /// lockpicks and show the hint on the utility slot for a little while.
+    pub fn handle_crosshair_lock(&mut self, level: u8) {
+        if !settings().show_lock_hints() {
+            return;
+        }
+        let level = LockLevel::from(level);
+        let lockpicks = lockpickCount();
+        log::trace!("Crosshair on a lock; level={level}; lockpicks={lockpicks};");
+        self.visual
+            .lock_hints
+            .show(level, lockpicks, Instant::now());
+        self.visual.touch(HudElement::Utility);
+    }
 
-        Box::new(candidate.clone()) // this clone is in a hot path
+    /// The renderer noticed the player dying, going into bleedout, or getting
+    /// back up. Going down cancels any equips we were about to make.
+    pub fn handle_vitals_changed(&mut self, alive: bool, bleeding_out: bool) {
+        match self.equipped.vitals.update(alive, bleeding_out) {
+            VitalsChange::Incapacitated => {
+                log::info!(
+                    "Player is down; refusing equips. alive={alive}; bleeding_out={bleeding_out};"
+                );
+                self.cancel_equip_timers("player down");
+            }
+            VitalsChange::Recovered => {
+                log::info!("Player is back up; equips allowed again.");
+            }
+            VitalsChange::Unchanged => {}
+        }
+    }
+
+    /// The game took the player's controls away or gave them back. Losing
+    /// them cancels pending equips. When they come back, whatever the game
+    /// equipped or took away in the meantime is read back from the game.
+    pub fn handle_controls_changed(&mut self, controls: bool, weapon_controls: bool) {
+        let was_disabled = self.equipped.vitals.controls_disabled();
+        let change = self
+            .equipped
+            .vitals
+            .update_controls(controls, weapon_controls);
+        let now_disabled = self.equipped.vitals.controls_disabled();
+        if now_disabled && !was_disabled {
+            log::info!(
+                "Player controls disabled; refusing equips. controls={controls}; weapon_controls={weapon_controls};"
+            );
+            if matches!(change, VitalsChange::Incapacitated) {
+                self.cancel_equip_timers("controls disabled");
+            }
+        } else if was_disabled && !now_disabled {
+            log::info!("Player controls are back; reading what's equipped from the game.");
+            self.update_hud();
+        }
+    }
+
+    /// The crosshair moved off whatever it was on.
+    pub fn handle_crosshair_clear(&mut self) {
+        if self.visual.lock_hints.clear() {
+            self.visual.touch(HudElement::Utility);
+        }
+    }
+
+    /// Open the cycle browser on this slot's cycle, replacing any browser
+    /// already open. Normal cycling for the slot waits until it closes.
+    pub fn open_cycle_browser(&mut self, slot: HudElement) -> bool {
+        let which = match slot {
+            HudElement::Power => CycleSlot::Power,
+            HudElement::Utility => CycleSlot::Utility,
+            HudElement::Left => CycleSlot::Left,
+            HudElement::Right => CycleSlot::Right,
+            _ => {
+                log::debug!("There's no cycle to browse for the {slot:?} slot.");
+                return false;
+            }
+        };
+        let current = self.visual.showing(&slot).map(|item| item.form_string());
+        let Some(browser) = CycleBrowser::open(
+            which.clone(),
+            self.cycles.formids(&which),
+            current.as_deref(),
+            Instant::now(),
+        ) else {
+            log::debug!("The {which} cycle is empty; nothing to browse.");
+            honk();
+            return false;
+        };
+
+        // A pending equip would land while the player is still choosing.
+        stopTimer(Action::from(which.clone()));
+        showBriefly();
+        log::debug!("Browsing the {which} cycle.");
+        self.visual.browser = Some(browser);
+        true
+    }
+
+    /// What the renderer should draw for the cycle browser. A browser left
+    /// alone too long closes here.
+    pub fn cycle_browser_view(&mut self) -> CycleBrowserView {
+        if self
+            .visual
+            .browser
+            .as_ref()
+            .is_some_and(|browser| browser.expired(Instant::now()))
+        {
+            log::debug!("The cycle browser timed out.");
+            self.visual.browser = None;
+        }
+        let Some(browser) = self.visual.browser.as_ref() else {
+            return CycleBrowserView::default();
+        };
+
+        let page = browser.page();
+        let selected = browser.selected() - page * super::browser::BROWSER_PAGE_SIZE;
+        let slot = HudElement::from(browser.slot());
+        let specs = browser.page_entries().to_vec();
+        let page_count = browser.page_count();
+        let total = browser.entries().len();
+        let names = specs
+            .iter()
+            .map(|spec| self.cache.get(spec).name())
+            .collect();
+        let descriptions = specs
+            .iter()
+            .map(|spec| self.cache.get(spec).description())
+            .collect();
+        CycleBrowserView {
+            open: true,
+            slot,
+            names,
+            descriptions,
+            selected: selected as u32,
+            page: page as u32,
+            page_count: page_count as u32,
+            total: total as u32,
+        }
+    }
+
+    /// Give the browser any key it wants while it's open. Returns nothing if
+    /// the key should be handled as usual.
+    fn route_to_browser(&mut self, key: u32, state: &KeyState) -> Option<KeyEventResponse> {
+        let now = Instant::now();
+        let browser = self.visual.browser.as_mut()?;
+        if browser.expired(now) {
+            self.visual.browser = None;
+            return None;
+        }
+
+        let options = settings();
+        let cycle_key = Hotkey::from(browser.slot()).key_for();
+        let action = browse_action_for(key, cycle_key as u32, options.activate());
+        if action == Action::None {
+            return None;
+        }
+        // Keep key state current, so the cycle key doesn't look held afterwards.
+        self.input.note_state(key, state.clone());
+        // Swallow the press; act on the release.
+        if *state != KeyState::Up {
+            return Some(KeyEventResponse::handled());
+        }
+
+        let which = browser.slot().clone();
+        match browser.handle(&action, now) {
+            BrowserOutcome::Browsing => Some(KeyEventResponse::handled()),
+            BrowserOutcome::Cancelled => {
+                log::debug!("Closed the {which} cycle browser.");
+                self.visual.browser = None;
+                Some(KeyEventResponse::handled())
+            }
+            BrowserOutcome::Chosen(form_spec) => {
+                self.visual.browser = None;
+                Some(self.choose_from_browser(which, form_spec))
+            }
+        }
+    }
+
+    /// The player picked an entry in the browser. It goes to the top of its
+    /// cycle and gets equipped or used, just as if they'd cycled to it.
+    fn choose_from_browser(&mut self, which: CycleSlot, form_spec: String) -> KeyEventResponse {
+        self.cycles.set_top(&which, &form_spec);
+        let item = self.cache.get_with_refresh(&form_spec);
+        log::info!("Picked '{}' from the {which} cycle browser.", item.name());
+        self.update_slot(HudElement::from(&which), &item);
+        if matches!(which, CycleSlot::Utility) {
+            self.use_item_now(&item)
+        } else {
+            self.timer_expired(Action::from(which.clone()));
+            KeyEventResponse {
+                handled: true,
+                outcome: KeyOutcome::ItemEquipped,
+                slot: HudElement::from(&which),
+                item_name: item.name(),
+                ..Default::default()
+            }
+        }
     }
 
     /// Call when loading or otherwise needing to reinitialize the HUD.
@@ -1286,14 +2942,14 @@ impl Controller {
     /// out of band, e.g., by using a menu, and only then if we screwed
     /// up an equip event. So don't call it except at initialization.
     fn update_hud(&mut self) {
-        self.cgo_alt_grip = useCGOAltGrip();
+        self.equipped.cgo_alt_grip = useCGOAltGrip();
         let right_spec = specEquippedRight();
         let right_entry = self.cache.get(&right_spec);
 
         let treat_right_as_2h = self.treat_as_two_handed(&right_entry);
         let right_changed = self.update_slot(HudElement::Right, &right_entry);
         if !treat_right_as_2h {
-            self.right_hand_cached = right_entry.form_string();
+            self.equipped.right_hand_cached = right_entry.form_string();
         }
 
         let left_spec = specEquippedLeft();
@@ -1301,17 +2957,17 @@ impl Controller {
         let treat_left_as_2h = self.treat_as_two_handed(&left_entry);
 
         let left_unexpected = if !treat_left_as_2h {
-            self.left_hand_cached = left_entry.form_string();
+            self.equipped.left_hand_cached = left_entry.form_string();
             self.update_slot(HudElement::Left, &left_entry)
         } else {
             // Two-handed item in the left hand, which means we show it as empty.
-            self.left_hand_cached = self
+            self.equipped.left_hand_cached = self
                 .cycles
                 .get_top(&CycleSlot::Left)
                 .map_or("".to_string(), |xs| xs);
             self.update_slot(HudElement::Left, &HudItem::default())
         };
-        self.two_hander_equipped = right_entry.two_handed(); // same item will be in both hands
+        self.equipped.two_hander_equipped = right_entry.two_handed(); // same item will be in both hands
 
         let power_form = specEquippedPower();
         let power = self.cache.get(&power_form);
@@ -1354,23 +3010,30 @@ impl Controller {
     /// Update the displayed slot for the specified HUD element.
     fn update_slot(&mut self, slot: HudElement, new_item: &HudItem) -> bool {
         log::trace!("updating hud slot '{slot}'; visible: {new_item}");
-        if let Some(replaced) = self.visible.insert(slot, new_item.clone()) {
-            replaced != *new_item
-        } else {
-            false
+        let mut new_item = new_item.clone();
+        if matches!(slot, HudElement::Utility) {
+            let uses_left = daily_limit(&new_item)
+                .map(|limit| self.cycles.daily_uses().remaining(&limit, game_day()));
+            new_item.set_uses_left(uses_left);
         }
+        if matches!(slot, HudElement::Left | HudElement::Right) {
+            let locked = !self.spell_castable(&new_item);
+            new_item.set_locked(locked);
+            let hand = if matches!(slot, HudElement::Left) {
+                CycleSlot::Left
+            } else {
+                CycleSlot::Right
+            };
+            new_item.set_grip(self.equipped.grips.chosen(&hand, &new_item.form_string()));
+        }
+        self.visual.show(slot, new_item)
     }
 
     /// The player has toggled a favorite. If our settings instruct us to link favorites
     /// to cycle entries, we do so now. We do our best to assign items to the cycles where
     /// they make the most sense. 90% of the work is contructing a useful feedback message
     /// for the player about what we did.
-    pub fn handle_favorite_event(
-        &mut self,
-        _button: &ButtonEvent,
-        is_favorite: bool,
-        item: HudItem,
-    ) {
+    pub fn handle_favorite_event(&mut self, is_favorite: bool, item: HudItem) {
         if !settings().link_to_favorites() {
             return;
         }
@@ -1385,26 +3048,26 @@ impl Controller {
             vars.insert("item".to_string(), item.name());
 
             let maybe_cycle = if item.is_utility() {
-                if self.cycles.remove_item(CycleSlot::Utility, &item) {
+                if self.remove_from_cycle(CycleSlot::Utility, &item) {
                     Some(translated_key(FMT_ITEM_UTILITIES_CYCLE))
                 } else {
                     None
                 }
             } else if item.is_power() {
-                if self.cycles.remove_item(CycleSlot::Power, &item) {
+                if self.remove_from_cycle(CycleSlot::Power, &item) {
                     Some(translated_key(FMT_ITEM_POWERS_CYCLE))
                 } else {
                     None
                 }
             } else if item.two_handed() {
-                if self.cycles.remove_item(CycleSlot::Right, &item) {
+                if self.remove_from_cycle(CycleSlot::Right, &item) {
                     Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
                 } else {
                     None
                 }
             } else {
-                let removed_right = self.cycles.remove_item(CycleSlot::Right, &item);
-                let removed_left = self.cycles.remove_item(CycleSlot::Left, &item);
+                let removed_right = self.remove_from_cycle(CycleSlot::Right, &item);
+                let removed_left = self.remove_from_cycle(CycleSlot::Left, &item);
                 if removed_right && removed_left {
                     Some(translated_key(FMT_ITEM_BOTH_HANDS))
                 } else if removed_left {
@@ -1427,47 +3090,7 @@ impl Controller {
             let mut vars = HashMap::new();
             vars.insert("item".to_string(), item.name());
 
-            let maybe_cycle = if item.is_utility() {
-                if self.cycles.add_item(CycleSlot::Utility, &item) {
-                    Some(translated_key(FMT_ITEM_UTILITIES_CYCLE))
-                } else {
-                    None
-                }
-            } else if item.is_power() {
-                if self.cycles.add_item(CycleSlot::Power, &item) {
-                    Some(translated_key(FMT_ITEM_POWERS_CYCLE))
-                } else {
-                    None
-                }
-            } else if item.two_handed() || matches!(item.kind(), BaseType::Scroll(_)) {
-                if self.cycles.add_item(CycleSlot::Right, &item) {
-                    Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
-                } else {
-                    None
-                }
-            } else if item.is_spell() || (item.right_hand_ok() && item.count() > 1) {
-                let added_right = self.cycles.add_item(CycleSlot::Right, &item);
-                let added_left = self.cycles.add_item(CycleSlot::Left, &item);
-                if added_right && added_left {
-                    Some(translated_key(FMT_ITEM_BOTH_HANDS))
-                } else if added_left {
-                    Some(translated_key(FMT_ITEM_LEFT_CYCLE))
-                } else if added_right {
-                    Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
-                } else {
-                    None
-                }
-            } else if item.right_hand_ok() {
-                if self.cycles.add_item(CycleSlot::Right, &item) {
-                    Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
-                } else {
-                    None
-                }
-            } else if self.cycles.add_item(CycleSlot::Left, &item) {
-                Some(translated_key(FMT_ITEM_LEFT_CYCLE))
-            } else {
-                None
-            };
+            let maybe_cycle = self.add_favorite(&item);
             if let Some(cycle) = maybe_cycle {
                 vars.insert("cycle".to_string(), cycle);
                 strfmt(&format, &vars).ok()
@@ -1484,12 +3107,358 @@ impl Controller {
         }
     }
 
-    pub fn handle_menu_event(&mut self, key: u32, button: &ButtonEvent) -> bool {
+    /// Add a favorited item to the cycles it belongs in: utility items and
+    /// powers to their own cycles, two-handers and scrolls to the right hand,
+    /// and things either hand can hold to both. Returns the translated name of
+    /// the cycle it went into, if any.
+    fn add_favorite(&mut self, item: &HudItem) -> Option<String> {
+        if item.is_utility() {
+            if self.add_to_cycle(CycleSlot::Utility, item) {
+                Some(translated_key(FMT_ITEM_UTILITIES_CYCLE))
+            } else {
+                None
+            }
+        } else if item.is_power() {
+            if self.add_to_cycle(CycleSlot::Power, item) {
+                Some(translated_key(FMT_ITEM_POWERS_CYCLE))
+            } else {
+                None
+            }
+        } else if item.two_handed() || matches!(item.kind(), BaseType::Scroll(_)) {
+            if self.add_to_cycle(CycleSlot::Right, item) {
+                Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
+            } else {
+                None
+            }
+        } else if item.is_spell() || (item.right_hand_ok() && item.count() > 1) {
+            let added_right = self.add_to_cycle(CycleSlot::Right, item);
+            let added_left = self.add_to_cycle(CycleSlot::Left, item);
+            if added_right && added_left {
+                Some(translated_key(FMT_ITEM_BOTH_HANDS))
+            } else if added_left {
+                Some(translated_key(FMT_ITEM_LEFT_CYCLE))
+            } else if added_right {
+                Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
+            } else {
+                None
+            }
+        } else if item.right_hand_ok() {
+            if self.add_to_cycle(CycleSlot::Right, item) {
+                Some(translated_key(FMT_ITEM_RIGHT_CYCLE))
+            } else {
+                None
+            }
+        } else if self.add_to_cycle(CycleSlot::Left, item) {
+            Some(translated_key(FMT_ITEM_LEFT_CYCLE))
+        } else {
+            None
+        }
+    }
+
+    /// Add everything the player has favorited to the cycles, as if each had
+    /// just been favorited. Items already in a cycle are left where they are.
+    /// Returns a message for the player.
+    pub fn import_favorites(&mut self) -> String {
+        let mut count = 0;
+        for form_spec in favoritedItems() {
+            let item = self.cache.get(&form_spec);
+            if matches!(item.kind(), BaseType::Empty) {
+                continue;
+            }
+            if self.add_favorite(&item).is_some() {
+                count += 1;
+            }
+        }
+        log::info!("Imported {count} favorites into the cycles.");
+        self.show_utility_top();
+
+        let format = translated_key(FMT_FAVORITES_IMPORTED);
+        let mut vars = HashMap::new();
+        vars.insert("count".to_string(), count.to_string());
+        strfmt(&format, &vars).unwrap_or_default()
+    }
+
+    /// Write the cycles to a toml file the player can edit or share. Tells the
+    /// player how it went.
+    pub fn export_cycles(&mut self, path: &str) -> bool {
+        let file = CycleFile::from_cycles(&self.cycles, &mut self.cache);
+        let written = file.write_to_file(path);
+        let format = if let Err(e) = &written {
+            log::warn!("Exporting cycles failed. {e:#}");
+            translated_key(FMT_CYCLES_NOT_EXPORTED)
+        } else {
+            log::info!("Exported cycles to {path}.");
+            translated_key(FMT_CYCLES_EXPORTED)
+        };
+        let mut vars = HashMap::new();
+        vars.insert("path".to_string(), path.to_string());
+        notify(&strfmt(&format, &vars).unwrap_or_default());
+        written.is_ok()
+    }
+
+    /// Read cycles from a toml file written by `export_cycles()` or by hand.
+    /// A file that can't be read changes nothing. Otherwise each entry goes
+    /// into its cycle if the game agrees it belongs there, either replacing
+    /// the cycles or adding to them. Tells the player how many entries made it.
+    pub fn import_cycles(&mut self, path: &str, replace: bool) -> bool {
+        let file = match CycleFile::read_from_file(path) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("Importing cycles failed. {e:#}");
+                let format = translated_key(FMT_CYCLES_NOT_IMPORTED);
+                let mut vars = HashMap::new();
+                vars.insert("path".to_string(), path.to_string());
+                notify(&strfmt(&format, &vars).unwrap_or_default());
+                return false;
+            }
+        };
+        let max_len = settings().max_cycle_length() as usize;
+        let cache = &mut self.cache;
+        let plan = file.plan_import(&self.cycles, replace, max_len, |spec| {
+            cache.get_with_refresh(&spec.to_string())
+        });
+        for (slot, specs) in plan.cycles {
+            self.cycles.set_cycle(&slot, specs);
+        }
+        // Earlier edits can't be undone one at a time across a bulk change.
+        self.undo.clear();
+        log::info!(
+            "Imported cycles from {path}; replace={replace}; accepted={}; rejected={};",
+            plan.accepted,
+            plan.rejected
+        );
+        self.update_hud();
+
+        let format = translated_key(FMT_CYCLES_IMPORTED);
+        let mut vars = HashMap::new();
+        vars.insert("accepted".to_string(), plan.accepted.to_string());
+        vars.insert("rejected".to_string(), plan.rejected.to_string());
+        notify(&strfmt(&format, &vars).unwrap_or_default());
+        true
+    }
+
+    /// Add an item to a cycle, remembering the edit so it can be undone.
+    fn add_to_cycle(&mut self, slot: CycleSlot, item: &HudItem) -> bool {
+        let added = self.cycles.add_item(slot.clone(), item);
+        if added {
+            self.undo.push(CycleEdit::Added {
+                slot,
+                form_spec: item.form_string(),
+            });
+        }
+        added
+    }
+
+    /// Remove an item from a cycle, remembering where it was so it can be undone.
+    fn remove_from_cycle(&mut self, slot: CycleSlot, item: &HudItem) -> bool {
+        let form_spec = item.form_string();
+        if self.cycles.position(&slot, &form_spec).is_none() {
+            return false;
+        }
+        let after = self.cycles.entry_before(&slot, &form_spec);
+        let removed = self.cycles.remove_item(slot.clone(), item);
+        if removed {
+            self.undo.push(CycleEdit::Removed {
+                slot,
+                form_spec,
+                after,
+            });
+        }
+        removed
+    }
+
+    /// Show whatever is now at the top of the utility cycle, after an edit
+    /// might have removed what was there.
+    fn show_utility_top(&mut self) {
+        if let Some(topmost) = self.cycles.get_top(&CycleSlot::Utility) {
+            let item = self.cache.get(&topmost);
+            self.update_slot(HudElement::Utility, &item);
+        } else {
+            self.update_slot(HudElement::Utility, &HudItem::default());
+        }
+    }
+
+    /// Cycle entries that haven't been used in more than `days` game days and
+    /// that we're allowed to remove. Equipped and favorited items stay, and so
+    /// do the grouped-potion proxies.
+    fn prune_candidates(&mut self, days: u32) -> Vec<(CycleSlot, String)> {
+        let today = game_day();
+        self.cycles.reconcile_usage_dates(today);
+        let equipped = [
+            specEquippedLeft(),
+            specEquippedRight(),
+            specEquippedPower(),
+            specEquippedAmmo(),
+        ];
+        self.cycles.prune_candidates(days, today, |spec| {
+            if spec.ends_with("_proxy") || equipped.iter().any(|xs| xs == spec) {
+                return true;
+            }
+            cxx::let_cxx_string!(form_spec = spec);
+            isFavorited(&form_spec)
+        })
+    }
+
+    /// The names of the entries `commit_prune()` would remove. The MCM shows
+    /// these to the player before anything changes.
+    pub fn preview_prune(&mut self, days: u32) -> Vec<String> {
+        let mut specs: Vec<String> = Vec::new();
+        for (_slot, spec) in self.prune_candidates(days) {
+            if !specs.contains(&spec) {
+                specs.push(spec);
+            }
+        }
+        specs
+            .iter()
+            .map(|spec| self.cache.get(spec).name())
+            .collect()
+    }
+
+    /// Remove cycle entries unused for more than `days` game days. Each
+    /// removal can be undone. Returns how many entries went away.
+    pub fn commit_prune(&mut self, days: u32) -> u32 {
+        let mut removed = 0;
+        for (slot, form_spec) in self.prune_candidates(days) {
+            let after = self.cycles.entry_before(&slot, &form_spec);
+            if self.cycles.remove_spec(&slot, &form_spec) {
+                log::info!("Pruned unused entry from the {slot} cycle: {form_spec}");
+                self.undo.push(CycleEdit::Removed {
+                    slot,
+                    form_spec,
+                    after,
+                });
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            self.show_utility_top();
+            self.update_hud();
+        }
+        removed
+    }
+
+    /// Ask to clear every cycle. Nothing happens until the returned token is confirmed.
+    pub fn request_clear_cycles(&mut self) -> ConfirmationRequest {
+        let count: usize = [
+            CycleSlot::Power,
+            CycleSlot::Utility,
+            CycleSlot::Left,
+            CycleSlot::Right,
+        ]
+        .iter()
+        .map(|slot| self.cycles.cycle_len(slot))
+        .sum();
+        let mut vars = HashMap::new();
+        vars.insert("count".to_string(), count.to_string());
+        let summary = strfmt(&translated_key(FMT_CONFIRM_CLEAR), &vars).unwrap_or_default();
+        let token = self
+            .confirmations
+            .request(PendingAction::ClearCycles, Instant::now());
+        ConfirmationRequest { token, summary }
+    }
+
+    /// Ask to prune entries unused for `days` game days. The summary lists
+    /// them. Nothing happens until the returned token is confirmed. If there
+    /// is nothing to prune, the token is 0.
+    pub fn request_prune(&mut self, days: u32) -> ConfirmationRequest {
+        let names = self.preview_prune(days);
+        if names.is_empty() {
+            return ConfirmationRequest {
+                token: 0,
+                summary: String::new(),
+            };
+        }
+        let mut vars = HashMap::new();
+        vars.insert("count".to_string(), names.len().to_string());
+        vars.insert("days".to_string(), days.to_string());
+        let header = strfmt(&translated_key(FMT_CONFIRM_PRUNE), &vars).unwrap_or_default();
+        let summary = format!("{header}\n{}", names.join("\n"));
+        let token = self
+            .confirmations
+            .request(PendingAction::Prune { days }, Instant::now());
+        ConfirmationRequest { token, summary }
+    }
+
+    /// Carry out the destructive action this token was issued for. Unknown,
+    /// expired, and already-used tokens do nothing. Returns a message for the
+    /// player, empty if nothing happened.
+    pub fn confirm(&mut self, token: u32) -> String {
+        let Some(action) = self.confirmations.take(token, Instant::now()) else {
+            log::info!("Confirmation token {token} is unknown, used, or expired; doing nothing.");
+            return String::new();
+        };
+        log::debug!("Confirmed: {action:?}");
+        match action {
+            PendingAction::ClearCycles => {
+                self.clear_cycles();
+                translated_key(MSG_CYCLES_CLEARED)
+            }
+            PendingAction::Prune { days } => {
+                let removed = self.commit_prune(days);
+                let mut vars = HashMap::new();
+                vars.insert("count".to_string(), removed.to_string());
+                strfmt(&translated_key(FMT_PRUNED), &vars).unwrap_or_default()
+            }
+        }
+    }
+
+    /// Reverse the most recent cycle edit. Returns a message describing what
+    /// we did, or an empty string if there was nothing to undo.
+    pub fn undo_last_cycle_edit(&mut self) -> String {
+        let Some(edit) = self.undo.pop() else {
+            log::info!("No cycle edits to undo.");
+            return String::new();
+        };
+
+        let slot = edit.slot().clone();
+        let (format, changed) = match &edit {
+            CycleEdit::Added { slot, form_spec } => (
+                translated_key(FMT_UNDO_ADDED),
+                self.cycles.remove_spec(slot, form_spec),
+            ),
+            CycleEdit::Removed {
+                slot,
+                form_spec,
+                after,
+            } => (
+                translated_key(FMT_UNDO_REMOVED),
+                self.cycles.insert_after(slot, form_spec, after.as_deref()),
+            ),
+        };
+        if !changed {
+            log::debug!("Undoing {edit:?} didn't change the cycle; it was already that way.");
+        }
+
+        if matches!(slot, CycleSlot::Utility) {
+            self.show_utility_top();
+        }
+
+        let item = self.cache.get(&edit.form_spec().to_string());
+        let cyclename = match slot {
+            CycleSlot::Power => translated_key(FMT_ITEM_POWERS_CYCLE),
+            CycleSlot::Left => translated_key(FMT_ITEM_LEFT_CYCLE),
+            CycleSlot::Right => translated_key(FMT_ITEM_RIGHT_CYCLE),
+            CycleSlot::Utility => translated_key(FMT_ITEM_UTILITIES_CYCLE),
+        };
+        let mut vars = HashMap::new();
+        vars.insert("item".to_string(), item.name());
+        vars.insert("cycle".to_string(), cyclename);
+        let message = strfmt(&format, &vars).unwrap_or_default();
+        log::info!("Undid cycle edit: {edit:?}");
+        message
+    }
+
+    pub fn handle_menu_event(&mut self, key: u32, button: &ButtonEvent, sequence: u64) -> bool {
         // Much simpler than the cycle loop. We care if the cycle modifier key
         // is down (if one is set), and we care if the cycle button itself has
         // been pressed.
 
-        let Some(tracked) = self.create_or_update_tracked(key, button, false) else {
+        self.input
+            .context
+            .record_menu_event(key, sequence, &KeyState::from(button));
+
+        let Some(tracked) = self.create_or_update_tracked(key, KeyState::from(button), false)
+        else {
             return false;
         };
         if !tracked.is_cycle_key() || !button.IsDown() {
@@ -1519,38 +3488,184 @@ impl Controller {
     /// This function is called when the player has pressed a hot key while hovering over an
     /// item in a menu. We'll remove the item if it's already in the matching cycle,
     /// or add it if it's an appropriate item. We signal back to the UI layer what we did.
-    pub fn handle_toggle_item(&mut self, action: Action, item: HudItem) {
+    pub fn handle_toggle_item(&mut self, action: Action, item: HudItem) -> MenuEventResponse {
+        if let Err(e) = item.validate() {
+            log::debug!(
+                "Ignoring invalid menu item: {e}; name='{}'; form_spec='{}'; kind={:?}; count={};",
+                item.raw_name().escape_debug(),
+                item.form_string().escape_debug(),
+                item.kind(),
+                item.count()
+            );
+            return MenuEventResponse::InvalidItem;
+        }
         let Ok(cycle_slot) = CycleSlot::try_from(action) else {
-            return;
+            return MenuEventResponse::Unhandled;
         };
+        // The menu's copy is the freshest we'll see, say after tempering renamed it.
+        self.cache.record(item.clone());
+
+        let spec = item.form_string();
+        let now = Instant::now();
+        if let Some(result) = self.input.toggle_memo.recall(&action, &spec, now) {
+            log::debug!(
+                "Ignoring repeated menu toggle; action={action:?}; form_spec='{}'; first result={result:?};",
+                spec.escape_debug()
+            );
+            return result;
+        }
+        let result = if self.wants_both_hands(&cycle_slot, &item) {
+            self.toggle_in_both_hands(item)
+        } else {
+            let result = self.toggle_in_cycle(&cycle_slot, item.clone());
+            self.notify_cycle_edit(&result, &cycle_slot, &item);
+            result
+        };
+        self.input.toggle_memo.remember(action, spec, result, now);
+        result
+    }
 
-        let result = self.cycles.toggle(&cycle_slot, item.clone());
+    /// Is the player holding the both-hands modifier while toggling something
+    /// either hand can hold?
+    fn wants_both_hands(&self, cycle_slot: &CycleSlot, item: &HudItem) -> bool {
+        matches!(cycle_slot, CycleSlot::Left | CycleSlot::Right)
+            && item.left_hand_ok()
+            && item.right_hand_ok()
+            && !item.two_handed()
+            && self.tracked_modifier(&Modifier::BothHands).is_pressed()
+    }
 
-        if matches!(result, MenuEventResponse::ItemRemoved) && matches!(action, Action::Utility) {
-            if let Some(topmost) = self.cycles.get_top(&CycleSlot::Utility) {
-                let item = self.cache.get(&topmost);
-                self.update_slot(HudElement::Utility, &item);
+    /// Put the item in both hand cycles, or take it out of both if it's already
+    /// in both. A hand that already looks right is left alone. The player hears
+    /// about it once if both hands agree, and hand by hand if they don't.
+    fn toggle_in_both_hands(&mut self, item: HudItem) -> MenuEventResponse {
+        let remove = self.cycles.includes(&CycleSlot::Left, &item)
+            && self.cycles.includes(&CycleSlot::Right, &item);
+        let mut toggle = |hand: &CycleSlot| {
+            if self.cycles.includes(hand, &item) != remove {
+                MenuEventResponse::Okay
             } else {
-                self.update_slot(HudElement::Utility, &HudItem::default());
+                self.toggle_in_cycle(hand, item.clone())
+            }
+        };
+        let left = toggle(&CycleSlot::Left);
+        let right = toggle(&CycleSlot::Right);
+
+        if left == right {
+            let both = translated_key(FMT_ITEM_BOTH_HANDS);
+            self.notify_edit_in(&left, both, &item);
+            return left;
+        }
+        for (result, hand) in [(&left, CycleSlot::Left), (&right, CycleSlot::Right)] {
+            if !matches!(*result, MenuEventResponse::Okay) {
+                self.notify_cycle_edit(result, &hand, &item);
             }
         }
+        log::info!(
+            "Both-hands toggle came out differently per hand; left={left:?}; right={right:?};"
+        );
+        MenuEventResponse::HandsDiffer
+    }
+
+    /// A script asks for an item to be added to or removed from a cycle. The
+    /// item is looked up from its form spec and goes through the same checks
+    /// as a menu toggle. Asking for what's already true changes nothing and
+    /// answers `Okay`. Pass `quiet` to skip telling the player.
+    pub fn edit_cycle_from_script(
+        &mut self,
+        which: i32,
+        form_spec: &str,
+        add: bool,
+        quiet: bool,
+    ) -> MenuEventResponse {
+        let Some(slot) = cycle_slot_for_papyrus(which) else {
+            log::debug!("A script asked to edit cycle {which}, which doesn't exist.");
+            return MenuEventResponse::Unhandled;
+        };
+        let item = self.cache.get_with_refresh(&form_spec.to_string());
+        if let Err(e) = item.validate() {
+            log::debug!(
+                "A script asked to edit the {slot} cycle with an invalid item: {e}; form_spec='{}';",
+                form_spec.escape_debug()
+            );
+            return MenuEventResponse::InvalidItem;
+        }
+        if self.cycles.includes(&slot, &item) == add {
+            return MenuEventResponse::Okay;
+        }
+
+        let result = self.toggle_in_cycle(&slot, item.clone());
+        log::info!(
+            "A script edited the {slot} cycle; form_spec='{}'; result={result:?};",
+            form_spec.escape_debug()
+        );
+        if !quiet {
+            self.notify_cycle_edit(&result, &slot, &item);
+        }
+        result
+    }
+
+    /// Add the item to the cycle or remove it, and remember the edit so it can
+    /// be undone.
+    fn toggle_in_cycle(&mut self, cycle_slot: &CycleSlot, item: HudItem) -> MenuEventResponse {
+        let equipped = equipped_spec(cycle_slot);
+        let removes_equipped = self.cycles.removes_equipped(cycle_slot, &item, &equipped);
+        if removes_equipped && settings().keep_equipped_in_cycles() {
+            return MenuEventResponse::ItemEquipped;
+        }
+
+        let spec = item.form_string();
+        let previous_neighbour = self
+            .cycles
+            .position(cycle_slot, &spec)
+            .map(|_| self.cycles.entry_before(cycle_slot, &spec));
+        let result = self.cycles.toggle(cycle_slot, item);
+        match (result, previous_neighbour) {
+            (MenuEventResponse::ItemAdded, _) => self.undo.push(CycleEdit::Added {
+                slot: cycle_slot.clone(),
+                form_spec: spec,
+            }),
+            (MenuEventResponse::ItemRemoved, Some(after)) => self.undo.push(CycleEdit::Removed {
+                slot: cycle_slot.clone(),
+                form_spec: spec,
+                after,
+            }),
+            _ => {}
+        }
 
-        // notify the player what happened...
-        let verb = match result {
+        if matches!(result, MenuEventResponse::ItemRemoved)
+            && matches!(cycle_slot, CycleSlot::Utility)
+        {
+            self.show_utility_top();
+        } else if removes_equipped && matches!(result, MenuEventResponse::ItemRemoved) {
+            // It's still equipped and the HUD still shows it, but it's no longer
+            // part of the cycle. Have the renderer take another look.
+            self.visual.touch(HudElement::from(cycle_slot));
+        }
+        result
+    }
+
+    /// Tell the player what a cycle edit did.
+    fn notify_cycle_edit(&self, result: &MenuEventResponse, which: &CycleSlot, item: &HudItem) {
+        let cyclename = match which {
+            CycleSlot::Power => translated_key(FMT_ITEM_POWERS_CYCLE),
+            CycleSlot::Left => translated_key(FMT_ITEM_LEFT_CYCLE),
+            CycleSlot::Right => translated_key(FMT_ITEM_RIGHT_CYCLE),
+            CycleSlot::Utility => translated_key(FMT_ITEM_UTILITIES_CYCLE),
+        };
+        self.notify_edit_in(result, cyclename, item);
+    }
+
+    /// Tell the player what a cycle edit did, naming the cycle as given.
+    fn notify_edit_in(&self, result: &MenuEventResponse, cyclename: String, item: &HudItem) {
+        let verb = match *result {
             MenuEventResponse::ItemAdded => translated_key(FMT_ITEM_ADDED),
             MenuEventResponse::ItemRemoved => translated_key(FMT_ITEM_REMOVED),
             MenuEventResponse::ItemInappropriate => translated_key(FMT_ITEM_REJECTED),
+            MenuEventResponse::ItemEquipped => translated_key(FMT_ITEM_EQUIPPED),
             MenuEventResponse::TooManyItems => translated_key(FMT_ITEM_TOOMANY),
             _ => translated_key(FMT_ITEM_NOCHANGE),
         };
-        let cyclename = match action {
-            Action::Power => translated_key(FMT_ITEM_POWERS_CYCLE),
-            Action::Left => translated_key(FMT_ITEM_LEFT_CYCLE),
-            Action::Right => translated_key(FMT_ITEM_RIGHT_CYCLE),
-            Action::Utility => translated_key(FMT_ITEM_UTILITIES_CYCLE),
-            _ => "any".to_string(), // should be unreachable
-        };
-
         let mut vars = HashMap::new();
         vars.insert("item".to_string(), item.name());
         vars.insert("cycle".to_string(), cyclename);
@@ -1567,83 +3682,46 @@ impl Controller {
     fn create_or_update_tracked(
         &mut self,
         key: u32,
-        button: &ButtonEvent,
+        state: KeyState,
         in_menu: bool,
     ) -> Option<TrackedKey> {
-        let mut return_the_key = true;
-        let should_start_timer = !in_menu && settings().should_start_long_press_timer(key);
-
-        let tracked = if let Some(previous) = self.tracked_keys.get_mut(&key) {
-            // We have seen this key before.
-            // Did this key just have a long-press event? if so, ignore a key-up.
-            // We ask this question before we update the tracking data.
-            if matches!(previous.state, KeyState::Pressed)
-                && previous.is_long_press()
-                && should_start_timer
-            {
-                return_the_key = false;
-            }
-
-            previous.update(button);
-            previous.clone()
-        } else {
-            let fresh = TrackedKey::new(key, button);
-            self.tracked_keys.insert(key, fresh.clone());
-            fresh
-        };
-
-        // long press timers; not started if we're in a menu
-        if should_start_timer {
-            let action = tracked.action();
-            if matches!(tracked.state, KeyState::Down) {
-                let duration = settings().long_press_ms();
-                match action {
-                    Action::Power => startTimer(Action::LongPressPower, duration),
-                    Action::Utility => startTimer(Action::LongPressUtility, duration),
-                    Action::Left => startTimer(Action::LongPressLeft, duration),
-                    Action::Right => startTimer(Action::LongPressRight, duration),
-                    _ => {}
-                }
-            } else if matches!(tracked.state, KeyState::Up) {
-                match action {
-                    Action::Power => stopTimer(Action::LongPressPower),
-                    Action::Utility => stopTimer(Action::LongPressUtility),
-                    Action::Left => stopTimer(Action::LongPressLeft),
-                    Action::Right => stopTimer(Action::LongPressRight),
-                    _ => {}
-                }
-            }
+        let should_start_timer = !in_menu
+            && (settings().should_start_long_press_timer(key)
+                || self.long_press_leaves_tool(key, &state));
+        let press = self.input.track(key, state, should_start_timer);
+        match press.timer {
+            LongPressTimer::Start(which) => startTimer(which),
+            LongPressTimer::Stop(which) => stopTimer(which),
+            LongPressTimer::Leave => {}
         }
 
-        if return_the_key {
-            Some(tracked)
-        } else {
+        if press.swallow {
             None
+        } else {
+            Some(press.tracked)
         }
     }
 
-    fn tracked_modifier(&self, modifier: &Modifier) -> TrackedKey {
-        let key = modifier.key_for();
-        if key < 0 {
-            return TrackedKey::default();
+    /// True if this is the right cycle key and a long press would put away a tool.
+    /// We look when the key goes down and remember the answer for the rest of
+    /// the press, so held-key events don't each search the equip history.
+    fn long_press_leaves_tool(&mut self, key: u32, state: &KeyState) -> bool {
+        if !matches!(Hotkey::from(key), Hotkey::Right) {
+            return false;
         }
-        if let Some(tracked) = self.tracked_keys.get(&key.unsigned_abs()) {
-            tracked.clone()
-        } else {
-            TrackedKey::default()
+        if matches!(state, KeyState::Down) {
+            self.input.leaving_tool = settings().timings_for(&Action::Right).long_press_enabled()
+                && self.main_weapon_to_restore().is_some();
         }
+        self.input.leaving_tool
+    }
+
+    fn tracked_modifier(&self, modifier: &Modifier) -> TrackedKey {
+        self.input.modifier(modifier)
     }
 
     fn tracked_key(&self, hotkey: &Hotkey) -> TrackedKey {
-        let key = hotkey.key_for();
-        if key < 0 {
-            return TrackedKey::default();
-        }
-        if let Some(tracked) = self.tracked_keys.get(&key.unsigned_abs()) {
-            tracked.clone()
-        } else {
-            TrackedKey::default()
-        }
+        self.input.hotkey(hotkey)
     }
 
     // ----------- equipment set functions
@@ -1671,26 +3749,44 @@ impl Controller {
     /// Rotate to the next equipment set in the cycle and start the timer.
     fn advance_cycle_equipset(&mut self) -> KeyEventResponse {
         let candidate = self.cycles.advance_equipset(1);
-        if let Some(_next) = candidate {
+        if let Some(next) = candidate {
             KeyEventResponse {
                 handled: true,
                 start_timer: Action::Equipment,
                 stop_timer: Action::None,
+                wrapped: false,
+                outcome: KeyOutcome::CycleAdvanced,
+                slot: HudElement::EquipSet,
+                item_name: next.name(),
             }
         } else {
-            KeyEventResponse {
-                handled: true,
-                ..Default::default()
-            }
+            KeyEventResponse::cycle_empty(HudElement::EquipSet)
         }
     }
 
     /// Called when equipment timer expires: equip that equip set!
     fn equip_selected_set(&mut self) {
+        if !self.equipped.vitals.permit("equip a set") {
+            return;
+        }
         let Some(equipset) = self.cycles.get_top_equipset() else {
             return;
         };
         log::debug!("Switching to equipment set '{}'.", equipset.name());
+
+        // We don't know what the set pushes out of its slots, so only count
+        // what the player isn't already wearing.
+        let worn = getEquippedItems().items;
+        let going_on: Vec<HudItem> = equipset
+            .items()
+            .iter()
+            .filter(|item| !worn.contains(item))
+            .map(|item| self.cache.get(item))
+            .collect();
+        if !self.carry_allows(&going_on) {
+            return;
+        }
+
         if settings().equip_sets_unequip() {
             equipset.empty_slots().iter().for_each(|shift| {
                 unequipSlotByShift(*shift);
@@ -1699,12 +3795,63 @@ impl Controller {
         equipset.items().iter().for_each(|item| {
             let cached = self.cache.get(item);
             let_cxx_string!(form_spec = item.identifier());
-            let_cxx_string!(name = cached.name());
+            let_cxx_string!(name = cached.raw_name());
             equipArmor(&form_spec, &name);
         });
 
         let set = HudItem::for_equip_set(equipset.name(), equipset.id(), equipset.icon.clone());
         self.update_slot(HudElement::EquipSet, &set);
+        self.equip_loadout(&equipset);
+    }
+
+    /// Put a set's hand items, power, and utility item in place. A two-handed
+    /// weapon for the right hand leaves the left hand to it.
+    fn equip_loadout(&mut self, equipset: &EquipSet) {
+        let mut two_handed = false;
+        for slot in [
+            CycleSlot::Right,
+            CycleSlot::Left,
+            CycleSlot::Power,
+            CycleSlot::Utility,
+        ] {
+            let Some(spec) = equipset.loadout_for(&slot) else {
+                continue;
+            };
+            if two_handed && matches!(slot, CycleSlot::Left) {
+                continue;
+            }
+            let item = self.cache.get(&spec.to_string());
+            if matches!(item.kind(), BaseType::Empty) {
+                log::info!(
+                    "Equipment set '{}' wants an item the player doesn't have in the {slot} slot; form_spec='{}';",
+                    equipset.name(),
+                    spec.escape_debug()
+                );
+                continue;
+            }
+            if matches!(slot, CycleSlot::Right) {
+                two_handed = self.treat_as_two_handed(&item);
+            }
+            self.cycles.set_top(&slot, spec);
+            self.update_slot(HudElement::from(&slot), &item);
+            if !matches!(slot, CycleSlot::Utility) {
+                self.equip_shown(Action::from(slot));
+            }
+        }
+    }
+
+    /// Save what the player is wearing and holding right now as a new
+    /// equipment set, with the current power and utility item too.
+    pub fn save_current_as_set(&mut self, name: String) -> bool {
+        let loadout = vec![
+            specEquippedPower(),
+            self.cycles.get_top(&CycleSlot::Utility).unwrap_or_default(),
+            specEquippedLeft(),
+            specEquippedRight(),
+        ];
+        log::info!("Saving equipment set '{name}' with loadout {loadout:?}.");
+        let data = getEquippedItems();
+        self.cycles.add_loadout(name, *data, loadout)
     }
 
     /// Called by the MCM code when it is showing a list of all items in an equipment set.
@@ -1752,6 +3899,36 @@ impl Default for KeyEventResponse {
             handled: false,
             stop_timer: Action::None,
             start_timer: Action::None,
+            wrapped: false,
+            outcome: KeyOutcome::Nothing,
+            slot: HudElement::None,
+            item_name: String::new(),
+        }
+    }
+}
+
+/// A closed browser.
+impl Default for CycleBrowserView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            slot: HudElement::None,
+            names: Vec::new(),
+            descriptions: Vec::new(),
+            selected: 0,
+            page: 0,
+            page_count: 0,
+            total: 0,
+        }
+    }
+}
+
+/// Nothing to confirm.
+impl Default for ConfirmationRequest {
+    fn default() -> Self {
+        Self {
+            token: 0,
+            summary: String::new(),
         }
     }
 }
@@ -1760,8 +3937,29 @@ impl KeyEventResponse {
     pub fn handled() -> Self {
         Self {
             handled: true,
-            stop_timer: Action::None,
-            start_timer: Action::None,
+            ..Default::default()
+        }
+    }
+
+    /// The player asked for something from this slot, and it had nothing to give.
+    pub fn cycle_empty(slot: HudElement) -> Self {
+        Self {
+            handled: true,
+            outcome: KeyOutcome::CycleEmpty,
+            slot,
+            ..Default::default()
+        }
+    }
+
+    /// A hand changed right away, so its timer stops.
+    pub fn equipped(hand: Action, slot: HudElement, item_name: String) -> Self {
+        Self {
+            handled: true,
+            stop_timer: hand,
+            outcome: KeyOutcome::ItemEquipped,
+            slot,
+            item_name,
+            ..Default::default()
         }
     }
 }
@@ -1769,43 +3967,35 @@ impl KeyEventResponse {
 impl From<u32> for Action {
     /// Turn the key code into an enum for easier processing.
     fn from(value: u32) -> Self {
-        let settings = settings();
+        key_action(value)
+    }
+}
 
-        if value == settings.left() {
-            Action::Left
-        } else if value == settings.right() {
-            Action::Right
-        } else if value == settings.power() {
-            Action::Power
-        } else if value == settings.utility() {
-            Action::Utility
-        } else if value == settings.activate() {
-            Action::Activate
-        } else if value == settings.showhide() {
-            Action::ShowHide
-        } else if value == settings.refresh_layout() {
-            Action::RefreshLayout
-        } else if value == settings.equipset() as u32 {
-            Action::Equipment
-        } else if value == settings.unequip_hotkey() as u32 {
-            Action::UnequipHands
-        } else {
-            Action::None
-        }
+/// The cycle a Papyrus caller means by this number.
+fn cycle_slot_for_papyrus(which: i32) -> Option<CycleSlot> {
+    match which {
+        0 => Some(CycleSlot::Power),
+        1 => Some(CycleSlot::Utility),
+        2 => Some(CycleSlot::Left),
+        3 => Some(CycleSlot::Right),
+        _ => None,
     }
 }
 
-/// What the controller did with a specific menu press event.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub enum MenuEventResponse {
-    Okay,
-    #[default]
-    Unhandled,
-    Error,
-    ItemAdded,
-    ItemRemoved,
-    ItemInappropriate,
-    TooManyItems,
+impl Default for MenuEventResponse {
+    fn default() -> Self {
+        MenuEventResponse::Unhandled
+    }
+}
+
+impl MenuEventResponse {
+    /// True if the cycle now looks the way the caller asked.
+    pub fn succeeded(&self) -> bool {
+        matches!(
+            *self,
+            MenuEventResponse::Okay | MenuEventResponse::ItemAdded | MenuEventResponse::ItemRemoved
+        )
+    }
 }
 
 /// TODO: derivable?
@@ -1815,6 +4005,56 @@ impl Default for Controller {
     }
 }
 
+/// Whole game days since the game started, for aging cycle entries.
+#[cfg(not(test))]
+fn game_day() -> u32 {
+    // float to int casts saturate, and NaN becomes 0
+    gameDaysPassed() as u32
+}
+
+#[cfg(test)]
+fn game_day() -> u32 {
+    0
+}
+
+/// The form spec of what the player has equipped in the slot this cycle feeds.
+#[cfg(not(test))]
+fn equipped_spec(which: &CycleSlot) -> String {
+    match which {
+        CycleSlot::Power => specEquippedPower(),
+        CycleSlot::Utility => specEquippedAmmo(),
+        CycleSlot::Left => specEquippedLeft(),
+        CycleSlot::Right => specEquippedRight(),
+    }
+}
+
+#[cfg(test)]
+fn equipped_spec(_which: &CycleSlot) -> String {
+    String::new()
+}
+
+/// Ask the game whether a spell-preparation mod lets the player cast this.
+#[cfg(not(test))]
+fn spell_castable_now(spec: &str) -> bool {
+    cxx::let_cxx_string!(form_spec = spec);
+    isSpellCastable(&form_spec)
+}
+
+#[cfg(test)]
+fn spell_castable_now(_spec: &str) -> bool {
+    true
+}
+
+/// Play the activation-failed sound. This shadows the bridge function so
+/// tests can reach code that honks.
+#[cfg(not(test))]
+fn honk() {
+    crate::plugin::honk();
+}
+
+#[cfg(test)]
+fn honk() {}
+
 #[cfg(not(test))]
 pub fn notify(msg: &str) {
     cxx::let_cxx_string!(message = msg);
@@ -1822,7 +4062,20 @@ pub fn notify(msg: &str) {
 }
 
 #[cfg(test)]
-pub fn notify(_msg: &str) {}
+thread_local! {
+    /// What tests would have shown the player, oldest first.
+    static NOTIFIED: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+#[cfg(test)]
+pub fn notify(msg: &str) {
+    NOTIFIED.with(|xs| xs.borrow_mut().push(msg.to_string()));
+}
+
+#[cfg(test)]
+fn take_notifications() -> Vec<String> {
+    NOTIFIED.with(|xs| xs.take())
+}
 
 /// Convenience function for doing the cxx macro boilerplate before
 /// calling C++ with a string.
@@ -1840,6 +4093,7 @@ pub fn translated_key(key: &str) -> String {
 const FMT_ITEM_REMOVED: &str = "$SoulsyHUD_fmt_ItemRemoved";
 const FMT_ITEM_ADDED: &str = "$SoulsyHUD_fmt_ItemAdded";
 const FMT_ITEM_REJECTED: &str = "$SoulsyHUD_fmt_ItemRejected";
+const FMT_ITEM_EQUIPPED: &str = "$SoulsyHUD_fmt_ItemEquipped";
 const FMT_ITEM_TOOMANY: &str = "$SoulsyHUD_fmt_TooMany";
 const FMT_ITEM_NOCHANGE: &str = "$SoulsyHUD_fmt_NoChange";
 const FMT_ITEM_POWERS_CYCLE: &str = "$SoulsyHUD_fmt_PowersCycle";
@@ -1847,6 +4101,34 @@ const FMT_ITEM_UTILITIES_CYCLE: &str = "$SoulsyHUD_fmt_UtilitiesCycle";
 const FMT_ITEM_LEFT_CYCLE: &str = "$SoulsyHUD_fmt_LeftHandCycle";
 const FMT_ITEM_RIGHT_CYCLE: &str = "$SoulsyHUD_fmt_RightHandCycle";
 const FMT_ITEM_BOTH_HANDS: &str = "$SoulsyHUD_fmt_BothHands";
+const FMT_UNDO_ADDED: &str = "$SoulsyHUD_fmt_UndoAdded";
+const FMT_UNDO_REMOVED: &str = "$SoulsyHUD_fmt_UndoRemoved";
+const FMT_DAILY_LIMIT: &str = "$SoulsyHUD_fmt_DailyLimit";
+const FMT_AMMO_RELOCATED: &str = "$SoulsyHUD_fmt_AmmoRelocated";
+const FMT_NO_AMMO: &str = "$SoulsyHUD_fmt_NoAmmo";
+const FMT_NOT_PREPARED: &str = "$SoulsyHUD_fmt_NotPrepared";
+const FMT_CONFIRM_CLEAR: &str = "$SoulsyHUD_fmt_ConfirmClear";
+const FMT_CONFIRM_PRUNE: &str = "$SoulsyHUD_fmt_ConfirmPrune";
+const FMT_PRUNED: &str = "$SoulsyHUD_fmt_Pruned";
+const FMT_OVERWEIGHT_WARNING: &str = "$SoulsyHUD_fmt_OverweightWarning";
+const FMT_OVERWEIGHT_REFUSED: &str = "$SoulsyHUD_fmt_OverweightRefused";
+const FMT_QUEST_ITEM: &str = "$SoulsyHUD_fmt_QuestItem";
+const FMT_USED_UP: &str = "$SoulsyHUD_fmt_UsedUp";
+const FMT_ITEM_USED: &str = "$SoulsyHUD_fmt_ItemUsed";
+const FMT_CHARGE_DRAINED: &str = "$SoulsyHUD_fmt_ChargeDrained";
+const FMT_POISON_APPLIED: &str = "$SoulsyHUD_fmt_PoisonApplied";
+const FMT_NOTHING_TO_POISON: &str = "$SoulsyHUD_fmt_NothingToPoison";
+const FMT_PRUNED_EMPTY: &str = "$SoulsyHUD_fmt_PrunedEmpty";
+const FMT_FAVORITES_IMPORTED: &str = "$SoulsyHUD_fmt_FavoritesImported";
+const FMT_QUARANTINED: &str = "$SoulsyHUD_fmt_Quarantined";
+const FMT_CYCLES_EXPORTED: &str = "$SoulsyHUD_fmt_CyclesExported";
+const FMT_CYCLES_NOT_EXPORTED: &str = "$SoulsyHUD_fmt_CyclesNotExported";
+const FMT_CYCLES_IMPORTED: &str = "$SoulsyHUD_fmt_CyclesImported";
+const FMT_CYCLES_NOT_IMPORTED: &str = "$SoulsyHUD_fmt_CyclesNotImported";
+const MSG_CYCLES_CLEARED: &str = "$SoulsyHUD_CyclesCleared_Message";
+const MSG_UNARMED: &str = "$SoulsyHUD_Unarmed_Message";
+const FMT_ARROWS: &str = "$SoulsyHUD_fmt_Arrows";
+const FMT_BOLTS: &str = "$SoulsyHUD_fmt_Bolts";
 
 /// Possible actions requested when a user presses a cycle key.
 /// The action is determined using the key pressed, the presence of modifiers,
@@ -1855,7 +4137,286 @@ pub enum RequestedAction {
     Advance,
     AdvanceAmmo,
     Consume,
+    /// Equip what the slot shows without waiting for the equip delay.
+    EquipNow,
     Match,
     Unequip,
     None,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_events_replay_when_the_load_never_finishes() {
+        let sword_spec = "Skyrim.esm|0x12eb7".to_string();
+        let mut ctrl = Controller::new();
+        ctrl.cache.get(&sword_spec);
+        assert!(!ctrl.has_clock_work());
+
+        let now = Instant::now();
+        ctrl.begin_load_grace_period();
+        ctrl.load_gate.defer(DeferredEvent::Inventory {
+            form_spec: sword_spec.clone(),
+            count: 7,
+        });
+        assert!(ctrl.has_clock_work());
+        ctrl.tick(now);
+        assert_ne!(ctrl.cache.peek_count(&sword_spec), Some(7));
+
+        // No load-complete signal and no further events: the tick replays them.
+        ctrl.tick(now + std::time::Duration::from_secs(60));
+        assert_eq!(ctrl.cache.peek_count(&sword_spec), Some(7));
+        assert!(!ctrl.has_clock_work());
+    }
+
+    #[test]
+    fn an_emptied_hand_shows_and_says_unarmed() {
+        let sword_spec = "Skyrim.esm|0x12eb7".to_string();
+        let mut ctrl = Controller::new();
+        let sword = ctrl.cache.get(&sword_spec);
+        ctrl.cycles.add_item(CycleSlot::Right, &sword);
+        ctrl.cycles
+            .add_item(CycleSlot::Right, &HudItem::make_unarmed_proxy());
+        ctrl.update_slot(HudElement::Right, &sword);
+        let before = ctrl.cycles.formids(&CycleSlot::Right);
+        take_notifications();
+
+        // The sword goes away and nothing replaces it.
+        assert!(ctrl.handle_item_unequipped(&sword_spec, &String::new(), &String::new()));
+        assert!(ctrl.visual.is_empty_hand(&HudElement::Right));
+        let shown = ctrl.entry_to_show_in_slot(HudElement::Right);
+        assert_eq!(shown.kind(), &BaseType::HandToHand);
+        assert_eq!(take_notifications(), vec![translated_key(MSG_UNARMED)]);
+        // The cycle stays put, so the next press picks up where it was.
+        assert_eq!(ctrl.cycles.formids(&CycleSlot::Right), before);
+
+        // A second report of the same thing is no news.
+        ctrl.hand_emptied(&CycleSlot::Right, "");
+        assert!(take_notifications().is_empty());
+    }
+
+    #[test]
+    fn grip_changes_leave_the_cycles_alone() {
+        use crate::data::color::InvColor;
+        use crate::data::weapon::{WeaponEquipType, WeaponType};
+        use crate::images::icons::Icon;
+
+        let sword = |spec: &str| {
+            HudItem::preclassified(
+                "Versatile Sword".to_string(),
+                spec.to_string(),
+                1,
+                BaseType::Weapon(WeaponType::new(
+                    Icon::WeaponSwordOneHanded,
+                    InvColor::default(),
+                    WeaponEquipType::EitherHand,
+                )),
+            )
+        };
+
+        let specs = ["Test.esp|0x801", "Test.esp|0x802", "Test.esp|0x803"];
+        let dagger_spec = "Skyrim.esm|0x1397e".to_string();
+        let mut ctrl = Controller::new();
+        for spec in specs {
+            let item = sword(spec);
+            ctrl.cache.record(item.clone());
+            ctrl.cycles.add_item(CycleSlot::Right, &item);
+        }
+        let dagger = ctrl.cache.get(&dagger_spec);
+        ctrl.cycles.add_item(CycleSlot::Left, &dagger);
+        ctrl.cycles.set_top(&CycleSlot::Right, specs[1]);
+        ctrl.update_slot(HudElement::Right, &sword(specs[1]));
+        ctrl.update_slot(HudElement::Left, &dagger);
+        ctrl.equipped.left_hand_cached = dagger_spec.clone();
+        let right_before = ctrl.cycles.formids(&CycleSlot::Right);
+        let left_before = ctrl.cycles.formids(&CycleSlot::Left);
+        let cycles_unchanged = |ctrl: &Controller| {
+            assert_eq!(ctrl.cycles.formids(&CycleSlot::Right), right_before);
+            assert_eq!(ctrl.cycles.formids(&CycleSlot::Left), left_before);
+            assert_eq!(
+                ctrl.cycles.get_top(&CycleSlot::Right),
+                Some(specs[1].to_string())
+            );
+        };
+
+        // Taking the sword in both hands clears the left slot, and nothing else.
+        ctrl.handle_hand_grip_change(Action::Right, true);
+        assert_eq!(
+            ctrl.equipped.grips.chosen(&CycleSlot::Right, specs[1]),
+            Some(true)
+        );
+        assert!(ctrl.equipped.two_hander_equipped);
+        let right = ctrl.entry_to_show_in_slot(HudElement::Right);
+        assert_eq!(right.form_string(), specs[1]);
+        assert_eq!(
+            ctrl.visual.showing(&HudElement::Left),
+            Some(&HudItem::default())
+        );
+        assert_eq!(ctrl.equipped.left_hand_cached, dagger_spec);
+        cycles_unchanged(&ctrl);
+
+        // The same grip again is no change at all.
+        ctrl.visual.take_change(&HudElement::Right);
+        ctrl.handle_hand_grip_change(Action::Right, true);
+        assert!(!ctrl.visual.take_change(&HudElement::Right));
+
+        // Back to one hand: the dagger returns to the left slot.
+        ctrl.handle_hand_grip_change(Action::Right, false);
+        assert!(!ctrl.equipped.two_hander_equipped);
+        let left = ctrl.entry_to_show_in_slot(HudElement::Left);
+        assert_eq!(left.form_string(), dagger_spec);
+        cycles_unchanged(&ctrl);
+
+        // Grips only matter for weapons the HUD is showing in that hand.
+        ctrl.handle_hand_grip_change(Action::Left, true);
+        ctrl.handle_hand_grip_change(Action::Power, true);
+        assert_eq!(
+            ctrl.equipped.grips.chosen(&CycleSlot::Left, &dagger_spec),
+            Some(true)
+        );
+        assert!(!ctrl.equipped.two_hander_equipped);
+        cycles_unchanged(&ctrl);
+    }
+
+    #[test]
+    fn cycling_passes_over_the_only_one_held_in_the_other_hand() {
+        use crate::data::color::InvColor;
+        use crate::data::weapon::{WeaponEquipType, WeaponType};
+        use crate::images::icons::Icon;
+
+        let dagger = |spec: &str, count: u32| {
+            HudItem::preclassified(
+                "Dagger".to_string(),
+                spec.to_string(),
+                count,
+                BaseType::Weapon(WeaponType::new(
+                    Icon::WeaponDagger,
+                    InvColor::default(),
+                    WeaponEquipType::EitherHand,
+                )),
+            )
+        };
+
+        let specs = ["Test.esp|0x801", "Test.esp|0x802", "Test.esp|0x803"];
+        let mut ctrl = Controller::new();
+        for spec in specs {
+            let item = dagger(spec, 1);
+            ctrl.cache.record(item.clone());
+            ctrl.cycles.add_item(CycleSlot::Right, &item);
+        }
+        ctrl.cycles.set_top(&CycleSlot::Right, specs[0]);
+        ctrl.update_slot(HudElement::Right, &dagger(specs[0], 1));
+        ctrl.update_slot(HudElement::Left, &dagger(specs[1], 1));
+
+        let response = ctrl.advance_hand_cycle(&CycleSlot::Right);
+        assert_eq!(response.outcome, KeyOutcome::CycleAdvanced);
+        assert_eq!(
+            ctrl.cycles.get_top(&CycleSlot::Right).as_deref(),
+            Some(specs[2])
+        );
+
+        // With two of them, both hands can hold one.
+        ctrl.update_slot(HudElement::Left, &dagger(specs[0], 2));
+        ctrl.advance_hand_cycle(&CycleSlot::Right);
+        assert_eq!(
+            ctrl.cycles.get_top(&CycleSlot::Right).as_deref(),
+            Some(specs[0])
+        );
+    }
+
+    #[test]
+    fn an_extra_cycle_key_cycles() {
+        use super::super::keycodes::KeyCode;
+        use super::super::settings::{publish_key_map, UserSettings};
+        use crate::data::color::InvColor;
+        use crate::data::weapon::{WeaponEquipType, WeaponType};
+        use crate::images::icons::Icon;
+
+        let mut options = UserSettings::default();
+        options
+            .read_from_str("[Controls]\nuRightCycleKey = 7, mouse:4\n")
+            .expect("settings text parses");
+        publish_key_map(options.key_map());
+        let mouse = KeyCode::parse("mouse:4")
+            .expect("mouse buttons parse")
+            .raw();
+
+        let dagger = |spec: &str| {
+            HudItem::preclassified(
+                "Dagger".to_string(),
+                spec.to_string(),
+                1,
+                BaseType::Weapon(WeaponType::new(
+                    Icon::WeaponDagger,
+                    InvColor::default(),
+                    WeaponEquipType::EitherHand,
+                )),
+            )
+        };
+        let specs = ["Test.esp|0x801", "Test.esp|0x802"];
+        let mut ctrl = Controller::new();
+        for spec in specs {
+            let item = dagger(spec);
+            ctrl.cache.record(item.clone());
+            ctrl.cycles.add_item(CycleSlot::Right, &item);
+        }
+        ctrl.cycles.set_top(&CycleSlot::Right, specs[0]);
+        ctrl.update_slot(HudElement::Right, &dagger(specs[0]));
+
+        assert_eq!(Hotkey::from(mouse), Hotkey::Right);
+        ctrl.respond_to_key(mouse, &KeyState::Down, 1);
+        let response = ctrl.respond_to_key(mouse, &KeyState::Up, 2);
+        assert_eq!(response.outcome, KeyOutcome::CycleAdvanced);
+        assert_eq!(
+            ctrl.cycles.get_top(&CycleSlot::Right).as_deref(),
+            Some(specs[1])
+        );
+    }
+
+    #[test]
+    fn garbage_menu_rows_leave_the_cycles_alone() {
+        let mut ctrl = Controller::new();
+        let sword = ctrl.cache.get(&"Skyrim.esm|0x12eb7".to_string());
+        ctrl.cycles.add_item(CycleSlot::Right, &sword);
+        let before = ctrl.cycles.clone();
+        take_notifications();
+
+        let potion = BaseType::Potion(crate::data::potion::PotionType::Health);
+        let garbage = [
+            HudItem::preclassified("Header".to_string(), String::new(), 1, potion.clone()),
+            HudItem::preclassified("Header".to_string(), "\0\0".to_string(), 1, potion.clone()),
+            HudItem::preclassified(
+                "Header".to_string(),
+                "Skyrim.esm|0x3eb42".to_string(),
+                1,
+                BaseType::Empty,
+            ),
+            HudItem::preclassified(
+                "Header".to_string(),
+                "Skyrim.esm|0x3eb42".to_string(),
+                u32::MAX,
+                potion,
+            ),
+            HudItem::default(),
+        ];
+        for item in garbage {
+            for action in [Action::Right, Action::Left, Action::Utility, Action::Power] {
+                let response = ctrl.handle_toggle_item(action, item.clone());
+                assert_eq!(response, MenuEventResponse::InvalidItem, "{item:?}");
+                assert!(!response.succeeded());
+            }
+        }
+        assert_eq!(
+            ctrl.cycles.formids(&CycleSlot::Right),
+            before.formids(&CycleSlot::Right)
+        );
+        for slot in [CycleSlot::Left, CycleSlot::Utility, CycleSlot::Power] {
+            assert!(ctrl.cycles.formids(&slot).is_empty());
+        }
+        assert!(ctrl.undo.is_empty());
+        assert!(take_notifications().is_empty());
+        assert_eq!(MenuEventResponse::default(), MenuEventResponse::Unhandled);
+    }
+}
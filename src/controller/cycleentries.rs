@@ -3,6 +3,7 @@
 //! for `Vec<String>` and `Vec<EquipSet>`, along with some other traits for
 //! each of those.
 
+use super::keys::CycleSlot;
 use crate::data::base::BaseType;
 use crate::data::huditem::HudItem;
 use crate::data::item_cache::ItemCache;
@@ -21,6 +22,10 @@ pub struct EquipSet {
     pub empty: Vec<u8>,
     /// Which icon to use.
     pub icon: Icon,
+    /// What the set puts in the power, utility, left, and right slots, in that
+    /// order. An empty string leaves its slot alone. Sets made from armor
+    /// alone have no loadout at all.
+    pub loadout: Vec<String>,
 }
 
 impl EquipSet {
@@ -39,6 +44,7 @@ impl EquipSet {
             items,
             empty,
             icon,
+            loadout: Vec::new(),
         }
     }
 
@@ -54,6 +60,7 @@ impl EquipSet {
             items,
             empty,
             icon,
+            loadout: Vec::new(),
         }
     }
 
@@ -87,9 +94,24 @@ impl EquipSet {
     pub fn icon(&self) -> &Icon {
         &self.icon
     }
+
+    /// What this set puts in the given cycle slot, if anything.
+    pub fn loadout_for(&self, slot: &CycleSlot) -> Option<&str> {
+        let idx = match slot {
+            CycleSlot::Power => 0,
+            CycleSlot::Utility => 1,
+            CycleSlot::Left => 2,
+            CycleSlot::Right => 3,
+        };
+        self.loadout
+            .get(idx)
+            .map(|xs| xs.as_str())
+            .filter(|xs| !xs.is_empty())
+    }
 }
 
-/// Trait for anything that can be in a cycle.
+/// Trait for anything that can be in a cycle. Cycles match entries by this
+/// identifier alone, never by display name.
 pub trait CycleEntry {
     /// A unique string identifier for this item.
     fn identifier(&self) -> String;
@@ -124,6 +146,8 @@ where
     fn advance(&mut self, amount: usize) -> Option<T>;
     fn peek_next(&self) -> Option<T>;
     fn includes(&self, item: &T) -> bool;
+    /// Add to the end. If an entry with the same identifier is already here,
+    /// it's refreshed in place instead, and this returns false.
     fn add(&mut self, item: &T) -> bool;
     fn delete(&mut self, item: &T) -> bool;
     fn filter_id(&mut self, id: &str) -> bool;
@@ -165,12 +189,16 @@ where
     }
 
     fn includes(&self, item: &T) -> bool {
-        self.iter().any(|xs| xs == item)
+        let id = item.identifier();
+        self.iter().any(|xs| xs.identifier() == id)
     }
 
     fn add(&mut self, item: &T) -> bool {
-        if self.iter().any(|xs| xs == item) {
-            false // we've already got one
+        let id = item.identifier();
+        if let Some(existing) = self.iter_mut().find(|xs| xs.identifier() == id) {
+            // we've already got one, but the new data might be fresher
+            *existing = item.clone();
+            false
         } else {
             self.push(item.clone());
             true
@@ -178,9 +206,7 @@ where
     }
 
     fn delete(&mut self, item: &T) -> bool {
-        let orig_len = self.len();
-        self.retain(|xs| xs != item);
-        orig_len != self.len()
+        self.filter_id(&item.identifier())
     }
 
     fn filter_id(&mut self, id: &str) -> bool {
@@ -197,6 +223,8 @@ pub trait HudItemCycle {
     fn advance_skipping(&mut self, skip: &HudItem) -> Option<String>;
     fn advance_skipping_twohanders(&mut self, cache: &mut ItemCache) -> Option<String>;
     fn names(&self, cache: &mut ItemCache) -> Vec<String>;
+    /// Item descriptions, lined up with `names()`.
+    fn descriptions(&self, cache: &mut ItemCache) -> Vec<String>;
 }
 
 impl HudItemCycle for Vec<String> {
@@ -206,6 +234,12 @@ impl HudItemCycle for Vec<String> {
             .collect::<Vec<_>>()
     }
 
+    fn descriptions(&self, cache: &mut ItemCache) -> Vec<String> {
+        self.iter()
+            .filter_map(|xs| cache.get_or_none(xs.as_str()).map(|xs| xs.description()))
+            .collect::<Vec<_>>()
+    }
+
     fn filter_kind(&mut self, unwanted: &BaseType, cache: &mut ItemCache) {
         self.retain(|xs| {
             let found = cache.get(xs);
@@ -418,6 +452,55 @@ mod tests {
         // advance_skipping_twohanders(&mut self, cache: &mut ItemCache) -> Option<String>;
     }
 
+    #[test]
+    fn entries_match_by_form_not_name() {
+        use crate::data::color::InvColor;
+        use crate::data::weapon::{WeaponEquipType, WeaponType};
+
+        let kind = BaseType::Weapon(WeaponType::new(
+            Icon::WeaponSwordOneHanded,
+            InvColor::default(),
+            WeaponEquipType::EitherHand,
+        ));
+        let vanilla = HudItem::preclassified(
+            "Iron Sword".to_string(),
+            "Skyrim.esm|0x12eb7".to_string(),
+            1,
+            kind.clone(),
+        );
+        let modded = HudItem::preclassified(
+            "Iron Sword".to_string(),
+            "Weapons.esp|0x801".to_string(),
+            1,
+            kind.clone(),
+        );
+        let mut cycle = Vec::<HudItem>::new();
+        assert!(cycle.add(&vanilla));
+        assert!(!cycle.includes(&modded));
+        assert!(cycle.add(&modded));
+        assert_eq!(cycle.len(), 2);
+        assert!(!vanilla.same_form(&modded));
+
+        // A grindstone renames the vanilla sword; it's still the same entry,
+        // and the cycle keeps the new name.
+        let tempered = HudItem::preclassified(
+            "Iron Sword (Fine)".to_string(),
+            "Skyrim.esm|0x12eb7".to_string(),
+            1,
+            kind,
+        );
+        assert!(tempered.same_form(&vanilla));
+        assert!(cycle.includes(&tempered));
+        assert!(!cycle.add(&tempered));
+        assert_eq!(cycle.len(), 2);
+        assert_eq!(
+            cycle.top().map(|xs| xs.name()),
+            Some("Iron Sword (Fine)".to_string())
+        );
+        assert!(cycle.delete(&vanilla));
+        assert_eq!(cycle.ids(), vec!["Weapons.esp|0x801"]);
+    }
+
     #[test]
     fn finding_the_next_id() {
         let mut cycle = Vec::<EquipSet>::new();
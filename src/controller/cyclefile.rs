@@ -0,0 +1,283 @@
+//! The cycles as a toml file players can read, edit by hand, and share. The
+//! file looks like this:
+//!
+//! ```toml
+//! format = 1
+//!
+//! [[power]]
+//! form_spec = "Skyrim.esm|0x13e09"
+//! name = "Unrelenting Force"
+//! kind = "Shout"
+//!
+//! [[right]]
+//! form_spec = "Skyrim.esm|0x12eb7"
+//! name = "Iron Sword"
+//! kind = "Sword"
+//! ```
+//!
+//! There's one array of tables for each of `power`, `utility`, `left`, and
+//! `right`. Only the form spec matters on import. Names and kinds are written
+//! for people reading the file, and the game's own data wins over them.
+
+use std::fs;
+
+use eyre::{eyre, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::cycles::CycleData;
+use super::keys::CycleSlot;
+use crate::data::item_cache::ItemCache;
+use crate::data::{BaseType, HudItem};
+
+/// The version of the file format we write, and the newest we can read.
+pub const CYCLE_FILE_FORMAT: u32 = 1;
+
+const SLOTS: [CycleSlot; 4] = [
+    CycleSlot::Power,
+    CycleSlot::Utility,
+    CycleSlot::Left,
+    CycleSlot::Right,
+];
+
+/// One cycle entry in the file.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CycleFileEntry {
+    pub form_spec: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: String,
+}
+
+impl From<&HudItem> for CycleFileEntry {
+    fn from(item: &HudItem) -> Self {
+        Self {
+            form_spec: item.form_string(),
+            name: item.name(),
+            kind: item.kind().to_string(),
+        }
+    }
+}
+
+/// The file as written.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CycleFile {
+    pub format: u32,
+    #[serde(default)]
+    pub power: Vec<CycleFileEntry>,
+    #[serde(default)]
+    pub utility: Vec<CycleFileEntry>,
+    #[serde(default)]
+    pub left: Vec<CycleFileEntry>,
+    #[serde(default)]
+    pub right: Vec<CycleFileEntry>,
+}
+
+/// What importing a file would do: the new contents of every cycle, and how
+/// many of the file's entries made it in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportPlan {
+    pub cycles: Vec<(CycleSlot, Vec<String>)>,
+    pub accepted: usize,
+    pub rejected: usize,
+}
+
+impl CycleFile {
+    /// Describe the cycles as they are now.
+    pub fn from_cycles(cycles: &CycleData, cache: &mut ItemCache) -> Self {
+        let mut file = CycleFile {
+            format: CYCLE_FILE_FORMAT,
+            ..Default::default()
+        };
+        for slot in SLOTS.iter() {
+            *file.entries_mut(slot) = cycles
+                .formids(slot)
+                .iter()
+                .map(|spec| CycleFileEntry::from(&cache.get(spec)))
+                .collect();
+        }
+        file
+    }
+
+    pub fn entries(&self, slot: &CycleSlot) -> &[CycleFileEntry] {
+        match slot {
+            CycleSlot::Power => &self.power,
+            CycleSlot::Utility => &self.utility,
+            CycleSlot::Left => &self.left,
+            CycleSlot::Right => &self.right,
+        }
+    }
+
+    fn entries_mut(&mut self, slot: &CycleSlot) -> &mut Vec<CycleFileEntry> {
+        match slot {
+            CycleSlot::Power => &mut self.power,
+            CycleSlot::Utility => &mut self.utility,
+            CycleSlot::Left => &mut self.left,
+            CycleSlot::Right => &mut self.right,
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).wrap_err("The cycles can't be written as toml.")
+    }
+
+    /// Parse a file. Anything that isn't a cycle file we understand is an error.
+    pub fn from_toml(buf: &str) -> Result<Self> {
+        let parsed =
+            toml::from_str::<CycleFile>(buf).wrap_err("The cycles file can't be parsed.")?;
+        if parsed.format == 0 || parsed.format > CYCLE_FILE_FORMAT {
+            return Err(eyre!(
+                "The cycles file is format {}, but we only understand format {CYCLE_FILE_FORMAT}.",
+                parsed.format
+            ));
+        }
+        Ok(parsed)
+    }
+
+    pub fn write_to_file(&self, pathstr: &str) -> Result<()> {
+        let buf = self.to_toml()?;
+        fs::write(pathstr, buf)
+            .wrap_err_with(|| format!("Unable to write the cycles file: {}", pathstr))
+    }
+
+    pub fn read_from_file(pathstr: &str) -> Result<Self> {
+        let buf = fs::read_to_string(pathstr)
+            .wrap_err_with(|| format!("Unable to read the cycles file: {}", pathstr))?;
+        CycleFile::from_toml(&buf)
+    }
+
+    /// Work out what importing this file would do, without doing it. Each
+    /// entry is looked up in the game and must be something its cycle takes,
+    /// exactly as if the player had toggled it from a menu. With `replace`,
+    /// the file's cycles stand in for the current ones; otherwise its entries
+    /// are added after what's already there.
+    pub fn plan_import<F>(
+        &self,
+        current: &CycleData,
+        replace: bool,
+        max_len: usize,
+        mut lookup: F,
+    ) -> ImportPlan
+    where
+        F: FnMut(&str) -> HudItem,
+    {
+        let mut plan = ImportPlan::default();
+        for slot in SLOTS.iter() {
+            let mut specs = if replace {
+                Vec::new()
+            } else {
+                current.formids(slot)
+            };
+            for entry in self.entries(slot) {
+                let item = lookup(&entry.form_spec);
+                let spec = item.form_string();
+                if matches!(item.kind(), BaseType::Empty) || !CycleData::accepts(slot, &item) {
+                    log::info!(
+                        "Not importing '{}' into the {slot} cycle; the game says it's a {}.",
+                        entry.form_spec,
+                        item.kind()
+                    );
+                    plan.rejected += 1;
+                } else if specs.contains(&spec) {
+                    plan.accepted += 1;
+                } else if specs.len() >= max_len {
+                    log::info!(
+                        "Not importing '{}' into the {slot} cycle; it's full.",
+                        entry.form_spec
+                    );
+                    plan.rejected += 1;
+                } else {
+                    specs.push(spec);
+                    plan.accepted += 1;
+                }
+            }
+            plan.cycles.push((slot.clone(), specs));
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::item_cache::fetch_game_item;
+
+    #[test]
+    fn cycles_survive_the_round_trip() {
+        let mut cache = ItemCache::default();
+        let mut cycles = CycleData::default();
+        let sword = cache.get(&"Skyrim.esm|0x12eb7".to_string());
+        let mace = cache.get(&"Skyrim.esm|0x13982".to_string());
+        cycles.add_item(CycleSlot::Right, &sword);
+        cycles.add_item(CycleSlot::Right, &mace);
+        cycles.add_item(CycleSlot::Left, &mace);
+
+        let file = CycleFile::from_cycles(&cycles, &mut cache);
+        let buf = file.to_toml().expect("the cycles can be written");
+        assert!(buf.contains("[[right]]"));
+        assert!(buf.contains(&format!("name = \"{}\"", sword.name())));
+        let read = CycleFile::from_toml(&buf).expect("and read back");
+        assert_eq!(read, file);
+        assert_eq!(read.format, CYCLE_FILE_FORMAT);
+        assert_eq!(read.entries(&CycleSlot::Right).len(), 2);
+        assert!(read.entries(&CycleSlot::Power).is_empty());
+
+        // Files we can't understand are refused whole.
+        assert!(CycleFile::from_toml("format = 1\n[[right]]\nname = 4").is_err());
+        assert!(CycleFile::from_toml("format = 99").is_err());
+        assert!(CycleFile::from_toml("[[left]]\nform_spec = \"a|0x1\"").is_err());
+    }
+
+    #[test]
+    fn imports_check_each_entry_against_its_cycle() {
+        let buf = r#"
+format = 1
+
+[[power]]
+form_spec = "Skyrim.esm|0x12eb7"
+name = "Not a shout"
+
+[[right]]
+form_spec = "Skyrim.esm|0x12eb7"
+
+[[right]]
+form_spec = "Skyrim.esm|0x13982"
+kind = "whatever the player typed"
+
+[[right]]
+form_spec = "Skyrim.esm|0x13983"
+"#;
+        let file = CycleFile::from_toml(buf).expect("the file parses");
+        let mut cycles = CycleData::default();
+        let mut cache = ItemCache::default();
+        let axe = cache.get(&"Skyrim.esm|0x13980".to_string());
+        cycles.add_item(CycleSlot::Right, &axe);
+
+        // The test game says everything is a weapon.
+        let merged = file.plan_import(&cycles, false, 3, fetch_game_item);
+        assert_eq!(merged.accepted, 2);
+        assert_eq!(merged.rejected, 2);
+        let right = merged
+            .cycles
+            .iter()
+            .find(|(slot, _)| *slot == CycleSlot::Right)
+            .map(|(_, specs)| specs.clone())
+            .unwrap_or_default();
+        assert_eq!(
+            right,
+            vec![
+                "Skyrim.esm|0x13980",
+                "Skyrim.esm|0x12eb7",
+                "Skyrim.esm|0x13982"
+            ]
+        );
+
+        let replaced = file.plan_import(&cycles, true, 3, fetch_game_item);
+        assert_eq!(replaced.accepted, 3);
+        assert_eq!(replaced.rejected, 1);
+        assert!(replaced
+            .cycles
+            .iter()
+            .all(|(_, specs)| !specs.contains(&"Skyrim.esm|0x13980".to_string())));
+    }
+}
@@ -1,20 +1,18 @@
 //! Management of the cycle data: serialization and mutation.
 
+use std::collections::HashMap;
 use std::fmt::Display;
 
 use cxx::CxxVector;
 
-use super::control::MenuEventResponse;
 use super::cycleentries::*;
 use super::keys::CycleSlot;
+use super::limits::DailyUses;
 use super::user_settings;
-use crate::data::item_cache::ItemCache;
+use crate::data::item_cache::{fetch_game_item, ItemCache};
 use crate::data::{BaseType, HudItem};
 use crate::images::icons::Icon;
-use crate::plugin::{
-    hasItemOrSpell, healthPotionCount, itemCount, magickaPotionCount, staminaPotionCount,
-    startAlphaTransition, EquippedData,
-};
+use crate::plugin::{formSpecExists, startAlphaTransition, EquippedData, MenuEventResponse};
 
 /// Manage the player's configured item cycles. Track changes, persist data in
 /// files, and advance the cycle when the player presses a cycle button. This
@@ -32,6 +30,27 @@ pub struct CycleData {
     utility: Vec<String>,
     /// Equipment sets.
     equipsets: Vec<EquipSet>,
+    /// Formspecs the player has flagged as tools, in addition to the ones we recognize.
+    tools: Vec<String>,
+    /// The game day on which each cycle entry was last equipped or used.
+    last_used: HashMap<String, u32>,
+    /// Uses today counted against the player's daily limits.
+    daily_uses: DailyUses,
+    /// How many times each cycle entry has been equipped or used. Sorting
+    /// cycles by usage reads these, and so can anything that wants stats.
+    use_counts: HashMap<String, u32>,
+    /// The game day cycles were last sorted by usage, if they ever were.
+    last_sorted: Option<u32>,
+    /// The entry each cycle starts from, so we can tell the player when a
+    /// cycle has come back around.
+    starts: HashMap<CycleSlot, String>,
+    /// Whether the latest move through each cycle crossed its start. Read
+    /// once by `take_wrapped()`. Not persisted.
+    wrapped: HashMap<CycleSlot, bool>,
+    /// Cycle entries whose forms couldn't be found when a save loaded, most
+    /// likely because the player uninstalled their plugin. Kept so that
+    /// reinstalling the plugin brings them back.
+    quarantined: Vec<(CycleSlot, String)>,
     /// Was the hud visible when we saved?
     pub hud_visible: bool,
     /// Was this cycle loaded from a cosave or are we operating on defaults?
@@ -46,6 +65,14 @@ impl Default for CycleData {
             power: Default::default(),
             utility: Default::default(),
             equipsets: Default::default(),
+            tools: Default::default(),
+            last_used: Default::default(),
+            daily_uses: Default::default(),
+            use_counts: Default::default(),
+            last_sorted: None,
+            starts: Default::default(),
+            wrapped: Default::default(),
+            quarantined: Default::default(),
             hud_visible: true,
             loaded: false,
         }
@@ -60,6 +87,14 @@ impl CycleData {
         self.left.clear();
         self.right.clear();
         self.equipsets.clear();
+        self.tools.clear();
+        self.last_used.clear();
+        self.daily_uses.clear();
+        self.use_counts.clear();
+        self.last_sorted = None;
+        self.starts.clear();
+        self.wrapped.clear();
+        self.quarantined.clear();
     }
 
     /// Internal use only. Get a mutable reference to the named cycle.
@@ -86,6 +121,10 @@ impl CycleData {
         self.get_cycle(which).names(cache)
     }
 
+    pub fn descriptions(&self, which: &CycleSlot, cache: &mut ItemCache) -> Vec<String> {
+        self.get_cycle(which).descriptions(cache)
+    }
+
     pub fn formids(&self, which: &CycleSlot) -> Vec<String> {
         let cycle = match which {
             CycleSlot::Power => &self.power,
@@ -97,22 +136,136 @@ impl CycleData {
     }
 
     /// Advance the given cycle by one. Returns a copy of the newly-top item.
+    /// Whether the move crossed the start of the cycle is waiting in
+    /// `take_wrapped()`.
     ///
     /// Called when the player presses a hotkey bound to one of the cycle slots.
     /// This does not equip or try to use the item in any way. It's pure management.
     pub fn advance(&mut self, which: &CycleSlot, amount: usize) -> Option<String> {
-        self.get_cycle_mut(which).advance(amount)
+        let before = self.get_cycle(which).clone();
+        let top = self.get_cycle_mut(which).advance(amount);
+        self.note_move(which, &before, top.as_deref(), true);
+        top
+    }
+
+    /// Keep advancing the given cycle until `usable` accepts the top entry,
+    /// starting with `first`, which is already on top. Gives up after one trip
+    /// around the cycle.
+    pub fn advance_until<F>(
+        &mut self,
+        which: &CycleSlot,
+        first: String,
+        mut usable: F,
+    ) -> Option<String>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut candidate = first;
+        for _ in 0..self.cycle_len(which) {
+            if usable(&candidate) {
+                return Some(candidate);
+            }
+            candidate = self.advance(which, 1)?;
+        }
+        None
+    }
+
+    /// Step the given cycle backwards. Returns a copy of the newly-top item.
+    pub fn retreat(&mut self, which: &CycleSlot, amount: usize) -> Option<String> {
+        let before = self.get_cycle(which).clone();
+        let cycle = self.get_cycle_mut(which);
+        let top = if cycle.is_empty() || amount > cycle.len() {
+            None
+        } else {
+            cycle.rotate_right(amount);
+            cycle.top()
+        };
+        self.note_move(which, &before, top.as_deref(), false);
+        top
     }
 
     /// Advance the given cycle, skipping over the passed-in item if necessary.
     pub fn advance_skipping(&mut self, which: &CycleSlot, skip: HudItem) -> Option<String> {
-        self.get_cycle_mut(which).advance_skipping(&skip)
+        let before = self.get_cycle(which).clone();
+        let top = self.get_cycle_mut(which).advance_skipping(&skip);
+        self.note_move(which, &before, top.as_deref(), true);
+        top
     }
 
     /// Advance the right-hand cycle skipping over all two-handed items to the next one-hander.
     pub fn advance_skipping_twohanders(&mut self, cache: &mut ItemCache) -> Option<String> {
         // This is only relevant for the right hand.
-        self.right.advance_skipping_twohanders(cache)
+        let before = self.right.clone();
+        let top = self.right.advance_skipping_twohanders(cache);
+        self.note_move(&CycleSlot::Right, &before, top.as_deref(), true);
+        top
+    }
+
+    /// Remember whether a move from the `before` order to the `landed` entry
+    /// crossed the start of the cycle. Several moves before anyone asks, as
+    /// when skipping entries, count as one move. A cycle starts from whatever was on top
+    /// the first time it moved; if that entry leaves the cycle, the top at the
+    /// next move takes over.
+    fn note_move(
+        &mut self,
+        which: &CycleSlot,
+        before: &[String],
+        landed: Option<&str>,
+        forward: bool,
+    ) {
+        let wrapped = match (landed, before.first()) {
+            (Some(landed), Some(first)) => {
+                let start = match self.starts.get(which) {
+                    Some(start) if before.contains(start) => start.clone(),
+                    _ => {
+                        self.starts.insert(which.clone(), first.clone());
+                        first.clone()
+                    }
+                };
+                let start_at = before.iter().position(|xs| *xs == start);
+                let landed_at = before.iter().position(|xs| xs == landed);
+                match (start_at, landed_at) {
+                    (Some(start_at), Some(landed_at)) => {
+                        crossed_start(start_at, landed_at, forward)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        *self.wrapped.entry(which.clone()).or_default() |= wrapped;
+    }
+
+    /// Did the latest move through this cycle cross its start? Answers once
+    /// per move; asking again says no.
+    pub fn take_wrapped(&mut self, which: &CycleSlot) -> bool {
+        self.wrapped.remove(which).unwrap_or_default()
+    }
+
+    /// The entries the player has flagged as tools. Keyword-recognized tools
+    /// aren't listed; `HudItem::is_tool()` knows those.
+    pub fn flagged_tools(&self) -> Vec<String> {
+        self.tools.clone()
+    }
+
+    /// Is this item a tool, either by its keywords or because the player said so?
+    pub fn is_tool(&self, item: &HudItem) -> bool {
+        item.is_tool() || self.tools.includes(&item.form_string())
+    }
+
+    /// Flip the player's tool flag for the item at this position in the given cycle.
+    /// Returns the item's new tool status.
+    pub fn toggle_tool_flag(&mut self, which: &CycleSlot, index: usize) -> bool {
+        let Some(spec) = self.get_cycle(which).get(index).cloned() else {
+            return false;
+        };
+        if self.tools.includes(&spec) {
+            self.tools.delete(&spec);
+            false
+        } else {
+            self.tools.add(&spec);
+            true
+        }
     }
 
     /// Get the length of the given cycle.
@@ -149,58 +302,84 @@ impl CycleData {
     /// Does not change the current item in the cycle, unless the current item is
     /// the one removed. Adds at the end.
     pub fn toggle(&mut self, which: &CycleSlot, item: HudItem) -> MenuEventResponse {
-        let cycle = match which {
-            CycleSlot::Power => {
-                if !matches!(item.kind(), BaseType::Power(_) | BaseType::Shout(_)) {
-                    return MenuEventResponse::ItemInappropriate;
-                }
-                &mut self.power
-            }
-            CycleSlot::Left => {
-                if !item.kind().left_hand_ok() {
-                    return MenuEventResponse::ItemInappropriate;
-                }
-                &mut self.left
-            }
-            CycleSlot::Right => {
-                if !item.kind().right_hand_ok() {
-                    return MenuEventResponse::ItemInappropriate;
-                }
-                &mut self.right
-            }
-            CycleSlot::Utility => {
-                if !item.kind().is_utility() {
-                    return MenuEventResponse::ItemInappropriate;
-                }
-                &mut self.utility
-            }
-        };
+        if !CycleData::accepts(which, &item) {
+            return MenuEventResponse::ItemInappropriate;
+        }
+        let cycle = self.get_cycle_mut(which);
 
-        // We have at most 20 items, so we do this blithely.
+        // Cycles are short, so we do this blithely. Removing always works, even
+        // from a cycle longer than the cap allows.
         let settings = user_settings();
         let spec = item.form_string();
-        if cycle.includes(&spec) {
+        let response = if cycle.includes(&spec) {
             cycle.delete(&spec);
             MenuEventResponse::ItemRemoved
-        } else if cycle.len() >= settings.maxlen() as usize {
+        } else if cycle.len() >= settings.max_cycle_length() as usize {
             return MenuEventResponse::TooManyItems;
         } else {
             cycle.add(&spec);
             MenuEventResponse::ItemAdded
-        }
+        };
+        self.use_counts.remove(&spec);
+        response
     }
 
-    pub fn remove_zero_count_items(&mut self, form_spec: &str, kind: &BaseType) {
+    /// Drop an item the player has none of from the cycles it can be in.
+    /// Returns true if any cycle had it.
+    pub fn remove_zero_count_items(&mut self, form_spec: &str, kind: &BaseType) -> bool {
         if kind.is_utility() {
-            self.utility.filter_id(form_spec);
-            return;
+            return self.utility.filter_id(form_spec);
         }
+        let mut removed = false;
         if kind.left_hand_ok() {
-            self.left.filter_id(form_spec);
+            removed |= self.left.filter_id(form_spec);
         }
         if kind.right_hand_ok() {
-            self.right.filter_id(form_spec);
+            removed |= self.right.filter_id(form_spec);
+        }
+        removed
+    }
+
+    /// Would toggling this item take the equipped entry out of the cycle?
+    /// `equipped` is the form spec in the slot the cycle feeds.
+    pub fn removes_equipped(&self, which: &CycleSlot, item: &HudItem, equipped: &str) -> bool {
+        !equipped.is_empty() && item.form_string() == equipped && self.includes(which, item)
+    }
+
+    /// Did this hand just use up the entry it was holding? Usually the HUD
+    /// still shows it. A scroll's last cast empties the hand before the count
+    /// reaches us, though, so a bare hand with that scroll still on top of
+    /// its cycle ran out too.
+    pub fn hand_ran_out(
+        &self,
+        which: &CycleSlot,
+        form_spec: &str,
+        kind: &BaseType,
+        shown: Option<&HudItem>,
+    ) -> bool {
+        match shown {
+            Some(visible) if visible.form_string() == form_spec => return true,
+            Some(visible) if !matches!(visible.kind(), BaseType::HandToHand | BaseType::Empty) => {
+                return false
+            }
+            _ => {}
         }
+        matches!(kind, BaseType::Scroll(_)) && self.get_top(which).as_deref() == Some(form_spec)
+    }
+
+    /// Is this the kind of thing that goes in the given cycle?
+    pub fn accepts(which: &CycleSlot, item: &HudItem) -> bool {
+        match which {
+            CycleSlot::Power => matches!(item.kind(), BaseType::Power(_) | BaseType::Shout(_)),
+            CycleSlot::Left => item.kind().left_hand_ok(),
+            CycleSlot::Right => item.kind().right_hand_ok(),
+            CycleSlot::Utility => item.kind().is_utility(),
+        }
+    }
+
+    /// Replace everything in the given cycle at once, as an import does.
+    pub fn set_cycle(&mut self, which: &CycleSlot, form_specs: Vec<String>) {
+        *self.get_cycle_mut(which) = form_specs;
     }
 
     /// Check if the given cycle includes the example item or not.
@@ -210,11 +389,251 @@ impl CycleData {
 
     /// Make sure the given cycle includes this item, adding it if it does not.
     pub fn add_item(&mut self, which: CycleSlot, item: &HudItem) -> bool {
-        self.get_cycle_mut(&which).add(&item.form_string())
+        let added = self.get_cycle_mut(&which).add(&item.form_string());
+        if added {
+            self.use_counts.remove(&item.form_string());
+        }
+        added
     }
 
     pub fn remove_item(&mut self, which: CycleSlot, item: &HudItem) -> bool {
-        self.get_cycle_mut(&which).delete(&item.form_string())
+        let removed = self.get_cycle_mut(&which).delete(&item.form_string());
+        if removed {
+            self.use_counts.remove(&item.form_string());
+        }
+        removed
+    }
+
+    /// Where this form spec sits in the given cycle, if it's there at all.
+    pub fn position(&self, which: &CycleSlot, form_spec: &str) -> Option<usize> {
+        self.get_cycle(which).iter().position(|xs| xs == form_spec)
+    }
+
+    /// Put this form spec back into the given cycle at a specific position.
+    /// Positions past the end append. Does nothing if the entry is already there.
+    pub fn insert_at(&mut self, which: &CycleSlot, form_spec: &str, index: usize) -> bool {
+        let cycle = self.get_cycle_mut(which);
+        if cycle.iter().any(|xs| xs == form_spec) {
+            return false;
+        }
+        let index = index.min(cycle.len());
+        cycle.insert(index, form_spec.to_string());
+        self.use_counts.remove(form_spec);
+        true
+    }
+
+    /// The entry just before this one in the given cycle. Cycles turn, so a
+    /// neighbour says where an entry belongs long after its index has gone
+    /// stale. None if the entry is on top or isn't in the cycle.
+    pub fn entry_before(&self, which: &CycleSlot, form_spec: &str) -> Option<String> {
+        let cycle = self.get_cycle(which);
+        match cycle.iter().position(|xs| xs == form_spec) {
+            Some(index) if index > 0 => Some(cycle[index - 1].clone()),
+            _ => None,
+        }
+    }
+
+    /// Put this form spec back right after `after` in the given cycle, or on
+    /// top if there's no neighbour. If the neighbour has left the cycle too,
+    /// the entry goes at the end. Does nothing if the entry is already there.
+    pub fn insert_after(
+        &mut self,
+        which: &CycleSlot,
+        form_spec: &str,
+        after: Option<&str>,
+    ) -> bool {
+        let index = match after {
+            None => 0,
+            Some(neighbour) => self
+                .position(which, neighbour)
+                .map(|xs| xs + 1)
+                .unwrap_or(usize::MAX),
+        };
+        self.insert_at(which, form_spec, index)
+    }
+
+    /// Move the entry at `from` to `to` in the given cycle, with positions
+    /// counted as they are now. The entry on top stays on top, even if it's
+    /// the one moving: the rest of the cycle turns around it. Returns the
+    /// moved entry's new position, or None if either position is out of range.
+    pub fn move_entry(&mut self, which: &CycleSlot, from: usize, to: usize) -> Option<usize> {
+        let cycle = self.get_cycle_mut(which);
+        if from >= cycle.len() || to >= cycle.len() {
+            return None;
+        }
+        if from == to {
+            return Some(to);
+        }
+        let top = cycle[0].clone();
+        let spec = cycle.remove(from);
+        cycle.insert(to, spec.clone());
+        if let Some(top_index) = cycle.iter().position(|xs| *xs == top) {
+            cycle.rotate_left(top_index);
+        }
+        let moved_to = cycle.iter().position(|xs| *xs == spec);
+        self.use_counts.remove(&spec);
+        moved_to
+    }
+
+    /// Put `new` where `old` sits in the given cycle, as when a reusable item
+    /// turns into a different form. If `new` is already in the cycle, `old`
+    /// simply goes. Returns false if `old` wasn't there.
+    pub fn swap_entry(&mut self, which: &CycleSlot, old: &str, new: &str) -> bool {
+        let Some(index) = self.position(which, old) else {
+            return false;
+        };
+        self.remove_spec(which, old);
+        self.insert_at(which, new, index);
+        true
+    }
+
+    /// Take this form spec out of the given cycle, wherever it is.
+    pub fn remove_spec(&mut self, which: &CycleSlot, form_spec: &str) -> bool {
+        let removed = self.get_cycle_mut(which).filter_id(form_spec);
+        if removed {
+            self.use_counts.remove(form_spec);
+        }
+        removed
+    }
+
+    /// Note that this item was equipped or used on the given game day.
+    pub fn mark_used(&mut self, form_spec: &str, today: u32) {
+        if self.in_any_cycle(form_spec) {
+            self.last_used.insert(form_spec.to_string(), today);
+            *self.use_counts.entry(form_spec.to_string()).or_default() += 1;
+        }
+    }
+
+    /// How many times this entry has been equipped or used since it was put
+    /// where it is in its cycle.
+    pub fn use_count(&self, form_spec: &str) -> u32 {
+        self.use_counts.get(form_spec).copied().unwrap_or_default()
+    }
+
+    /// Reorder the cycles so the most-used entries come up soonest. This
+    /// happens at most once per game day, so cycles don't shuffle under the
+    /// player's fingers. The top of each cycle stays put, because it might be
+    /// a selection that's waiting to be equipped; everything after it is
+    /// sorted by use, with ties keeping their current order. Returns the slots
+    /// whose order changed.
+    pub fn sort_by_usage(&mut self, today: u32, cache: &mut ItemCache) -> Vec<CycleSlot> {
+        if self.last_sorted == Some(today) {
+            return Vec::new();
+        }
+        self.last_sorted = Some(today);
+
+        let mut changed = Vec::new();
+        for slot in [
+            CycleSlot::Power,
+            CycleSlot::Utility,
+            CycleSlot::Left,
+            CycleSlot::Right,
+        ] {
+            let counts = &self.use_counts;
+            let cycle = self.get_cycle(&slot);
+            if cycle.len() < 3 {
+                continue;
+            }
+            let mut rest = cycle[1..].to_vec();
+            rest.sort_by_key(|spec| {
+                std::cmp::Reverse(counts.get(spec).copied().unwrap_or_default())
+            });
+            if rest[..] == cycle[1..] {
+                continue;
+            }
+
+            let cycle = self.get_cycle_mut(&slot);
+            cycle.truncate(1);
+            cycle.extend(rest);
+            let order: Vec<String> = self
+                .get_cycle(&slot)
+                .iter()
+                .map(|spec| format!("{} ({})", cache.get(spec).name(), self.use_count(spec)))
+                .collect();
+            log::info!(
+                "Sorted the {slot:?} cycle by how often you use each entry: {}",
+                order.join(", ")
+            );
+            changed.push(slot);
+        }
+        changed
+    }
+
+    fn in_any_cycle(&self, form_spec: &str) -> bool {
+        [&self.power, &self.utility, &self.left, &self.right]
+            .iter()
+            .any(|cycle| cycle.iter().any(|xs| xs == form_spec))
+    }
+
+    /// Bring usage dates in line with the cycles and the calendar. Entries we
+    /// have no date for start aging today. Dates in the future come from
+    /// loading an older save, so they're clamped to today. Dates for entries
+    /// no longer in any cycle are dropped.
+    pub fn reconcile_usage_dates(&mut self, today: u32) {
+        let mut dates: HashMap<String, u32> = HashMap::new();
+        for cycle in [&self.power, &self.utility, &self.left, &self.right] {
+            for spec in cycle.iter() {
+                let day = self.last_used.get(spec).copied().unwrap_or(today);
+                dates.insert(spec.clone(), day.min(today));
+            }
+        }
+        self.last_used = dates;
+        let last_used = &self.last_used;
+        self.use_counts
+            .retain(|spec, _| last_used.contains_key(spec));
+    }
+
+    /// Find cycle entries that haven't been used in more than `days` game
+    /// days. Entries the `keep` test approves of are never candidates.
+    pub fn prune_candidates<F>(&self, days: u32, today: u32, keep: F) -> Vec<(CycleSlot, String)>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut found = Vec::new();
+        for slot in [
+            CycleSlot::Power,
+            CycleSlot::Utility,
+            CycleSlot::Left,
+            CycleSlot::Right,
+        ] {
+            for spec in self.get_cycle(&slot).iter() {
+                let last = self.last_used.get(spec).copied().unwrap_or(today);
+                if today.saturating_sub(last) > days && !keep(spec) {
+                    found.push((slot.clone(), spec.clone()));
+                }
+            }
+        }
+        found
+    }
+
+    /// Today's uses counted against the player's daily limits.
+    pub fn daily_uses(&mut self) -> &mut DailyUses {
+        &mut self.daily_uses
+    }
+
+    /// Ammo entries in the utility cycle, in cycle order and without duplicates.
+    /// Older configs and other HUDs put ammo there.
+    pub fn utility_ammo(&self, cache: &mut ItemCache) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        for spec in self.utility.iter() {
+            if cache.get(spec).is_ammo() && !found.contains(spec) {
+                found.push(spec.clone());
+            }
+        }
+        found
+    }
+
+    /// Move ammo out of the utility cycle and leave it to ammo cycling, which
+    /// rotates through every ammo type in the player's inventory. Entries the
+    /// ammo cycle already covers are not duplicated anywhere. The utility
+    /// cycle keeps its current top item unless that item was ammo, in which
+    /// case the next non-ammo entry becomes the top. Returns what moved.
+    pub fn relocate_utility_ammo(&mut self, cache: &mut ItemCache) -> Vec<String> {
+        let relocated = self.utility_ammo(cache);
+        if !relocated.is_empty() {
+            self.utility.retain(|xs| !relocated.contains(xs));
+        }
+        relocated
     }
 
     pub fn filter_kind(&mut self, which: &CycleSlot, unwanted: &BaseType, cache: &mut ItemCache) {
@@ -242,10 +661,11 @@ impl CycleData {
 
     // ---------- validation
 
-    /// Remove any items that have vanished from the game or from the player's
-    /// inventory. This is called rarely and at times where we can spend the
-    /// cycles to look up the answer.
-    pub fn validate(&mut self, cache: &mut ItemCache) {
+    /// Log what's in the cycles, and remove any items that have vanished from
+    /// the game or from the player's inventory if the player wants them pruned.
+    /// This is called rarely and at times where we can spend the cycles to look
+    /// up the answer.
+    pub fn validate(&mut self, cache: &mut ItemCache, prune: bool) {
         let to_check = vec![
             (CycleSlot::Power, "power"),
             (CycleSlot::Utility, "utility"),
@@ -261,33 +681,29 @@ impl CycleData {
                 CycleSlot::Right => &mut self.right,
             };
             log::info!("validating {name} cycle");
-            let filtered: Vec<_> = cycle
+            let mut present = Vec::new();
+            let mut filtered: Vec<_> = cycle
                 .iter()
                 .filter_map(|incoming| {
                     let spec = incoming.clone();
                     let item = cache.get(&spec.identifier()); // works if vec of HudItem or vec<string>
-
-                    cxx::let_cxx_string!(form_spec = spec.clone());
-                    if hasItemOrSpell(&form_spec) {
+                    if player_has(&spec) {
                         log::info!("    {item}");
-                        return Some(spec);
-                    }
-
-                    let count = match spec.as_str() {
-                        "health_proxy" => healthPotionCount(),
-                        "magicka_proxy" => magickaPotionCount(),
-                        "stamina_proxy" => staminaPotionCount(),
-                        "unarmed_proxy" => 1,
-                        _ => itemCount(&form_spec),
-                    };
-                    if count > 0 {
-                        log::info!("    {incoming}");
+                        present.push(true);
                         Some(spec)
-                    } else {
+                    } else if prune {
                         None
+                    } else {
+                        log::info!("    {item} (not in inventory)");
+                        present.push(false);
+                        Some(spec)
                     }
                 })
                 .collect();
+            // The top of each cycle is what the player had selected when they
+            // saved. If that's gone, the selection moves to the next entry they
+            // still have rather than back to the start.
+            rotate_to_first_present(&mut filtered, &present);
 
             match &xs.0 {
                 CycleSlot::Power => {
@@ -322,6 +738,55 @@ impl CycleData {
         log::info!("Have a nice day and remember to put on a cloak if it starts snowing.");
     }
 
+    // ---------- missing plugins
+
+    /// Take out of the cycles every entry from a plugin whose form can't be
+    /// found, and hold onto it in case the plugin comes back. Returns what was
+    /// taken out.
+    pub fn quarantine_missing<F>(&mut self, mut exists: F) -> Vec<(CycleSlot, String)>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut removed = Vec::new();
+        for slot in [
+            CycleSlot::Power,
+            CycleSlot::Utility,
+            CycleSlot::Left,
+            CycleSlot::Right,
+        ] {
+            let cycle = self.get_cycle_mut(&slot);
+            cycle.retain(|spec| {
+                let gone = names_plugin_form(spec) && !exists(spec);
+                if gone {
+                    removed.push((slot.clone(), spec.clone()));
+                }
+                !gone
+            });
+        }
+        for entry in removed.iter() {
+            if !self.quarantined.contains(entry) {
+                self.quarantined.push(entry.clone());
+            }
+        }
+        removed
+    }
+
+    /// Put quarantined entries whose forms can be found again back at the end
+    /// of their cycles. Returns what came back.
+    pub fn restore_quarantined<F>(&mut self, mut exists: F) -> Vec<(CycleSlot, String)>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let (found, still_missing): (Vec<_>, Vec<_>) = std::mem::take(&mut self.quarantined)
+            .into_iter()
+            .partition(|(_, spec)| exists(spec));
+        self.quarantined = still_missing;
+        for (slot, spec) in found.iter() {
+            self.get_cycle_mut(slot).add(spec);
+        }
+        found
+    }
+
     // equipset cycling
 
     pub fn get_top_equipset(&self) -> Option<EquipSet> {
@@ -344,6 +809,21 @@ impl CycleData {
         self.equipsets.add(&set)
     }
 
+    /// Add an equipment set that also fills the power, utility, and hand slots.
+    /// The loadout lists those in that order, as `EquipSet::loadout` does.
+    pub fn add_loadout(&mut self, name: String, data: EquippedData, loadout: Vec<String>) -> bool {
+        let id = self.equipsets.find_next_id();
+        let mut set = EquipSet::new(
+            id,
+            name,
+            data.items,
+            data.empty_slots,
+            "ArmorHeavy".to_string(),
+        );
+        set.loadout = loadout;
+        self.equipsets.add(&set)
+    }
+
     pub fn update_equipset(&mut self, id: u32, data: EquippedData) -> bool {
         self.equipsets.update_set(id, data.items, data.empty_slots)
     }
@@ -391,11 +871,11 @@ impl CycleData {
     // bincode serialization to cosave
 
     pub fn serialize_version() -> u32 {
-        cosave_v2::VERSION
+        cosave_v3::VERSION
     }
 
     pub fn serialize(&self) -> Vec<u8> {
-        let value = cosave_v2::CycleSerialized::from(self);
+        let value = cosave_v3::CycleSerialized::from(self);
         let config = bincode::config::standard();
         let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
         log::info!(
@@ -412,6 +892,7 @@ impl CycleData {
             0 => cosave_v0::deserialize(bytes),
             1 => cosave_v1::deserialize(bytes),
             2 => cosave_v2::deserialize(bytes),
+            3 => cosave_v3::deserialize(bytes),
             _ => {
                 log::warn!(
                     "Cosave data is version {version}, which this plugin version cannot decode."
@@ -440,15 +921,281 @@ impl Display for CycleData {
     }
 }
 
+/// Did a move through a cycle cross its start? Positions count from the old
+/// top, going forward. Going forward crosses when we land on or pass over the
+/// start; going backward crosses when we step back off it. Landing where we
+/// began is a full loop, which always crosses: that's every press in a cycle
+/// of one.
+pub fn crossed_start(start: usize, landed: usize, forward: bool) -> bool {
+    if landed == 0 {
+        return true;
+    }
+    if forward {
+        start != 0 && start <= landed
+    } else {
+        start == 0 || start > landed
+    }
+}
+
+/// Rotate a cycle so its first entry is the first one the player still has,
+/// keeping the order. Leaves it alone if they have none of them.
+fn rotate_to_first_present(cycle: &mut [String], present: &[bool]) {
+    if let Some(first) = present.iter().position(|has| *has) {
+        cycle.rotate_left(first);
+    }
+}
+
+/// Does the game know this form? False for forms from plugins that aren't loaded.
+pub fn form_exists(form_spec: &str) -> bool {
+    cxx::let_cxx_string!(spec = form_spec);
+    formSpecExists(&spec)
+}
+
+/// Is this the spec of a form from a plugin? Proxies aren't, and neither are
+/// forms the game made at runtime.
+fn names_plugin_form(form_spec: &str) -> bool {
+    form_spec
+        .split_once('|')
+        .is_some_and(|(plugin, _)| !plugin.is_empty() && plugin != "dynamic")
+}
+
+/// The spec to keep for a cycle entry read from a cosave, or None to drop it.
+fn restore_spec(xs: &str) -> Option<String> {
+    match xs {
+        "health_proxy" => Some(xs.to_owned()),
+        "magicka_proxy" => Some(xs.to_owned()),
+        "stamina_proxy" => Some(xs.to_owned()),
+        "unarmed_proxy" => Some(xs.to_owned()),
+        "" => None,
+        _ => {
+            // Noting here that we do not go through the cache at all
+            // while loading these items. We probably should. TODO
+            let found = fetch_game_item(xs);
+            if !matches!(found.kind(), BaseType::Empty) {
+                Some(found.form_string())
+            } else if names_plugin_form(xs) {
+                // Perhaps its plugin is gone. The load pass quarantines it.
+                Some(xs.to_owned())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Does the player have this item or spell right now? The potion proxies count
+/// if the player has any potion of their kind, and bare hands are always there.
+#[cfg(not(test))]
+pub fn player_has(form_spec: &str) -> bool {
+    use crate::plugin::{
+        hasItemOrSpell, healthPotionCount, itemCount, magickaPotionCount, staminaPotionCount,
+    };
+
+    cxx::let_cxx_string!(spec = form_spec);
+    if hasItemOrSpell(&spec) {
+        return true;
+    }
+    let count = match form_spec {
+        "health_proxy" => healthPotionCount(),
+        "magicka_proxy" => magickaPotionCount(),
+        "stamina_proxy" => staminaPotionCount(),
+        "unarmed_proxy" => 1,
+        _ => itemCount(&spec),
+    };
+    count > 0
+}
+
+#[cfg(test)]
+pub fn player_has(_form_spec: &str) -> bool {
+    true
+}
+
 // cosave version modules.
 
+pub mod cosave_v3 {
+    use bincode::{Decode, Encode};
+
+    use crate::controller::cycleentries::*;
+    use crate::controller::cycles::CycleData;
+    use crate::controller::keys::CycleSlot;
+    use crate::controller::limits::DailyUses;
+
+    pub const VERSION: u32 = 3;
+
+    pub fn deserialize(bytes: Vec<u8>) -> Option<CycleData> {
+        let config = bincode::config::standard();
+        log::debug!(
+            "reading cosave format version {VERSION}; data len={};",
+            bytes.len()
+        );
+
+        match bincode::decode_from_slice::<CycleSerialized, _>(&bytes[..], config) {
+            Ok((value, _len)) => {
+                log::info!("Cycles successfully read from cosave data version {VERSION}. Save data was {} bytes.", bytes.len());
+                Some(value.into())
+            }
+            Err(e) => {
+                log::error!("Bincode cannot decode the cosave data. len={}", bytes.len());
+                log::error!("{e:#}");
+                None
+            }
+        }
+    }
+
+    /// The order of per-cycle lists in the serialized data.
+    const SLOTS: [CycleSlot; 4] = [
+        CycleSlot::Power,
+        CycleSlot::Utility,
+        CycleSlot::Left,
+        CycleSlot::Right,
+    ];
+
+    type SerializedEquipSet = Vec<(u32, String, Vec<String>, Vec<u8>, String, Vec<String>)>;
+
+    /// The serialization format is a list of form strings. Two drivers for
+    /// this choice: 1) It's compact. 2) It can be deserialized into any
+    /// Rust type we want, thus making it not care about implementation details.
+    /// So the struct uses only built-in rust types, no crate types.
+    /// Version 3 adds tool flags, usage history, daily limits, where each cycle
+    /// starts, equipment set loadouts, and entries whose plugins went missing.
+    #[derive(Decode, Encode, Hash, Debug, Clone, PartialEq, Eq)]
+    pub struct CycleSerialized {
+        left: Vec<String>,
+        right: Vec<String>,
+        power: Vec<String>,
+        utility: Vec<String>,
+        // Vec of tuples of (id, name, Vec<formspec>, Vec<empty_slot>, icon_as_string,
+        // loadout as Vec<formspec or empty> in power, utility, left, right order)
+        equipsets: SerializedEquipSet,
+        tools: Vec<String>,
+        // Vec of tuples of (formspec, game day last used)
+        last_used: Vec<(String, u32)>,
+        // tuple of (game day, Vec<(limit key, uses that day)>)
+        daily_uses: (u32, Vec<(String, u32)>),
+        // Vec of tuples of (formspec, times used)
+        use_counts: Vec<(String, u32)>,
+        // The game day cycles were last sorted by usage, if ever.
+        last_sorted: Option<u32>,
+        // The start of each cycle, in SLOTS order. Empty if a cycle has no start yet.
+        starts: Vec<String>,
+        // Vec of tuples of (index of the cycle in SLOTS, formspec)
+        quarantined: Vec<(u8, String)>,
+        hud_visible: bool,
+    }
+
+    impl From<&CycleData> for CycleSerialized {
+        fn from(value: &CycleData) -> Self {
+            Self {
+                left: value.left.ids(),
+                right: value.right.ids(),
+                power: value.power.ids(),
+                utility: value.utility.ids(),
+                equipsets: value
+                    .equipsets
+                    .iter()
+                    .map(|xs| {
+                        (
+                            xs.id(),
+                            xs.name(),
+                            xs.items.to_vec(),
+                            xs.empty.to_vec(),
+                            xs.icon.to_string(),
+                            xs.loadout.clone(),
+                        )
+                    })
+                    .collect(),
+                tools: value.tools.ids(),
+                last_used: {
+                    let mut dates: Vec<(String, u32)> = value
+                        .last_used
+                        .iter()
+                        .map(|(spec, day)| (spec.clone(), *day))
+                        .collect();
+                    dates.sort();
+                    dates
+                },
+                daily_uses: (value.daily_uses.day(), value.daily_uses.counts()),
+                use_counts: {
+                    let mut counts: Vec<(String, u32)> = value
+                        .use_counts
+                        .iter()
+                        .map(|(spec, count)| (spec.clone(), *count))
+                        .collect();
+                    counts.sort();
+                    counts
+                },
+                last_sorted: value.last_sorted,
+                starts: SLOTS
+                    .iter()
+                    .map(|slot| value.starts.get(slot).cloned().unwrap_or_default())
+                    .collect(),
+                quarantined: value
+                    .quarantined
+                    .iter()
+                    .filter_map(|(slot, spec)| {
+                        let index = SLOTS.iter().position(|xs| xs == slot)?;
+                        Some((index as u8, spec.clone()))
+                    })
+                    .collect(),
+                hud_visible: value.hud_visible,
+            }
+        }
+    }
+
+    impl From<CycleSerialized> for CycleData {
+        fn from(value: CycleSerialized) -> Self {
+            let restore = |specs: Vec<String>| -> Vec<String> {
+                specs
+                    .iter()
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
+                    .collect()
+            };
+
+            Self {
+                left: restore(value.left),
+                right: restore(value.right),
+                power: restore(value.power),
+                utility: restore(value.utility),
+                hud_visible: value.hud_visible,
+                equipsets: value
+                    .equipsets
+                    .into_iter()
+                    .map(|(id, name, items, empty, icon, loadout)| {
+                        let mut set = EquipSet::new(id, name, items, empty, icon);
+                        set.loadout = loadout;
+                        set
+                    })
+                    .collect(),
+                tools: restore(value.tools),
+                last_used: value.last_used.into_iter().collect(),
+                daily_uses: DailyUses::new(
+                    value.daily_uses.0,
+                    value.daily_uses.1.into_iter().collect(),
+                ),
+                use_counts: value.use_counts.into_iter().collect(),
+                last_sorted: value.last_sorted,
+                starts: SLOTS
+                    .into_iter()
+                    .zip(value.starts)
+                    .filter(|(_, spec)| !spec.is_empty())
+                    .collect(),
+                wrapped: Default::default(),
+                quarantined: value
+                    .quarantined
+                    .into_iter()
+                    .filter_map(|(index, spec)| Some((SLOTS.get(index as usize)?.clone(), spec)))
+                    .collect(),
+                loaded: true,
+            }
+        }
+    }
+}
+
 pub mod cosave_v2 {
     use bincode::{Decode, Encode};
 
     use crate::controller::cycleentries::*;
     use crate::controller::cycles::CycleData;
-    use crate::data::base::BaseType;
-    use crate::data::item_cache::fetch_game_item;
 
     pub const VERSION: u32 = 2;
 
@@ -516,46 +1263,26 @@ pub mod cosave_v2 {
 
     impl From<CycleSerialized> for CycleData {
         fn from(value: CycleSerialized) -> Self {
-            fn filter_func(xs: &str) -> Option<String> {
-                match xs {
-                    "health_proxy" => Some(xs.to_owned()),
-                    "magicka_proxy" => Some(xs.to_owned()),
-                    "stamina_proxy" => Some(xs.to_owned()),
-                    "unarmed_proxy" => Some(xs.to_owned()),
-                    "" => None,
-                    _ => {
-                        // Noting here that we do not go through the cache at all
-                        // while loading these items. We probably should. TODO
-                        let found = fetch_game_item(xs);
-                        if matches!(found.kind(), BaseType::Empty) {
-                            None
-                        } else {
-                            Some(found.form_string())
-                        }
-                    }
-                }
-            }
-
             Self {
                 left: value
                     .left
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 right: value
                     .right
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 power: value
                     .power
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 utility: value
                     .utility
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 hud_visible: value.hud_visible,
                 equipsets: value
@@ -571,6 +1298,14 @@ pub mod cosave_v2 {
                         )
                     })
                     .collect(),
+                tools: Vec::new(),
+                last_used: Default::default(),
+                daily_uses: Default::default(),
+                use_counts: Default::default(),
+                last_sorted: None,
+                starts: Default::default(),
+                wrapped: Default::default(),
+                quarantined: Default::default(),
                 loaded: true,
             }
         }
@@ -581,8 +1316,6 @@ pub mod cosave_v1 {
     use bincode::{Decode, Encode};
 
     use crate::controller::cycles::CycleData;
-    use crate::data::base::BaseType;
-    use crate::data::item_cache::fetch_game_item;
 
     pub const VERSION: u32 = 1;
 
@@ -630,49 +1363,37 @@ pub mod cosave_v1 {
 
     impl From<CycleSerialized> for CycleData {
         fn from(value: CycleSerialized) -> Self {
-            fn filter_func(xs: &str) -> Option<String> {
-                match xs {
-                    "health_proxy" => Some(xs.to_owned()),
-                    "magicka_proxy" => Some(xs.to_owned()),
-                    "stamina_proxy" => Some(xs.to_owned()),
-                    "unarmed_proxy" => Some(xs.to_owned()),
-                    "" => None,
-                    _ => {
-                        // Noting here that we do not go through the cache at all
-                        // while loading these items. We probably should. TODO
-                        let found = fetch_game_item(xs);
-                        if matches!(found.kind(), BaseType::Empty) {
-                            None
-                        } else {
-                            Some(found.form_string())
-                        }
-                    }
-                }
-            }
-
             Self {
                 left: value
                     .left
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 right: value
                     .right
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 power: value
                     .power
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 utility: value
                     .utility
                     .iter()
-                    .filter_map(|xs| filter_func(xs.as_str()))
+                    .filter_map(|xs| super::restore_spec(xs.as_str()))
                     .collect(),
                 hud_visible: value.hud_visible,
                 equipsets: Vec::new(),
+                tools: Vec::new(),
+                last_used: Default::default(),
+                daily_uses: Default::default(),
+                use_counts: Default::default(),
+                last_sorted: None,
+                starts: Default::default(),
+                wrapped: Default::default(),
+                quarantined: Default::default(),
                 loaded: true,
             }
         }
@@ -753,7 +1474,15 @@ pub mod cosave_v0 {
                 power: value.power.iter().filter_map(filter_func).collect(),
                 utility: value.utility.iter().filter_map(filter_func).collect(),
                 equipsets: Vec::new(),
+                tools: Vec::new(),
                 hud_visible: value.hud_visible,
+                last_used: Default::default(),
+                daily_uses: Default::default(),
+                use_counts: Default::default(),
+                last_sorted: None,
+                starts: Default::default(),
+                wrapped: Default::default(),
+                quarantined: Default::default(),
                 loaded: true,
             }
         }
@@ -763,8 +1492,685 @@ pub mod cosave_v0 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::controller::limits::Limit;
+    use crate::data::ammo::AmmoType;
+    use crate::data::spell::SpellType;
     use crate::plugin::EquippedData;
 
+    #[test]
+    fn version_3() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+
+        let one = cache.get(&"fake-one".to_string());
+        let two = cache.get(&"fake-two".to_string());
+        cycle.add_item(CycleSlot::Right, &one);
+        cycle.add_item(CycleSlot::Right, &two);
+        assert!(cycle.toggle_tool_flag(&CycleSlot::Right, 1));
+
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        assert_eq!(decoded.right.len(), cycle.right.len());
+        assert_eq!(decoded.tools.len(), 1);
+    }
+
+    #[test]
+    fn utility_ammo_relocates() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+
+        let arrow = HudItem::preclassified(
+            "Iron Arrow".to_string(),
+            "Skyrim.esm|0x1397d".to_string(),
+            20,
+            BaseType::Ammo(AmmoType::default()),
+        );
+        cache.record(arrow.clone());
+        let one = cache.get(&"fake-one".to_string());
+        let two = cache.get(&"fake-two".to_string());
+        cycle.utility = vec![
+            one.form_string(),
+            arrow.form_string(),
+            two.form_string(),
+            arrow.form_string(),
+        ];
+
+        let moved = cycle.relocate_utility_ammo(&mut cache);
+        assert_eq!(moved, vec![arrow.form_string()]);
+        assert_eq!(cycle.utility, vec![one.form_string(), two.form_string()]);
+        assert_eq!(cycle.get_top(&CycleSlot::Utility), Some(one.form_string()));
+        assert!(cycle.relocate_utility_ammo(&mut cache).is_empty());
+
+        // ammo at the top hands the top to the next entry in rotation
+        cycle.utility = vec![arrow.form_string(), two.form_string(), one.form_string()];
+        cycle.relocate_utility_ammo(&mut cache);
+        assert_eq!(cycle.get_top(&CycleSlot::Utility), Some(two.form_string()));
+    }
+
+    #[test]
+    fn tool_flags_toggle() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+
+        let one = cache.get(&"fake-one".to_string());
+        cycle.add_item(CycleSlot::Right, &one);
+        assert!(cycle.toggle_tool_flag(&CycleSlot::Right, 0));
+        assert!(cycle.is_tool(&one));
+        assert!(!cycle.toggle_tool_flag(&CycleSlot::Right, 0));
+        // out of range does nothing
+        assert!(!cycle.toggle_tool_flag(&CycleSlot::Right, 5));
+        assert!(cycle.tools.is_empty());
+    }
+
+    #[test]
+    fn toggling_refuses_items_for_the_wrong_cycle() {
+        use crate::data::color::InvColor;
+        use crate::data::potion::PotionType;
+        use crate::data::shout::ShoutType;
+        use crate::data::weapon::{WeaponEquipType, WeaponType};
+
+        let mut cycle = CycleData::default();
+        let sword = HudItem::preclassified(
+            "Iron Sword".to_string(),
+            "Skyrim.esm|0x12eb7".to_string(),
+            1,
+            BaseType::Weapon(WeaponType::new(
+                Icon::WeaponSwordOneHanded,
+                InvColor::default(),
+                WeaponEquipType::EitherHand,
+            )),
+        );
+        let greatsword = HudItem::preclassified(
+            "Iron Greatsword".to_string(),
+            "Skyrim.esm|0x1359d".to_string(),
+            1,
+            BaseType::Weapon(WeaponType::new(
+                Icon::WeaponSwordTwoHanded,
+                InvColor::default(),
+                WeaponEquipType::TwoHanded,
+            )),
+        );
+        let potion = HudItem::preclassified(
+            "Potion of Minor Healing".to_string(),
+            "Skyrim.esm|0x3eadd".to_string(),
+            3,
+            BaseType::Potion(PotionType::Health),
+        );
+        let shout = HudItem::preclassified(
+            "Unrelenting Force".to_string(),
+            "Skyrim.esm|0x13e07".to_string(),
+            1,
+            BaseType::Shout(ShoutType::new(vec![])),
+        );
+
+        for slot in [CycleSlot::Power, CycleSlot::Utility] {
+            assert_eq!(
+                cycle.toggle(&slot, sword.clone()),
+                MenuEventResponse::ItemInappropriate
+            );
+        }
+        assert_eq!(
+            cycle.toggle(&CycleSlot::Left, greatsword.clone()),
+            MenuEventResponse::ItemInappropriate
+        );
+        assert_eq!(
+            cycle.toggle(&CycleSlot::Left, potion.clone()),
+            MenuEventResponse::ItemInappropriate
+        );
+        assert_eq!(
+            cycle.toggle(&CycleSlot::Right, shout.clone()),
+            MenuEventResponse::ItemInappropriate
+        );
+        assert!(cycle.left.is_empty() && cycle.right.is_empty());
+        assert!(cycle.power.is_empty() && cycle.utility.is_empty());
+
+        assert_eq!(
+            cycle.toggle(&CycleSlot::Right, greatsword),
+            MenuEventResponse::ItemAdded
+        );
+        assert_eq!(
+            cycle.toggle(&CycleSlot::Utility, potion),
+            MenuEventResponse::ItemAdded
+        );
+        assert_eq!(
+            cycle.toggle(&CycleSlot::Power, shout),
+            MenuEventResponse::ItemAdded
+        );
+    }
+
+    #[test]
+    fn restored_selection_skips_missing_entries() {
+        let mut cycle: Vec<String> = ["sword", "mace", "axe"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        rotate_to_first_present(&mut cycle, &[true, false, true]);
+        assert_eq!(cycle, vec!["sword", "mace", "axe"]);
+        rotate_to_first_present(&mut cycle, &[false, false, true]);
+        assert_eq!(cycle, vec!["axe", "sword", "mace"]);
+        // With nothing to fall back to, the order stays as saved.
+        rotate_to_first_present(&mut cycle, &[false, false, false]);
+        assert_eq!(cycle, vec!["axe", "sword", "mace"]);
+    }
+
+    #[test]
+    fn removing_the_equipped_entry_is_noticed_in_every_cycle() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+        let one = cache.get(&"fake-one".to_string());
+        let two = cache.get(&"fake-two".to_string());
+
+        for slot in [
+            CycleSlot::Power,
+            CycleSlot::Utility,
+            CycleSlot::Left,
+            CycleSlot::Right,
+        ] {
+            cycle.add_item(slot.clone(), &one);
+            assert!(cycle.removes_equipped(&slot, &one, &one.form_string()));
+            // Something else is equipped, or nothing is.
+            assert!(!cycle.removes_equipped(&slot, &one, &two.form_string()));
+            assert!(!cycle.removes_equipped(&slot, &one, ""));
+            // Equipped but not in this cycle, so toggling would add it.
+            assert!(!cycle.removes_equipped(&slot, &two, &two.form_string()));
+        }
+    }
+
+    #[test]
+    fn a_spent_scroll_moves_its_hand_along() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+        let scroll = HudItem::preclassified(
+            "Scroll of Firebolt".to_string(),
+            "fake-scroll".to_string(),
+            0,
+            BaseType::Scroll(SpellType::default()),
+        );
+        let sword = cache.get(&"fake-sword".to_string());
+        cycle.add_item(CycleSlot::Right, &scroll);
+        cycle.add_item(CycleSlot::Right, &sword);
+        let spec = scroll.form_string();
+        let ran_out = |shown: &HudItem| {
+            cycle.hand_ran_out(&CycleSlot::Right, &spec, scroll.kind(), Some(shown))
+        };
+
+        // The hand still shows it, or the last cast left it bare.
+        assert!(ran_out(&scroll));
+        assert!(ran_out(&HudItem::make_unarmed_proxy()));
+        assert!(ran_out(&HudItem::default()));
+        // Something else took its place already.
+        assert!(!ran_out(&sword));
+        // A bare hand means nothing for the other cycle, or for a weapon.
+        assert!(!cycle.hand_ran_out(&CycleSlot::Left, &spec, scroll.kind(), None));
+        assert!(!cycle.hand_ran_out(
+            &CycleSlot::Right,
+            &sword.form_string(),
+            sword.kind(),
+            Some(&HudItem::make_unarmed_proxy())
+        ));
+    }
+
+    #[test]
+    fn moving_entries_keeps_the_top_on_top() {
+        let mut cycle = CycleData::default();
+        let mut cache = ItemCache::default();
+        let items: Vec<HudItem> = ["fake-a", "fake-b", "fake-c", "fake-d"]
+            .iter()
+            .map(|spec| cache.get(&spec.to_string()))
+            .collect();
+        for item in items.iter() {
+            cycle.add_item(CycleSlot::Left, item);
+        }
+        let specs = |cycle: &CycleData| cycle.formids(&CycleSlot::Left);
+
+        assert_eq!(cycle.move_entry(&CycleSlot::Left, 3, 1), Some(1));
+        assert_eq!(specs(&cycle), vec!["fake-a", "fake-d", "fake-b", "fake-c"]);
+
+        // Moving the top entry turns the rest of the cycle around it.
+        assert_eq!(cycle.move_entry(&CycleSlot::Left, 0, 2), Some(0));
+        assert_eq!(specs(&cycle), vec!["fake-a", "fake-c", "fake-d", "fake-b"]);
+        assert_eq!(cycle.get_top(&CycleSlot::Left), Some("fake-a".to_string()));
+
+        assert_eq!(cycle.move_entry(&CycleSlot::Left, 1, 1), Some(1));
+        assert!(cycle.move_entry(&CycleSlot::Left, 4, 0).is_none());
+        assert!(cycle.move_entry(&CycleSlot::Right, 0, 0).is_none());
+        assert_eq!(specs(&cycle), vec!["fake-a", "fake-c", "fake-d", "fake-b"]);
+    }
+
+    #[test]
+    fn unused_entries_age_out() {
+        let mut cycle = CycleData::default();
+        for spec in ["sword", "mace", "axe"] {
+            cycle.right.add(&spec.to_string());
+        }
+        cycle.left.add(&"mace".to_string());
+        cycle.reconcile_usage_dates(10);
+        cycle.mark_used("sword", 40);
+        cycle.mark_used("not-in-a-cycle", 40);
+        assert!(!cycle.last_used.contains_key("not-in-a-cycle"));
+
+        let candidates = cycle.prune_candidates(20, 40, |_| false);
+        assert_eq!(
+            candidates,
+            vec![
+                (CycleSlot::Left, "mace".to_string()),
+                (CycleSlot::Right, "mace".to_string()),
+                (CycleSlot::Right, "axe".to_string()),
+            ]
+        );
+        let kept = cycle.prune_candidates(20, 40, |spec| spec == "axe");
+        assert_eq!(kept.len(), 2);
+        assert!(cycle.prune_candidates(30, 40, |_| false).is_empty());
+    }
+
+    #[test]
+    fn usage_dates_survive_rollbacks() {
+        let mut cycle = CycleData::default();
+        cycle.right.add(&"sword".to_string());
+        cycle.reconcile_usage_dates(100);
+        // an older save loads: the calendar went backwards
+        cycle.reconcile_usage_dates(30);
+        assert_eq!(cycle.last_used.get("sword"), Some(&30));
+        assert!(cycle.prune_candidates(0, 5, |_| false).is_empty());
+    }
+
+    #[test]
+    fn version_3_keeps_last_used_days() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+
+        let one = cache.get(&"fake-one".to_string());
+        cycle.add_item(CycleSlot::Right, &one);
+        cycle.mark_used(&one.form_string(), 12);
+
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        assert_eq!(decoded.right.len(), 1);
+        assert_eq!(decoded.last_used.get(&one.form_string()), Some(&12));
+    }
+
+    #[test]
+    fn version_3_keeps_daily_uses() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+
+        let one = cache.get(&"fake-one".to_string());
+        cycle.add_item(CycleSlot::Utility, &one);
+        let limit = Limit {
+            key: "category:potion".to_string(),
+            per_day: 3,
+        };
+        cycle.daily_uses().record(&limit, 12);
+
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let mut decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        assert_eq!(decoded.utility.len(), 1);
+        assert_eq!(decoded.daily_uses().remaining(&limit, 12), 2);
+    }
+
+    #[test]
+    fn version_3_keeps_use_counts() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+
+        let one = cache.get(&"fake-one".to_string());
+        cycle.add_item(CycleSlot::Right, &one);
+        cycle.mark_used(&one.form_string(), 12);
+        cycle.mark_used(&one.form_string(), 13);
+        cycle.sort_by_usage(13, &mut cache);
+
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        assert_eq!(decoded.use_count(&one.form_string()), 2);
+        assert_eq!(decoded.last_sorted, Some(13));
+    }
+
+    fn use_times(cycle: &mut CycleData, spec: &str, times: u32, today: u32) {
+        for _ in 0..times {
+            cycle.mark_used(spec, today);
+        }
+    }
+
+    #[test]
+    fn favorites_drift_up_once_a_day() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+        for spec in ["sword", "mace", "axe", "dagger", "bow"] {
+            cycle.right.add(&spec.to_string());
+        }
+        use_times(&mut cycle, "sword", 2, 1);
+        use_times(&mut cycle, "bow", 5, 1);
+        use_times(&mut cycle, "axe", 3, 1);
+        use_times(&mut cycle, "mace", 1, 1);
+
+        assert_eq!(cycle.sort_by_usage(1, &mut cache), vec![CycleSlot::Right]);
+        assert_eq!(
+            cycle.formids(&CycleSlot::Right),
+            vec!["sword", "bow", "axe", "mace", "dagger"]
+        );
+
+        // The dagger gets a workout, but not until tomorrow does it move.
+        use_times(&mut cycle, "dagger", 9, 1);
+        assert!(cycle.sort_by_usage(1, &mut cache).is_empty());
+        assert_eq!(cycle.position(&CycleSlot::Right, "dagger"), Some(4));
+        assert_eq!(cycle.sort_by_usage(2, &mut cache), vec![CycleSlot::Right]);
+        assert_eq!(
+            cycle.formids(&CycleSlot::Right),
+            vec!["sword", "dagger", "bow", "axe", "mace"]
+        );
+
+        // Nothing changes when nothing's been used differently.
+        assert!(cycle.sort_by_usage(3, &mut cache).is_empty());
+    }
+
+    #[test]
+    fn the_top_stays_put_while_sorting() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+        for spec in ["flames", "frostbite", "sparks", "healing"] {
+            cycle.power.add(&spec.to_string());
+        }
+        use_times(&mut cycle, "healing", 4, 7);
+        use_times(&mut cycle, "flames", 6, 7);
+
+        // The player has cycled to sparks and it's waiting to be equipped.
+        cycle.set_top(&CycleSlot::Power, "sparks");
+        cycle.sort_by_usage(7, &mut cache);
+        assert_eq!(cycle.get_top(&CycleSlot::Power), Some("sparks".to_string()));
+        assert_eq!(
+            cycle.formids(&CycleSlot::Power),
+            vec!["sparks", "flames", "healing", "frostbite"]
+        );
+    }
+
+    #[test]
+    fn manual_edits_reset_usage() {
+        let mut cache = ItemCache::default();
+        let mut cycle = CycleData::default();
+        for spec in ["one", "two", "three"] {
+            cycle.utility.add(&spec.to_string());
+        }
+        use_times(&mut cycle, "three", 5, 1);
+        use_times(&mut cycle, "two", 2, 1);
+
+        // The player moves "three" by hand: out and back in at the end.
+        let index = cycle
+            .position(&CycleSlot::Utility, "three")
+            .expect("three is in the cycle");
+        assert!(cycle.remove_spec(&CycleSlot::Utility, "three"));
+        assert!(cycle.insert_at(&CycleSlot::Utility, "three", index));
+        assert_eq!(cycle.use_count("three"), 0);
+        assert_eq!(cycle.use_count("two"), 2);
+
+        cycle.sort_by_usage(2, &mut cache);
+        assert_eq!(
+            cycle.formids(&CycleSlot::Utility),
+            vec!["one", "two", "three"]
+        );
+
+        // Entries that leave every cycle take their counts with them.
+        cycle.utility.retain(|xs| xs != "two");
+        cycle.reconcile_usage_dates(2);
+        assert_eq!(cycle.use_count("two"), 0);
+        assert!(!cycle.use_counts.contains_key("two"));
+    }
+
+    #[test]
+    fn version_3_keeps_cycle_starts() {
+        let mut cycle = CycleData::default();
+        for spec in ["fake-one", "fake-two"] {
+            cycle.right.add(&spec.to_string());
+        }
+        cycle.advance(&CycleSlot::Right, 1);
+
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let mut decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        assert_eq!(
+            decoded.starts.get(&CycleSlot::Right),
+            Some(&"fake-one".to_string())
+        );
+        assert!(!decoded.starts.contains_key(&CycleSlot::Left));
+        decoded.advance(&CycleSlot::Right, 1);
+        assert!(decoded.take_wrapped(&CycleSlot::Right));
+    }
+
+    #[test]
+    fn version_3_keeps_loadouts() {
+        let mut cycle = CycleData::default();
+        let data = EquippedData {
+            items: Vec::new(),
+            empty_slots: Vec::new(),
+        };
+        cycle.add_equipset("armor only".to_string(), data.clone());
+        let loadout = vec![
+            String::new(),
+            "health_proxy".to_string(),
+            "unarmed_proxy".to_string(),
+            "unarmed_proxy".to_string(),
+        ];
+        cycle.add_loadout("fists".to_string(), data, loadout.clone());
+
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        let fists = decoded
+            .equipsets
+            .iter()
+            .find(|xs| xs.name() == "fists")
+            .expect("the loadout set survives");
+        assert_eq!(fists.loadout, loadout);
+        assert_eq!(fists.loadout_for(&CycleSlot::Power), None);
+        assert_eq!(fists.loadout_for(&CycleSlot::Right), Some("unarmed_proxy"));
+        let armor = decoded
+            .equipsets
+            .iter()
+            .find(|xs| xs.name() == "armor only")
+            .expect("the armor set survives");
+        assert!(armor.loadout_for(&CycleSlot::Left).is_none());
+    }
+
+    #[test]
+    fn version_3_keeps_quarantined_entries() {
+        let cycle = CycleData {
+            quarantined: vec![
+                (CycleSlot::Left, "Gone.esp|0x801".to_string()),
+                (CycleSlot::Utility, "Gone.esp|0x802".to_string()),
+            ],
+            ..Default::default()
+        };
+        let value = cosave_v3::CycleSerialized::from(&cycle);
+        let config = bincode::config::standard();
+        let bytes: Vec<u8> = bincode::encode_to_vec(value, config).unwrap_or_default();
+        let decoded = cosave_v3::deserialize(bytes).expect("data should be decodeable");
+        assert_eq!(decoded.quarantined, cycle.quarantined);
+    }
+
+    #[test]
+    fn entries_from_missing_plugins_wait_for_their_return() {
+        let mut cycle = CycleData::default();
+        for spec in ["Skyrim.esm|0x12eb7", "Gone.esp|0x801", "unarmed_proxy"] {
+            cycle.right.add(&spec.to_string());
+        }
+        cycle.utility.add(&"dynamic|0xff000801".to_string());
+        let installed = |spec: &str| spec.starts_with("Skyrim.esm");
+
+        let removed = cycle.quarantine_missing(installed);
+        assert_eq!(
+            removed,
+            vec![(CycleSlot::Right, "Gone.esp|0x801".to_string())]
+        );
+        assert_eq!(
+            cycle.formids(&CycleSlot::Right),
+            vec!["Skyrim.esm|0x12eb7", "unarmed_proxy"]
+        );
+        // Forms made at runtime aren't ours to quarantine.
+        assert_eq!(cycle.formids(&CycleSlot::Utility).len(), 1);
+        assert!(cycle.restore_quarantined(installed).is_empty());
+
+        // The plugin comes back.
+        let restored = cycle.restore_quarantined(|_| true);
+        assert_eq!(restored, removed);
+        assert!(cycle.quarantined.is_empty());
+        assert_eq!(cycle.position(&CycleSlot::Right, "Gone.esp|0x801"), Some(2));
+    }
+
+    #[test]
+    fn wrapping_forward_at_both_ends() {
+        let mut cycle = CycleData::default();
+        for spec in ["a", "b", "c"] {
+            cycle.left.add(&spec.to_string());
+        }
+        // From the start, through the middle, to the end: no wrap.
+        assert_eq!(cycle.advance(&CycleSlot::Left, 1), Some("b".to_string()));
+        assert!(!cycle.take_wrapped(&CycleSlot::Left));
+        assert_eq!(cycle.advance(&CycleSlot::Left, 1), Some("c".to_string()));
+        assert!(!cycle.take_wrapped(&CycleSlot::Left));
+        // From the end back onto the start: wrap, and only once.
+        assert_eq!(cycle.advance(&CycleSlot::Left, 1), Some("a".to_string()));
+        assert!(cycle.take_wrapped(&CycleSlot::Left));
+        assert!(!cycle.take_wrapped(&CycleSlot::Left));
+
+        // Jumping over the start wraps too.
+        cycle.advance(&CycleSlot::Left, 2);
+        cycle.take_wrapped(&CycleSlot::Left);
+        assert_eq!(cycle.advance(&CycleSlot::Left, 2), Some("b".to_string()));
+        assert!(cycle.take_wrapped(&CycleSlot::Left));
+    }
+
+    #[test]
+    fn wrapping_backward_at_both_ends() {
+        let mut cycle = CycleData::default();
+        for spec in ["a", "b", "c"] {
+            cycle.left.add(&spec.to_string());
+        }
+        // Stepping back off the start wraps to the end.
+        assert_eq!(cycle.retreat(&CycleSlot::Left, 1), Some("c".to_string()));
+        assert!(cycle.take_wrapped(&CycleSlot::Left));
+        assert_eq!(cycle.retreat(&CycleSlot::Left, 1), Some("b".to_string()));
+        assert!(!cycle.take_wrapped(&CycleSlot::Left));
+        // Arriving at the start from behind doesn't; leaving it again does.
+        assert_eq!(cycle.retreat(&CycleSlot::Left, 1), Some("a".to_string()));
+        assert!(!cycle.take_wrapped(&CycleSlot::Left));
+        assert_eq!(cycle.retreat(&CycleSlot::Left, 1), Some("c".to_string()));
+        assert!(cycle.take_wrapped(&CycleSlot::Left));
+
+        // There and back again crosses twice.
+        cycle.advance(&CycleSlot::Left, 1);
+        assert!(cycle.take_wrapped(&CycleSlot::Left));
+    }
+
+    #[test]
+    fn single_entries_always_wrap() {
+        let mut cycle = CycleData::default();
+        cycle.power.add(&"shout".to_string());
+        for _ in 0..3 {
+            assert_eq!(
+                cycle.advance(&CycleSlot::Power, 1),
+                Some("shout".to_string())
+            );
+            assert!(cycle.take_wrapped(&CycleSlot::Power));
+            assert_eq!(
+                cycle.retreat(&CycleSlot::Power, 1),
+                Some("shout".to_string())
+            );
+            assert!(cycle.take_wrapped(&CycleSlot::Power));
+        }
+        // Empty cycles don't move, so they don't wrap.
+        assert_eq!(cycle.advance(&CycleSlot::Utility, 1), None);
+        assert!(!cycle.take_wrapped(&CycleSlot::Utility));
+    }
+
+    #[test]
+    fn equips_from_outside_the_cycle_leave_it_put() {
+        let mut cycle = CycleData::default();
+        for spec in ["one", "two", "three"] {
+            cycle.left.add(&spec.to_string());
+        }
+        // The player equipped something from the favorites menu.
+        cycle.set_top(&CycleSlot::Left, "three");
+        assert_eq!(cycle.formids(&CycleSlot::Left), vec!["three", "one", "two"]);
+        // The player equipped something the cycle doesn't have.
+        cycle.set_top(&CycleSlot::Left, "four");
+        assert_eq!(cycle.get_top(&CycleSlot::Left), Some("three".to_string()));
+        assert_eq!(cycle.position(&CycleSlot::Left, "four"), None);
+    }
+
+    #[test]
+    fn cycling_passes_over_missing_entries() {
+        let mut cycle = CycleData::default();
+        for spec in ["sword", "sold", "mace", "dropped"] {
+            cycle.right.add(&spec.to_string());
+        }
+        let missing = ["sold", "dropped"];
+        let first = cycle
+            .advance(&CycleSlot::Right, 1)
+            .expect("the cycle has entries");
+        assert_eq!(first, "sold");
+        let landed = cycle.advance_until(&CycleSlot::Right, first, |xs| !missing.contains(&xs));
+        assert_eq!(landed.as_deref(), Some("mace"));
+        // Passed over, not removed.
+        assert_eq!(cycle.cycle_len(&CycleSlot::Right), 4);
+
+        // One trip around is all we take.
+        let mut looked = 0;
+        let first = cycle
+            .get_top(&CycleSlot::Right)
+            .expect("the cycle has entries");
+        let landed = cycle.advance_until(&CycleSlot::Right, first, |_| {
+            looked += 1;
+            false
+        });
+        assert!(landed.is_none());
+        assert_eq!(looked, 4);
+    }
+
+    #[test]
+    fn removed_entries_return_to_their_place() {
+        let mut cycle = CycleData::default();
+        for spec in ["one", "two", "three", "four"] {
+            cycle.right.add(&spec.to_string());
+        }
+        let after = cycle.entry_before(&CycleSlot::Right, "three");
+        assert_eq!(after.as_deref(), Some("two"));
+        assert!(cycle.remove_spec(&CycleSlot::Right, "three"));
+        // The cycle turns before the undo; "three" still goes back after "two".
+        cycle.right.rotate_left(2);
+        assert_eq!(cycle.formids(&CycleSlot::Right), vec!["four", "one", "two"]);
+        assert!(cycle.insert_after(&CycleSlot::Right, "three", after.as_deref()));
+        assert_eq!(
+            cycle.formids(&CycleSlot::Right),
+            vec!["four", "one", "two", "three"]
+        );
+
+        // The top entry goes back on top.
+        assert_eq!(cycle.entry_before(&CycleSlot::Right, "four"), None);
+        assert!(cycle.remove_spec(&CycleSlot::Right, "four"));
+        assert!(cycle.insert_after(&CycleSlot::Right, "four", None));
+        assert_eq!(cycle.get_top(&CycleSlot::Right).as_deref(), Some("four"));
+
+        // A neighbour that's gone too sends the entry to the end.
+        assert!(cycle.remove_spec(&CycleSlot::Right, "one"));
+        assert!(cycle.remove_spec(&CycleSlot::Right, "two"));
+        assert!(cycle.insert_after(&CycleSlot::Right, "two", Some("one")));
+        assert_eq!(
+            cycle.formids(&CycleSlot::Right),
+            vec!["four", "three", "two"]
+        );
+        // no duplicates
+        assert!(!cycle.insert_after(&CycleSlot::Right, "two", None));
+    }
+
     #[test]
     fn version_2() {
         let mut cache = ItemCache::default();
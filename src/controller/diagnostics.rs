@@ -0,0 +1,323 @@
+//! Install health checks, run on request from the MCM. Most support requests
+//! turn out to be broken installs: a missing layout file, settings the ini
+//! parser chokes on, an incomplete icon pack, a co-save from a newer version.
+//! Each check here looks at one of those things and reports what it found in
+//! words a player can act on.
+//!
+//! Everything in this file runs in exactly the situation where things are
+//! missing or broken, so nothing here is allowed to panic.
+
+use std::fmt::Display;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use strum::VariantNames;
+
+use super::cycles::CycleData;
+use super::settings::{UserSettings, SETTINGS_PATH};
+use crate::images::icons::{is_in_core_set, Icon};
+use crate::images::ICON_SVG_PATH;
+use crate::layouts::{Layout, LAYOUT_PATH};
+
+/// Where we keep the files we write: layouts, overrides, and the like.
+const PERSISTENCE_DIR: &str = "./data/SKSE/Plugins";
+
+/// What we learned the last time a co-save was handed to us: its format
+/// version and whether we could decode it. None until a save loads.
+static COSAVE_STATUS: Lazy<Mutex<Option<(u32, bool)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Called when a co-save is loaded so diagnostics can report on it later.
+pub fn record_cosave_load(version: u32, decoded: bool) {
+    match COSAVE_STATUS.lock() {
+        Ok(mut status) => *status = Some((version, decoded)),
+        Err(poisoned) => *poisoned.into_inner() = Some((version, decoded)),
+    }
+}
+
+fn cosave_status() -> Option<(u32, bool)> {
+    match COSAVE_STATUS.lock() {
+        Ok(status) => *status,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
+
+/// How bad a finding is. Sorts from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Ok => write!(f, "ok"),
+            Severity::Warning => write!(f, "warn"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// One thing a check found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    fn ok(message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Ok,
+            message: message.into(),
+        }
+    }
+
+    fn warn(message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.severity, self.message)
+    }
+}
+
+/// Run every check against the real install. The first entry is the overall
+/// result, one of `ok`, `warn`, or `error`. The rest are the findings, most
+/// severe first.
+pub fn run_diagnostics() -> Vec<String> {
+    let mut findings = vec![
+        check_settings(SETTINGS_PATH),
+        check_layout(LAYOUT_PATH),
+        check_icons(ICON_SVG_PATH),
+        check_cosave(cosave_status()),
+        check_persistence_dir(PERSISTENCE_DIR),
+    ];
+    let (overall, lines) = summarize(&mut findings);
+    log::info!("Diagnostics finished; overall={overall};");
+    for line in lines.iter() {
+        log::info!("    {line}");
+    }
+
+    let mut result = vec![overall.to_string()];
+    result.extend(lines);
+    result
+}
+
+/// Sort findings most severe first and report the worst severity.
+fn summarize(findings: &mut [Finding]) -> (Severity, Vec<String>) {
+    findings.sort_by_key(|xs| std::cmp::Reverse(xs.severity));
+    let overall = findings
+        .first()
+        .map_or(Severity::Ok, |worst| worst.severity);
+    let lines = findings.iter().map(|xs| xs.to_string()).collect();
+    (overall, lines)
+}
+
+/// Can we read the MCM settings file?
+pub fn check_settings(pathstr: &str) -> Finding {
+    if !Path::new(pathstr).exists() {
+        return Finding::warn(format!(
+            "No settings file at '{pathstr}'. Defaults are in use until you change something in the MCM."
+        ));
+    }
+    let mut settings = UserSettings::default();
+    match settings.read_from_file(pathstr) {
+        Ok(_) => Finding::ok(format!("Settings file '{pathstr}' reads cleanly.")),
+        Err(e) => Finding::error(format!(
+            "The settings file '{pathstr}' can't be read: {e:#}. Delete it to get defaults back."
+        )),
+    }
+}
+
+/// Is there a layout file, and does it parse? Unlike loading a layout, this
+/// never writes a default file or notifies.
+pub fn check_layout(pathstr: &str) -> Finding {
+    let buf = match fs::read_to_string(pathstr) {
+        Ok(v) => v,
+        Err(e) => {
+            return Finding::error(format!(
+                "The layout file '{pathstr}' can't be read: {e}. Reinstall the mod or pick a layout again."
+            ));
+        }
+    };
+    match toml::from_str::<Layout>(&buf) {
+        Ok(_) => Finding::ok(format!("Layout file '{pathstr}' parses.")),
+        Err(e) => Finding::error(format!(
+            "The layout file '{pathstr}' isn't a valid layout: {e}"
+        )),
+    }
+}
+
+/// Are all of the core icons present? Packs may leave out anything else.
+pub fn check_icons(dir: &str) -> Finding {
+    if !Path::new(dir).is_dir() {
+        return Finding::error(format!(
+            "The icon directory '{dir}' is missing. Reinstall the mod."
+        ));
+    }
+    let missing: Vec<String> = Icon::VARIANTS
+        .iter()
+        .filter_map(|name| Icon::from_str(name).ok())
+        .filter(is_in_core_set)
+        .filter(|icon| !Path::new(dir).join(icon.icon_file()).exists())
+        .map(|icon| icon.icon_file())
+        .collect();
+    if missing.is_empty() {
+        Finding::ok("All core icons are present.")
+    } else {
+        Finding::error(format!(
+            "{} core icons are missing from '{dir}': {}. Reinstall the mod.",
+            missing.len(),
+            missing.join(", ")
+        ))
+    }
+}
+
+/// Did the last co-save decode, and was it a format we know?
+pub fn check_cosave(status: Option<(u32, bool)>) -> Finding {
+    let current = CycleData::serialize_version();
+    match status {
+        None => Finding::ok("No save has been loaded yet, so there's no co-save to check."),
+        Some((version, _)) if version > current => Finding::error(format!(
+            "The co-save is format version {version}, from a newer version of the mod. This version reads up to {current}."
+        )),
+        Some((version, false)) => Finding::error(format!(
+            "The co-save (format version {version}) couldn't be decoded, so cycles started empty."
+        )),
+        Some((version, true)) if version < current => Finding::ok(format!(
+            "The co-save is format version {version}; it will be upgraded to {current} on the next save."
+        )),
+        Some((version, true)) => Finding::ok(format!(
+            "The co-save is format version {version} and decodes cleanly."
+        )),
+    }
+}
+
+/// Can we write files where we keep them? Checked with a real write.
+pub fn check_persistence_dir(dir: &str) -> Finding {
+    let probe = Path::new(dir).join("SoulsyHUD_write_test.tmp");
+    let written = fs::File::create(&probe).and_then(|mut fp| fp.write_all(b"soulsy"));
+    match written {
+        Ok(_) => {
+            if let Err(e) = fs::remove_file(&probe) {
+                return Finding::warn(format!(
+                    "'{dir}' is writable but the test file couldn't be removed: {e}"
+                ));
+            }
+            Finding::ok(format!("'{dir}' is writable."))
+        }
+        Err(e) => Finding::error(format!(
+            "Can't write to '{dir}': {e}. Layout and override files can't be saved."
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_check() {
+        assert_eq!(
+            check_settings("tests/fixtures/test-settings.ini").severity,
+            Severity::Ok
+        );
+        assert_eq!(
+            check_settings("tests/fixtures/nope.ini").severity,
+            Severity::Warning
+        );
+
+        let broken = std::env::temp_dir().join("SoulsyHUD_broken_settings.ini");
+        fs::write(&broken, "[Options\nsLogLevel = info\n").expect("temp dir is writable");
+        assert_eq!(
+            check_settings(&broken.to_string_lossy()).severity,
+            Severity::Error
+        );
+        fs::remove_file(&broken).ok();
+    }
+
+    #[test]
+    fn layout_check() {
+        assert_eq!(
+            check_layout("tests/fixtures/layout-v2.toml").severity,
+            Severity::Ok
+        );
+        assert_eq!(
+            check_layout("tests/fixtures/test-settings.ini").severity,
+            Severity::Error
+        );
+        assert_eq!(
+            check_layout("tests/fixtures/nope.toml").severity,
+            Severity::Error
+        );
+        assert!(!Path::new("tests/fixtures/nope.toml").exists());
+    }
+
+    #[test]
+    fn icon_check() {
+        assert_eq!(check_icons(ICON_SVG_PATH).severity, Severity::Ok);
+        assert_eq!(check_icons("tests/fixtures").severity, Severity::Error);
+        assert_eq!(check_icons("no/such/dir").severity, Severity::Error);
+    }
+
+    #[test]
+    fn cosave_check() {
+        let current = CycleData::serialize_version();
+        assert_eq!(check_cosave(None).severity, Severity::Ok);
+        assert_eq!(check_cosave(Some((current, true))).severity, Severity::Ok);
+        assert_eq!(check_cosave(Some((0, true))).severity, Severity::Ok);
+        assert_eq!(
+            check_cosave(Some((current, false))).severity,
+            Severity::Error
+        );
+        assert_eq!(
+            check_cosave(Some((current + 1, true))).severity,
+            Severity::Error
+        );
+    }
+
+    #[test]
+    fn persistence_check() {
+        let dir = std::env::temp_dir();
+        let dir = dir.to_string_lossy();
+        assert_eq!(check_persistence_dir(&dir).severity, Severity::Ok);
+        assert_eq!(
+            check_persistence_dir("no/such/dir").severity,
+            Severity::Error
+        );
+    }
+
+    #[test]
+    fn everything_missing_is_survivable() {
+        let mut findings = vec![
+            check_settings("no/such/file.ini"),
+            check_layout("no/such/file.toml"),
+            check_icons("no/such/dir"),
+            check_cosave(Some((99, false))),
+            check_persistence_dir("no/such/dir"),
+        ];
+        let (overall, lines) = summarize(&mut findings);
+        assert_eq!(overall, Severity::Error);
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("[error]"));
+        assert!(lines[4].starts_with("[warn]"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Carry weight checks for equips the HUD makes on the player's behalf. Survival
+//! setups can count gear against carry weight when it's put on, so equipping a
+//! heavy shield or a whole equip set can tip the player over the limit with no
+//! obvious cause. Before we equip, we add up what's going on, using the weights
+//! stored on the items, and say whether it still fits. What comes off stays in
+//! the inventory, so it frees nothing.
+
+use crate::data::HudItem;
+use crate::plugin::CarryWeight;
+
+/// What the carry weight check found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadCheck {
+    /// Everything fits.
+    Fits,
+    /// This item is the one that takes the player past their limit.
+    Overweight {
+        /// The name of the item that crossed the line.
+        item: String,
+        /// The carry weight after everything is equipped.
+        total: f32,
+        max: f32,
+    },
+}
+
+/// Would equipping `going_on` push the player past their carry weight? Being at
+/// exactly the limit is fine; the game only complains once you're over it. The
+/// offending item is the first one that crosses the limit. A limit of zero
+/// means the game couldn't tell us, so everything fits.
+pub fn check_load(carry: &CarryWeight, going_on: &[HudItem]) -> LoadCheck {
+    if carry.max <= 0.0 {
+        return LoadCheck::Fits;
+    }
+    let mut total = carry.current;
+    let mut offender: Option<String> = None;
+
+    for item in going_on {
+        total += item.weight();
+        if offender.is_none() && total > carry.max {
+            offender = Some(item.name());
+        }
+    }
+
+    match offender {
+        Some(item) => LoadCheck::Overweight {
+            item,
+            total,
+            max: carry.max,
+        },
+        None => LoadCheck::Fits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::item_cache::ItemCache;
+    use crate::data::BaseType;
+
+    fn item(name: &str, spec: &str, weight: f32) -> HudItem {
+        let mut item =
+            HudItem::preclassified(name.to_string(), spec.to_string(), 1, BaseType::Empty);
+        item.set_weight(weight);
+        item
+    }
+
+    #[test]
+    fn exactly_at_capacity_fits() {
+        let carry = CarryWeight {
+            current: 280.0,
+            max: 300.0,
+        };
+        let shield = item("Ebony Shield", "Skyrim.esm|0x13a89", 20.0);
+        assert_eq!(
+            check_load(&carry, std::slice::from_ref(&shield)),
+            LoadCheck::Fits
+        );
+
+        let ring = item("Gold Ring", "Skyrim.esm|0x9cb64", 0.25);
+        match check_load(&carry, &[shield.clone(), ring.clone()]) {
+            LoadCheck::Overweight { item, total, max } => {
+                assert_eq!(item, "Gold Ring");
+                assert_eq!(total, 300.25);
+                assert_eq!(max, 300.0);
+            }
+            LoadCheck::Fits => panic!("a quarter over the limit is over the limit"),
+        }
+
+        // The shield already in hand stays in the inventory, so swapping it
+        // for the new one frees nothing.
+        assert!(matches!(
+            check_load(&carry, &[shield, ring]),
+            LoadCheck::Overweight { .. }
+        ));
+
+        // The game's "no weight" is no weight.
+        let spell = item("Flames", "Skyrim.esm|0x12fcd", -1.0);
+        let full = CarryWeight {
+            current: 300.0,
+            max: 300.0,
+        };
+        assert_eq!(check_load(&full, &[spell]), LoadCheck::Fits);
+    }
+
+    #[test]
+    fn stale_weights_are_refreshed() {
+        let mut cache = ItemCache::new();
+        let spec = "Survival.esp|0x801".to_string();
+        cache.record(item("Steel Plate Armor", &spec, 25.0));
+
+        let carry = CarryWeight {
+            current: 260.0,
+            max: 300.0,
+        };
+        let stale = cache.get(&spec);
+        assert_eq!(check_load(&carry, &[stale]), LoadCheck::Fits);
+
+        // A survival mod made the armor heavier; the inventory hook tells us.
+        cache.update_weight(&spec, 45.0);
+        let fresh = cache.get(&spec);
+        assert_eq!(fresh.weight(), 45.0);
+        assert!(matches!(
+            check_load(&carry, &[fresh]),
+            LoadCheck::Overweight { .. }
+        ));
+
+        // Items we've never cached are left alone.
+        cache.update_weight("Survival.esp|0x802", 10.0);
+        assert!(!cache.contains("Survival.esp|0x802"));
+    }
+}
@@ -0,0 +1,106 @@
+//! What the player has equipped, as far as the controller knows: the hands'
+//! contents from before a two-hander went on, grips, equip history, when each
+//! slot last changed, and whether the player is in any state to equip at all.
+//! Nothing in here talks to the game.
+
+use std::time::Instant;
+
+use super::flurry::RotationLimiter;
+use super::grips::GripTracker;
+use super::history::EquipHistory;
+use super::keys::CycleSlot;
+use super::slotage::SlotAges;
+use super::vitals::Vitals;
+use crate::plugin::{Action, HudElement};
+
+#[derive(Debug, Clone, Default)]
+pub struct EquippedState {
+    /// True if we've got a two-handed weapon equipped right now.
+    pub two_hander_equipped: bool,
+    /// The form spec of the left-hand item we were holding before a
+    /// two-hander was equipped.
+    pub left_hand_cached: String,
+    /// The right-hand form spec, cached the same way.
+    pub right_hand_cached: String,
+    /// True if we're using CGO's alternative grip.
+    pub cgo_alt_grip: bool,
+    /// Grips the player chose for weapons that can be held either way.
+    pub grips: GripTracker,
+    /// What the player has recently had equipped in each slot.
+    pub history: EquipHistory,
+    /// When the game last confirmed a change to each slot's content.
+    pub slot_ages: SlotAges,
+    /// Keeps equip bursts from attack flurries from spinning the hand cycles.
+    pub rotations: RotationLimiter,
+    /// Whether the player is alive and on their feet.
+    pub vitals: Vitals,
+}
+
+impl EquippedState {
+    /// Remember what's going into this hand, for when a two-hander comes off.
+    /// Slots other than the left hand count as the right.
+    pub fn remember_hand(&mut self, which: &Action, form_spec: String) {
+        if matches!(*which, Action::Left) {
+            self.left_hand_cached = form_spec;
+        } else {
+            self.right_hand_cached = form_spec;
+        }
+    }
+
+    /// What we remember being in this hand.
+    pub fn remembered_hand(&self, which: &CycleSlot) -> &str {
+        if matches!(which, CycleSlot::Left) {
+            &self.left_hand_cached
+        } else {
+            &self.right_hand_cached
+        }
+    }
+
+    /// The game confirmed the hands now hold these items. Returns true if
+    /// either hand changed.
+    pub fn confirm_hands(&mut self, right: &str, left: &str, now: Instant) -> bool {
+        let right_changed = self.slot_ages.confirm(HudElement::Right, right, now);
+        let left_changed = self.slot_ages.confirm(HudElement::Left, left, now);
+        right_changed || left_changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::slotage::NEVER_CHANGED;
+
+    #[test]
+    fn hands_are_remembered_separately() {
+        let mut equipped = EquippedState::default();
+        equipped.remember_hand(&Action::Left, "Skyrim.esm|0x1397e".to_string());
+        equipped.remember_hand(&Action::Right, "Skyrim.esm|0x12eb7".to_string());
+        assert_eq!(
+            equipped.remembered_hand(&CycleSlot::Left),
+            "Skyrim.esm|0x1397e"
+        );
+        assert_eq!(
+            equipped.remembered_hand(&CycleSlot::Right),
+            "Skyrim.esm|0x12eb7"
+        );
+        // A long-press timer's action belongs to the right hand too.
+        equipped.remember_hand(&Action::LongPressRight, String::new());
+        assert!(equipped.remembered_hand(&CycleSlot::Right).is_empty());
+        assert!(equipped.vitals.can_act());
+    }
+
+    #[test]
+    fn hand_confirmations_age_the_slots() {
+        let now = Instant::now();
+        let mut equipped = EquippedState::default();
+        assert!(equipped.confirm_hands("Skyrim.esm|0x12eb7", "", now));
+        assert!(!equipped.confirm_hands("Skyrim.esm|0x12eb7", "", now));
+        assert_eq!(equipped.slot_ages.age_millis(&HudElement::Right, now), 0);
+        equipped.slot_ages.begin_settling();
+        assert!(!equipped.confirm_hands("Skyrim.esm|0x13982", "", now));
+        assert_eq!(
+            equipped.slot_ages.age_millis(&HudElement::Right, now),
+            NEVER_CHANGED
+        );
+    }
+}
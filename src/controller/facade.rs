@@ -4,13 +4,24 @@
 //! demand the controller. In particular, it implements some support for
 //! papyrus functions.
 
+use std::time::Instant;
+
 use cxx::CxxVector;
 
 use super::cycles::*;
-use super::settings::{settings, UserSettings};
+use super::diagnostics::record_cosave_load;
+use super::keys::KeyState;
+use super::limits::DailyLimits;
+use super::overrides::ActivationOverrides;
+use super::rebind::SlotBindings;
+use super::settings::{key_is_watched, settings, UserSettings};
+use super::shutdown::accepting_events;
+use super::timers::{advance_timers, timer_fraction_left, timer_running};
+use super::trace::{self, TraceEvent};
 use crate::control;
 use crate::data::huditem::RelevantExtraData;
 use crate::data::*;
+use crate::images::refresh_icon_pack;
 use crate::layouts::{hud_layout, Layout};
 use crate::plugin::*;
 
@@ -29,24 +40,42 @@ pub fn initialize_hud() {
     log::info!("Reading and applying settings. Your settings are:");
     log::info!("{settings}");
 
-    Layout::refresh();
-    let hud = hud_layout();
-    let mut ctrl = control::get();
+    crate::images::notify_resource_mismatch_once();
+    control::dispatch(move |ctrl| {
+        ctrl.refresh_layout();
+        let hud = hud_layout();
 
-    if settings.autofade() {
-        log::info!("The HUD is in autofade mode and ready to go.");
-    } else {
-        log::info!(
-            "The HUD is in toggle mode and ready to go. Currently visible: {}",
-            ctrl.cycles.hud_visible()
-        );
-    }
-    log::info!("HUD location is: x={}; y={};", hud.anchor.x, hud.anchor.y);
+        if settings.autofade() {
+            log::info!("The HUD is in autofade mode and ready to go.");
+        } else {
+            log::info!(
+                "The HUD is in toggle mode and ready to go. Currently visible: {}",
+                ctrl.cycles.hud_visible()
+            );
+        }
+        log::info!("HUD location is: x={}; y={};", hud.anchor.x, hud.anchor.y);
+    });
+}
+
+/// The game has finished loading a save or starting a new game. Any equip or
+/// inventory events that arrived while we were restoring state get handled now.
+pub fn handle_load_complete() {
+    trace::record(|| TraceEvent::LoadComplete);
+    control::dispatch(|ctrl| ctrl.handle_load_complete());
 }
 
 /// Function for C++ to call to send a relevant button event to us.
-pub fn handle_key_event(key: u32, button: &ButtonEvent) -> KeyEventResponse {
-    control::get().handle_key_event(key, button)
+pub fn handle_key_event(key: u32, button: &ButtonEvent, sequence: u64) -> KeyEventResponse {
+    trace::record(|| TraceEvent::Key {
+        key,
+        state: KeyState::from(button),
+        sequence,
+    });
+    // Most keys mean nothing to us. Don't wait on the controller to learn that.
+    if !key_is_watched(key) {
+        return KeyEventResponse::default();
+    }
+    control::query(|ctrl| ctrl.handle_key_event(key, button, sequence))
 }
 
 /// Function for C++ to call to send a relevant menu button-event to us.
@@ -54,24 +83,99 @@ pub fn handle_key_event(key: u32, button: &ButtonEvent) -> KeyEventResponse {
 /// We get a fully-filled out HudItem struct to use as we see fit.
 // menu_item is boxed because it's arriving from C++.
 #[allow(clippy::boxed_local)]
-pub fn toggle_item(key: u32, #[allow(clippy::boxed_local)] menu_item: Box<HudItem>) {
+pub fn toggle_item(
+    key: u32,
+    #[allow(clippy::boxed_local)] menu_item: Box<HudItem>,
+) -> MenuEventResponse {
+    trace::record(|| TraceEvent::Toggle {
+        key,
+        form_spec: menu_item.form_string(),
+    });
     let action = Action::from(key);
-    control::get().handle_toggle_item(action, *menu_item)
+    control::query(move |ctrl| ctrl.handle_toggle_item(action, *menu_item))
+}
+
+/// A spell-preparation mod told us the player's prepared spells changed.
+pub fn spell_preparation_changed() {
+    control::dispatch(|ctrl| ctrl.handle_spell_preparation_changed());
+}
+
+/// A grip-switching mod says the weapon in this hand changed grip.
+pub fn handle_hand_grip_change(hand: Action, two_handed: bool) {
+    control::dispatch(move |ctrl| ctrl.handle_hand_grip_change(hand, two_handed));
 }
 
 /// Pass along menu events to the controller.
-pub fn handle_menu_event(key: u32, button: &ButtonEvent) -> bool {
-    control::get().handle_menu_event(key, button)
+pub fn handle_menu_event(key: u32, button: &ButtonEvent, sequence: u64) -> bool {
+    trace::record(|| TraceEvent::MenuKey {
+        key,
+        state: KeyState::from(button),
+        sequence,
+    });
+    control::query(|ctrl| ctrl.handle_menu_event(key, button, sequence))
 }
 
 /// Get information about the item equipped in a specific slot.
 pub fn entry_to_show_in_slot(element: HudElement) -> Box<HudItem> {
-    control::get().entry_to_show_in_slot(element)
+    control::query(|ctrl| ctrl.entry_to_show_in_slot(element))
+}
+
+/// Has the item in this slot changed since the renderer last asked?
+pub fn huditem_changed(element: HudElement) -> bool {
+    control::query(|ctrl| ctrl.huditem_changed(element))
+}
+
+/// The player died, went into bleedout, or got back up.
+pub fn player_vitals_changed(alive: bool, bleeding_out: bool) {
+    control::dispatch(move |ctrl| ctrl.handle_vitals_changed(alive, bleeding_out));
+}
+
+/// The game took the player's controls away or gave them back.
+pub fn player_controls_changed(controls: bool, weapon_controls: bool) {
+    control::dispatch(move |ctrl| ctrl.handle_controls_changed(controls, weapon_controls));
+}
+
+/// The player got staggered or knocked down.
+pub fn player_staggered() {
+    control::dispatch(|ctrl| ctrl.handle_player_staggered());
+}
+
+/// The game opened a menu. Tracked even before a game is loaded, so we
+/// never miss a close.
+pub fn menu_opened(name: &str) {
+    let name = name.to_string();
+    control::dispatch(move |ctrl| ctrl.handle_menu_changed(&name, true));
+}
+
+/// The game closed a menu.
+pub fn menu_closed(name: &str) {
+    let name = name.to_string();
+    control::dispatch(move |ctrl| ctrl.handle_menu_changed(&name, false));
+}
+
+/// The crosshair is on something locked, at this lock level.
+pub fn on_crosshair_lock(level: u8) {
+    control::dispatch(move |ctrl| ctrl.handle_crosshair_lock(level));
+}
+
+/// The crosshair is no longer on a lock.
+pub fn on_crosshair_clear() {
+    control::dispatch(|ctrl| ctrl.handle_crosshair_clear());
+}
+
+/// Open the cycle browser on this slot's cycle.
+pub fn open_cycle_browser(slot: HudElement) -> bool {
+    control::query(|ctrl| ctrl.open_cycle_browser(slot))
+}
+
+/// The renderer wants to know what the cycle browser looks like right now.
+pub fn cycle_browser_view() -> CycleBrowserView {
+    control::query(|ctrl| ctrl.cycle_browser_view())
 }
 
 /// Refresh our view of what's needs to be in the HUD right now.
 pub fn refresh_hud_items() {
-    control::get().refresh_hud_items();
+    control::dispatch(|ctrl| ctrl.refresh_hud_items());
 }
 
 /// Fill out some extra data info.
@@ -95,60 +199,212 @@ pub fn relevant_extra_data(
     ))
 }
 
+/// Is this slot waiting to equip what the player cycled to, or did a hotbar key
+/// just pick its entry? The equip highlight lasts exactly as long as the slot's
+/// equip timer, so anything that stops the timer early, like Activate or
+/// equipping right away, ends it too.
+pub fn is_slot_highlighted(slot: Action) -> bool {
+    timer_running(&slot) || hotbar_flash(slot) > 0.0
+}
+
+/// How much of the slot's highlight is left, from 1.0 down to 0.0.
+pub fn slot_highlight_fraction(slot: Action) -> f32 {
+    timer_fraction_left(&slot).max(hotbar_flash(slot))
+}
+
+fn hotbar_flash(slot: Action) -> f32 {
+    control::peek(|ctrl| ctrl.hotbar_flash(slot, Instant::now())).unwrap_or(0.0)
+}
+
+/// The renderer calls this once a frame with the seconds since the last call.
+/// Equip timers advance by that much; anything else waiting on the clock goes
+/// by wall time. Returns true if any equip timers are still running.
+pub fn tick(delta: f32) -> bool {
+    let running = advance_timers(delta);
+    if control::peek(|ctrl| ctrl.has_clock_work()).unwrap_or(false) {
+        control::dispatch(|ctrl| ctrl.tick(Instant::now()));
+    }
+    running
+}
+
 // Handle an equip delay timer expiring.
 pub fn timer_expired(slot: Action) {
-    control::get().timer_expired(slot);
+    trace::record(|| TraceEvent::Timer { action: slot });
+    control::dispatch(move |ctrl| ctrl.timer_expired(slot));
 }
 
 /// We know for sure the player just equipped this item.
-pub fn handle_item_equipped(
-    equipped: bool,
-    form_spec: &String,
-    right: &String,
-    left: &String,
-) -> bool {
-    control::get().handle_item_equipped(equipped, form_spec, right, left)
+#[allow(clippy::ptr_arg)] // needed to bridge with C++
+pub fn handle_item_equipped(equipped: bool, form_spec: &String, right: &String, left: &String) {
+    trace::record(|| TraceEvent::Equipped {
+        equipped,
+        form_spec: form_spec.clone(),
+        right: right.clone(),
+        left: left.clone(),
+    });
+    let (form_spec, right, left) = (form_spec.clone(), right.clone(), left.clone());
+    control::dispatch(move |ctrl| {
+        ctrl.handle_item_equipped(equipped, &form_spec, &right, &left);
+    });
 }
 
 /// Pass along a CGO grip-change event to the controller.
 pub fn handle_grip_change(use_alt_grip: bool) {
-    control::get().handle_grip_change(use_alt_grip);
+    control::dispatch(move |ctrl| ctrl.handle_grip_change(use_alt_grip));
 }
 
 /// A consumable's count changed. Record if relevant.
-pub fn handle_inventory_changed(form_spec: &String, count: u32) {
-    control::get().handle_inventory_changed(form_spec, count);
+#[allow(clippy::ptr_arg)] // needed to bridge with C++
+pub fn handle_inventory_changed(form_spec: &String, count: u32, weight: f32, quest_item: bool) {
+    trace::record(|| TraceEvent::Inventory {
+        form_spec: form_spec.clone(),
+        count,
+    });
+    let form_spec = form_spec.clone();
+    control::dispatch(move |ctrl| {
+        ctrl.handle_inventory_changed(&form_spec, count, weight, quest_item)
+    });
 }
 
 /// Handle an item being favorited.
 pub fn handle_favorite_event(
-    button: &ButtonEvent,
+    _button: &ButtonEvent,
     is_favorite: bool,
     #[allow(clippy::boxed_local)] item: Box<HudItem>, // needed to bridge with C++
 ) {
-    control::get().handle_favorite_event(button, is_favorite, *item);
+    control::dispatch(move |ctrl| ctrl.handle_favorite_event(is_favorite, *item));
 }
 
 /// Ask the control to refresh settings.
 pub fn refresh_user_settings() {
+    if !accepting_events() {
+        return;
+    }
+    let before = SlotBindings::from_settings(&settings());
     if let Some(e) = UserSettings::refresh().err() {
         log::warn!("Failed to read user settings! using defaults; {e:#}");
         return;
     }
-    control::get().apply_settings();
+    ActivationOverrides::refresh();
+    DailyLimits::refresh();
+    Layout::refresh_geometry();
+    refresh_icon_pack();
+    trace::set_recording(settings().record_input_trace());
+    control::dispatch(move |ctrl| {
+        let teardown = ctrl.cancel_rebound_input(&before);
+        if !teardown.is_empty() {
+            log::info!("Key bindings changed; {teardown}");
+        }
+        ctrl.apply_settings();
+    });
+}
+
+/// Change an equip delay from the MCM and use it right away, even for a timer
+/// that's already running. Returns false if it couldn't be saved.
+pub fn set_equip_delay(which: i32, millis: u32) -> bool {
+    match UserSettings::set_equip_delay(which, millis) {
+        Ok(_) => true,
+        Err(e) => {
+            log::warn!("Failed to set the equip delay; which={which}; millis={millis}; {e:#}");
+            false
+        }
+    }
 }
 
-/// Clear all cycles. MCM -> this function -> controller.
-pub fn clear_cycles() {
-    control::get().clear_cycles();
+/// Ask to clear all cycles. MCM -> this function -> controller. The cycles
+/// are cleared only when the token is confirmed.
+pub fn request_clear_cycles() -> ConfirmationRequest {
+    control::query(|ctrl| ctrl.request_clear_cycles())
+}
+
+/// Carry out a destructive action the player confirmed.
+pub fn confirm_action(token: u32) -> String {
+    control::query(|ctrl| ctrl.confirm(token))
 }
 
 pub fn get_cycle_names(which: i32) -> Vec<String> {
-    control::get().cycle_names(which)
+    control::query(|ctrl| ctrl.cycle_names(which))
+}
+
+pub fn get_cycle_descriptions(which: i32) -> Vec<String> {
+    control::query(|ctrl| ctrl.cycle_descriptions(which))
 }
 
 pub fn get_cycle_formids(which: i32) -> Vec<String> {
-    control::get().cycle_formids(which)
+    control::query(|ctrl| ctrl.cycle_formids(which))
+}
+
+/// The controller is locked only while the entries are copied out, not while
+/// the caller reads them.
+pub fn cycle_contents(slot: Action) -> Vec<CycleEntryView> {
+    control::query(|ctrl| ctrl.cycle_contents(slot))
+}
+
+/// Mark or unmark a cycle entry as a tool. Returns the new flag value.
+pub fn toggle_tool_flag(which: i32, index: i32) -> bool {
+    control::query(|ctrl| ctrl.toggle_tool_flag(which, index))
+}
+
+/// Move a cycle entry for the MCM. Returns its new position, or -1.
+pub fn move_cycle_entry(which: i32, from: i32, to: i32) -> i32 {
+    control::query(|ctrl| Some(ctrl.move_cycle_entry(which, from, to))).unwrap_or(-1)
+}
+
+/// Remove the cycle entry at this position for the MCM.
+pub fn remove_cycle_entry(which: i32, index: i32) -> bool {
+    control::query(|ctrl| ctrl.remove_cycle_entry(which, index))
+}
+
+/// Add an item to a cycle for a script. True if it's in the cycle afterwards.
+pub fn add_to_cycle(which: i32, form_spec: &str, quiet: bool) -> bool {
+    control::query(|ctrl| {
+        ctrl.edit_cycle_from_script(which, form_spec, true, quiet)
+            .succeeded()
+    })
+}
+
+/// Remove an item from a cycle for a script. True if it's gone afterwards.
+pub fn remove_from_cycle(which: i32, form_spec: &str, quiet: bool) -> bool {
+    control::query(|ctrl| {
+        ctrl.edit_cycle_from_script(which, form_spec, false, quiet)
+            .succeeded()
+    })
+}
+
+pub fn undo_last_cycle_edit() -> String {
+    control::query(|ctrl| ctrl.undo_last_cycle_edit())
+}
+
+pub fn import_favorites() -> String {
+    control::query(|ctrl| ctrl.import_favorites())
+}
+
+pub fn export_cycles(path: &str) -> bool {
+    control::query(|ctrl| ctrl.export_cycles(path))
+}
+
+pub fn import_cycles(path: &str, replace: bool) -> bool {
+    control::query(|ctrl| ctrl.import_cycles(path, replace))
+}
+
+/// Write the controller's state to the log as one entry, so it can't be
+/// interleaved with lines from other threads. Only in debug mode.
+pub fn dump_state() -> bool {
+    if !settings().debug_mode() {
+        log::info!("Asked to dump controller state, but debug mode is off.");
+        return false;
+    }
+    let state = control::query(|ctrl| ctrl.dump_state());
+    log::info!("{state}");
+    true
+}
+
+pub fn preview_prune(days: u32) -> Vec<String> {
+    control::query(|ctrl| ctrl.preview_prune(days))
+}
+
+pub fn request_prune(days: u32) -> ConfirmationRequest {
+    control::query(|ctrl| ctrl.request_prune(days))
 }
 
 pub fn serialize_version() -> u32 {
@@ -157,29 +413,38 @@ pub fn serialize_version() -> u32 {
 
 /// Serialize cycles for cosave.
 pub fn serialize_cycles() -> Vec<u8> {
-    control::get().cycles.serialize()
+    control::query(|ctrl| ctrl.cycles.serialize())
 }
 
 /// Cycle data loaded from cosave.
 pub fn cycle_loaded_from_cosave(bytes: &CxxVector<u8>, version: u32) {
     refresh_user_settings();
-    let mut ctrl = control::get();
-    if let Some(cosave_cycle) = CycleData::deserialize(bytes, version) {
-        ctrl.cycles = cosave_cycle;
-        ctrl.refresh_after_load();
-        log::info!("Cycles loaded and ready to rock.");
-    } else {
-        log::warn!("Cosave load failed. Defaulting to fresh start. Is your save corrupt?");
-    }
+    let decoded = CycleData::deserialize(bytes, version);
+    record_cosave_load(version, decoded.is_some());
+    control::dispatch(move |ctrl| {
+        ctrl.begin_load_grace_period();
+        if let Some(cosave_cycle) = decoded {
+            ctrl.cycles = cosave_cycle;
+            ctrl.refresh_after_load();
+            log::info!("Cycles loaded and ready to rock.");
+        } else {
+            log::warn!("Cosave load failed. Defaulting to fresh start. Is your save corrupt?");
+        }
+    });
 }
 
 pub fn clear_cache() {
-    control::get().cache.clear();
+    control::dispatch(|ctrl| ctrl.cache.clear());
+}
+
+/// SKSE is reverting game state before a load or a new game.
+pub fn cosave_reverted() {
+    control::dispatch(|ctrl| ctrl.revert_to_defaults());
 }
 
 /// Crash logger support.
 pub fn cache_size() -> usize {
-    control::get().cache.len()
+    control::query(|ctrl| ctrl.cache.len())
 }
 
 /// This is straight-up papyrus support. We choose to return -1 to signal
@@ -198,7 +463,7 @@ pub fn equipset_index_to_id(idx: String) -> i32 {
     let Ok(parsed) = idx.parse::<u16>() else {
         return -1;
     };
-    let ids = control::get().cycles.equipset_ids();
+    let ids = control::query(|ctrl| ctrl.cycles.equipset_ids());
     if parsed as usize >= ids.len() {
         return -1;
     }
@@ -210,36 +475,43 @@ pub fn equipset_index_to_id(idx: String) -> i32 {
 }
 
 pub fn get_equipset_names() -> Vec<String> {
-    control::get().cycles.equipset_names()
+    control::query(|ctrl| ctrl.cycles.equipset_names())
 }
 
 pub fn get_equipset_ids() -> Vec<String> {
-    control::get()
-        .cycles
-        .equipset_ids()
-        .iter()
-        .map(|xs| xs.to_string())
-        .collect()
+    control::query(|ctrl| {
+        ctrl.cycles
+            .equipset_ids()
+            .iter()
+            .map(|xs| xs.to_string())
+            .collect()
+    })
 }
 
 pub fn handle_create_equipset(name: String) -> bool {
     let data = getEquippedItems();
-    control::get().cycles.add_equipset(name, *data)
+    control::query(|ctrl| ctrl.cycles.add_equipset(name, *data))
+}
+
+/// Make a new equipment set from the player's armor, hands, power, and
+/// utility item.
+pub fn save_current_as_set(name: String) -> bool {
+    control::query(|ctrl| ctrl.save_current_as_set(name))
 }
 
 pub fn handle_update_equipset(id: u32) -> bool {
     let data = getEquippedItems();
-    control::get().cycles.update_equipset(id, *data)
+    control::query(|ctrl| ctrl.cycles.update_equipset(id, *data))
 }
 
 /// Rename the equipset with the given ID.
 pub fn handle_rename_equipset(id: u32, name: String) -> bool {
-    control::get().cycles.rename_equipset(id, name)
+    control::query(|ctrl| ctrl.cycles.rename_equipset(id, name))
 }
 
 /// Remove the equipset with the given ID.
 pub fn handle_remove_equipset(id: u32) -> bool {
-    control::get().cycles.remove_equipset(id.to_string())
+    control::query(|ctrl| ctrl.cycles.remove_equipset(id.to_string()))
 }
 
 /// Create the equipped data struct.
@@ -249,16 +521,16 @@ pub fn equipped_data(items: Vec<String>, empty_slots: Vec<u8>) -> Box<EquippedDa
 
 pub fn get_equipset_item_names(id: u32) -> Vec<String> {
     // this needs the cache
-    control::get().get_equipset_item_names(id)
+    control::query(|ctrl| ctrl.get_equipset_item_names(id))
 }
 
 /// Use the icon from the named item for the equipment set with the given id.
 pub fn set_equipset_icon(id: u32, itemname: String) -> bool {
-    control::get().set_equipset_icon(id, itemname)
+    control::query(|ctrl| ctrl.set_equipset_icon(id, itemname))
 }
 
 /// Look up an equipset by name, returning its id. Since uniqueness is not
 /// really enforced for names, this returns the first one found.
 pub fn look_up_equipset_by_name(name: String) -> u32 {
-    control::get().cycles.equipset_by_name(name)
+    control::query(|ctrl| ctrl.cycles.equipset_by_name(name))
 }
@@ -0,0 +1,149 @@
+//! Dual-wield attack flurries. Some animation mods make the game unequip and
+//! re-equip the weapons in both hands mid-combo, so we get a burst of equip
+//! events that change nothing. Handled naively, they drag the hand cycles back
+//! to whatever the burst named, undoing the player's own cycling.
+//!
+//! Two guards. An event naming the item a hand already shows is dropped while
+//! the player is attacking. And an equip the HUD didn't ask for rotates a hand
+//! cycle to the same item at most once in a short window. An event naming a
+//! different item always goes through, and if the game can't tell us whether
+//! the player is attacking, we assume not.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::keys::CycleSlot;
+
+/// How long after an out-of-band rotation to a given item another rotation to
+/// that same item is ignored. A flurry's burst is over well inside this.
+pub const ROTATION_WINDOW: Duration = Duration::from_millis(400);
+
+/// Is this equip event animation noise? It is if the player is attacking and
+/// it names an item one of the hands already shows. Empty specs never match.
+pub fn is_flurry_noise(
+    form_spec: &str,
+    shown_right: &str,
+    shown_left: &str,
+    attacking: bool,
+) -> bool {
+    attacking && !form_spec.is_empty() && (form_spec == shown_right || form_spec == shown_left)
+}
+
+/// The last out-of-band cycle rotation for each hand.
+#[derive(Debug, Clone, Default)]
+pub struct RotationLimiter {
+    last: HashMap<CycleSlot, (String, Instant)>,
+}
+
+impl RotationLimiter {
+    /// May an equip the HUD didn't ask for rotate this hand's cycle to this
+    /// item now? Always, unless it's the item the last rotation named and that
+    /// was only a moment ago. Records the rotation if allowed.
+    pub fn allow(&mut self, hand: &CycleSlot, form_spec: &str, now: Instant) -> bool {
+        let repeat = self.last.get(hand).is_some_and(|(spec, when)| {
+            spec == form_spec && now.saturating_duration_since(*when) < ROTATION_WINDOW
+        });
+        if !repeat {
+            self.last.insert(hand.clone(), (form_spec.to_string(), now));
+        }
+        !repeat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::cycles::CycleData;
+    use crate::data::item_cache::ItemCache;
+
+    const SWORD: &str = "Skyrim.esm|0x12eb7";
+    const DAGGER: &str = "Skyrim.esm|0x1397e";
+    const MACE: &str = "Skyrim.esm|0x13982";
+
+    /// An equip event for the right hand as a hook reported it: the item, when
+    /// it arrived in milliseconds, and whether the player was attacking.
+    struct Recorded(&'static str, u64, Option<bool>);
+
+    /// Play events through the guards the way the controller does, rotating
+    /// the right-hand cycle for each one that gets through.
+    fn replay(cycles: &mut CycleData, shown_left: &str, events: &[Recorded]) -> usize {
+        let start = Instant::now();
+        let mut limiter = RotationLimiter::default();
+        let mut rotations = 0;
+        for Recorded(spec, at, attacking) in events {
+            let now = start + Duration::from_millis(*at);
+            let shown_right = cycles.get_top(&CycleSlot::Right).unwrap_or_default();
+            // No word on attack state means we process normally.
+            let attacking = attacking.unwrap_or(false);
+            if is_flurry_noise(spec, &shown_right, shown_left, attacking) {
+                continue;
+            }
+            if limiter.allow(&CycleSlot::Right, spec, now) {
+                cycles.set_top(&CycleSlot::Right, spec);
+                rotations += 1;
+            }
+        }
+        rotations
+    }
+
+    fn cycles() -> CycleData {
+        let mut cache = ItemCache::default();
+        let mut cycles = CycleData::default();
+        for spec in [SWORD, MACE, DAGGER] {
+            cycles.add_item(CycleSlot::Right, &cache.get(&spec.to_string()));
+        }
+        cycles
+    }
+
+    #[test]
+    fn a_flurry_leaves_the_cycles_alone() {
+        let mut cycles = cycles();
+        let before = cycles.position(&CycleSlot::Right, MACE);
+
+        // The sword is in the right hand and the dagger in the left. Mid-combo,
+        // the hooks re-report both weapons in quick succession, sometimes
+        // naming the off-hand dagger against the right hand.
+        let burst = [
+            Recorded(SWORD, 0, Some(true)),
+            Recorded(DAGGER, 16, Some(true)),
+            Recorded(SWORD, 33, Some(true)),
+            Recorded(DAGGER, 50, Some(true)),
+            Recorded(SWORD, 66, Some(true)),
+        ];
+        assert_eq!(replay(&mut cycles, DAGGER, &burst), 0);
+        assert_eq!(cycles.get_top(&CycleSlot::Right).as_deref(), Some(SWORD));
+        assert_eq!(cycles.position(&CycleSlot::Right, MACE), before);
+    }
+
+    #[test]
+    fn real_swaps_always_get_through() {
+        let mut cycles = cycles();
+        // A different item swaps in even mid-attack.
+        let swap = [Recorded(MACE, 0, Some(true))];
+        assert_eq!(replay(&mut cycles, DAGGER, &swap), 1);
+        assert_eq!(cycles.get_top(&CycleSlot::Right).as_deref(), Some(MACE));
+
+        // Without an attack-state flag, a re-equip of the shown item is
+        // processed, but repeats of it inside the window rotate only once.
+        let mut cycles = self::cycles();
+        let unknown = [
+            Recorded(SWORD, 0, None),
+            Recorded(SWORD, 20, None),
+            Recorded(SWORD, 40, None),
+        ];
+        assert_eq!(replay(&mut cycles, DAGGER, &unknown), 1);
+        assert_eq!(cycles.get_top(&CycleSlot::Right).as_deref(), Some(SWORD));
+    }
+
+    #[test]
+    fn repeats_rotate_again_after_the_window() {
+        let now = Instant::now();
+        let mut limiter = RotationLimiter::default();
+        assert!(limiter.allow(&CycleSlot::Left, DAGGER, now));
+        assert!(!limiter.allow(&CycleSlot::Left, DAGGER, now));
+        // Hands are limited separately.
+        assert!(limiter.allow(&CycleSlot::Right, DAGGER, now));
+        assert!(limiter.allow(&CycleSlot::Left, DAGGER, now + ROTATION_WINDOW));
+        assert!(!is_flurry_noise("", "", "", true));
+    }
+}
@@ -0,0 +1,151 @@
+//! A lock around the controller that can't deadlock when the game calls back
+//! into us. Controller methods call into C++ all the time: to equip, to
+//! notify, to ask about the inventory. Some of those calls make the game fire
+//! events synchronously, and our event sinks hand those straight back to the
+//! controller over the bridge, on the same thread, while the first call still
+//! holds the lock. A plain mutex hangs the game right there.
+//!
+//! The gate remembers which thread is inside. Work that arrives from that same
+//! thread is queued and run, in order, as soon as the outer call finishes and
+//! before the lock is let go, so nothing is lost and nothing runs in the
+//! middle of another controller method. A question asked from inside can't be
+//! answered without seeing the controller half-way through a change, so it
+//! gets the default answer and a log line instead. Other threads wait their
+//! turn as usual.
+
+use std::sync::{Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+type Deferred<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+pub struct Gate<T> {
+    inner: Mutex<T>,
+    /// The thread inside the gate right now, if any.
+    holder: Mutex<Option<ThreadId>>,
+    /// Work that arrived from the holding thread while it was busy.
+    deferred: Mutex<Vec<Deferred<T>>>,
+}
+
+impl<T> Gate<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Mutex::new(value),
+            holder: Mutex::new(None),
+            deferred: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Do this work with the value now, or as soon as this thread is done
+    /// with it if we got here from inside another call.
+    pub fn dispatch<F>(&self, work: F)
+    where
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        if self.held_here() {
+            log::debug!("Reentrant controller call; running it when the current call finishes.");
+            self.deferred_work().push(Box::new(work));
+            return;
+        }
+        self.enter(work);
+    }
+
+    /// Ask the value something. A question from inside another call on this
+    /// thread gets the default answer.
+    pub fn query<R, F>(&self, work: F) -> R
+    where
+        R: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        if self.held_here() {
+            log::warn!("Reentrant controller query; answering with the default.");
+            return R::default();
+        }
+        self.enter(work)
+    }
+
+    /// Look without waiting. None if anyone is inside.
+    pub fn try_peek<R, F>(&self, work: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.inner.try_lock().ok().map(|value| work(&value))
+    }
+
+    fn enter<R, F>(&self, work: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut value = self
+            .inner
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire controller lock. Exiting.");
+        *self.holder() = Some(thread::current().id());
+        let result = work(&mut value);
+        // Deferred work can defer more work of its own.
+        loop {
+            let pending = std::mem::take(&mut *self.deferred_work());
+            if pending.is_empty() {
+                break;
+            }
+            for deferred in pending {
+                deferred(&mut value);
+            }
+        }
+        *self.holder() = None;
+        result
+    }
+
+    fn held_here(&self) -> bool {
+        *self.holder() == Some(thread::current().id())
+    }
+
+    fn holder(&self) -> MutexGuard<'_, Option<ThreadId>> {
+        self.holder
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn deferred_work(&self) -> MutexGuard<'_, Vec<Deferred<T>>> {
+        self.deferred
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use once_cell::sync::Lazy;
+
+    use super::*;
+
+    static GATE: Lazy<Gate<Vec<&'static str>>> = Lazy::new(|| Gate::new(Vec::new()));
+
+    /// Stands in for a C++ call that fires a game event, which comes back to
+    /// us over the bridge before the call returns.
+    fn equip_in_game() {
+        GATE.dispatch(|log| log.push("equip event"));
+        let count = GATE.query(|log| log.len());
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn calls_that_come_back_around_wait_their_turn() {
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || {
+            GATE.dispatch(|log| {
+                log.push("cycle");
+                equip_in_game();
+                log.push("cycle done");
+            });
+            done.send(GATE.query(|log| log.clone())).ok();
+        });
+        let seen = finished
+            .recv_timeout(Duration::from_secs(5))
+            .expect("a reentrant call must not hang");
+        assert_eq!(seen, vec!["cycle", "cycle done", "equip event"]);
+        assert_eq!(GATE.try_peek(|log| log.len()), Some(3));
+    }
+}
@@ -0,0 +1,82 @@
+//! Some mods let one weapon be wielded either one- or two-handed. The game
+//! tells us the weapon's usual grip; this remembers the grip the player chose
+//! for it, per hand, so a grip switch doesn't look like a new equip.
+
+use std::collections::HashMap;
+
+use super::keys::CycleSlot;
+use crate::data::HudItem;
+
+/// The grip the player picked for each switchable weapon, by hand.
+#[derive(Debug, Clone, Default)]
+pub struct GripTracker {
+    chosen: HashMap<(CycleSlot, String), bool>,
+}
+
+impl GripTracker {
+    /// Record the grip for the weapon in this hand. Returns true if it changed.
+    pub fn set(&mut self, hand: &CycleSlot, form_spec: &str, two_handed: bool) -> bool {
+        let previous = self
+            .chosen
+            .insert((hand.clone(), form_spec.to_string()), two_handed);
+        previous != Some(two_handed)
+    }
+
+    /// The grip the player chose for this weapon in this hand, if they ever
+    /// switched it.
+    pub fn chosen(&self, hand: &CycleSlot, form_spec: &str) -> Option<bool> {
+        self.chosen
+            .get(&(hand.clone(), form_spec.to_string()))
+            .copied()
+    }
+
+    /// Is this item held in both hands when in this hand? The chosen grip
+    /// wins over the weapon's usual one.
+    pub fn two_handed(&self, hand: &CycleSlot, item: &HudItem) -> bool {
+        self.chosen(hand, &item.form_string())
+            .unwrap_or_else(|| item.two_handed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::color::InvColor;
+    use crate::data::weapon::{WeaponEquipType, WeaponType};
+    use crate::data::BaseType;
+    use crate::images::icons::Icon;
+
+    fn sword(spec: &str) -> HudItem {
+        HudItem::preclassified(
+            "Versatile Sword".to_string(),
+            spec.to_string(),
+            1,
+            BaseType::Weapon(WeaponType::new(
+                Icon::WeaponSwordOneHanded,
+                InvColor::default(),
+                WeaponEquipType::EitherHand,
+            )),
+        )
+    }
+
+    #[test]
+    fn grips_are_remembered_per_hand() {
+        let specs = ["Test.esp|0x801", "Test.esp|0x802"];
+        let mut grips = GripTracker::default();
+        let held = sword(specs[1]);
+        assert!(held.is_weapon());
+        assert!(!grips.two_handed(&CycleSlot::Right, &held));
+        assert_eq!(grips.chosen(&CycleSlot::Right, specs[1]), None);
+
+        assert!(grips.set(&CycleSlot::Right, specs[1], true));
+        assert!(grips.two_handed(&CycleSlot::Right, &held));
+        assert!(!grips.set(&CycleSlot::Right, specs[1], true));
+        assert!(grips.set(&CycleSlot::Right, specs[1], false));
+        assert!(!grips.two_handed(&CycleSlot::Right, &held));
+
+        // The grip is remembered per hand and per weapon.
+        assert!(grips.set(&CycleSlot::Right, specs[1], true));
+        assert_eq!(grips.chosen(&CycleSlot::Left, specs[1]), None);
+        assert_eq!(grips.chosen(&CycleSlot::Right, specs[0]), None);
+    }
+}
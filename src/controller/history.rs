@@ -0,0 +1,149 @@
+//! A short memory of what the player has had equipped in each cycle slot.
+//! This lets us answer questions like "what was the last real weapon in my
+//! right hand before I picked up this pickaxe?"
+
+use std::collections::{HashMap, VecDeque};
+
+use super::keys::CycleSlot;
+
+/// How many entries we remember for each slot.
+const HISTORY_LEN: usize = 10;
+
+/// Most-recent-first lists of form specs, one per slot. Not persisted.
+#[derive(Debug, Clone, Default)]
+pub struct EquipHistory {
+    slots: HashMap<CycleSlot, VecDeque<String>>,
+}
+
+impl EquipHistory {
+    /// Record that this item was just equipped in the given slot. An item
+    /// appears in a slot's history at most once, at its most recent position.
+    pub fn record(&mut self, slot: &CycleSlot, form_spec: &str) {
+        if form_spec.is_empty() {
+            return;
+        }
+        let entries = self.slots.entry(slot.clone()).or_default();
+        entries.retain(|xs| xs != form_spec);
+        entries.push_front(form_spec.to_string());
+        entries.truncate(HISTORY_LEN);
+    }
+
+    /// The item most recently equipped in this slot, if we know of one.
+    pub fn latest(&self, slot: &CycleSlot) -> Option<String> {
+        self.slots.get(slot).and_then(|xs| xs.front().cloned())
+    }
+
+    /// The item this slot held before its latest one, if we know of one.
+    pub fn previous(&self, slot: &CycleSlot) -> Option<String> {
+        self.slots.get(slot).and_then(|xs| xs.get(1).cloned())
+    }
+
+    /// Find the most recently-equipped item in this slot that passes the test.
+    pub fn most_recent_matching<F>(&self, slot: &CycleSlot, mut predicate: F) -> Option<String>
+    where
+        F: FnMut(&String) -> bool,
+    {
+        self.slots
+            .get(slot)
+            .and_then(|entries| entries.iter().find(|xs| predicate(xs)).cloned())
+    }
+
+    /// Forget everything. Called when a new save is loaded.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_tool(spec: &str) -> bool {
+        spec.contains("pickaxe") || spec.contains("woodaxe")
+    }
+
+    #[test]
+    fn records_most_recent_first() {
+        let mut history = EquipHistory::default();
+        assert!(history.latest(&CycleSlot::Right).is_none());
+        history.record(&CycleSlot::Right, "sword");
+        history.record(&CycleSlot::Right, "mace");
+        history.record(&CycleSlot::Left, "shield");
+        assert_eq!(history.latest(&CycleSlot::Right), Some("mace".to_string()));
+        assert_eq!(history.latest(&CycleSlot::Left), Some("shield".to_string()));
+        history.record(&CycleSlot::Right, "sword");
+        assert_eq!(history.latest(&CycleSlot::Right), Some("sword".to_string()));
+    }
+
+    #[test]
+    fn swapping_back_twice_returns_to_the_start() {
+        let mut history = EquipHistory::default();
+        history.record(&CycleSlot::Right, "sword");
+        assert!(history.previous(&CycleSlot::Right).is_none());
+        history.record(&CycleSlot::Right, "mace");
+        assert_eq!(
+            history.previous(&CycleSlot::Right),
+            Some("sword".to_string())
+        );
+
+        // The swap is an equip like any other.
+        history.record(&CycleSlot::Right, "sword");
+        assert_eq!(
+            history.previous(&CycleSlot::Right),
+            Some("mace".to_string())
+        );
+        history.record(&CycleSlot::Right, "mace");
+        assert_eq!(history.latest(&CycleSlot::Right), Some("mace".to_string()));
+        assert!(history.previous(&CycleSlot::Left).is_none());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = EquipHistory::default();
+        for i in 0..(HISTORY_LEN + 5) {
+            history.record(&CycleSlot::Right, format!("item-{i}").as_str());
+        }
+        assert!(history
+            .most_recent_matching(&CycleSlot::Right, |xs| xs == "item-0")
+            .is_none());
+        assert_eq!(
+            history.latest(&CycleSlot::Right),
+            Some(format!("item-{}", HISTORY_LEN + 4))
+        );
+    }
+
+    #[test]
+    fn tool_sequences_preserve_main_weapon() {
+        let mut history = EquipHistory::default();
+        history.record(&CycleSlot::Right, "sword");
+        history.record(&CycleSlot::Right, "pickaxe");
+        let main = history.most_recent_matching(&CycleSlot::Right, |xs| !is_tool(xs));
+        assert_eq!(main, Some("sword".to_string()));
+
+        // tool -> main -> tool again: the main weapon is still the sword
+        history.record(&CycleSlot::Right, "sword");
+        history.record(&CycleSlot::Right, "woodaxe");
+        let main = history.most_recent_matching(&CycleSlot::Right, |xs| !is_tool(xs));
+        assert_eq!(main, Some("sword".to_string()));
+
+        // switching between tools doesn't disturb it either
+        history.record(&CycleSlot::Right, "pickaxe");
+        let main = history.most_recent_matching(&CycleSlot::Right, |xs| !is_tool(xs));
+        assert_eq!(main, Some("sword".to_string()));
+
+        // a new main weapon takes over
+        history.record(&CycleSlot::Right, "mace");
+        history.record(&CycleSlot::Right, "pickaxe");
+        let main = history.most_recent_matching(&CycleSlot::Right, |xs| !is_tool(xs));
+        assert_eq!(main, Some("mace".to_string()));
+    }
+
+    #[test]
+    fn only_tools_means_no_main() {
+        let mut history = EquipHistory::default();
+        history.record(&CycleSlot::Right, "pickaxe");
+        history.record(&CycleSlot::Right, "woodaxe");
+        let main = history.most_recent_matching(&CycleSlot::Right, |xs| !is_tool(xs));
+        assert!(main.is_none());
+    }
+}
@@ -0,0 +1,94 @@
+//! Hotbar keys pick cycle entries by position, the way an MMO hotbar does. The
+//! cycle turns whenever the player cycles or equips something, so a position
+//! can't count from whatever is on top right now: the same key would pick a
+//! different entry every time. Each cycle's hotbar counts from an anchor entry
+//! instead, the one on top the first time a hotbar key reached that cycle.
+//! Position N stays the same entry however the cycle turns, until the anchor
+//! itself leaves the cycle and the next press picks a new one.
+
+use std::collections::HashMap;
+
+use super::keys::CycleSlot;
+
+/// The entry each cycle's hotbar counts from.
+#[derive(Debug, Clone, Default)]
+pub struct HotbarAnchors {
+    anchors: HashMap<CycleSlot, String>,
+}
+
+impl HotbarAnchors {
+    /// The entry at this position in the cycle, counting from the cycle's
+    /// anchor. Positions count from 0. None if the cycle is too short.
+    pub fn resolve(
+        &mut self,
+        which: &CycleSlot,
+        cycle: &[String],
+        position: usize,
+    ) -> Option<String> {
+        if position >= cycle.len() {
+            return None;
+        }
+        let start = self
+            .anchors
+            .get(which)
+            .and_then(|anchor| cycle.iter().position(|xs| xs == anchor))
+            .unwrap_or_else(|| {
+                self.anchors.insert(which.clone(), cycle[0].clone());
+                0
+            });
+        Some(cycle[(start + position) % cycle.len()].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::cycles::CycleData;
+
+    #[test]
+    fn positions_survive_the_cycle_turning() {
+        let mut cycles = CycleData::default();
+        for spec in ["one", "two", "three", "four"] {
+            cycles.insert_at(&CycleSlot::Right, spec, usize::MAX);
+        }
+        let mut hotbar = HotbarAnchors::default();
+        let mut press = |cycles: &mut CycleData, position: usize| {
+            let picked = hotbar.resolve(
+                &CycleSlot::Right,
+                &cycles.formids(&CycleSlot::Right),
+                position,
+            );
+            // The controller turns the cycle to the pick, as it does here.
+            if let Some(spec) = picked.as_ref() {
+                cycles.set_top(&CycleSlot::Right, spec);
+            }
+            picked
+        };
+
+        assert_eq!(press(&mut cycles, 2).as_deref(), Some("three"));
+        assert_eq!(cycles.get_top(&CycleSlot::Right).as_deref(), Some("three"));
+        // The same key again is the same entry, not the one two past it.
+        assert_eq!(press(&mut cycles, 2).as_deref(), Some("three"));
+        assert_eq!(press(&mut cycles, 0).as_deref(), Some("one"));
+        assert_eq!(press(&mut cycles, 3).as_deref(), Some("four"));
+
+        // Cycling the ordinary way doesn't move the hotbar either.
+        cycles.advance(&CycleSlot::Right, 1);
+        assert_eq!(press(&mut cycles, 1).as_deref(), Some("two"));
+
+        // Past the end of the cycle is nothing, and changes nothing.
+        assert_eq!(press(&mut cycles, 4), None);
+        assert_eq!(cycles.get_top(&CycleSlot::Right).as_deref(), Some("two"));
+
+        // If the anchor goes, the hotbar counts from the top again.
+        cycles.remove_spec(&CycleSlot::Right, "one");
+        assert_eq!(press(&mut cycles, 0).as_deref(), Some("two"));
+        assert_eq!(press(&mut cycles, 2).as_deref(), Some("four"));
+
+        assert_eq!(
+            hotbar.resolve(&CycleSlot::Utility, &[], 0),
+            None,
+            "an empty cycle has nothing at any position"
+        );
+    }
+}
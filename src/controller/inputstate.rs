@@ -0,0 +1,197 @@
+//! The controller's view of the player's hands on the keyboard: which keys are
+//! down and for how long, which presses belong to menus, held keys repeating,
+//! and the last menu toggle. Nothing in here talks to the game; the controller
+//! turns what this decides into timer starts and stops on the C++ side.
+
+use std::collections::HashMap;
+
+use super::hotbar::HotbarAnchors;
+use super::keylog::KeyLog;
+use super::keys::{Hotkey, InputContext, KeyState, Modifier, TrackedKey};
+use super::menutoggle::ToggleMemo;
+use super::presstiming::AutoRepeat;
+use crate::plugin::Action;
+
+/// What a key event means for the key's long-press timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongPressTimer {
+    Start(Action),
+    Stop(Action),
+    Leave,
+}
+
+/// The outcome of tracking one key event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Press {
+    /// The key's state after this event.
+    pub tracked: TrackedKey,
+    /// True if this is the key-up that ends a long press, which was already
+    /// acted on when its timer fired.
+    pub swallow: bool,
+    pub timer: LongPressTimer,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    /// Every key we've seen, and its state. We need this for modifier keys.
+    pub tracked_keys: HashMap<u32, TrackedKey>,
+    /// Which keys and input batches belong to menus rather than gameplay.
+    pub context: InputContext,
+    /// Repeat counts for cycle keys being held down.
+    pub repeats: AutoRepeat,
+    /// The last menu toggle, so a doubly-delivered event toggles only once.
+    pub toggle_memo: ToggleMemo,
+    /// The last few gameplay key events and our answers, for the state dump.
+    pub recent: KeyLog,
+    /// Where each cycle's hotbar positions count from.
+    pub hotbar: HotbarAnchors,
+    /// Whether the right cycle key went down while a long press would put away
+    /// a tool. Decided once per press, not on every event.
+    pub leaving_tool: bool,
+}
+
+impl InputState {
+    /// Track a key event. `long_press` says whether this key uses a long-press
+    /// timer right now.
+    pub fn track(&mut self, key: u32, state: KeyState, long_press: bool) -> Press {
+        let mut swallow = false;
+        let tracked = if let Some(previous) = self.tracked_keys.get_mut(&key) {
+            // Did this key just have a long-press event? If so, ignore a key-up.
+            // We ask this question before we update the tracking data.
+            if matches!(previous.state, KeyState::Pressed) && previous.is_long_press() && long_press
+            {
+                swallow = true;
+            }
+            previous.set_state(state);
+            previous.clone()
+        } else {
+            let fresh = TrackedKey::with_state(key, state);
+            self.tracked_keys.insert(key, fresh.clone());
+            fresh
+        };
+
+        let timer = match (long_press, long_press_action(&tracked.action())) {
+            (true, Some(action)) if matches!(tracked.state, KeyState::Down) => {
+                LongPressTimer::Start(action)
+            }
+            (true, Some(action)) if matches!(tracked.state, KeyState::Up) => {
+                LongPressTimer::Stop(action)
+            }
+            _ => LongPressTimer::Leave,
+        };
+
+        Press {
+            tracked,
+            swallow,
+            timer,
+        }
+    }
+
+    /// Note a key's new state without acting on it, if we're tracking it.
+    pub fn note_state(&mut self, key: u32, state: KeyState) {
+        if let Some(tracked) = self.tracked_keys.get_mut(&key) {
+            tracked.set_state(state);
+        }
+    }
+
+    /// The state of the key bound to this hotkey. If the hotkey also has a
+    /// gamepad button and only the button is held, that's the state we report.
+    pub fn hotkey(&self, hotkey: &Hotkey) -> TrackedKey {
+        let key = self.by_code(hotkey.key_for());
+        if key.is_up() {
+            let button = self.by_code(hotkey.gamepad_key_for());
+            if !button.is_up() {
+                return button;
+            }
+        }
+        key
+    }
+
+    /// The state of the key bound to this modifier.
+    pub fn modifier(&self, modifier: &Modifier) -> TrackedKey {
+        self.by_code(modifier.key_for())
+    }
+
+    fn by_code(&self, key: i32) -> TrackedKey {
+        if key < 0 {
+            return TrackedKey::default();
+        }
+        self.tracked_keys
+            .get(&key.unsigned_abs())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// The long-press timer that belongs to this cycle action, if it has one.
+pub fn long_press_action(action: &Action) -> Option<Action> {
+    match *action {
+        Action::Power => Some(Action::LongPressPower),
+        Action::Utility => Some(Action::LongPressUtility),
+        Action::Left => Some(Action::LongPressLeft),
+        Action::Right => Some(Action::LongPressRight),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::controller::timers::EquipTimers;
+
+    #[test]
+    fn presses_start_and_stop_long_press_timers() {
+        let mut input = InputState::default();
+        // Key 19 isn't bound to anything by default, so it has no timer.
+        let press = input.track(19, KeyState::Down, true);
+        assert_eq!(press.timer, LongPressTimer::Leave);
+        assert!(!press.swallow);
+
+        let right = Hotkey::Right.key_for().unsigned_abs();
+        let mut timers = EquipTimers::default();
+        let down = input.track(right, KeyState::Down, true);
+        assert_eq!(down.timer, LongPressTimer::Start(Action::LongPressRight));
+        timers.start(Action::LongPressRight);
+        assert!(input.hotkey(&Hotkey::Right).is_pressed());
+
+        let up = input.track(right, KeyState::Up, true);
+        assert_eq!(up.timer, LongPressTimer::Stop(Action::LongPressRight));
+        assert!(!up.swallow);
+        timers.stop(&Action::LongPressRight);
+        assert!(timers.advance(5.0, |_| 100).is_empty());
+        assert!(input.hotkey(&Hotkey::Right).is_up());
+
+        // Without long-press behavior, the same key leaves timers alone.
+        assert_eq!(
+            input.track(right, KeyState::Down, false).timer,
+            LongPressTimer::Leave
+        );
+    }
+
+    #[test]
+    fn the_key_up_after_a_long_press_is_swallowed() {
+        let mut input = InputState::default();
+        let left = Hotkey::Left.key_for().unsigned_abs();
+        input.track(left, KeyState::Down, true);
+        input.track(left, KeyState::Pressed, true);
+        // Pretend the key has been held for a long time.
+        if let Some(tracked) = input.tracked_keys.get_mut(&left) {
+            tracked.press_start = Instant::now().checked_sub(Duration::from_secs(10));
+        }
+        let up = input.track(left, KeyState::Up, true);
+        assert!(up.swallow);
+
+        // A quick tap isn't.
+        input.track(left, KeyState::Down, true);
+        input.track(left, KeyState::Pressed, true);
+        assert!(!input.track(left, KeyState::Up, true).swallow);
+
+        // Menu events update state without starting anything.
+        input.note_state(left, KeyState::Down);
+        assert!(input.hotkey(&Hotkey::Left).is_pressed());
+        input.note_state(4242, KeyState::Down);
+        assert!(!input.tracked_keys.contains_key(&4242));
+    }
+}
@@ -0,0 +1,146 @@
+//! Input codes and the devices they come from. The game numbers keyboard keys,
+//! mouse buttons, and gamepad buttons separately, so the same small number can
+//! be a keyboard key or a mouse button. The C++ hooks fold the device into the
+//! code the way SkyUI does, and everything on this side of the bridge sees
+//! only the folded code:
+//!
+//! - keyboard scancodes are left as they are: 0 through 255;
+//! - mouse buttons start at 256: the left button is 256, the fourth button is
+//!   259, and the wheel is 264 and 265;
+//! - gamepad buttons start at 266, in the order in `keycodes.cpp`.
+//!
+//! Settings files written by the MCM hold folded codes. A hand-edited file can
+//! name the device instead, e.g. `mouse:4`, and a bare number is a folded code,
+//! which for anything under 256 means a keyboard key.
+
+use std::fmt::Display;
+
+/// Where mouse button codes start.
+pub const MOUSE_OFFSET: u32 = 256;
+/// Where gamepad button codes start.
+pub const GAMEPAD_OFFSET: u32 = 266;
+/// How many gamepad buttons we know about.
+const GAMEPAD_BUTTONS: u32 = 16;
+
+const GAMEPAD_NAMES: [&str; GAMEPAD_BUTTONS as usize] = [
+    "Up", "Down", "Left", "Right", "Start", "Back", "LS", "RS", "LB", "RB", "A", "B", "X", "Y",
+    "LT", "RT",
+];
+
+/// The device an input code belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    Keyboard,
+    Mouse,
+    Gamepad,
+}
+
+/// An input code split back into its device and the device's own number for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCode {
+    pub device: InputDevice,
+    /// The game's number for the input on that device, counting from 0.
+    pub code: u32,
+}
+
+impl KeyCode {
+    pub fn new(device: InputDevice, code: u32) -> Self {
+        Self { device, code }
+    }
+
+    /// Split a folded input code. None if it's out of range for every device.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        if raw < MOUSE_OFFSET {
+            Some(Self::new(InputDevice::Keyboard, raw))
+        } else if raw < GAMEPAD_OFFSET {
+            Some(Self::new(InputDevice::Mouse, raw - MOUSE_OFFSET))
+        } else if raw < GAMEPAD_OFFSET + GAMEPAD_BUTTONS {
+            Some(Self::new(InputDevice::Gamepad, raw - GAMEPAD_OFFSET))
+        } else {
+            None
+        }
+    }
+
+    /// The folded input code, as the C++ hooks send it.
+    pub fn raw(&self) -> u32 {
+        match self.device {
+            InputDevice::Keyboard => self.code,
+            InputDevice::Mouse => self.code + MOUSE_OFFSET,
+            InputDevice::Gamepad => self.code + GAMEPAD_OFFSET,
+        }
+    }
+
+    /// Read a binding that names its device: `keyboard:5`, `mouse:4`, or
+    /// `gamepad:10`. Mouse buttons count from 1 here, as players count them;
+    /// keyboard scancodes and gamepad buttons count from 0, as the game does.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (device, number) = value.trim().split_once(':')?;
+        let number = number.trim().parse::<u32>().ok()?;
+        let keycode = match device.trim().to_lowercase().as_str() {
+            "keyboard" | "key" => Self::new(InputDevice::Keyboard, number),
+            "mouse" => Self::new(InputDevice::Mouse, number.checked_sub(1)?),
+            "gamepad" => Self::new(InputDevice::Gamepad, number),
+            _ => return None,
+        };
+        // Reject anything that would spill into the next device's codes.
+        Self::from_raw(keycode.raw()).filter(|xs| *xs == keycode)
+    }
+}
+
+impl Display for KeyCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.device {
+            InputDevice::Keyboard => write!(f, "Key {}", self.code),
+            InputDevice::Mouse => match self.code {
+                8 => write!(f, "Mouse Wheel Up"),
+                9 => write!(f, "Mouse Wheel Down"),
+                n => write!(f, "Mouse {}", n + 1),
+            },
+            InputDevice::Gamepad => write!(f, "Gamepad {}", GAMEPAD_NAMES[self.code as usize]),
+        }
+    }
+}
+
+/// A name for this input code that says which device it's on.
+pub fn key_name(raw: u32) -> String {
+    match KeyCode::from_raw(raw) {
+        Some(keycode) => keycode.to_string(),
+        None => format!("Unknown input {raw}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn devices_do_not_share_codes() {
+        let key = KeyCode::parse("keyboard:5").expect("keyboard 5 is a key");
+        let mouse = KeyCode::parse("mouse:5").expect("mouse 5 is a button");
+        assert_ne!(key.raw(), mouse.raw());
+        assert_eq!(key.raw(), 5);
+        assert_eq!(mouse.raw(), 260);
+        assert_eq!(KeyCode::from_raw(260), Some(mouse));
+        assert_eq!(key_name(5), "Key 5");
+        assert_eq!(key_name(259), "Mouse 4");
+        assert_eq!(key_name(264), "Mouse Wheel Up");
+        assert_eq!(key_name(276), "Gamepad A");
+        assert_eq!(key_name(400), "Unknown input 400");
+    }
+
+    #[test]
+    fn bindings_stay_on_their_device() {
+        assert_eq!(
+            KeyCode::parse(" Mouse : 1 ").map(|xs| xs.raw()),
+            Some(MOUSE_OFFSET)
+        );
+        assert_eq!(KeyCode::parse("gamepad:15").map(|xs| xs.raw()), Some(281));
+        // Mouse buttons count from 1, and there are only ten of them.
+        assert!(KeyCode::parse("mouse:0").is_none());
+        assert!(KeyCode::parse("mouse:11").is_none());
+        assert!(KeyCode::parse("keyboard:256").is_none());
+        assert!(KeyCode::parse("gamepad:16").is_none());
+        assert!(KeyCode::parse("trackball:1").is_none());
+        assert!(KeyCode::parse("42").is_none());
+    }
+}
@@ -0,0 +1,88 @@
+//! The last few key events the controller handled and what it made of them.
+//! Kept for the state dump, so a report like "my cycle skipped an item" comes
+//! with the presses that led up to it. Not persisted.
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use super::keycodes::key_name;
+use super::keys::KeyState;
+use crate::plugin::{Action, KeyEventResponse};
+
+/// How many key events we remember.
+const KEY_LOG_LEN: usize = 10;
+
+/// One key event and our answer to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyLogEntry {
+    pub key: u32,
+    pub state: KeyState,
+    /// The action the key is bound to, if any.
+    pub action: Action,
+    pub response: KeyEventResponse,
+}
+
+impl Display for KeyLogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}) {} -> {:?}; handled={}; start_timer={:?}; stop_timer={:?}; outcome={:?};",
+            key_name(self.key),
+            self.key,
+            self.state,
+            self.action,
+            self.response.handled,
+            self.response.start_timer,
+            self.response.stop_timer,
+            self.response.outcome,
+        )
+    }
+}
+
+/// Oldest first, bounded.
+#[derive(Debug, Clone, Default)]
+pub struct KeyLog {
+    entries: VecDeque<KeyLogEntry>,
+}
+
+impl KeyLog {
+    pub fn record(&mut self, key: u32, state: KeyState, response: &KeyEventResponse) {
+        if self.entries.len() == KEY_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(KeyLogEntry {
+            key,
+            state,
+            action: Action::from(key),
+            response: response.clone(),
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &KeyLogEntry> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_latest_keys_are_kept() {
+        let mut log = KeyLog::default();
+        assert_eq!(log.entries().count(), 0);
+        for key in 0..(KEY_LOG_LEN as u32 + 3) {
+            log.record(key, KeyState::Up, &KeyEventResponse::default());
+        }
+        let keys: Vec<u32> = log.entries().map(|xs| xs.key).collect();
+        assert_eq!(keys.len(), KEY_LOG_LEN);
+        assert_eq!(keys.first(), Some(&3));
+        assert_eq!(keys.last(), Some(&(KEY_LOG_LEN as u32 + 2)));
+
+        let handled = KeyEventResponse::handled();
+        log.record(200, KeyState::Down, &handled);
+        let latest = log.entries().last().expect("just recorded one");
+        assert_eq!(latest.response, handled);
+        assert!(latest.to_string().contains("(200) Down"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Structs and trait impls for considering keyboard/controller state.
 //! There are too many enums here and a substantial rework is called for.
 
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::time::{Duration, Instant};
 
@@ -9,7 +10,8 @@ use eyre::eyre;
 use strum::Display;
 
 use super::control::RequestedAction;
-use super::settings::{settings, ActivationMethod, UnarmedMethod};
+use super::keycodes::key_name;
+use super::settings::{key_action, key_is_watched, settings, ActivationMethod, UnarmedMethod};
 use crate::plugin::{hasRangedEquipped, Action, ButtonEvent, HudElement};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Display)]
@@ -26,6 +28,8 @@ pub enum Modifier {
     Cycle,
     Activate,
     Menu,
+    Hotbar,
+    BothHands,
 }
 
 impl Modifier {
@@ -36,6 +40,8 @@ impl Modifier {
             Modifier::Cycle => options.cycle_modifier(),
             Modifier::Activate => options.activate_modifier(),
             Modifier::Menu => options.menu_modifier(),
+            Modifier::Hotbar => options.hotbar_modifier(),
+            Modifier::BothHands => options.both_hands_modifier(),
         }
     }
 }
@@ -51,6 +57,11 @@ pub enum Hotkey {
     UnequipHands,
     Refresh,
     ShowHide,
+    Ammo,
+    LeftPrevious,
+    RightPrevious,
+    /// Equip or use the entry at this position in the hotbar's cycle.
+    HotbarSlot(usize),
     Modifier(EnumSet<Modifier>), // for overloaded modifiers
     #[default]
     None,
@@ -58,6 +69,9 @@ pub enum Hotkey {
 
 impl From<u32> for Hotkey {
     fn from(v: u32) -> Self {
+        if !key_is_watched(v) {
+            return Hotkey::None;
+        }
         let options = settings();
         let mut set: EnumSet<Modifier> = EnumSet::new();
 
@@ -77,27 +91,25 @@ impl From<u32> for Hotkey {
         if options.menu_modifier().is_positive() && v == options.menu_modifier().unsigned_abs() {
             set.insert(Modifier::Menu);
         }
+        if options.hotbar_modifier().is_positive() && v == options.hotbar_modifier().unsigned_abs()
+        {
+            set.insert(Modifier::Hotbar);
+        }
+        if options.both_hands_modifier().is_positive()
+            && v == options.both_hands_modifier().unsigned_abs()
+        {
+            set.insert(Modifier::BothHands);
+        }
 
         if !set.is_empty() {
-            Hotkey::Modifier(set)
-        } else if v == options.power() {
-            Hotkey::Power
-        } else if v == options.utility() {
-            Hotkey::Utility
-        } else if v == options.left() {
-            Hotkey::Left
-        } else if v == options.right() {
-            Hotkey::Right
-        } else if v == options.equipset() as u32 {
-            Hotkey::Equipment
-        } else if v == options.refresh_layout() {
-            Hotkey::Refresh
-        } else if v == options.showhide() {
-            Hotkey::ShowHide
-        } else if v == options.activate() {
-            Hotkey::Activate
-        } else if v == options.unequip_hotkey() as u32 {
-            Hotkey::UnequipHands
+            return Hotkey::Modifier(set);
+        }
+        // The key map knows every binding, extra keys and gamepad buttons included.
+        let action = key_action(v);
+        if action != Action::None {
+            Hotkey::from(&action)
+        } else if let Some(slot) = options.hotbar_slot_for(v) {
+            Hotkey::HotbarSlot(slot)
         } else {
             Hotkey::None
         }
@@ -118,6 +130,10 @@ impl Hotkey {
             Hotkey::UnequipHands => options.unequip_hotkey() as i32,
             Hotkey::Refresh => options.refresh_layout() as i32,
             Hotkey::ShowHide => options.showhide() as i32,
+            Hotkey::Ammo => options.ammo_hotkey(),
+            Hotkey::LeftPrevious => options.left_previous_hotkey(),
+            Hotkey::RightPrevious => options.right_previous_hotkey(),
+            Hotkey::HotbarSlot(slot) => options.hotbar_key(*slot),
             Hotkey::Modifier(meanings) => {
                 // This is going to map to a single re-used key.
                 if let Some(meaning) = meanings.iter().find_map(Some) {
@@ -130,14 +146,31 @@ impl Hotkey {
         }
     }
 
+    /// The gamepad button bound to this hotkey alongside its key, or -1.
+    pub fn gamepad_key_for(&self) -> i32 {
+        let options = settings();
+
+        match self {
+            Hotkey::Power => options.power_gamepad(),
+            Hotkey::Utility => options.utility_gamepad(),
+            Hotkey::Left => options.left_gamepad(),
+            Hotkey::Right => options.right_gamepad(),
+            Hotkey::Activate => options.activate_gamepad(),
+            _ => -1,
+        }
+    }
+
     pub fn long_press_action(&self) -> RequestedAction {
         let settings = settings();
         let advance = matches!(settings.cycle_advance_method(), ActivationMethod::LongPress);
         let unequip = matches!(settings.unequip_method(), UnarmedMethod::LongPress);
+        let equip_now = settings.long_press_equips_now();
 
         if matches!(self, Hotkey::Power) {
             if unequip {
                 RequestedAction::Unequip
+            } else if equip_now {
+                RequestedAction::EquipNow
             } else if advance {
                 RequestedAction::Advance
             } else {
@@ -148,7 +181,7 @@ impl Hotkey {
                 settings.utility_activation_method(),
                 ActivationMethod::LongPress
             );
-            if consume {
+            if consume || equip_now {
                 RequestedAction::Consume
             } else if advance {
                 RequestedAction::Advance
@@ -161,6 +194,8 @@ impl Hotkey {
                 RequestedAction::Unequip
             } else if dual_wield {
                 RequestedAction::Match
+            } else if equip_now {
+                RequestedAction::EquipNow
             } else if advance {
                 if matches!(self, Hotkey::Left) && settings.cycle_ammo() && hasRangedEquipped() {
                     RequestedAction::AdvanceAmmo
@@ -200,6 +235,9 @@ impl From<&Action> for Hotkey {
             Action::Utility => Hotkey::Utility,
             Action::RefreshLayout => Hotkey::Refresh,
             Action::UnequipHands => Hotkey::UnequipHands,
+            Action::Ammo => Hotkey::Ammo,
+            Action::LeftPrevious => Hotkey::LeftPrevious,
+            Action::RightPrevious => Hotkey::RightPrevious,
             _ => Hotkey::None,
         }
     }
@@ -240,8 +278,12 @@ pub struct TrackedKey {
 
 impl TrackedKey {
     pub fn new(key: u32, event: &ButtonEvent) -> Self {
+        Self::with_state(key, KeyState::from(event))
+    }
+
+    /// Start tracking a key we've just seen in this state.
+    pub fn with_state(key: u32, state: KeyState) -> Self {
         let press_start = Some(Instant::now());
-        let state = KeyState::from(event);
         let hotkey = Hotkey::from(key);
 
         Self {
@@ -264,6 +306,15 @@ impl TrackedKey {
         matches!(self.hotkey, Hotkey::Modifier(_))
     }
 
+    /// The hotbar position this key selects, if it's a hotbar key.
+    pub fn hotbar_slot(&self) -> Option<usize> {
+        if let Hotkey::HotbarSlot(slot) = self.hotkey {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+
     pub fn is_cycle_key(&self) -> bool {
         matches!(
             self.hotkey,
@@ -272,7 +323,11 @@ impl TrackedKey {
     }
 
     pub fn update(&mut self, event: &ButtonEvent) {
-        self.state = KeyState::from(event);
+        self.set_state(KeyState::from(event));
+    }
+
+    pub fn set_state(&mut self, state: KeyState) {
+        self.state = state;
         match self.state {
             KeyState::Up => {
                 // nothing?
@@ -288,15 +343,20 @@ impl TrackedKey {
         }
     }
 
+    /// Has this key been held long enough to be a long press for its slot?
     pub fn is_long_press(&self) -> bool {
-        if let Some(start) = self.press_start {
-            let elapsed_time = start.elapsed();
-            elapsed_time > Duration::from_millis(settings().long_press_ms().into())
+        if let Some(held) = self.held_for() {
+            settings().timings_for(&self.action()).is_long_press(held)
         } else {
             false
         }
     }
 
+    /// How long the key has been held in the current press, if it's down.
+    pub fn held_for(&self) -> Option<Duration> {
+        self.press_start.map(|start| start.elapsed())
+    }
+
     pub fn is_up(&self) -> bool {
         matches!(self.state, KeyState::Up)
     }
@@ -319,7 +379,86 @@ impl Default for TrackedKey {
 
 impl Display for TrackedKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Key: kind={}; state={};", self.key, self.state)
+        write!(
+            f,
+            "Key: kind={} ({}); state={};",
+            self.key,
+            key_name(self.key),
+            self.state
+        )
+    }
+}
+
+/// Which input context key events arrived in. Menus and gameplay can share
+/// physical keys, so we remember which keys were pressed while a menu had them
+/// and refuse to interpret those presses as gameplay input. The C++ hooks hand
+/// us a sequence number per input batch, so an event the menu already saw is
+/// recognizable when the gameplay sink receives it later.
+///
+/// While a game menu is open, nothing that reaches the gameplay sink counts,
+/// so cycles don't move behind the menu.
+#[derive(Debug, Default, Clone)]
+pub struct InputContext {
+    /// The most recent input batch the menu hook saw.
+    last_menu_seq: u64,
+    /// Keys pressed in a menu that haven't yet been released and pressed again in gameplay.
+    menu_owned: HashSet<u32>,
+    /// Game menus open right now. The C++ side tells us only about menus
+    /// that pause the game or take the cursor, not overlays like the HUD.
+    open_menus: HashSet<String>,
+}
+
+impl InputContext {
+    /// Note that the game opened or closed a menu.
+    pub fn menu_changed(&mut self, name: &str, opening: bool) {
+        if opening {
+            self.open_menus.insert(name.to_string());
+        } else {
+            self.open_menus.remove(name);
+        }
+    }
+
+    /// Is a game menu open?
+    pub fn in_menu(&self) -> bool {
+        !self.open_menus.is_empty()
+    }
+
+    /// Note that the menu hook saw this key event.
+    pub fn record_menu_event(&mut self, key: u32, sequence: u64, state: &KeyState) {
+        self.last_menu_seq = self.last_menu_seq.max(sequence);
+        if matches!(state, KeyState::Down | KeyState::Pressed) {
+            self.menu_owned.insert(key);
+        }
+    }
+
+    /// Decide if a gameplay key event should be acted on. Events from a batch the menu
+    /// already handled are dropped, as is anything a menu-claimed key does until it is
+    /// freshly pressed again outside the menu.
+    pub fn accept_world_event(&mut self, key: u32, sequence: u64, state: &KeyState) -> bool {
+        if self.in_menu() {
+            // The release will come after the menu closes. It isn't ours either.
+            if matches!(state, KeyState::Down | KeyState::Pressed) {
+                self.menu_owned.insert(key);
+            }
+            return false;
+        }
+        if sequence <= self.last_menu_seq {
+            return false;
+        }
+        if !self.menu_owned.contains(&key) {
+            return true;
+        }
+        match state {
+            KeyState::Down => {
+                self.menu_owned.remove(&key);
+                true
+            }
+            KeyState::Up => {
+                self.menu_owned.remove(&key);
+                false
+            }
+            KeyState::Pressed => false,
+        }
     }
 }
 
@@ -334,6 +473,9 @@ impl From<&Hotkey> for Action {
             Hotkey::Refresh => Action::RefreshLayout,
             Hotkey::ShowHide => Action::ShowHide,
             Hotkey::Equipment => Action::Equipment,
+            Hotkey::Ammo => Action::Ammo,
+            Hotkey::LeftPrevious => Action::LeftPrevious,
+            Hotkey::RightPrevious => Action::RightPrevious,
             _ => Action::None,
         }
     }
@@ -401,3 +543,71 @@ impl From<CycleSlot> for Action {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_events_pass_without_menu_activity() {
+        let mut context = InputContext::default();
+        assert!(context.accept_world_event(3, 1, &KeyState::Down));
+        assert!(context.accept_world_event(3, 2, &KeyState::Pressed));
+        assert!(context.accept_world_event(3, 3, &KeyState::Up));
+    }
+
+    #[test]
+    fn queued_events_from_menu_batches_are_dropped() {
+        let mut context = InputContext::default();
+        context.record_menu_event(3, 10, &KeyState::Down);
+        // The gameplay sink sees the same batch after the menu hook did.
+        assert!(!context.accept_world_event(3, 10, &KeyState::Down));
+        // An older batch delivered late is also dropped, for any key.
+        assert!(!context.accept_world_event(4, 9, &KeyState::Up));
+        // A later batch for an unrelated key is fine.
+        assert!(context.accept_world_event(4, 11, &KeyState::Down));
+    }
+
+    #[test]
+    fn key_released_after_menu_closes_does_not_act() {
+        let mut context = InputContext::default();
+        context.record_menu_event(3, 10, &KeyState::Down);
+        assert!(!context.accept_world_event(3, 11, &KeyState::Pressed));
+        assert!(!context.accept_world_event(3, 12, &KeyState::Up));
+        // The next fresh press in gameplay is ours again.
+        assert!(context.accept_world_event(3, 13, &KeyState::Down));
+        assert!(context.accept_world_event(3, 14, &KeyState::Up));
+    }
+
+    #[test]
+    fn interleaved_menu_and_world_events() {
+        let mut context = InputContext::default();
+        assert!(context.accept_world_event(5, 1, &KeyState::Down));
+        context.record_menu_event(3, 2, &KeyState::Down);
+        // key 5 went down in gameplay before the menu opened, so its release is ours
+        assert!(context.accept_world_event(5, 3, &KeyState::Up));
+        assert!(!context.accept_world_event(3, 3, &KeyState::Up));
+        context.record_menu_event(5, 4, &KeyState::Up);
+        assert!(!context.accept_world_event(5, 4, &KeyState::Down));
+        assert!(context.accept_world_event(5, 5, &KeyState::Down));
+    }
+
+    #[test]
+    fn nothing_counts_while_a_menu_is_open() {
+        let mut context = InputContext::default();
+        assert!(!context.in_menu());
+        context.menu_changed("InventoryMenu", true);
+        context.menu_changed("Dialogue Menu", true);
+        assert!(!context.accept_world_event(3, 1, &KeyState::Down));
+        context.menu_changed("InventoryMenu", false);
+        assert!(!context.accept_world_event(3, 2, &KeyState::Pressed));
+        context.menu_changed("Dialogue Menu", false);
+        assert!(!context.in_menu());
+        // The key went down in the menu, so letting go of it does nothing.
+        assert!(!context.accept_world_event(3, 3, &KeyState::Up));
+        assert!(context.accept_world_event(3, 4, &KeyState::Down));
+        // Closing a menu we never saw open is harmless.
+        context.menu_changed("MapMenu", false);
+        assert!(!context.in_menu());
+    }
+}
@@ -0,0 +1,291 @@
+//! Self-imposed daily limits on utility items, for players who want rules like
+//! "at most three potions per in-game day." Limits come from a toml file the
+//! player edits by hand; without the file there are no limits. A limit can
+//! apply to a whole category or to one specific item:
+//!
+//! ```toml
+//! [categories]
+//! potion = 3
+//! food = 5
+//! poison = 2
+//!
+//! [items]
+//! "Skyrim.esm|0x3eb42" = 1
+//! ```
+//!
+//! An item with its own limit counts only against that limit, not against its
+//! category's. Counts are kept per game day and saved with the cycle data.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+use eyre::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::data::potion::PotionType;
+use crate::data::{BaseType, HudItem};
+
+static LIMITS_PATH: &str = "./data/SKSE/Plugins/SoulsyHUD_daily_limits.toml";
+
+/// There can be only one. Not public because we want access managed.
+static LIMITS: Lazy<Mutex<DailyLimits>> =
+    Lazy::new(|| Mutex::new(DailyLimits::read_or_default(LIMITS_PATH)));
+
+/// The daily limit that applies to this item, if the player has set one.
+pub fn daily_limit(item: &HudItem) -> Option<Limit> {
+    let limits = LIMITS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire daily limits lock.");
+    limits.lookup(item)
+}
+
+/// The kinds of consumable a category limit can apply to.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitCategory {
+    Potion,
+    Food,
+    Poison,
+}
+
+impl LimitCategory {
+    /// Which category this item belongs to, if any. The grouped-potion
+    /// proxies count as potions.
+    pub fn for_item(item: &HudItem) -> Option<Self> {
+        match item.kind() {
+            BaseType::Potion(PotionType::Poison) => Some(LimitCategory::Poison),
+            BaseType::Potion(_) | BaseType::PotionProxy(_) => Some(LimitCategory::Potion),
+            BaseType::Food(_) => Some(LimitCategory::Food),
+            _ => None,
+        }
+    }
+}
+
+/// A limit that applies to an item: the key its uses are counted under and
+/// how many uses a day it allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limit {
+    pub key: String,
+    pub per_day: u32,
+}
+
+/// The file as written.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+struct LimitsFile {
+    #[serde(default)]
+    categories: HashMap<LimitCategory, u32>,
+    #[serde(default)]
+    items: HashMap<String, u32>,
+}
+
+/// The validated limits.
+#[derive(Debug, Clone, Default)]
+pub struct DailyLimits {
+    categories: HashMap<LimitCategory, u32>,
+    items: HashMap<String, u32>,
+}
+
+impl DailyLimits {
+    /// Re-read the limits file to pick up any changes to it.
+    pub fn refresh() {
+        let fresh = DailyLimits::read_or_default(LIMITS_PATH);
+        let mut limits = LIMITS
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire daily limits lock.");
+        *limits = fresh;
+    }
+
+    /// Read the file if it exists. A missing file means no limits; a broken
+    /// file is logged and also means no limits.
+    fn read_or_default(pathstr: &str) -> Self {
+        let path = std::path::Path::new(pathstr);
+        if !path.exists() {
+            return DailyLimits::default();
+        }
+        match DailyLimits::read_from_file(pathstr) {
+            Ok(v) => {
+                log::info!("Read {} daily limits.", v.len());
+                v
+            }
+            Err(e) => {
+                log::warn!("Problem reading the daily limits file! {e:#}");
+                DailyLimits::default()
+            }
+        }
+    }
+
+    pub fn read_from_file(pathstr: &str) -> Result<Self> {
+        let buf = fs::read_to_string(pathstr)
+            .wrap_err_with(|| format!("Unable to read the daily limits file: {}", pathstr))?;
+        DailyLimits::from_toml(&buf)
+    }
+
+    pub fn from_toml(buf: &str) -> Result<Self> {
+        let parsed =
+            toml::from_str::<LimitsFile>(buf).wrap_err("The daily limits file can't be parsed.")?;
+        Ok(DailyLimits {
+            categories: parsed.categories,
+            items: parsed.items,
+        })
+    }
+
+    /// An item's own limit wins over its category's.
+    pub fn lookup(&self, item: &HudItem) -> Option<Limit> {
+        let spec = item.form_string();
+        if let Some(per_day) = self.items.get(&spec) {
+            return Some(Limit {
+                key: spec,
+                per_day: *per_day,
+            });
+        }
+        let category = LimitCategory::for_item(item)?;
+        self.categories.get(&category).map(|per_day| Limit {
+            key: format!("category:{category:?}").to_lowercase(),
+            per_day: *per_day,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len() + self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How many times each limit has been used on one game day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyUses {
+    day: u32,
+    counts: HashMap<String, u32>,
+}
+
+impl DailyUses {
+    pub fn new(day: u32, counts: HashMap<String, u32>) -> Self {
+        Self { day, counts }
+    }
+
+    /// Start counting afresh if the day has changed. Going backwards means a
+    /// save from an earlier day was loaded, which also starts over.
+    pub fn roll_to(&mut self, today: u32) {
+        if today != self.day {
+            self.day = today;
+            self.counts.clear();
+        }
+    }
+
+    /// Uses left today under this limit.
+    pub fn remaining(&mut self, limit: &Limit, today: u32) -> u32 {
+        self.roll_to(today);
+        let used = self.counts.get(&limit.key).copied().unwrap_or(0);
+        limit.per_day.saturating_sub(used)
+    }
+
+    /// Count one use against this limit.
+    pub fn record(&mut self, limit: &Limit, today: u32) {
+        self.roll_to(today);
+        *self.counts.entry(limit.key.clone()).or_insert(0) += 1;
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Counts sorted by key, for stable serialization.
+    pub fn counts(&self) -> Vec<(String, u32)> {
+        let mut counts: Vec<(String, u32)> = self
+            .counts
+            .iter()
+            .map(|(key, count)| (key.clone(), *count))
+            .collect();
+        counts.sort();
+        counts
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::base::Proxy;
+
+    fn potion(spec: &str) -> HudItem {
+        HudItem::preclassified(
+            "Potion of Minor Healing".to_string(),
+            spec.to_string(),
+            4,
+            BaseType::Potion(PotionType::Health),
+        )
+    }
+
+    #[test]
+    fn parses_limits() {
+        let buf = r#"
+[categories]
+potion = 3
+poison = 1
+
+[items]
+"Skyrim.esm|0x3eb42" = 1
+"#;
+        let limits = DailyLimits::from_toml(buf).expect("this should parse");
+        assert_eq!(limits.len(), 3);
+
+        let special = limits
+            .lookup(&potion("Skyrim.esm|0x3eb42"))
+            .expect("this item has its own limit");
+        assert_eq!(special.per_day, 1);
+        assert_eq!(special.key, "Skyrim.esm|0x3eb42");
+
+        let ordinary = limits
+            .lookup(&potion("Skyrim.esm|0x3eadd"))
+            .expect("potions are limited");
+        assert_eq!(ordinary.per_day, 3);
+        assert_eq!(ordinary.key, "category:potion");
+
+        let proxy = limits
+            .lookup(&HudItem::preclassified(
+                "Health".to_string(),
+                "health_proxy".to_string(),
+                1,
+                BaseType::PotionProxy(Proxy::Health),
+            ))
+            .expect("grouped potions count as potions");
+        assert_eq!(proxy.key, "category:potion");
+
+        assert!(DailyLimits::from_toml("")
+            .expect("empty files are fine")
+            .is_empty());
+        assert!(DailyLimits::from_toml("[categories]\nscrolls = 2\n").is_err());
+    }
+
+    #[test]
+    fn counts_reset_with_the_day() {
+        let limit = Limit {
+            key: "category:potion".to_string(),
+            per_day: 2,
+        };
+        let mut uses = DailyUses::default();
+        assert_eq!(uses.remaining(&limit, 10), 2);
+        uses.record(&limit, 10);
+        uses.record(&limit, 10);
+        assert_eq!(uses.remaining(&limit, 10), 0);
+        uses.record(&limit, 10);
+        assert_eq!(uses.remaining(&limit, 10), 0);
+
+        // the next day starts fresh
+        assert_eq!(uses.remaining(&limit, 11), 2);
+        uses.record(&limit, 11);
+        assert_eq!(uses.counts(), vec![("category:potion".to_string(), 1)]);
+
+        // loading an older save starts fresh too
+        assert_eq!(uses.remaining(&limit, 9), 2);
+        assert_eq!(uses.day(), 9);
+    }
+}
@@ -0,0 +1,262 @@
+//! A short grace period after a save loads. The game fires a burst of equip
+//! and inventory events for the restored character while we are still
+//! rebuilding our cycles, and acting on them against half-built state gives
+//! us wrong rotations. So we hold onto them until loading finishes, then
+//! replay them in order.
+
+use std::time::{Duration, Instant};
+
+/// How long we wait for the load-complete signal before giving up and
+/// processing events as they arrive.
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// An event we received during the grace period and will replay later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeferredEvent {
+    Equip {
+        equipped: bool,
+        form_spec: String,
+        right: String,
+        left: String,
+    },
+    Inventory {
+        form_spec: String,
+        count: u32,
+    },
+}
+
+impl DeferredEvent {
+    /// Two events with the same key are redundant: only the later one matters.
+    /// Equip events into a hand are keyed by the hand, so a burst of equips
+    /// into the right hand collapses to the last one. Everything else is keyed
+    /// by the item.
+    fn dedup_key(&self) -> String {
+        match self {
+            DeferredEvent::Equip {
+                equipped,
+                form_spec,
+                right,
+                left,
+            } => {
+                let in_right = *equipped && form_spec == right;
+                let in_left = *equipped && form_spec == left;
+                match (in_right, in_left) {
+                    (true, true) => "equip:both".to_string(),
+                    (true, false) => "equip:right".to_string(),
+                    (false, true) => "equip:left".to_string(),
+                    (false, false) => format!("equip:{form_spec}"),
+                }
+            }
+            DeferredEvent::Inventory { form_spec, .. } => format!("inventory:{form_spec}"),
+        }
+    }
+}
+
+/// Buffers events between a save load starting and the HUD being ready.
+#[derive(Debug, Clone, Default)]
+pub struct LoadGate {
+    /// When the current grace period started, if one is running.
+    opened_at: Option<Instant>,
+    pending: Vec<DeferredEvent>,
+}
+
+impl LoadGate {
+    /// Start buffering. Called when we begin restoring state from a save.
+    pub fn open(&mut self, now: Instant) {
+        self.opened_at = Some(now);
+        self.pending.clear();
+    }
+
+    /// True if events arriving now should be held for later.
+    pub fn is_buffering(&self, now: Instant) -> bool {
+        self.opened_at
+            .is_some_and(|start| now.saturating_duration_since(start) < GRACE_PERIOD)
+    }
+
+    /// True from opening until closing, grace period over or not.
+    pub fn is_open(&self) -> bool {
+        self.opened_at.is_some()
+    }
+
+    /// True if the grace period ran out without anyone closing the gate.
+    pub fn timed_out(&self, now: Instant) -> bool {
+        self.is_open() && !self.is_buffering(now)
+    }
+
+    /// True if we have held events that haven't been replayed yet.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Hold onto an event, dropping any earlier event it makes redundant.
+    pub fn defer(&mut self, event: DeferredEvent) {
+        let key = event.dedup_key();
+        self.pending.retain(|xs| xs.dedup_key() != key);
+        self.pending.push(event);
+    }
+
+    /// Stop buffering and hand back everything we held, in arrival order.
+    pub fn close(&mut self) -> Vec<DeferredEvent> {
+        self.opened_at = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// A toy model of what the controller tracks: what's in each hand and
+    /// how many of each item the player has.
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct Model {
+        right: String,
+        left: String,
+        counts: HashMap<String, u32>,
+    }
+
+    impl Model {
+        fn apply(&mut self, event: &DeferredEvent) {
+            match event {
+                DeferredEvent::Equip { right, left, .. } => {
+                    self.right = right.clone();
+                    self.left = left.clone();
+                }
+                DeferredEvent::Inventory { form_spec, count } => {
+                    self.counts.insert(form_spec.clone(), *count);
+                }
+            }
+        }
+    }
+
+    fn equip(form_spec: &str, right: &str, left: &str) -> DeferredEvent {
+        DeferredEvent::Equip {
+            equipped: true,
+            form_spec: form_spec.to_string(),
+            right: right.to_string(),
+            left: left.to_string(),
+        }
+    }
+
+    fn unequip(form_spec: &str, right: &str, left: &str) -> DeferredEvent {
+        DeferredEvent::Equip {
+            equipped: false,
+            form_spec: form_spec.to_string(),
+            right: right.to_string(),
+            left: left.to_string(),
+        }
+    }
+
+    fn count(form_spec: &str, count: u32) -> DeferredEvent {
+        DeferredEvent::Inventory {
+            form_spec: form_spec.to_string(),
+            count,
+        }
+    }
+
+    fn burst() -> Vec<DeferredEvent> {
+        vec![
+            unequip("Skyrim.esm|0x12eb7", "", ""),
+            equip("Skyrim.esm|0x12eb7", "Skyrim.esm|0x12eb7", ""),
+            count("Skyrim.esm|0x3eb42", 4),
+            equip(
+                "Skyrim.esm|0x1397e",
+                "Skyrim.esm|0x12eb7",
+                "Skyrim.esm|0x1397e",
+            ),
+            equip(
+                "Skyrim.esm|0x13982",
+                "Skyrim.esm|0x13982",
+                "Skyrim.esm|0x1397e",
+            ),
+            count("Skyrim.esm|0x3eb42", 3),
+            equip(
+                "Skyrim.esm|0x1397e",
+                "Skyrim.esm|0x13982",
+                "Skyrim.esm|0x1397e",
+            ),
+            equip(
+                "Skyrim.esm|0x139c0",
+                "Skyrim.esm|0x13982",
+                "Skyrim.esm|0x1397e",
+            ),
+            count("Skyrim.esm|0x3eb42", 5),
+        ]
+    }
+
+    #[test]
+    fn buffers_until_closed() {
+        let start = Instant::now();
+        let mut gate = LoadGate::default();
+        assert!(!gate.is_buffering(start));
+
+        gate.open(start);
+        assert!(gate.is_buffering(start));
+        gate.defer(count("Skyrim.esm|0x3eb42", 4));
+        assert!(gate.has_pending());
+
+        let replay = gate.close();
+        assert_eq!(replay.len(), 1);
+        assert!(!gate.is_buffering(start));
+        assert!(!gate.has_pending());
+    }
+
+    #[test]
+    fn grace_period_times_out() {
+        let start = Instant::now();
+        let mut gate = LoadGate::default();
+        gate.open(start);
+        assert!(gate.is_buffering(start + Duration::from_secs(1)));
+        assert!(!gate.timed_out(start + Duration::from_secs(1)));
+        assert!(!gate.is_buffering(start + GRACE_PERIOD));
+        assert!(gate.timed_out(start + GRACE_PERIOD));
+        gate.close();
+        assert!(!gate.is_open());
+        assert!(!gate.timed_out(start + GRACE_PERIOD));
+    }
+
+    #[test]
+    fn redundant_events_collapse() {
+        let mut gate = LoadGate::default();
+        gate.open(Instant::now());
+        for event in burst() {
+            gate.defer(event);
+        }
+        let replay = gate.close();
+
+        let right_equips = replay
+            .iter()
+            .filter(|xs| xs.dedup_key() == "equip:right")
+            .count();
+        assert_eq!(right_equips, 1);
+        let counts = replay
+            .iter()
+            .filter(|xs| matches!(xs, DeferredEvent::Inventory { .. }))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![&count("Skyrim.esm|0x3eb42", 5)]);
+        // a surviving event keeps the position of its latest arrival
+        assert_eq!(replay.last(), Some(&count("Skyrim.esm|0x3eb42", 5)));
+    }
+
+    #[test]
+    fn replay_matches_processing_after_ready() {
+        let mut expected = Model::default();
+        for event in burst() {
+            expected.apply(&event);
+        }
+
+        let mut gate = LoadGate::default();
+        gate.open(Instant::now());
+        for event in burst() {
+            gate.defer(event);
+        }
+        let mut replayed = Model::default();
+        for event in gate.close() {
+            replayed.apply(&event);
+        }
+
+        assert_eq!(replayed, expected);
+    }
+}
@@ -0,0 +1,136 @@
+//! When the crosshair lands on something locked, the utility slot can show how
+//! many lockpicks the player has and how hard the lock is. The game tells us
+//! when the crosshair finds a lock and when it moves off; the hint also goes
+//! away by itself after a few seconds, so a player who stares at a door isn't
+//! stuck with it.
+
+use std::time::{Duration, Instant};
+
+use strum::Display;
+
+/// How long a lock hint stays up if the crosshair doesn't move.
+pub const LOCK_HINT_DURATION: Duration = Duration::from_secs(4);
+
+/// The game's lock levels, as numbered in the game data.
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum LockLevel {
+    VeryEasy,
+    Easy,
+    Average,
+    Hard,
+    VeryHard,
+    RequiresKey,
+}
+
+impl From<u8> for LockLevel {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => LockLevel::VeryEasy,
+            1 => LockLevel::Easy,
+            2 => LockLevel::Average,
+            3 => LockLevel::Hard,
+            4 => LockLevel::VeryHard,
+            _ => LockLevel::RequiresKey,
+        }
+    }
+}
+
+/// A lock under the crosshair, and the lockpicks the player had when it got there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockHint {
+    pub level: LockLevel,
+    pub lockpicks: u32,
+    shown: Instant,
+}
+
+/// The lock hint showing right now, if any.
+#[derive(Debug, Clone, Default)]
+pub struct LockHintTracker {
+    current: Option<LockHint>,
+}
+
+impl LockHintTracker {
+    /// Show a hint for this lock, replacing any other.
+    pub fn show(&mut self, level: LockLevel, lockpicks: u32, now: Instant) {
+        self.current = Some(LockHint {
+            level,
+            lockpicks,
+            shown: now,
+        });
+    }
+
+    /// Take the hint down. Returns true if one was showing.
+    pub fn clear(&mut self) -> bool {
+        self.current.take().is_some()
+    }
+
+    /// Take the hint down if it's been up too long. Returns true if it expired.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let stale = self
+            .current
+            .as_ref()
+            .is_some_and(|hint| now.saturating_duration_since(hint.shown) >= LOCK_HINT_DURATION);
+        if stale {
+            self.current = None;
+        }
+        stale
+    }
+
+    /// Is a hint up, stale or not?
+    pub fn is_showing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// The hint to show at this moment, if there is one.
+    pub fn active(&self, now: Instant) -> Option<&LockHint> {
+        self.current
+            .as_ref()
+            .filter(|hint| now.saturating_duration_since(hint.shown) < LOCK_HINT_DURATION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_expire_on_their_own() {
+        let now = Instant::now();
+        let mut hints = LockHintTracker::default();
+        assert!(hints.active(now).is_none());
+        assert!(!hints.expire(now));
+
+        hints.show(LockLevel::from(3), 12, now);
+        let almost = now + LOCK_HINT_DURATION - Duration::from_millis(1);
+        assert!(!hints.expire(almost));
+        let hint = hints.active(almost).expect("the hint is still up");
+        assert_eq!(hint.level, LockLevel::Hard);
+        assert_eq!(hint.lockpicks, 12);
+
+        // Even before the tick gets to it, a stale hint isn't shown.
+        let later = now + LOCK_HINT_DURATION;
+        assert!(hints.active(later).is_none());
+        assert!(hints.expire(later));
+        assert!(!hints.clear());
+
+        // Looking at another lock starts the clock over.
+        hints.show(LockLevel::Easy, 12, now);
+        hints.show(LockLevel::VeryHard, 12, almost);
+        assert!(hints.active(later).is_some());
+        assert!(hints.clear());
+        assert!(hints.active(later).is_none());
+    }
+
+    #[test]
+    fn no_lockpicks_is_still_worth_saying() {
+        let now = Instant::now();
+        let mut hints = LockHintTracker::default();
+        hints.show(LockLevel::from(2), 0, now);
+        let hint = hints
+            .active(now)
+            .expect("zero lockpicks still shows a hint");
+        assert_eq!(hint.lockpicks, 0);
+        assert_eq!(hint.level, LockLevel::Average);
+        assert_eq!(LockLevel::from(200), LockLevel::RequiresKey);
+    }
+}
@@ -13,6 +13,7 @@ use std::path::Path;
 use simplelog::*;
 
 use super::settings::settings;
+use super::trace::{set_recording, set_trace_directory};
 
 // ---------- logging
 
@@ -26,7 +27,7 @@ pub fn initialize_rust_logging(_logdir: &cxx::CxxVector<u16>) {
     let chonky_path = OsString::from_wide(_logdir.as_slice());
     let path = Path::new(chonky_path.as_os_str()).with_file_name("SoulsyHUD.log");
 
-    let Ok(logfile) = File::create(path) else {
+    let Ok(logfile) = File::create(&path) else {
         // Welp, we failed and I have nowhere to write the darn error. Ha ha.
         return;
     };
@@ -44,6 +45,8 @@ pub fn initialize_rust_logging(_logdir: &cxx::CxxVector<u16>) {
         "SoulsyHUD version {} coming online.",
         env!("CARGO_PKG_VERSION")
     );
+    set_trace_directory(&path);
+    set_recording(settings().record_input_trace());
 }
 
 pub fn log_error(message: String) {
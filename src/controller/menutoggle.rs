@@ -0,0 +1,140 @@
+//! Some menu mods forward the same hover-toggle event through two UI hooks, so
+//! one keypress reaches us twice in the same frame. Toggling twice adds the
+//! item and takes it right back out again, with two notifications that
+//! contradict each other. We remember the last toggle for a moment and answer
+//! an identical one that arrives right behind it with the first answer.
+
+use std::time::{Duration, Instant};
+
+use crate::plugin::{Action, MenuEventResponse};
+
+/// How long a toggle counts as a duplicate of the one before it. A frame or two
+/// at the frame rates people play at; far shorter than anyone can press twice.
+pub const DUPLICATE_TOGGLE_WINDOW: Duration = Duration::from_millis(40);
+
+#[derive(Debug, Clone)]
+struct LastToggle {
+    action: Action,
+    form_spec: String,
+    result: MenuEventResponse,
+    when: Instant,
+}
+
+/// The most recent menu toggle, kept only long enough to spot a duplicate.
+#[derive(Debug, Clone, Default)]
+pub struct ToggleMemo {
+    last: Option<LastToggle>,
+}
+
+impl ToggleMemo {
+    /// If this toggle repeats the one just made, the result of that one.
+    pub fn recall(
+        &self,
+        action: &Action,
+        form_spec: &str,
+        now: Instant,
+    ) -> Option<MenuEventResponse> {
+        let last = self.last.as_ref()?;
+        let fresh = now.saturating_duration_since(last.when) < DUPLICATE_TOGGLE_WINDOW;
+        if fresh && last.action == *action && last.form_spec == form_spec {
+            Some(last.result)
+        } else {
+            None
+        }
+    }
+
+    /// Note a toggle we just acted on.
+    pub fn remember(
+        &mut self,
+        action: Action,
+        form_spec: String,
+        result: MenuEventResponse,
+        now: Instant,
+    ) {
+        self.last = Some(LastToggle {
+            action,
+            form_spec,
+            result,
+            when: now,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::cycles::CycleData;
+    use crate::controller::keys::CycleSlot;
+    use crate::data::potion::PotionType;
+    use crate::data::{BaseType, HudItem};
+
+    fn toggle(
+        memo: &mut ToggleMemo,
+        cycles: &mut CycleData,
+        item: &HudItem,
+        now: Instant,
+    ) -> MenuEventResponse {
+        let spec = item.form_string();
+        if let Some(result) = memo.recall(&Action::Utility, &spec, now) {
+            return result;
+        }
+        let result = cycles.toggle(&CycleSlot::Utility, item.clone());
+        memo.remember(Action::Utility, spec, result, now);
+        result
+    }
+
+    #[test]
+    fn one_press_delivered_twice_toggles_once() {
+        let mut memo = ToggleMemo::default();
+        let mut cycles = CycleData::default();
+        let potion = HudItem::preclassified(
+            "Potion of Healing".to_string(),
+            "Skyrim.esm|0x3eadd".to_string(),
+            3,
+            BaseType::Potion(PotionType::Health),
+        );
+
+        let now = Instant::now();
+        let same_frame = now + Duration::from_millis(2);
+        assert_eq!(
+            toggle(&mut memo, &mut cycles, &potion, now),
+            MenuEventResponse::ItemAdded
+        );
+        assert_eq!(
+            toggle(&mut memo, &mut cycles, &potion, same_frame),
+            MenuEventResponse::ItemAdded
+        );
+        assert!(cycles.includes(&CycleSlot::Utility, &potion));
+
+        // A real second press a moment later takes it back out.
+        let later = now + Duration::from_millis(600);
+        assert_eq!(
+            toggle(&mut memo, &mut cycles, &potion, later),
+            MenuEventResponse::ItemRemoved
+        );
+        assert!(!cycles.includes(&CycleSlot::Utility, &potion));
+    }
+
+    #[test]
+    fn only_identical_toggles_are_duplicates() {
+        let now = Instant::now();
+        let mut memo = ToggleMemo::default();
+        assert!(memo.recall(&Action::Left, "a", now).is_none());
+
+        memo.remember(
+            Action::Left,
+            "a".to_string(),
+            MenuEventResponse::ItemRemoved,
+            now,
+        );
+        assert_eq!(
+            memo.recall(&Action::Left, "a", now),
+            Some(MenuEventResponse::ItemRemoved)
+        );
+        assert!(memo.recall(&Action::Right, "a", now).is_none());
+        assert!(memo.recall(&Action::Left, "b", now).is_none());
+        assert!(memo
+            .recall(&Action::Left, "a", now + DUPLICATE_TOGGLE_WINDOW)
+            .is_none());
+    }
+}
@@ -7,16 +7,49 @@
 //!
 //! There is little defined in this module file, but everything it re-exports
 //! is available to be bridged to C++ in the `plugin` module.
+pub mod browser;
+pub mod castable;
+pub mod confirm;
 pub mod control;
 pub mod cycleentries;
+pub mod cyclefile;
 pub mod cycles;
+pub mod diagnostics;
+pub mod encumbrance;
+pub mod equipstate;
 pub mod facade;
+pub mod flurry;
+pub mod gate;
+pub mod grips;
+pub mod history;
+pub mod hotbar;
+pub mod inputstate;
+pub mod keycodes;
+pub mod keylog;
 pub mod keys;
+pub mod limits;
+pub mod loadgate;
+pub mod lockhint;
 pub mod logs;
+pub mod menutoggle;
+pub mod overrides;
+pub mod presstiming;
+pub mod rebind;
 pub mod settings;
+pub mod shutdown;
+pub mod slotage;
 pub mod strings;
+pub mod swaps;
+pub mod timers;
+pub mod trace;
+pub mod undo;
+pub mod visualstate;
+pub mod vitals;
 
+pub use diagnostics::run_diagnostics;
 pub use facade::*;
 pub use logs::*;
 pub use settings::UserSettings;
+pub use shutdown::shutdown;
 pub use strings::*;
+pub use timers::{advance_timers, timer_started, timer_stopped};
@@ -0,0 +1,277 @@
+//! Item-specific activation overrides, read from a toml file players edit by
+//! hand. An override replaces what happens when the player activates a
+//! utility item: a mod's "whistle for horse" misc item might cast that mod's
+//! spell instead of being used in the generic way.
+//!
+//! The file looks like this:
+//!
+//! ```toml
+//! [[override]]
+//! form_spec = "HorseWhistle.esp|0x800"
+//! action = "cast_spell"
+//! spell = "HorseWhistle.esp|0x801"
+//!
+//! [[override]]
+//! form_spec = "Skyrim.esm|0x1397e"
+//! action = "equip"
+//! hand = "left"
+//! ```
+//!
+//! There's no bridge hook for running console commands, so overrides can't do that.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+use eyre::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+static OVERRIDES_PATH: &str = "./data/SKSE/Plugins/SoulsyHUD_activation_overrides.toml";
+
+/// There can be only one. Not public because we want access managed.
+static OVERRIDES: Lazy<Mutex<ActivationOverrides>> =
+    Lazy::new(|| Mutex::new(ActivationOverrides::read_or_default(OVERRIDES_PATH)));
+
+/// Look up the override for this item, if the player has configured one.
+pub fn activation_override(form_spec: &str) -> Option<ActivationAction> {
+    let overrides = OVERRIDES
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire overrides lock.");
+    overrides.lookup(form_spec).cloned()
+}
+
+/// Stop using an override that turned out not to work. It's reported once
+/// and activation falls back to default handling until the file is reloaded.
+pub fn disable_activation_override(form_spec: &str, why: &str) {
+    let mut overrides = OVERRIDES
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire overrides lock.");
+    if overrides.table.remove(form_spec).is_some() {
+        log::warn!(
+            "Activation override for '{form_spec}' can't be used; falling back to default handling. {why}"
+        );
+    }
+}
+
+/// What to do instead of the usual thing when a utility item is activated.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ActivationAction {
+    /// Cast the given spell on the player.
+    CastSpell { spell: String },
+    /// Equip the item in a hand instead of using it.
+    Equip { hand: OverrideHand },
+    /// Use the item the way we would without an override.
+    UseAsNormal,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideHand {
+    Left,
+    Right,
+}
+
+/// One entry in the file.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+struct OverrideEntry {
+    form_spec: String,
+    #[serde(flatten)]
+    action: ActivationAction,
+}
+
+/// The file as written.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+struct OverridesFile {
+    #[serde(default, rename = "override")]
+    overrides: Vec<OverrideEntry>,
+}
+
+/// The validated override table, keyed by form spec.
+#[derive(Debug, Clone, Default)]
+pub struct ActivationOverrides {
+    table: HashMap<String, ActivationAction>,
+}
+
+impl ActivationOverrides {
+    /// Re-read the overrides file to pick up any changes to it.
+    pub fn refresh() {
+        let fresh = ActivationOverrides::read_or_default(OVERRIDES_PATH);
+        let mut overrides = OVERRIDES
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire overrides lock.");
+        *overrides = fresh;
+    }
+
+    /// Read the file if it exists. A missing file means no overrides; a
+    /// broken file is logged and also means no overrides.
+    fn read_or_default(pathstr: &str) -> Self {
+        let path = std::path::Path::new(pathstr);
+        if !path.exists() {
+            return ActivationOverrides::default();
+        }
+        match ActivationOverrides::read_from_file(pathstr) {
+            Ok(v) => {
+                log::info!("Read {} activation overrides.", v.len());
+                v
+            }
+            Err(e) => {
+                log::warn!("Problem reading the activation overrides file! {e:#}");
+                ActivationOverrides::default()
+            }
+        }
+    }
+
+    pub fn read_from_file(pathstr: &str) -> Result<Self> {
+        let buf = fs::read_to_string(pathstr)
+            .wrap_err_with(|| format!("Unable to read the overrides file: {}", pathstr))?;
+        ActivationOverrides::from_toml(&buf)
+    }
+
+    /// Parse and validate. Entries with malformed form specs are logged and
+    /// skipped rather than failing the whole file.
+    pub fn from_toml(buf: &str) -> Result<Self> {
+        let parsed = toml::from_str::<OverridesFile>(buf)
+            .wrap_err("The activation overrides file can't be parsed.")?;
+
+        let mut table = HashMap::new();
+        for entry in parsed.overrides {
+            if !looks_like_form_spec(&entry.form_spec) {
+                log::warn!(
+                    "Skipping activation override with malformed form spec '{}'.",
+                    entry.form_spec
+                );
+                continue;
+            }
+            if let ActivationAction::CastSpell { spell } = &entry.action {
+                if !looks_like_form_spec(spell) {
+                    log::warn!(
+                        "Skipping activation override for '{}': malformed spell form spec '{spell}'.",
+                        entry.form_spec
+                    );
+                    continue;
+                }
+            }
+            if table.contains_key(&entry.form_spec) {
+                log::warn!(
+                    "Duplicate activation override for '{}'; the last one wins.",
+                    entry.form_spec
+                );
+            }
+            table.insert(entry.form_spec, entry.action);
+        }
+
+        Ok(ActivationOverrides { table })
+    }
+
+    pub fn lookup(&self, form_spec: &str) -> Option<&ActivationAction> {
+        self.table.get(form_spec)
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
+/// Form specs look like `Plugin.esp|0x800`.
+fn looks_like_form_spec(spec: &str) -> bool {
+    let Some((plugin, id)) = spec.rsplit_once('|') else {
+        return false;
+    };
+    let Some(hex) = id.strip_prefix("0x") else {
+        return false;
+    };
+    !plugin.is_empty() && u32::from_str_radix(hex, 16).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_overrides() {
+        let buf = r#"
+[[override]]
+form_spec = "HorseWhistle.esp|0x800"
+action = "cast_spell"
+spell = "HorseWhistle.esp|0x801"
+
+[[override]]
+form_spec = "Skyrim.esm|0x1397e"
+action = "equip"
+hand = "left"
+
+[[override]]
+form_spec = "Skyrim.esm|0x3eb42"
+action = "use_as_normal"
+"#;
+        let overrides = ActivationOverrides::from_toml(buf).expect("this should parse");
+        assert_eq!(overrides.len(), 3);
+        assert_eq!(
+            overrides.lookup("HorseWhistle.esp|0x800"),
+            Some(&ActivationAction::CastSpell {
+                spell: "HorseWhistle.esp|0x801".to_string()
+            })
+        );
+        assert_eq!(
+            overrides.lookup("Skyrim.esm|0x1397e"),
+            Some(&ActivationAction::Equip {
+                hand: OverrideHand::Left
+            })
+        );
+        assert_eq!(
+            overrides.lookup("Skyrim.esm|0x3eb42"),
+            Some(&ActivationAction::UseAsNormal)
+        );
+        assert!(overrides.lookup("Skyrim.esm|0x12eb7").is_none());
+    }
+
+    #[test]
+    fn invalid_entries_are_skipped() {
+        let buf = r#"
+[[override]]
+form_spec = "not a form spec"
+action = "use_as_normal"
+
+[[override]]
+form_spec = "HorseWhistle.esp|0x800"
+action = "cast_spell"
+spell = "HorseWhistle.esp|whistle"
+
+[[override]]
+form_spec = "Skyrim.esm|0x1397e"
+action = "equip"
+hand = "right"
+"#;
+        let overrides = ActivationOverrides::from_toml(buf).expect("this should parse");
+        assert_eq!(overrides.len(), 1);
+        assert!(overrides.lookup("Skyrim.esm|0x1397e").is_some());
+    }
+
+    #[test]
+    fn unknown_actions_fail_the_file() {
+        let buf = r#"
+[[override]]
+form_spec = "Skyrim.esm|0x1397e"
+action = "juggle"
+"#;
+        assert!(ActivationOverrides::from_toml(buf).is_err());
+        assert!(ActivationOverrides::from_toml("")
+            .expect("empty files are fine")
+            .is_empty());
+    }
+
+    #[test]
+    fn form_spec_shapes() {
+        assert!(looks_like_form_spec("Skyrim.esm|0x1397e"));
+        assert!(looks_like_form_spec("Some|Odd.esp|0x800"));
+        assert!(!looks_like_form_spec("health_proxy"));
+        assert!(!looks_like_form_spec("|0x800"));
+        assert!(!looks_like_form_spec("Skyrim.esm|1397e"));
+    }
+}
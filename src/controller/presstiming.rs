@@ -0,0 +1,104 @@
+//! Press timings for each cycle slot. Every slot can have its own long-press
+//! time and its own auto-repeat rate, falling back to the shared settings when
+//! it doesn't; a time of zero turns that behavior off for the slot. A long
+//! list of potions wants a held key to keep cycling quickly, while the power
+//! slot is better off never repeating at all.
+//!
+//! Auto-repeat applies only to slots cycled by plain hotkey presses with no
+//! long-press behavior, so a held key never has to mean two things. Once a
+//! held key has repeated, releasing it doesn't cycle again.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The press timings for one slot, after settings fallbacks. Zero turns a
+/// behavior off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotTimings {
+    /// How long a press must be held to count as a long press.
+    pub hold_ms: u32,
+    /// How often a held key cycles again.
+    pub repeat_ms: u32,
+}
+
+impl SlotTimings {
+    /// Has a key held this long become a long press?
+    pub fn is_long_press(&self, held: Duration) -> bool {
+        self.hold_ms > 0 && held > Duration::from_millis(self.hold_ms.into())
+    }
+
+    pub fn long_press_enabled(&self) -> bool {
+        self.hold_ms > 0
+    }
+}
+
+/// How many times each held key has repeated during its current press.
+#[derive(Debug, Clone, Default)]
+pub struct AutoRepeat {
+    fired: HashMap<u32, u128>,
+}
+
+impl AutoRepeat {
+    /// The key has been held this long. Returns true if it's time for it to
+    /// repeat. A repeat is due every `repeat_ms`; if events were slow to
+    /// arrive, we repeat once rather than catching up all at once.
+    pub fn poll(&mut self, key: u32, held: Duration, timings: &SlotTimings) -> bool {
+        if timings.repeat_ms == 0 {
+            return false;
+        }
+        let due = held.as_millis() / u128::from(timings.repeat_ms);
+        let fired = self.fired.entry(key).or_default();
+        if due > *fired {
+            *fired = due;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The key went up or was pressed afresh. Returns true if it repeated
+    /// while it was held, which means the release has nothing left to do.
+    pub fn release(&mut self, key: u32) -> bool {
+        self.fired.remove(&key).is_some_and(|fired| fired > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hold a key for a second with events every 50ms, then let go. Returns
+    /// how many times it repeated and whether the release was used up.
+    fn hold_for_a_second(key: u32, timings: &SlotTimings) -> (usize, bool, bool) {
+        let mut repeats = AutoRepeat::default();
+        let fired = (1..=20)
+            .filter(|tick| repeats.poll(key, Duration::from_millis(tick * 50), timings))
+            .count();
+        let long_press = timings.is_long_press(Duration::from_millis(1000));
+        (fired, long_press, repeats.release(key))
+    }
+
+    #[test]
+    fn the_same_press_diverges_between_slots() {
+        let utility = SlotTimings {
+            hold_ms: 0,
+            repeat_ms: 200,
+        };
+        let power = SlotTimings {
+            hold_ms: 750,
+            repeat_ms: 0,
+        };
+
+        // The utility key cycles five more times while held, and the release
+        // does nothing further. It never becomes a long press.
+        assert_eq!(hold_for_a_second(6, &utility), (5, false, true));
+        // The power key never repeats, becomes a long press, and its release
+        // is still the player's to act on.
+        assert_eq!(hold_for_a_second(3, &power), (0, true, false));
+
+        // A quick tap doesn't repeat on either.
+        let mut repeats = AutoRepeat::default();
+        assert!(!repeats.poll(6, Duration::from_millis(150), &utility));
+        assert!(!repeats.release(6));
+    }
+}
@@ -0,0 +1,195 @@
+//! Settings can change under a key the player is holding: the MCM writes the
+//! INI while the game is paused, and a press that started before the pause
+//! finishes after it. If that key no longer drives its slot, whatever the
+//! press started has to go: the tracked key, the long-press timer, and any
+//! pending equip. Cycle data is left alone; only in-flight input is dropped.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use super::settings::UserSettings;
+use crate::plugin::Action;
+
+/// The slots a press or timer can be in flight for, in reporting order.
+const SLOTS: [Action; 5] = [
+    Action::Power,
+    Action::Utility,
+    Action::Left,
+    Action::Right,
+    Action::Equipment,
+];
+
+/// The key bound to each slot. `None` means the slot has no key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotBindings {
+    keys: HashMap<Action, Option<u32>>,
+}
+
+impl SlotBindings {
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        let bound = |key: u32| if key == 0 { None } else { Some(key) };
+        let equipset = settings.equipset();
+        let keys = HashMap::from([
+            (Action::Power, bound(settings.power())),
+            (Action::Utility, bound(settings.utility())),
+            (Action::Left, bound(settings.left())),
+            (Action::Right, bound(settings.right())),
+            (Action::Equipment, bound(equipset.max(0) as u32)),
+        ]);
+        Self { keys }
+    }
+
+    /// The key for this slot, if it has one.
+    pub fn key_for(&self, slot: &Action) -> Option<u32> {
+        self.keys.get(slot).copied().flatten()
+    }
+
+    /// Bind this slot to a key, or to nothing.
+    pub fn bind(&mut self, slot: Action, key: Option<u32>) {
+        self.keys.insert(slot, key);
+    }
+
+    /// The slots that had a key here and have a different key, or none, in
+    /// `after`. Each comes with the key it used to have.
+    pub fn lost(&self, after: &SlotBindings) -> Vec<(Action, u32)> {
+        SLOTS
+            .iter()
+            .filter_map(|slot| {
+                let old = self.key_for(slot)?;
+                if after.key_for(slot) == Some(old) {
+                    None
+                } else {
+                    Some((*slot, old))
+                }
+            })
+            .collect()
+    }
+}
+
+/// The timers a press on this slot's key can have started.
+fn timers_for(slot: &Action) -> Vec<Action> {
+    match *slot {
+        Action::Power => vec![Action::Power, Action::LongPressPower],
+        Action::Utility => vec![Action::Utility, Action::LongPressUtility],
+        Action::Left => vec![Action::Left, Action::LongPressLeft],
+        Action::Right => vec![Action::Right, Action::LongPressRight],
+        _ => vec![*slot],
+    }
+}
+
+/// What a settings refresh cancelled, for the log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Teardown {
+    /// The slots whose key changed or went away.
+    pub slots: Vec<Action>,
+    /// The old keys that were held down when the settings changed.
+    pub presses: Vec<u32>,
+    /// The timers that were running for those slots.
+    pub timers: Vec<Action>,
+}
+
+impl Teardown {
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+impl Display for Teardown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no slot bindings changed");
+        }
+        write!(
+            f,
+            "rebound slots: {:?}; presses cancelled: {:?}; timers stopped: {:?}",
+            self.slots, self.presses, self.timers
+        )
+    }
+}
+
+/// Drop any in-flight input for slots that lost their key between `before`
+/// and `after`. Held keys are forgotten from `pressed`; `stop` is called for
+/// each timer a press on the slot could have started, and says whether that
+/// timer was running.
+pub fn tear_down<K, F>(
+    before: &SlotBindings,
+    after: &SlotBindings,
+    pressed: &mut HashMap<u32, K>,
+    mut stop: F,
+) -> Teardown
+where
+    F: FnMut(&Action) -> bool,
+{
+    let mut teardown = Teardown::default();
+    for (slot, old_key) in before.lost(after) {
+        if pressed.remove(&old_key).is_some() {
+            teardown.presses.push(old_key);
+        }
+        for timer in timers_for(&slot) {
+            if stop(&timer) {
+                teardown.timers.push(timer);
+            }
+        }
+        teardown.slots.push(slot);
+    }
+    teardown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::settings::settings;
+    use crate::controller::timers::EquipTimers;
+
+    #[test]
+    fn disabling_a_slot_mid_press_leaves_nothing_behind() {
+        let before = SlotBindings::from_settings(&settings());
+        let key = before
+            .key_for(&Action::Right)
+            .expect("the right hand has a key by default");
+        let mut after = before.clone();
+        after.bind(Action::Right, None);
+
+        // The player holds the right cycle key; both its timers are going.
+        let mut pressed = HashMap::from([(key, ())]);
+        let mut timers = EquipTimers::default();
+        timers.start(Action::Right);
+        timers.start(Action::LongPressRight);
+        timers.start(Action::Power);
+        assert!(timers.advance(0.1, |_| 500).is_empty());
+
+        let teardown = tear_down(&before, &after, &mut pressed, |which| {
+            let running = timers.is_running(which);
+            timers.stop(which);
+            running
+        });
+        assert_eq!(teardown.slots, vec![Action::Right]);
+        assert_eq!(teardown.presses, vec![key]);
+        assert_eq!(teardown.timers, vec![Action::Right, Action::LongPressRight]);
+
+        // Well past the old delay, only the untouched slot's timer fires.
+        assert_eq!(timers.advance(5.0, |_| 500), vec![Action::Power]);
+        assert!(timers.is_empty());
+        assert!(pressed.is_empty());
+    }
+
+    #[test]
+    fn unchanged_bindings_cancel_nothing() {
+        let before = SlotBindings::from_settings(&settings());
+        let key = before
+            .key_for(&Action::Power)
+            .expect("powers have a key by default");
+        let mut pressed = HashMap::from([(key, ())]);
+        let teardown = tear_down(&before, &before.clone(), &mut pressed, |_| true);
+        assert!(teardown.is_empty());
+        assert_eq!(pressed.len(), 1);
+
+        // Moving a slot to another key counts as losing the old one.
+        let mut after = before.clone();
+        after.bind(Action::Power, Some(key + 100));
+        let teardown = tear_down(&before, &after, &mut pressed, |_| false);
+        assert_eq!(teardown.slots, vec![Action::Power]);
+        assert_eq!(teardown.presses, vec![key]);
+        assert!(teardown.timers.is_empty());
+    }
+}
@@ -3,26 +3,79 @@
 //! validation and some translation from older versions, but this file is
 //! otherwise all fairly predictable.
 
-use std::{path::Path, sync::Mutex};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
 
 use eyre::Result;
-use ini::Ini;
+use ini::{EscapePolicy, Ini};
 use log::Level;
 use once_cell::sync::Lazy;
 use strum::Display;
 
-use super::keys::Hotkey;
-use crate::{layouts::shared::NamedAnchor, plugin::HudElement};
+use super::browser::BROWSE_KEYS;
+use super::keycodes::{InputDevice, KeyCode};
+use super::keys::{CycleSlot, Hotkey};
+use super::presstiming::SlotTimings;
+use crate::{
+    layouts::shared::NamedAnchor,
+    plugin::{Action, HudElement},
+};
 
 /// This is the path to players's modified settings.
-static SETTINGS_PATH: &str = "./data/MCM/Settings/SoulsyHUD.ini";
+pub static SETTINGS_PATH: &str = "./data/MCM/Settings/SoulsyHUD.ini";
 
 /// This is the path to the mod settings definition file.
 /// static INI_PATH: &str = "./data/MCM/Config/SoulsyHUD/settings.ini";
 
+/// How many hotbar keys a player can bind.
+pub const HOTBAR_SIZE: usize = 8;
+
+/// No real settings file comes anywhere near this size.
+const MAX_SETTINGS_BYTES: usize = 1024 * 1024;
+
+/// The ini keys for the per-slot equip delays, in the order we store them.
+const SLOT_DELAY_KEYS: [&str; 4] = [
+    "uEquipDelayPower",
+    "uEquipDelayUtility",
+    "uEquipDelayLeft",
+    "uEquipDelayRight",
+];
+
+/// The ini keys for the per-slot long-press times, in the order we store them.
+const SLOT_LONG_PRESS_KEYS: [&str; 4] = [
+    "iLongPressPower",
+    "iLongPressUtility",
+    "iLongPressLeft",
+    "iLongPressRight",
+];
+
+/// The ini keys for the per-slot repeat times, in the order we store them.
+const SLOT_REPEAT_KEYS: [&str; 4] = [
+    "iRepeatPower",
+    "iRepeatUtility",
+    "iRepeatLeft",
+    "iRepeatRight",
+];
+
+/// The slot each per-slot setting belongs to, in the order we store them.
+const SLOT_ACTIONS: [Action; 4] = [Action::Power, Action::Utility, Action::Left, Action::Right];
+
+/// Repeating faster than this cycles past items before the player can read them.
+fn clamp_repeat(millis: u32) -> u32 {
+    if millis == 0 {
+        0
+    } else {
+        millis.clamp(100, 2500)
+    }
+}
+
 /// There can be only one. Not public because we want access managed.
-static SETTINGS: Lazy<Mutex<UserSettings>> =
-    Lazy::new(|| Mutex::new(UserSettings::new_from_file(SETTINGS_PATH)));
+static SETTINGS: Lazy<Mutex<UserSettings>> = Lazy::new(|| {
+    let mut settings = UserSettings::default();
+    settings.read_with_backup(SETTINGS_PATH).unwrap_or_default();
+    Mutex::new(settings)
+});
 
 pub fn settings() -> UserSettings {
     let settings = SETTINGS
@@ -31,6 +84,53 @@ pub fn settings() -> UserSettings {
     settings.clone()
 }
 
+/// What each input code means to us, rebuilt whenever the settings are. The
+/// key handler sees every key the player presses, and most of them mean nothing
+/// to us; this answers that without cloning the settings.
+static KEY_MAP: Lazy<RwLock<KeyMap>> = Lazy::new(|| RwLock::new(settings().key_map()));
+
+/// The action bound to this input code, from the cached key map.
+pub fn key_action(key: u32) -> Action {
+    KEY_MAP
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .action_for(key)
+}
+
+/// Does this input code mean anything to us at all? False means the key
+/// handler can drop the event without asking the controller.
+pub fn key_is_watched(key: u32) -> bool {
+    KEY_MAP
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .watches(key)
+}
+
+pub(crate) fn publish_key_map(keys: KeyMap) {
+    *KEY_MAP
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = keys;
+}
+
+/// A snapshot of the key bindings, small enough to consult on every keystroke.
+#[derive(Debug, Clone, Default)]
+pub struct KeyMap {
+    actions: HashMap<u32, Action>,
+    /// Every code the controller might act on: bound keys, modifiers, hotbar
+    /// keys, and the cycle browser's keys.
+    watched: HashSet<u32>,
+}
+
+impl KeyMap {
+    pub fn action_for(&self, key: u32) -> Action {
+        self.actions.get(&key).copied().unwrap_or(Action::None)
+    }
+
+    pub fn watches(&self, key: u32) -> bool {
+        self.watched.contains(&key)
+    }
+}
+
 /// Wrapper for C++ convenience; logs errors but does no more
 pub fn refresh_user_settings() {
     match UserSettings::refresh() {
@@ -53,6 +153,12 @@ pub fn refresh_user_settings() {
 pub struct UserSettings {
     /// Desired log level. `sLogLevel`
     log_level: Level,
+    /// Debug mode: log at debug level at least, and allow the controller state
+    /// to be dumped to the log. `bDebugMode`
+    debug_mode: bool,
+    /// Whether to record every event the game sends us to a trace file, for
+    /// reproducing bugs. `bRecordInputTrace`
+    record_input_trace: bool,
 
     /// The key for powers. uPowerCycleKey
     power: u32,
@@ -64,6 +170,16 @@ pub struct UserSettings {
     right: u32,
     /// The key for equip sets. iEquipSetCycleKey
     equipset: i32,
+    /// A gamepad button for powers, alongside the key. iPowerCycleGamepad
+    power_gamepad: i32,
+    /// A gamepad button for utility items. iUtilityCycleGamepad
+    utility_gamepad: i32,
+    /// A gamepad button for the left hand's cycle. iLeftCycleGamepad
+    left_gamepad: i32,
+    /// A gamepad button for the right hand's cycle. iRightCycleGamepad
+    right_gamepad: i32,
+    /// A gamepad button to use the utility item. iUtilityActivateGamepad
+    activate_gamepad: i32,
 
     /// How the player wants to use the utility item. uHowToActivate
     how_to_activate: ActivationMethod,
@@ -73,6 +189,10 @@ pub struct UserSettings {
     activate_modifier: i32,
     /// If magicka, health, and stamina potions are grouped and auto-selected.
     group_potions: bool,
+    /// Let the utility key use up items a quest still needs. bActivateQuestItems
+    activate_quest_items: bool,
+    /// Show lockpicks and lock difficulty while the crosshair is on a lock. bShowLockHints
+    show_lock_hints: bool,
 
     /// How the player wants to advance a cycle. uHowToAdvance
     how_to_cycle: ActivationMethod,
@@ -83,18 +203,62 @@ pub struct UserSettings {
     how_to_toggle: ActivationMethod,
     /// Optional menu modifier key
     menu_modifier: i32,
+    /// Hold this while toggling an item in a menu with either hand's cycle key
+    /// to add it to both hand cycles at once. iBothHandsModifierKey
+    both_hands_modifier: i32,
     /// Favoriting weapons and spells adds to cycles.
     link_to_favorites: bool,
 
     /// How the player wants to handle unequipping slots. uHowToUnequip
     unarmed_handling: UnarmedMethod,
+    /// When unarmed is added to cycles, add it to the left hand's. bUnarmedInLeftCycle
+    unarmed_in_left: bool,
+    /// The same for the right hand's cycle. bUnarmedInRightCycle
+    unarmed_in_right: bool,
     /// An optional modifier key for unequipping a specific slot. iUnequipModifierKey
     unequip_modifier: i32,
     /// An optional dedicated hotkey for unequipping both hands. iUnequipHotkey
     unequip_hotkey: i32,
+    /// An optional dedicated hotkey for cycling ammo. Without it, the left
+    /// hand's key cycles ammo while a bow or crossbow is out. iAmmoCycleKey
+    ammo_hotkey: i32,
+    /// Optional hotkeys that swap a hand back to what it held before the
+    /// current item. iLeftPreviousKey, iRightPreviousKey
+    left_previous_hotkey: i32,
+    right_previous_hotkey: i32,
 
     /// Matching left and right hands. bLongPressMatches
     long_press_matches: bool,
+    /// Long-pressing the right cycle key while holding a tool returns to the
+    /// last weapon. bLongPressLeavesTools
+    long_press_leaves_tools: bool,
+    /// Long-pressing a cycle key equips what the slot shows right away, or
+    /// uses the utility item. bLongPressEquipsNow
+    long_press_equips_now: bool,
+    /// Cycling the right hand passes over tools. bCycleSkipsTools
+    cycle_skips_tools: bool,
+    /// Cycling the hands passes over spells a spell-preparation mod says
+    /// aren't prepared. bSkipUnpreparedSpells
+    skip_unprepared_spells: bool,
+    /// Take entries the player no longer has out of their cycles, instead of
+    /// passing over them until the player has them again. bPruneMissingItems
+    prune_missing_items: bool,
+    /// Refuse to take an item out of a cycle while it's equipped, instead of
+    /// taking it out and leaving it equipped. bKeepEquippedInCycles
+    keep_equipped_in_cycles: bool,
+    /// Whether and how cycles reorder themselves. uCycleAutoSort
+    cycle_auto_sort: CycleAutoSort,
+    /// The most entries a cycle can hold. Lowering it leaves longer cycles
+    /// alone, but nothing can be added to them. uMaxCycleLength
+    max_cycle_length: u32,
+
+    /// Optional keys that equip or use a cycle entry by position. iHotbarKey1 .. iHotbarKey8
+    hotbar_keys: [i32; HOTBAR_SIZE],
+    /// Which cycle the hotbar keys pick from. uHotbarCycle; 0 = powers, 1 = utility,
+    /// 2 = left, 3 = right.
+    hotbar_cycle: u32,
+    /// An optional modifier that points the hotbar at the utility cycle. iHotbarModifier
+    hotbar_modifier: i32,
 
     /// Show/hide shortcut key. uShowHideKey
     showhide: u32,
@@ -105,10 +269,31 @@ pub struct UserSettings {
 
     /// The number of milliseconds to delay before equipping a selection. Max 2500, min 0.
     equip_delay_ms: u32,
+    /// Per-slot equip delays, for power, utility, left, and right in that order.
+    /// 0 means use the shared delay. uEquipDelayPower, uEquipDelayUtility,
+    /// uEquipDelayLeft, uEquipDelayRight
+    slot_delays_ms: [u32; 4],
     /// The number of milliseconds it takes for a press to be a long one.
     long_press_ms: u32,
+    /// Per-slot long-press times, for power, utility, left, and right in that
+    /// order. -1 means use the shared time; 0 turns long presses off for the
+    /// slot. iLongPressPower, iLongPressUtility, iLongPressLeft, iLongPressRight
+    slot_long_press_ms: [i32; 4],
+    /// How often a held cycle key cycles again, in milliseconds. 0 means never.
+    /// uRepeatMillis
+    repeat_ms: u32,
+    /// Per-slot repeat times, in the same order and with the same meanings as
+    /// the long-press times. iRepeatPower, iRepeatUtility, iRepeatLeft, iRepeatRight
+    slot_repeat_ms: [i32; 4],
     /// Whether to fade out hud when not in combat.
     autofade: bool,
+    /// When auto-fade fades the HUD out. uAutoFadeWhen
+    fade_when: FadeWhen,
+    /// How long to wait, in milliseconds, before starting to fade out. uFadeDelay
+    fade_delay: u32,
+    /// True if a key press that brings back a faded HUD should do nothing
+    /// else. bFadeWakeupOnly
+    fade_wakeup_only: bool,
     /// The time in milliseconds it takes to fade out.
     fade_time: u32,
     /// Max alpha: the most transparent the HUD goes.
@@ -125,21 +310,55 @@ pub struct UserSettings {
     controller_kind: u32, // 0 = pc, 1 = ps, 2 = xbox
     /// True if the player wants us to cycle through ammo.
     cycle_ammo: bool,
+    /// Equipping a bow or crossbow also equips matching ammo if none is
+    /// equipped. bAutoEquipAmmo
+    auto_equip_ammo: bool,
+    /// When a held torch burns out, light another one from the inventory.
+    /// bRelightTorches
+    relight_torches: bool,
+    /// Extra keywords, comma-separated, that mark items the utility slot uses
+    /// without using up. sReusableKeywords
+    reusable_keywords: String,
+    /// Comma-separated `full:empty` keyword pairs for reusable items that swap
+    /// forms when used. sReusableSwaps
+    reusable_swaps: String,
     /// True if icons should be drawn in living color.
     colorize_icons: bool,
+    /// Color items by their type alone, ignoring OCF color keywords.
+    /// bIgnoreKeywordColors
+    ignore_keyword_colors: bool,
     /// Equip sets unequip. bEquipSetsUnequip
     equip_sets_unequip: bool,
+    /// Refuse, rather than warn about, equips that would put the player over
+    /// their carry weight. bRefuseOverweight
+    refuse_overweight: bool,
+    /// Tell the player when an equipped weapon's enchantment runs dry.
+    /// bWarnDrainedCharge
+    warn_drained_charge: bool,
     /// The identifier for the mod in SKSE cosaves. Defaults to SOLS.
     skse_identifier: String,
+    /// Item names longer than this many characters are cut short. uMaxNameLength
+    max_name_length: u32,
+    /// Item descriptions in list views longer than this are cut short. uMaxDescriptionLength
+    max_description_length: u32,
 
     /// Settings we need from DisplayTweaks, if it exists
     display_tweaks: DisplayTweaks,
+
+    /// Codes listed after the first in a key binding: more ways to press the
+    /// same action. The first code stays in the binding's own field.
+    extra_keys: Vec<(Action, u32)>,
+    /// Which action each bound input code means. Rebuilt whenever settings are
+    /// read; not itself in the ini.
+    key_actions: HashMap<u32, Action>,
 }
 
 impl Default for UserSettings {
     fn default() -> Self {
-        Self {
+        let mut settings = Self {
             log_level: Level::Info,
+            debug_mode: false,
+            record_input_trace: false,
             // The map in key_path.h starts with numeral 1 => 2.
             showhide: 2,
             power: 3,
@@ -147,24 +366,55 @@ impl Default for UserSettings {
             utility: 6,
             right: 7,
             equipset: 9,
+            power_gamepad: -1,
+            utility_gamepad: -1,
+            left_gamepad: -1,
+            right_gamepad: -1,
+            activate_gamepad: -1,
             refresh_layout: 8,
             anchor_loc: NamedAnchor::None,
             how_to_activate: ActivationMethod::Hotkey,
             activate: 4,
             activate_modifier: -1,
             group_potions: false,
+            activate_quest_items: false,
+            show_lock_hints: false,
             how_to_cycle: ActivationMethod::Hotkey,
             cycle_modifier: -1,
             long_press_matches: false,
+            long_press_leaves_tools: true,
+            long_press_equips_now: false,
+            cycle_skips_tools: false,
+            skip_unprepared_spells: false,
+            prune_missing_items: false,
+            keep_equipped_in_cycles: false,
+            cycle_auto_sort: CycleAutoSort::Off,
+            max_cycle_length: 20,
             how_to_toggle: ActivationMethod::Hotkey,
             menu_modifier: -1,
+            both_hands_modifier: -1,
             link_to_favorites: false,
             unarmed_handling: UnarmedMethod::None,
+            unarmed_in_left: true,
+            unarmed_in_right: true,
             unequip_modifier: -1,
             unequip_hotkey: -1,
+            ammo_hotkey: -1,
+            left_previous_hotkey: -1,
+            right_previous_hotkey: -1,
+            hotbar_keys: [-1; HOTBAR_SIZE],
+            hotbar_cycle: 3,
+            hotbar_modifier: -1,
             equip_delay_ms: 750, // in milliseconds
+            slot_delays_ms: [0; 4],
             long_press_ms: 1250, // in milliseconds
+            slot_long_press_ms: [-1; 4],
+            repeat_ms: 0,
+            slot_repeat_ms: [-1; 4],
             autofade: true,
+            fade_when: FadeWhen::Sheathed,
+            fade_delay: 0,
+            fade_wakeup_only: false,
             max_alpha: 1.0,
             min_alpha: 0.0,
             fade_time: 2000,    // in milliseconds
@@ -172,11 +422,24 @@ impl Default for UserSettings {
             cycling_slows_time: false,
             slow_time_factor: 0.25,
             cycle_ammo: true,
+            auto_equip_ammo: false,
+            relight_torches: false,
+            reusable_keywords: String::new(),
+            reusable_swaps: String::new(),
             colorize_icons: true,
+            ignore_keyword_colors: false,
             equip_sets_unequip: true,
+            refuse_overweight: false,
+            warn_drained_charge: true,
             skse_identifier: "SOLS".to_string(),
+            max_name_length: 80,
+            max_description_length: 48,
             display_tweaks: DisplayTweaks::default(),
-        }
+            extra_keys: Vec::new(),
+            key_actions: HashMap::new(),
+        };
+        settings.rebuild_key_map();
+        settings
     }
 }
 
@@ -188,23 +451,109 @@ impl UserSettings {
     }
 
     pub fn refresh() -> Result<()> {
-        let mut settings = SETTINGS
-            .lock()
-            .expect("Unrecoverable runtime problem: cannot acquire settings lock.");
-        settings.read_from_file(SETTINGS_PATH)
+        // The settings lock is let go before the key map is published, because
+        // the key map's first use reads the settings.
+        let (result, keys) = {
+            let mut settings = SETTINGS
+                .lock()
+                .expect("Unrecoverable runtime problem: cannot acquire settings lock.");
+            let result = settings.read_with_backup(SETTINGS_PATH);
+            (result, settings.key_map())
+        };
+        publish_key_map(keys);
+        result
+    }
+
+    /// Change an equip delay from the MCM: write it to the settings file and
+    /// use it right away, without waiting for the MCM to close. `which` is 0-3
+    /// for power, utility, left, and right, or -1 for the shared delay.
+    pub fn set_equip_delay(which: i32, millis: u32) -> Result<()> {
+        UserSettings::write_equip_delay(SETTINGS_PATH, which, millis)?;
+        UserSettings::refresh()
+    }
+
+    fn write_equip_delay(fpath: &str, which: i32, millis: u32) -> Result<()> {
+        let key = match which {
+            -1 => "uEquipDelay",
+            0..=3 => SLOT_DELAY_KEYS[which as usize],
+            _ => return Err(eyre::eyre!("There's no equip delay for slot {which}.")),
+        };
+        let mut conf = if Path::new(fpath).exists() {
+            Ini::load_from_file(fpath)?
+        } else {
+            Ini::new()
+        };
+        conf.with_section(Some("Options"))
+            .set(key, millis.clamp(0, 2500).to_string());
+        // The whole file is written back, so escape anything that would read
+        // back differently than it does now. It's written beside the real file
+        // and moved over it, so a crash mid-write can't leave half a file.
+        let temp = format!("{fpath}.tmp");
+        conf.write_to_file_policy(&temp, EscapePolicy::Reserved)?;
+        std::fs::rename(&temp, fpath)?;
+        Ok(())
     }
 
     pub fn refresh_with(fpath: &str) -> Result<()> {
-        let mut settings = SETTINGS
-            .lock()
-            .expect("Unrecoverable runtime problem: cannot acquire settings lock.");
-        settings.read_from_file(fpath)
+        let (result, keys) = {
+            let mut settings = SETTINGS
+                .lock()
+                .expect("Unrecoverable runtime problem: cannot acquire settings lock.");
+            let result = settings.read_from_file(fpath);
+            (result, settings.key_map())
+        };
+        publish_key_map(keys);
+        result
+    }
+
+    /// Read the live settings file. A file that reads cleanly is copied to a
+    /// backup; a file that doesn't is passed over for the backup, if there is
+    /// one.
+    pub fn read_with_backup(&mut self, fpath: &str) -> Result<()> {
+        let backup = format!("{fpath}.bak");
+        match self.read_from_file(fpath) {
+            Ok(()) => {
+                if let Err(e) = std::fs::copy(fpath, &backup) {
+                    log::debug!("Unable to back up the settings file; backup={backup}; {e:#}");
+                }
+                Ok(())
+            }
+            Err(e) if Path::new(fpath).exists() && Path::new(&backup).exists() => {
+                log::warn!("Unable to read the settings file; using the last good copy. file={fpath}; backup={backup}; {e:#}");
+                self.read_from_file(&backup)
+            }
+            Err(e) => Err(e),
+        }
     }
 
     /// Refresh ourselves from the MCM-controlled file.
     pub fn read_from_file(&mut self, fpath: &str) -> Result<()> {
         // We'll fall back to defaults at a different level.
+        let size = std::fs::metadata(fpath)?.len();
+        if size > MAX_SETTINGS_BYTES as u64 {
+            return Err(eyre::eyre!(
+                "The settings file is {size} bytes, which is far too big to be real. file={fpath}"
+            ));
+        }
         let conf = Ini::load_from_file(fpath)?;
+        self.read_from_conf(&conf);
+        Ok(())
+    }
+
+    /// Refresh ourselves from settings text rather than a file.
+    pub fn read_from_str(&mut self, text: &str) -> Result<()> {
+        if text.len() > MAX_SETTINGS_BYTES {
+            return Err(eyre::eyre!(
+                "Settings text is {} bytes, which is far too big to be real.",
+                text.len()
+            ));
+        }
+        let conf = Ini::load_from_str(text)?;
+        self.read_from_conf(&conf);
+        Ok(())
+    }
+
+    fn read_from_conf(&mut self, conf: &Ini) {
         let empty = ini::Properties::new();
 
         // This is the sound of my brain going clonk.
@@ -221,54 +570,178 @@ impl UserSettings {
         };
 
         self.log_level = read_from_ini(self.log_level, "sLogLevel", options);
-        let debug = read_from_ini(false, "bDebugMode", options);
+        self.debug_mode = read_from_ini(self.debug_mode, "bDebugMode", options);
         // Allow the player toggle setting to function while also letting me set a level.
-        if debug && self.log_level > Level::Debug {
+        if self.debug_mode && self.log_level > Level::Debug {
             self.log_level = Level::Debug;
         }
-
-        self.left = read_from_ini(self.left, "uLeftCycleKey", controls);
-        self.right = read_from_ini(self.right, "uRightCycleKey", controls);
-        self.power = read_from_ini(self.power, "uPowerCycleKey", controls);
-        self.utility = read_from_ini(self.utility, "uUtilityCycleKey", controls);
+        self.record_input_trace =
+            read_from_ini(self.record_input_trace, "bRecordInputTrace", options);
+
+        self.left = self.read_keys_from_ini(self.left, "uLeftCycleKey", Action::Left, controls);
+        self.right = self.read_keys_from_ini(self.right, "uRightCycleKey", Action::Right, controls);
+        self.power = self.read_keys_from_ini(self.power, "uPowerCycleKey", Action::Power, controls);
+        self.utility =
+            self.read_keys_from_ini(self.utility, "uUtilityCycleKey", Action::Utility, controls);
+        self.power_gamepad =
+            read_gamepad_from_ini(self.power_gamepad, "iPowerCycleGamepad", controls);
+        self.utility_gamepad =
+            read_gamepad_from_ini(self.utility_gamepad, "iUtilityCycleGamepad", controls);
+        self.left_gamepad = read_gamepad_from_ini(self.left_gamepad, "iLeftCycleGamepad", controls);
+        self.right_gamepad =
+            read_gamepad_from_ini(self.right_gamepad, "iRightCycleGamepad", controls);
+        self.activate_gamepad =
+            read_gamepad_from_ini(self.activate_gamepad, "iUtilityActivateGamepad", controls);
         self.how_to_cycle = read_from_ini(self.how_to_cycle, "uHowToCycle", controls);
-        self.cycle_modifier = read_from_ini(self.cycle_modifier, "iCycleModifierKey", controls);
+        self.cycle_modifier = read_key_from_ini(self.cycle_modifier, "iCycleModifierKey", controls);
         self.long_press_matches =
             read_from_ini(self.long_press_matches, "bLongPressMatches", controls);
+        self.long_press_leaves_tools = read_from_ini(
+            self.long_press_leaves_tools,
+            "bLongPressLeavesTools",
+            controls,
+        );
+        self.long_press_equips_now =
+            read_from_ini(self.long_press_equips_now, "bLongPressEquipsNow", controls);
+        self.cycle_skips_tools = read_from_ini(self.cycle_skips_tools, "bCycleSkipsTools", options);
+        self.skip_unprepared_spells = read_from_ini(
+            self.skip_unprepared_spells,
+            "bSkipUnpreparedSpells",
+            options,
+        );
+        self.prune_missing_items =
+            read_from_ini(self.prune_missing_items, "bPruneMissingItems", options);
+        self.keep_equipped_in_cycles = read_from_ini(
+            self.keep_equipped_in_cycles,
+            "bKeepEquippedInCycles",
+            options,
+        );
+        self.cycle_auto_sort = read_from_ini(self.cycle_auto_sort, "uCycleAutoSort", options);
+        self.max_cycle_length = u32::clamp(
+            read_from_ini(self.max_cycle_length, "uMaxCycleLength", options),
+            2,
+            50,
+        );
 
         self.how_to_toggle = read_from_ini(self.how_to_toggle, "uHowToggleInMenus", controls);
-        self.menu_modifier = read_from_ini(self.menu_modifier, "iMenuModifierKey", controls);
+        self.menu_modifier = read_key_from_ini(self.menu_modifier, "iMenuModifierKey", controls);
+        self.both_hands_modifier =
+            read_key_from_ini(self.both_hands_modifier, "iBothHandsModifierKey", controls);
         self.link_to_favorites = read_from_ini(self.link_to_favorites, "bLinkToFavorites", options);
 
         self.how_to_activate = read_from_ini(self.how_to_activate, "uHowToActivate", controls);
-        self.activate = read_from_ini(self.activate, "uUtilityActivateKey", controls);
+        self.activate = self.read_keys_from_ini(
+            self.activate,
+            "uUtilityActivateKey",
+            Action::Activate,
+            controls,
+        );
         self.activate_modifier =
-            read_from_ini(self.activate_modifier, "iUtilityActivateModifier", controls);
+            read_key_from_ini(self.activate_modifier, "iUtilityActivateModifier", controls);
         self.group_potions = read_from_ini(self.group_potions, "bGroupPotions", options);
+        self.activate_quest_items =
+            read_from_ini(self.activate_quest_items, "bActivateQuestItems", options);
+        self.show_lock_hints = read_from_ini(self.show_lock_hints, "bShowLockHints", options);
 
-        self.showhide = read_from_ini(self.showhide, "uShowHideKey", controls);
-        self.refresh_layout = read_from_ini(self.refresh_layout, "uRefreshKey", controls);
+        self.showhide =
+            self.read_keys_from_ini(self.showhide, "uShowHideKey", Action::ShowHide, controls);
+        self.refresh_layout = self.read_keys_from_ini(
+            self.refresh_layout,
+            "uRefreshKey",
+            Action::RefreshLayout,
+            controls,
+        );
         self.anchor_loc = read_from_ini(self.anchor_loc.clone(), "uAnchorLocation", options);
 
         self.unarmed_handling = read_from_ini(self.unarmed_handling, "uHowToUnequip", controls);
+        self.unarmed_in_left = read_from_ini(self.unarmed_in_left, "bUnarmedInLeftCycle", controls);
+        self.unarmed_in_right =
+            read_from_ini(self.unarmed_in_right, "bUnarmedInRightCycle", controls);
         self.unequip_modifier =
-            read_from_ini(self.unequip_modifier, "iUnequipModifierKey", controls);
-        self.unequip_hotkey = read_from_ini(self.unequip_hotkey, "iUnequipHotkey", controls);
+            read_key_from_ini(self.unequip_modifier, "iUnequipModifierKey", controls);
+        self.unequip_hotkey = self.read_keys_from_ini(
+            self.unequip_hotkey,
+            "iUnequipHotkey",
+            Action::UnequipHands,
+            controls,
+        );
+        self.ammo_hotkey =
+            self.read_keys_from_ini(self.ammo_hotkey, "iAmmoCycleKey", Action::Ammo, controls);
+        self.left_previous_hotkey = self.read_keys_from_ini(
+            self.left_previous_hotkey,
+            "iLeftPreviousKey",
+            Action::LeftPrevious,
+            controls,
+        );
+        self.right_previous_hotkey = self.read_keys_from_ini(
+            self.right_previous_hotkey,
+            "iRightPreviousKey",
+            Action::RightPrevious,
+            controls,
+        );
+
+        for (idx, key) in self.hotbar_keys.iter_mut().enumerate() {
+            *key = read_key_from_ini(*key, format!("iHotbarKey{}", idx + 1).as_str(), controls);
+        }
+        self.hotbar_cycle = u32::clamp(
+            read_from_ini(self.hotbar_cycle, "uHotbarCycle", controls),
+            0,
+            3,
+        );
+        self.hotbar_modifier = read_key_from_ini(self.hotbar_modifier, "iHotbarModifier", controls);
 
         self.equip_delay_ms = u32::clamp(
             read_from_ini(self.equip_delay_ms, "uEquipDelay", options),
             0,
             2500,
         );
+        for (idx, delay) in self.slot_delays_ms.iter_mut().enumerate() {
+            *delay = u32::clamp(
+                read_from_ini(*delay, SLOT_DELAY_KEYS[idx], options),
+                0,
+                2500,
+            );
+        }
         self.long_press_ms = read_from_ini(self.equip_delay_ms, "uLongPressMillis", options);
         if self.long_press_ms < self.equip_delay_ms {
             self.long_press_ms = self.equip_delay_ms + 100;
         }
+        // Settings files from before per-slot timings don't have these keys, and
+        // their slots keep using the shared times.
+        for idx in 0..4 {
+            let own = read_from_ini(
+                self.slot_long_press_ms[idx],
+                SLOT_LONG_PRESS_KEYS[idx],
+                options,
+            );
+            let delay = self.equip_delay_for(&SLOT_ACTIONS[idx]);
+            self.slot_long_press_ms[idx] = if own > 0 && (own as u32) < delay {
+                (delay + 100) as i32
+            } else {
+                own.clamp(-1, 5000)
+            };
+        }
+        self.repeat_ms = clamp_repeat(read_from_ini(self.repeat_ms, "uRepeatMillis", options));
+        for (idx, repeat) in self.slot_repeat_ms.iter_mut().enumerate() {
+            let own = read_from_ini(*repeat, SLOT_REPEAT_KEYS[idx], options);
+            *repeat = if own < 0 {
+                -1
+            } else {
+                clamp_repeat(own as u32) as i32
+            };
+        }
 
         self.autofade = read_from_ini(self.autofade, "bAutoFade", options);
+        self.fade_when = read_from_ini(self.fade_when, "uAutoFadeWhen", options);
+        self.fade_delay = u32::clamp(
+            read_from_ini(self.fade_delay, "uFadeDelay", options),
+            0,
+            10000,
+        );
+        self.fade_wakeup_only = read_from_ini(self.fade_wakeup_only, "bFadeWakeupOnly", options);
         self.fade_time = u32::clamp(read_from_ini(self.fade_time, "uFadeTime", options), 0, 2500);
-        self.max_alpha = read_from_ini(self.max_alpha, "fMaxAlpha", options);
-        self.min_alpha = read_from_ini(self.min_alpha, "fMinAlpha", options);
+        self.max_alpha = read_from_ini(self.max_alpha, "fMaxAlpha", options).clamp(0.0, 1.0);
+        self.min_alpha = read_from_ini(self.min_alpha, "fMinAlpha", options).clamp(0.0, 1.0);
 
         self.controller_kind = u32::clamp(
             read_from_ini(self.controller_kind, "uControllerKind", options),
@@ -278,21 +751,122 @@ impl UserSettings {
 
         self.cycling_slows_time =
             read_from_ini(self.cycling_slows_time, "bCyclingSlowsTime", options);
-        let percentage = read_from_ini(25, "uSlowTimeFactor", options);
+        let percentage = u32::clamp(read_from_ini(25, "uSlowTimeFactor", options), 1, 100);
         self.slow_time_factor = percentage as f32 / 100.0;
 
         self.cycle_ammo = read_from_ini(self.cycle_ammo, "bCycleAmmo", options);
+        self.auto_equip_ammo = read_from_ini(self.auto_equip_ammo, "bAutoEquipAmmo", options);
+        self.relight_torches = read_from_ini(self.relight_torches, "bRelightTorches", options);
+        self.reusable_keywords =
+            read_from_ini(self.reusable_keywords.clone(), "sReusableKeywords", options);
+        self.reusable_swaps = read_from_ini(self.reusable_swaps.clone(), "sReusableSwaps", options);
         self.colorize_icons = read_from_ini(self.colorize_icons, "bColorizeIcons", options);
+        self.ignore_keyword_colors =
+            read_from_ini(self.ignore_keyword_colors, "bIgnoreKeywordColors", options);
         self.skse_identifier =
             read_from_ini(self.skse_identifier.clone(), "sSKSEIdentifier", options);
+        self.max_name_length = u32::clamp(
+            read_from_ini(self.max_name_length, "uMaxNameLength", options),
+            8,
+            512,
+        );
+        self.max_description_length = u32::clamp(
+            read_from_ini(
+                self.max_description_length,
+                "uMaxDescriptionLength",
+                options,
+            ),
+            8,
+            256,
+        );
 
-        self.equipset = read_from_ini(self.equipset, "iEquipSetCycleKey", controls);
+        self.equipset = self.read_keys_from_ini(
+            self.equipset,
+            "iEquipSetCycleKey",
+            Action::Equipment,
+            controls,
+        );
         self.equip_sets_unequip =
             read_from_ini(self.equip_sets_unequip, "bEquipSetsUnequip", options);
+        self.refuse_overweight =
+            read_from_ini(self.refuse_overweight, "bRefuseOverweight", options);
+        self.warn_drained_charge =
+            read_from_ini(self.warn_drained_charge, "bWarnDrainedCharge", options);
 
         self.display_tweaks.read_ini();
-
-        Ok(())
+        self.rebuild_key_map();
+    }
+
+    /// Read a binding that can list several codes separated by commas, like
+    /// `uRightCycleKey = 19, mouse:4`. The first code is returned, and is the one
+    /// the MCM and the HUD know about. The rest become extra keys for the action.
+    fn read_keys_from_ini<T>(
+        &mut self,
+        default: T,
+        key: &str,
+        action: Action,
+        section: &ini::Properties,
+    ) -> T
+    where
+        KeyBinding<T>: FromIniStr,
+    {
+        let Some(value) = section.get(key) else {
+            return default;
+        };
+        let mut codes = value.split(',').map(str::trim).filter(|xs| !xs.is_empty());
+        let first = codes
+            .next()
+            .and_then(KeyBinding::<T>::from_ini)
+            .map_or(default, |xs| xs.0);
+        self.extra_keys.retain(|(bound, _)| *bound != action);
+        for code in codes {
+            match KeyBinding::<u32>::from_ini(code) {
+                Some(KeyBinding(extra)) => self.extra_keys.push((action, extra)),
+                None => {
+                    log::warn!("{key} lists a key we don't understand; ignoring it; value={code};")
+                }
+            }
+        }
+        first
+    }
+
+    /// Build the map from input codes to actions. When two actions claim the
+    /// same code, the first binding wins and we say so in the log.
+    fn rebuild_key_map(&mut self) {
+        let primary = [
+            (self.left as i64, Action::Left),
+            (self.right as i64, Action::Right),
+            (self.power as i64, Action::Power),
+            (self.utility as i64, Action::Utility),
+            (self.activate as i64, Action::Activate),
+            (self.showhide as i64, Action::ShowHide),
+            (self.refresh_layout as i64, Action::RefreshLayout),
+            (self.equipset as i64, Action::Equipment),
+            (self.unequip_hotkey as i64, Action::UnequipHands),
+            (self.ammo_hotkey as i64, Action::Ammo),
+            (self.left_previous_hotkey as i64, Action::LeftPrevious),
+            (self.right_previous_hotkey as i64, Action::RightPrevious),
+        ];
+        let extras = self
+            .extra_keys
+            .iter()
+            .map(|(action, code)| (*code as i64, *action));
+
+        self.key_actions.clear();
+        for (code, action) in primary.into_iter().chain(extras) {
+            let Ok(code) = u32::try_from(code) else {
+                continue;
+            };
+            match self.key_actions.get(&code) {
+                Some(existing) if *existing != action => log::warn!(
+                    "Key {code} is bound to both {existing:?} and {action:?}; {existing:?} wins."
+                ),
+                Some(_) => {}
+                None => {
+                    self.key_actions.insert(code, action);
+                }
+            }
+        }
     }
 
     pub fn log_level(&self) -> Level {
@@ -310,9 +884,28 @@ impl UserSettings {
         }
     }
 
+    pub fn debug_mode(&self) -> bool {
+        self.debug_mode
+    }
+
+    pub fn record_input_trace(&self) -> bool {
+        self.record_input_trace
+    }
+
     pub fn unequip_method(&self) -> &UnarmedMethod {
         &self.unarmed_handling
     }
+    /// Should this hand's cycle have an unarmed entry?
+    pub fn unarmed_in_cycle(&self, which: &CycleSlot) -> bool {
+        if !matches!(self.unarmed_handling, UnarmedMethod::AddToCycles) {
+            return false;
+        }
+        match which {
+            CycleSlot::Left => self.unarmed_in_left,
+            CycleSlot::Right => self.unarmed_in_right,
+            _ => false,
+        }
+    }
     pub fn unequip_modifier(&self) -> i32 {
         self.unequip_modifier
     }
@@ -323,8 +916,44 @@ impl UserSettings {
             -1
         }
     }
+    pub fn ammo_hotkey(&self) -> i32 {
+        self.ammo_hotkey
+    }
+    pub fn left_previous_hotkey(&self) -> i32 {
+        self.left_previous_hotkey
+    }
+    pub fn right_previous_hotkey(&self) -> i32 {
+        self.right_previous_hotkey
+    }
+
+    /// Which hotbar position this key is bound to, if any. Positions count from 0.
+    pub fn hotbar_slot_for(&self, key: u32) -> Option<usize> {
+        self.hotbar_keys
+            .iter()
+            .position(|xs| xs.is_positive() && xs.unsigned_abs() == key)
+    }
+    pub fn hotbar_key(&self, slot: usize) -> i32 {
+        self.hotbar_keys.get(slot).copied().unwrap_or(-1)
+    }
+    pub fn hotbar_cycle(&self) -> CycleSlot {
+        match self.hotbar_cycle {
+            0 => CycleSlot::Power,
+            1 => CycleSlot::Utility,
+            2 => CycleSlot::Left,
+            _ => CycleSlot::Right,
+        }
+    }
+    pub fn hotbar_modifier(&self) -> i32 {
+        self.hotbar_modifier
+    }
 
     pub fn should_start_long_press_timer(&self, key: u32) -> bool {
+        if !self
+            .timings_for(&self.action_for_key(key))
+            .long_press_enabled()
+        {
+            return false;
+        }
         let hotkey = Hotkey::from(key);
         let is_hand_cycle = matches!(hotkey, Hotkey::Left | Hotkey::Right);
         let can_be_unequipped = matches!(hotkey, Hotkey::Left | Hotkey::Power | Hotkey::Right);
@@ -345,6 +974,14 @@ impl UserSettings {
         if matches!(self.unarmed_handling, UnarmedMethod::LongPress) && can_be_unequipped {
             return true;
         }
+        if self.long_press_equips_now
+            && matches!(
+                hotkey,
+                Hotkey::Left | Hotkey::Power | Hotkey::Right | Hotkey::Utility
+            )
+        {
+            return true;
+        }
 
         false
     }
@@ -355,6 +992,9 @@ impl UserSettings {
     pub fn menu_modifier(&self) -> i32 {
         self.menu_modifier
     }
+    pub fn both_hands_modifier(&self) -> i32 {
+        self.both_hands_modifier
+    }
     pub fn link_to_favorites(&self) -> bool {
         self.link_to_favorites
     }
@@ -374,12 +1014,130 @@ impl UserSettings {
         self.long_press_matches
     }
 
+    pub fn long_press_leaves_tools(&self) -> bool {
+        self.long_press_leaves_tools
+    }
+
+    pub fn long_press_equips_now(&self) -> bool {
+        self.long_press_equips_now
+    }
+
+    pub fn cycle_skips_tools(&self) -> bool {
+        self.cycle_skips_tools
+    }
+
+    pub fn skip_unprepared_spells(&self) -> bool {
+        self.skip_unprepared_spells
+    }
+
+    pub fn prune_missing_items(&self) -> bool {
+        self.prune_missing_items
+    }
+
+    pub fn keep_equipped_in_cycles(&self) -> bool {
+        self.keep_equipped_in_cycles
+    }
+
+    pub fn cycle_auto_sort(&self) -> &CycleAutoSort {
+        &self.cycle_auto_sort
+    }
+
+    pub fn max_cycle_length(&self) -> u32 {
+        self.max_cycle_length
+    }
+
+    /// The action bound to this input code. Codes carry their device, so a
+    /// mouse button never stands in for the keyboard key with the same number.
+    pub fn action_for_key(&self, key: u32) -> Action {
+        // A button bound for the gamepad wins over a key binding that happens
+        // to hold the same button's code.
+        if let Some(action) = self.gamepad_action_for(key) {
+            return action;
+        }
+        self.key_actions.get(&key).copied().unwrap_or(Action::None)
+    }
+
+    /// Everything the key handler needs to know about the bindings, so it can
+    /// answer without a copy of the settings.
+    pub fn key_map(&self) -> KeyMap {
+        let mut actions = self.key_actions.clone();
+        // Gamepad buttons win, as they do in action_for_key().
+        for (button, action) in [
+            (self.left_gamepad, Action::Left),
+            (self.right_gamepad, Action::Right),
+            (self.power_gamepad, Action::Power),
+            (self.utility_gamepad, Action::Utility),
+            (self.activate_gamepad, Action::Activate),
+        ] {
+            if self.gamepad_action_for(button.unsigned_abs()) == Some(action) {
+                actions.insert(button.unsigned_abs(), action);
+            }
+        }
+
+        let modifiers = [
+            self.activate_modifier,
+            self.cycle_modifier,
+            self.unequip_modifier,
+            self.menu_modifier,
+            self.both_hands_modifier,
+            self.hotbar_modifier,
+        ];
+        let watched = actions
+            .keys()
+            .copied()
+            .chain(
+                modifiers
+                    .into_iter()
+                    .chain(self.hotbar_keys.iter().copied())
+                    .filter(|code| code.is_positive())
+                    .map(i32::unsigned_abs),
+            )
+            .chain(BROWSE_KEYS)
+            .collect();
+        KeyMap { actions, watched }
+    }
+
+    /// The action bound to this gamepad button in the gamepad bindings, if any.
+    pub fn gamepad_action_for(&self, key: u32) -> Option<Action> {
+        if !matches!(KeyCode::from_raw(key), Some(code) if code.device == InputDevice::Gamepad) {
+            return None;
+        }
+        [
+            (self.left_gamepad, Action::Left),
+            (self.right_gamepad, Action::Right),
+            (self.power_gamepad, Action::Power),
+            (self.utility_gamepad, Action::Utility),
+            (self.activate_gamepad, Action::Activate),
+        ]
+        .into_iter()
+        .find(|(button, _)| *button >= 0 && button.unsigned_abs() == key)
+        .map(|(_, action)| action)
+    }
+
+    /// The hotkey to show for this slot while the player is using a gamepad:
+    /// its gamepad button if it has one, or its key if not.
+    pub fn gamepad_hotkey_for(&self, action: HudElement) -> u32 {
+        let button = match action {
+            HudElement::Power => self.power_gamepad,
+            HudElement::Utility => self.utility_gamepad,
+            HudElement::Left | HudElement::Ammo => self.left_gamepad,
+            HudElement::Right => self.right_gamepad,
+            _ => -1,
+        };
+        if button >= 0 {
+            button.unsigned_abs()
+        } else {
+            self.hotkey_for(action)
+        }
+    }
+
     pub fn hotkey_for(&self, action: HudElement) -> u32 {
         match action {
             HudElement::Power => self.power,
             HudElement::Utility => self.utility,
             HudElement::Left => self.left,
             HudElement::Right => self.right,
+            HudElement::Ammo if self.ammo_hotkey >= 0 => self.ammo_hotkey.unsigned_abs(),
             HudElement::Ammo => self.left, // This is objectively correct.
             HudElement::EquipSet => self.equipset as u32,
             _ => self.refresh_layout, // Required because this is a C-style enum. But wrong.
@@ -401,9 +1159,30 @@ impl UserSettings {
     pub fn equipset(&self) -> i32 {
         self.equipset
     }
+    pub fn power_gamepad(&self) -> i32 {
+        self.power_gamepad
+    }
+    pub fn utility_gamepad(&self) -> i32 {
+        self.utility_gamepad
+    }
+    pub fn left_gamepad(&self) -> i32 {
+        self.left_gamepad
+    }
+    pub fn right_gamepad(&self) -> i32 {
+        self.right_gamepad
+    }
+    pub fn activate_gamepad(&self) -> i32 {
+        self.activate_gamepad
+    }
     pub fn equip_sets_unequip(&self) -> bool {
         self.equip_sets_unequip
     }
+    pub fn refuse_overweight(&self) -> bool {
+        self.refuse_overweight
+    }
+    pub fn warn_drained_charge(&self) -> bool {
+        self.warn_drained_charge
+    }
 
     pub fn utility_activation_method(&self) -> &ActivationMethod {
         &self.how_to_activate
@@ -420,6 +1199,14 @@ impl UserSettings {
         self.group_potions
     }
 
+    pub fn activate_quest_items(&self) -> bool {
+        self.activate_quest_items
+    }
+
+    pub fn show_lock_hints(&self) -> bool {
+        self.show_lock_hints
+    }
+
     pub fn showhide(&self) -> u32 {
         self.showhide
     }
@@ -429,18 +1216,78 @@ impl UserSettings {
     pub fn anchor_loc(&self) -> &NamedAnchor {
         &self.anchor_loc
     }
-    pub fn maxlen(&self) -> u32 {
-        20
-    }
     pub fn equip_delay_ms(&self) -> u32 {
         self.equip_delay_ms
     }
+    /// The equip delay for this slot: its own if it has one, the shared one if not.
+    pub fn equip_delay_for(&self, which: &Action) -> u32 {
+        let own = match *which {
+            Action::Power => self.slot_delays_ms[0],
+            Action::Utility => self.slot_delays_ms[1],
+            Action::Left => self.slot_delays_ms[2],
+            Action::Right => self.slot_delays_ms[3],
+            _ => 0,
+        };
+        if own > 0 {
+            own
+        } else {
+            self.equip_delay_ms
+        }
+    }
+    /// How long a timer for this action runs: the slot's long-press time for
+    /// long-press timers, and the slot's equip delay for everything else.
+    pub fn timer_duration_for(&self, which: &Action) -> u32 {
+        match *which {
+            Action::LongPressPower
+            | Action::LongPressUtility
+            | Action::LongPressLeft
+            | Action::LongPressRight => self.timings_for(which).hold_ms,
+            _ => self.equip_delay_for(which),
+        }
+    }
+    /// The press timings for the slot this action belongs to, with the shared
+    /// times filled in where the slot doesn't have its own.
+    pub fn timings_for(&self, which: &Action) -> SlotTimings {
+        let idx = match *which {
+            Action::Power | Action::LongPressPower => Some(0),
+            Action::Utility | Action::LongPressUtility => Some(1),
+            Action::Left | Action::LongPressLeft => Some(2),
+            Action::Right | Action::LongPressRight => Some(3),
+            _ => None,
+        };
+        let pick = |own: &[i32; 4], shared: u32| match idx.map(|i| own[i]) {
+            Some(v) if v >= 0 => v as u32,
+            _ => shared,
+        };
+        SlotTimings {
+            hold_ms: pick(&self.slot_long_press_ms, self.long_press_ms),
+            repeat_ms: pick(&self.slot_repeat_ms, self.repeat_ms),
+        }
+    }
     pub fn long_press_ms(&self) -> u32 {
         self.long_press_ms
     }
     pub fn autofade(&self) -> bool {
         self.autofade
     }
+    /// Should auto-fade fade the HUD out, given what the player is doing?
+    /// Always false if auto-fade is off.
+    pub fn should_fade_out(&self, in_combat: bool, weapons_drawn: bool) -> bool {
+        if !self.autofade {
+            return false;
+        }
+        match self.fade_when {
+            FadeWhen::Sheathed => !in_combat && !weapons_drawn,
+            FadeWhen::OutOfCombat => !in_combat,
+            FadeWhen::Idle => true,
+        }
+    }
+    pub fn fade_delay(&self) -> u32 {
+        self.fade_delay
+    }
+    pub fn fade_wakeup_only(&self) -> bool {
+        self.fade_wakeup_only
+    }
     pub fn fade_time(&self) -> u32 {
         self.fade_time
     }
@@ -464,19 +1311,51 @@ impl UserSettings {
         self.cycle_ammo
     }
 
+    pub fn auto_equip_ammo(&self) -> bool {
+        self.auto_equip_ammo
+    }
+
+    pub fn relight_torches(&self) -> bool {
+        self.relight_torches
+    }
+
+    pub fn reusable_keywords(&self) -> &str {
+        self.reusable_keywords.as_str()
+    }
+
+    pub fn reusable_swaps(&self) -> &str {
+        self.reusable_swaps.as_str()
+    }
+
     pub fn colorize_icons(&self) -> bool {
         self.colorize_icons
     }
 
+    pub fn ignore_keyword_colors(&self) -> bool {
+        self.ignore_keyword_colors
+    }
+
+    /// The identifier must be four ASCII characters. Shorter ones are padded
+    /// with spaces and longer ones cut short; anything else gets the default.
     pub fn skse_identifier(&self) -> u32 {
-        let exactly_four = format!("{:4}", self.skse_identifier);
-        let slice: [u8; 4] = exactly_four
-            .as_bytes()
-            .try_into()
-            .expect("You must provide exactly four characters as the mod identifier string.");
+        let mut slice = *b"    ";
+        if !self.skse_identifier.is_ascii() || self.skse_identifier.is_empty() {
+            slice = *b"SOLS";
+        } else {
+            for (dest, byte) in slice.iter_mut().zip(self.skse_identifier.bytes()) {
+                *dest = byte;
+            }
+        }
         u32::from_le_bytes(slice)
     }
 
+    pub fn max_name_length(&self) -> u32 {
+        self.max_name_length
+    }
+    pub fn max_description_length(&self) -> u32 {
+        self.max_description_length
+    }
+
     pub fn is_upscaling(&self) -> bool {
         self.display_tweaks.upscaling()
     }
@@ -499,6 +1378,51 @@ fn read_from_ini<T: FromIniStr>(default: T, key: &str, section: &ini::Properties
     }
 }
 
+/// Read a key binding. Bindings are folded input codes, but can also name
+/// their device, like `mouse:4`; see the `keycodes` module. Bare numbers from
+/// older files are folded codes already, so they keep meaning what they meant.
+fn read_key_from_ini<T>(default: T, key: &str, section: &ini::Properties) -> T
+where
+    KeyBinding<T>: FromIniStr,
+{
+    read_from_ini(KeyBinding(default), key, section).0
+}
+
+/// Read a gamepad-only binding. Anything that isn't a gamepad button leaves
+/// the binding unset, so it can't shadow a keyboard key or mouse button.
+fn read_gamepad_from_ini(default: i32, key: &str, section: &ini::Properties) -> i32 {
+    let button = read_key_from_ini(default, key, section);
+    if button < 0 {
+        return -1;
+    }
+    match KeyCode::from_raw(button.unsigned_abs()) {
+        Some(code) if code.device == InputDevice::Gamepad => button,
+        _ => {
+            log::warn!("{key} must be a gamepad button; ignoring it; value={button};");
+            -1
+        }
+    }
+}
+
+/// A key binding as read from the ini file.
+struct KeyBinding<T>(T);
+
+impl FromIniStr for KeyBinding<u32> {
+    fn from_ini(value: &str) -> Option<Self> {
+        u32::from_ini(value)
+            .or_else(|| KeyCode::parse(value).map(|xs| xs.raw()))
+            .map(KeyBinding)
+    }
+}
+
+impl FromIniStr for KeyBinding<i32> {
+    fn from_ini(value: &str) -> Option<Self> {
+        i32::from_ini(value)
+            .or_else(|| KeyCode::parse(value).map(|xs| xs.raw() as i32))
+            .map(KeyBinding)
+    }
+}
+
 /// Trait and implementations for reading from the ini file
 trait FromIniStr {
     fn from_ini(value: &str) -> Option<Self>
@@ -545,6 +1469,48 @@ impl FromIniStr for Level {
     }
 }
 
+/// How cycles reorder themselves, if at all.
+#[derive(Debug, Clone, Display, Copy, PartialEq, Eq)]
+pub enum CycleAutoSort {
+    /// Cycles stay in the order the player made.
+    Off,
+    /// Once a game day, the most-used entries move up.
+    Usage,
+}
+
+impl FromIniStr for CycleAutoSort {
+    fn from_ini(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "0" | "off" => Some(CycleAutoSort::Off),
+            "1" | "usage" => Some(CycleAutoSort::Usage),
+            _ => None,
+        }
+    }
+}
+
+/// When auto-fade fades the HUD out. It fades back in whenever the
+/// condition no longer holds, and briefly whenever the player cycles.
+#[derive(Debug, Clone, Display, Copy, PartialEq, Eq)]
+pub enum FadeWhen {
+    /// When the player is out of combat with weapons sheathed.
+    Sheathed,
+    /// Whenever the player is out of combat, drawn weapons or not.
+    OutOfCombat,
+    /// Always, once the fade delay has passed since the HUD was last shown.
+    Idle,
+}
+
+impl FromIniStr for FadeWhen {
+    fn from_ini(value: &str) -> Option<Self> {
+        match value {
+            "0" => Some(FadeWhen::Sheathed),
+            "1" => Some(FadeWhen::OutOfCombat),
+            "2" => Some(FadeWhen::Idle),
+            _ => None,
+        }
+    }
+}
+
 /// How the player wants to handle unarmed combat.
 #[derive(Debug, Clone, Display, Copy)]
 pub enum UnarmedMethod {
@@ -614,20 +1580,20 @@ impl FromIniStr for i32 {
 
 impl FromIniStr for f32 {
     fn from_ini(value: &str) -> Option<Self> {
-        if let Ok(v) = value.parse::<f32>() {
-            Some(v)
-        } else {
-            None
+        // NaN and the infinities parse, but no setting wants them.
+        match value.parse::<f32>() {
+            Ok(v) if v.is_finite() => Some(v),
+            _ => None,
         }
     }
 }
 
 impl FromIniStr for f64 {
     fn from_ini(value: &str) -> Option<Self> {
-        if let Ok(v) = value.parse::<f64>() {
-            Some(v)
-        } else {
-            None
+        // NaN and the infinities parse, but no setting wants them.
+        match value.parse::<f64>() {
+            Ok(v) if v.is_finite() => Some(v),
+            _ => None,
         }
     }
 }
@@ -671,11 +1637,18 @@ impl std::fmt::Display for UserSettings {
         write!(
             f,
             r#"  log level: {}
+                  debug mode: {}
+          record input trace: {}
            show/hide HUD key: {}
              power cycle key: {}
            utility cycle key: {}
               left cycle key: {}
              right cycle key: {}
+         power cycle gamepad: {}
+       utility cycle gamepad: {}
+          left cycle gamepad: {}
+         right cycle gamepad: {}
+            activate gamepad: {}
           equipset cycle key: {}
           refresh layout key: {}
       layout anchor override: {}
@@ -683,18 +1656,45 @@ impl std::fmt::Display for UserSettings {
         activate consumables: {}
            activate_modifier: {}
                group_potions: {}
+        activate_quest_items: {}
+             show_lock_hints: {}
                 how_to_cycle: {}
               cycle_modifier: {}
     dual-wield on long press: {}
+     long press leaves tools: {}
+       long press equips now: {}
+           cycle skips tools: {}
+      skip unprepared spells: {}
+         prune missing items: {}
+     keep equipped in cycles: {}
+             cycle_auto_sort: {}
+            max_cycle_length: {}
                how_to_toggle: {}
                menu_modifier: {}
+         both_hands_modifier: {}
            link_to_favorites: {}
             unarmed_handling: {}
+             unarmed in left: {}
+            unarmed in right: {}
             unequip_modifier: {}
               unequip_hotkey: {}
+                 ammo_hotkey: {}
+        left_previous_hotkey: {}
+       right_previous_hotkey: {}
+                  extra_keys: {:?}
+                 hotbar_keys: {:?}
+                hotbar_cycle: {}
+             hotbar_modifier: {}
               equip_delay_ms: {} ms
+              slot_delays_ms: {:?}
                long_press_ms: {} ms
+          slot_long_press_ms: {:?}
+                   repeat_ms: {} ms
+              slot_repeat_ms: {:?}
                     autofade: {}
+                   fade_when: {}
+                  fade_delay: {} ms
+            fade_wakeup_only: {}
                    fade_time: {} ms
                    max alpha: {}
                    min alpha: {}
@@ -702,15 +1702,31 @@ impl std::fmt::Display for UserSettings {
           cycling_slows_time: {}
             slow_time_factor: {} %
                   cycle_ammo: {}
+             auto_equip_ammo: {}
+             relight_torches: {}
+           reusable_keywords: {}
+              reusable_swaps: {}
               colorize_icons: {}
+       ignore_keyword_colors: {}
           equip_sets_unequip: {}
-             skse_identifier: {}"#,
+           refuse_overweight: {}
+         warn_drained_charge: {}
+             skse_identifier: {}
+             max_name_length: {}
+      max_description_length: {}"#,
             self.log_level,
+            self.debug_mode,
+            self.record_input_trace,
             self.showhide,
             self.power,
             self.utility,
             self.left,
             self.right,
+            self.power_gamepad,
+            self.utility_gamepad,
+            self.left_gamepad,
+            self.right_gamepad,
+            self.activate_gamepad,
             self.equipset,
             self.refresh_layout,
             self.anchor_loc,
@@ -718,18 +1734,45 @@ impl std::fmt::Display for UserSettings {
             self.activate,
             self.activate_modifier,
             self.group_potions,
+            self.activate_quest_items,
+            self.show_lock_hints,
             self.how_to_cycle,
             self.cycle_modifier,
             self.long_press_matches,
+            self.long_press_leaves_tools,
+            self.long_press_equips_now,
+            self.cycle_skips_tools,
+            self.skip_unprepared_spells,
+            self.prune_missing_items,
+            self.keep_equipped_in_cycles,
+            self.cycle_auto_sort,
+            self.max_cycle_length,
             self.how_to_toggle,
             self.menu_modifier,
+            self.both_hands_modifier,
             self.link_to_favorites,
             self.unarmed_handling,
+            self.unarmed_in_left,
+            self.unarmed_in_right,
             self.unequip_modifier,
             self.unequip_hotkey,
+            self.ammo_hotkey,
+            self.left_previous_hotkey,
+            self.right_previous_hotkey,
+            self.extra_keys,
+            self.hotbar_keys,
+            self.hotbar_cycle(),
+            self.hotbar_modifier,
             self.equip_delay_ms,
+            self.slot_delays_ms,
             self.long_press_ms,
+            self.slot_long_press_ms,
+            self.repeat_ms,
+            self.slot_repeat_ms,
             self.autofade,
+            self.fade_when,
+            self.fade_delay,
+            self.fade_wakeup_only,
             self.fade_time,
             self.max_alpha,
             self.min_alpha,
@@ -737,9 +1780,18 @@ impl std::fmt::Display for UserSettings {
             self.cycling_slows_time,
             self.slow_time_factor,
             self.cycle_ammo,
+            self.auto_equip_ammo,
+            self.relight_torches,
+            self.reusable_keywords,
+            self.reusable_swaps,
             self.colorize_icons,
+            self.ignore_keyword_colors,
             self.equip_sets_unequip,
-            self.skse_identifier
+            self.refuse_overweight,
+            self.warn_drained_charge,
+            self.skse_identifier,
+            self.max_name_length,
+            self.max_description_length
         )
     }
 }
@@ -801,6 +1853,7 @@ impl Default for DisplayTweaks {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fuzzing;
 
     #[test]
     #[allow(clippy::approx_constant)]
@@ -849,4 +1902,412 @@ mod tests {
         let le_options = UserSettings::new_from_file("./tests/fixtures/SoulsyHUD.ini");
         assert!(le_options.long_press_ms > le_options.equip_delay_ms);
     }
+
+    #[test]
+    fn mouse_buttons_bind_apart_from_keys() {
+        let options = UserSettings::new_from_file("./tests/fixtures/mouse-bindings.ini");
+        // A bare number from an older file is a keyboard key.
+        assert_eq!(options.left(), 5);
+        assert_eq!(options.right(), 260);
+        assert_eq!(options.power(), 3);
+        assert_eq!(options.equipset(), 259);
+        // Mouse button 11 doesn't exist, so the default stands.
+        assert_eq!(
+            options.cycle_modifier(),
+            UserSettings::default().cycle_modifier()
+        );
+
+        assert_eq!(options.action_for_key(5), Action::Left);
+        assert_eq!(options.action_for_key(260), Action::Right);
+        assert_eq!(options.action_for_key(259), Action::Equipment);
+        // Power is keyboard 3; mouse button 3 is a different input and unbound.
+        assert_eq!(options.action_for_key(258), Action::None);
+    }
+
+    #[test]
+    fn gamepad_bindings_sit_alongside_keys() {
+        let mut settings = UserSettings::default();
+        // Gamepad A is bound as the left cycle key in an older file, and as the
+        // right cycle's gamepad button.
+        settings
+            .read_from_str(
+                "[Controls]\nuLeftCycleKey = 276\niRightCycleGamepad = gamepad:10\n\
+                 iPowerCycleGamepad = 3\niUtilityCycleGamepad = 277\n",
+            )
+            .expect("settings text parses");
+        assert_eq!(settings.right_gamepad(), 276);
+        assert_eq!(settings.action_for_key(276), Action::Right);
+        assert_eq!(settings.action_for_key(277), Action::Utility);
+        // The keyboard key still works.
+        assert_eq!(settings.action_for_key(7), Action::Right);
+        assert_eq!(settings.action_for_key(6), Action::Utility);
+
+        // A keyboard key can't be a gamepad binding.
+        assert_eq!(settings.power_gamepad(), -1);
+        assert_eq!(settings.action_for_key(3), Action::Power);
+        assert_eq!(settings.gamepad_action_for(3), None);
+        assert_eq!(settings.gamepad_hotkey_for(HudElement::Power), 3);
+        assert_eq!(settings.gamepad_hotkey_for(HudElement::Utility), 277);
+    }
+
+    #[test]
+    fn the_key_map_knows_every_key_that_matters() {
+        let mut settings = UserSettings::default();
+        settings
+            .read_from_str(
+                "[Controls]\nuLeftCycleKey = 276\niRightCycleGamepad = gamepad:10\n\
+                 iUtilityActivateModifier = 42\niHotbarKey1 = 79\niBothHandsModifierKey = 56\n",
+            )
+            .expect("settings text parses");
+        let keys = settings.key_map();
+        for key in [3, 4, 6, 7, 276, 42, 56, 79, 200, 277] {
+            assert_eq!(keys.action_for(key), settings.action_for_key(key), "{key}");
+            assert!(keys.watches(key), "{key}");
+        }
+        assert_eq!(keys.action_for(276), Action::Right);
+        // Modifiers and hotbar keys aren't actions, but we still want them.
+        assert_eq!(keys.action_for(42), Action::None);
+        assert!(!keys.watches(5));
+        assert!(!keys.watches(19));
+        assert!(!keys.watches(u32::MAX));
+    }
+
+    #[test]
+    #[ignore]
+    fn key_map_is_cheaper_than_a_settings_copy() {
+        // Timing, not correctness. Run it in release with --ignored --nocapture.
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        const ROUNDS: u32 = 100_000;
+        UserSettings::refresh_with("tests/fixtures/test-settings.ini")
+            .expect("the test settings fixture reads");
+        // A bound key, a modifier, and keys nobody binds, which are most of them.
+        let keys = [4, 42, 19, 30, 57, 265];
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for key in keys {
+                black_box(settings().action_for_key(black_box(key)));
+            }
+        }
+        let cloned = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for key in keys {
+                black_box(key_action(black_box(key)));
+            }
+        }
+        let mapped = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for key in keys {
+                black_box(key_is_watched(black_box(key)));
+            }
+        }
+        let watched = start.elapsed();
+
+        let per_key = |spent: std::time::Duration| {
+            spent.as_nanos() as f64 / (f64::from(ROUNDS) * keys.len() as f64)
+        };
+        eprintln!(
+            "settings copy: {:.1} ns/key; key_action: {:.1} ns/key; key_is_watched: {:.1} ns/key",
+            per_key(cloned),
+            per_key(mapped),
+            per_key(watched)
+        );
+        assert!(mapped < cloned);
+        assert!(watched < cloned);
+    }
+
+    #[test]
+    fn autofade_modes_decide_when_to_fade() {
+        let mut settings = UserSettings::default();
+        assert!(settings.should_fade_out(false, false));
+        assert!(!settings.should_fade_out(false, true));
+        assert!(!settings.should_fade_out(true, false));
+
+        settings
+            .read_from_str("[Options]\nuAutoFadeWhen = 1\nuFadeDelay = 3000\n")
+            .expect("settings text parses");
+        assert!(settings.should_fade_out(false, true));
+        assert!(!settings.should_fade_out(true, true));
+        assert_eq!(settings.fade_delay(), 3000);
+
+        settings
+            .read_from_str("[Options]\nuAutoFadeWhen = 2\nbAutoFade = 0\n")
+            .expect("settings text parses");
+        assert!(!settings.should_fade_out(false, false));
+    }
+
+    #[test]
+    fn waking_the_hud_cycles_too_by_default() {
+        let mut settings = UserSettings::default();
+        assert!(!settings.fade_wakeup_only());
+        settings
+            .read_from_str("[Options]\nbFadeWakeupOnly = 1\n")
+            .expect("settings text parses");
+        assert!(settings.fade_wakeup_only());
+    }
+
+    #[test]
+    fn torches_stay_out_unless_asked() {
+        let mut settings = UserSettings::default();
+        assert!(!settings.relight_torches());
+        settings
+            .read_from_str("[Options]\nbRelightTorches = 1\n")
+            .expect("settings text parses");
+        assert!(settings.relight_torches());
+    }
+
+    #[test]
+    fn ammo_can_have_a_key_of_its_own() {
+        let mut settings = UserSettings::default();
+        assert_eq!(settings.ammo_hotkey(), -1);
+        assert_eq!(settings.hotkey_for(HudElement::Ammo), settings.left());
+
+        settings
+            .read_from_str("[Controls]\niAmmoCycleKey = 45\n")
+            .expect("settings text parses");
+        assert_eq!(settings.action_for_key(45), Action::Ammo);
+        assert_eq!(settings.hotkey_for(HudElement::Ammo), 45);
+        assert_eq!(settings.action_for_key(settings.left()), Action::Left);
+    }
+
+    #[test]
+    fn an_action_can_have_several_keys() {
+        let mut settings = UserSettings::default();
+        settings
+            .read_from_str("[Controls]\nuRightCycleKey = 19, mouse:4\nuPowerCycleKey = 11, 19\n")
+            .expect("settings text parses");
+        let mouse = KeyCode::parse("mouse:4")
+            .expect("mouse buttons parse")
+            .raw();
+        assert_eq!(settings.right(), 19);
+        assert_eq!(settings.action_for_key(19), Action::Right);
+        assert_eq!(settings.action_for_key(mouse), Action::Right);
+        // Power asked for 19 too, but the right hand got there first.
+        assert_eq!(settings.action_for_key(11), Action::Power);
+
+        // Rereading a binding replaces its extra keys.
+        settings
+            .read_from_str("[Controls]\nuRightCycleKey = 19\n")
+            .expect("settings text parses");
+        assert_eq!(settings.action_for_key(mouse), Action::None);
+    }
+
+    #[test]
+    fn hotbar_keys_are_optional() {
+        let defaults = UserSettings::default();
+        assert!((0..300).all(|key| defaults.hotbar_slot_for(key).is_none()));
+        assert_eq!(defaults.hotbar_cycle(), CycleSlot::Right);
+
+        let options = UserSettings::new_from_file("./tests/fixtures/test-settings.ini");
+        assert_eq!(options.hotbar_slot_for(79), Some(0));
+        assert_eq!(options.hotbar_slot_for(81), Some(2));
+        assert_eq!(options.hotbar_slot_for(80), None);
+        assert_eq!(options.hotbar_key(1), -1);
+        assert_eq!(options.hotbar_key(12), -1);
+        assert_eq!(options.hotbar_cycle(), CycleSlot::Utility);
+    }
+
+    #[test]
+    fn equip_delays_per_slot() {
+        let fpath = std::env::temp_dir().join("SoulsyHUD_equip_delays.ini");
+        let fpath = fpath.to_string_lossy();
+        std::fs::copy("./tests/fixtures/test-settings.ini", fpath.as_ref())
+            .expect("temp dir is writable");
+
+        let before = UserSettings::new_from_file(&fpath);
+        let shared = before.equip_delay_ms();
+        assert_eq!(before.equip_delay_for(&Action::Left), shared);
+        assert_eq!(
+            before.timer_duration_for(&Action::LongPressLeft),
+            before.long_press_ms()
+        );
+
+        UserSettings::write_equip_delay(&fpath, 2, 300).expect("the delay can be written");
+        UserSettings::write_equip_delay(&fpath, -1, 5000).expect("the delay can be written");
+        assert!(UserSettings::write_equip_delay(&fpath, 7, 300).is_err());
+
+        let after = UserSettings::new_from_file(&fpath);
+        assert_eq!(after.equip_delay_for(&Action::Left), 300);
+        assert_eq!(after.equip_delay_for(&Action::Right), 2500);
+        assert_eq!(after.timer_duration_for(&Action::Left), 300);
+        // unrelated settings survive the write
+        assert_eq!(after.hotbar_slot_for(79), Some(0));
+        assert!(!Path::new(&format!("{fpath}.tmp")).exists());
+        std::fs::remove_file(fpath.as_ref()).ok();
+    }
+
+    #[test]
+    fn a_broken_file_falls_back_to_the_last_good_one() {
+        let fpath = std::env::temp_dir().join("SoulsyHUD_broken.ini");
+        let fpath = fpath.to_string_lossy();
+        std::fs::write(fpath.as_ref(), "[Options]\nuEquipDelay = 1234\n")
+            .expect("temp dir is writable");
+        let mut good = UserSettings::default();
+        good.read_with_backup(&fpath)
+            .expect("the file reads cleanly");
+        assert_eq!(good.equip_delay_ms(), 1234);
+
+        // The game crashed halfway through writing it.
+        std::fs::write(fpath.as_ref(), "[Options\nuEquipDel").expect("temp dir is writable");
+        let mut recovered = UserSettings::default();
+        recovered
+            .read_with_backup(&fpath)
+            .expect("the backup reads cleanly");
+        assert_eq!(recovered.equip_delay_ms(), 1234);
+
+        // With no backup, the error gets through.
+        std::fs::remove_file(format!("{fpath}.bak")).expect("the backup was written");
+        assert!(UserSettings::default().read_with_backup(&fpath).is_err());
+        std::fs::remove_file(fpath.as_ref()).ok();
+    }
+
+    #[test]
+    fn press_timings_per_slot() {
+        // An older settings file knows only the shared long-press time.
+        let mut settings = UserSettings::default();
+        settings
+            .read_from_str("[Options]\nuEquipDelay = 500\nuLongPressMillis = 900\n")
+            .expect("settings text parses");
+        for action in SLOT_ACTIONS.iter() {
+            let timings = settings.timings_for(action);
+            assert_eq!(timings.hold_ms, 900);
+            assert_eq!(timings.repeat_ms, 0);
+        }
+
+        settings
+            .read_from_str(
+                "[Options]\nuEquipDelay = 500\nuLongPressMillis = 900\nuRepeatMillis = 400\n\
+                 iLongPressUtility = 0\niRepeatUtility = 150\niRepeatPower = 0\n\
+                 iLongPressLeft = 200\niRepeatRight = 20\n",
+            )
+            .expect("settings text parses");
+        let utility = settings.timings_for(&Action::Utility);
+        assert_eq!((utility.hold_ms, utility.repeat_ms), (0, 150));
+        let power = settings.timings_for(&Action::LongPressPower);
+        assert_eq!((power.hold_ms, power.repeat_ms), (900, 0));
+        // A long press can't be shorter than the slot's equip delay.
+        assert_eq!(settings.timings_for(&Action::Left).hold_ms, 600);
+        assert_eq!(settings.timings_for(&Action::Right).repeat_ms, 100);
+        assert_eq!(settings.timer_duration_for(&Action::LongPressLeft), 600);
+    }
+
+    #[test]
+    fn long_press_can_equip_right_away() {
+        let mut settings = UserSettings::default();
+        assert!(!settings.long_press_equips_now());
+        assert!(!settings.should_start_long_press_timer(settings.utility()));
+
+        settings
+            .read_from_str("[Controls]\nbLongPressEquipsNow = 1\n")
+            .expect("settings text parses");
+        assert!(settings.long_press_equips_now());
+        assert!(settings.should_start_long_press_timer(settings.utility()));
+        assert!(settings.should_start_long_press_timer(settings.power()));
+    }
+
+    #[test]
+    fn cycle_length_cap_is_kept_sensible() {
+        let mut settings = UserSettings::default();
+        assert_eq!(settings.max_cycle_length(), 20);
+        settings
+            .read_from_str("[Options]\nuMaxCycleLength = 8\n")
+            .expect("settings text parses");
+        assert_eq!(settings.max_cycle_length(), 8);
+        settings
+            .read_from_str("[Options]\nuMaxCycleLength = 0\n")
+            .expect("settings text parses");
+        assert_eq!(settings.max_cycle_length(), 2);
+    }
+
+    #[test]
+    fn unarmed_joins_the_hands_the_player_picks() {
+        let mut settings = UserSettings::default();
+        assert!(!settings.unarmed_in_cycle(&CycleSlot::Left));
+        settings
+            .read_from_str("[Controls]\nuHowToUnequip = 3\nbUnarmedInRightCycle = 0\n")
+            .expect("settings text parses");
+        assert!(settings.unarmed_in_cycle(&CycleSlot::Left));
+        assert!(!settings.unarmed_in_cycle(&CycleSlot::Right));
+        assert!(!settings.unarmed_in_cycle(&CycleSlot::Utility));
+    }
+
+    #[test]
+    fn skse_identifiers_of_any_length() {
+        let mut settings = UserSettings::default();
+        assert_eq!(settings.skse_identifier(), u32::from_le_bytes(*b"SOLS"));
+        settings.skse_identifier = "SOULSY".to_string();
+        assert_eq!(settings.skse_identifier(), u32::from_le_bytes(*b"SOUL"));
+        settings.skse_identifier = "HD".to_string();
+        assert_eq!(settings.skse_identifier(), u32::from_le_bytes(*b"HD  "));
+        settings.skse_identifier = "ÿÿ".to_string();
+        assert_eq!(settings.skse_identifier(), u32::from_le_bytes(*b"SOLS"));
+        settings.skse_identifier = String::new();
+        assert_eq!(settings.skse_identifier(), u32::from_le_bytes(*b"SOLS"));
+    }
+
+    #[test]
+    fn fuzzed_settings_are_survivable() {
+        let seeds = fuzzing::seeds(&["./tests/fixtures", "./tests/fixtures/fuzz"], "ini");
+        assert!(!seeds.is_empty(), "the settings seed corpus is missing");
+
+        let mut rng = fuzzing::rng(0x5e771265);
+        for (path, seed) in seeds.iter() {
+            for _ in 0..fuzzing::iterations() {
+                let input = fuzzing::mutate(seed, &mut rng);
+                let mut settings = UserSettings::default();
+                if settings.read_from_str(&input).is_err() {
+                    continue;
+                }
+                settings.skse_identifier();
+                assert!(
+                    (0.0..=1.0).contains(&settings.max_alpha()),
+                    "{path}: {input:?}"
+                );
+                assert!(
+                    (0.0..=1.0).contains(&settings.min_alpha()),
+                    "{path}: {input:?}"
+                );
+                assert!(settings.slow_time_factor() > 0.0, "{path}: {input:?}");
+                assert!(settings.long_press_ms() >= settings.equip_delay_ms());
+
+                // Reading the same text again changes nothing.
+                let once = settings.to_string();
+                settings
+                    .read_from_str(&input)
+                    .expect("this text parsed a moment ago");
+                assert_eq!(once, settings.to_string(), "{path}: {input:?}");
+            }
+        }
+
+        let oversized = "; padding\n".repeat(MAX_SETTINGS_BYTES / 10 + 1);
+        assert!(UserSettings::default().read_from_str(&oversized).is_err());
+    }
+
+    #[test]
+    fn settings_survive_being_written_back() {
+        let seeds = fuzzing::seeds(&["./tests/fixtures", "./tests/fixtures/fuzz"], "ini");
+        for (path, seed) in seeds.iter() {
+            let Ok(conf) = Ini::load_from_str(seed) else {
+                continue;
+            };
+            let mut original = UserSettings::default();
+            original.read_from_conf(&conf);
+
+            let mut buf = Vec::new();
+            conf.write_to_policy(&mut buf, EscapePolicy::Reserved)
+                .expect("writing to memory works");
+            let written = String::from_utf8(buf).expect("the ini writer writes utf-8");
+            let mut reread = UserSettings::default();
+            reread
+                .read_from_str(&written)
+                .expect("what we write, we can read");
+            assert_eq!(original.to_string(), reread.to_string(), "{path}");
+        }
+    }
 }
@@ -0,0 +1,119 @@
+//! The end of a session. When the game exits, the C++ side calls `shutdown()`
+//! once on its way out. From then on the controller takes no more work and
+//! answers every question with the default, so a late event can't act on state
+//! we've already let go of. Anything we still hold for disk is flushed right
+//! there on the calling thread. We get called from process teardown, under the
+//! loader lock, where starting a thread and waiting on it only stalls.
+//!
+//! Shutting down twice is harmless, and so is shutting down before the HUD
+//! was ever set up.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::trace::flush_trace;
+use crate::control;
+
+static LIFECYCLE: Lifecycle = Lifecycle::new();
+
+/// Whether we're still taking events.
+#[derive(Debug, Default)]
+pub struct Lifecycle {
+    stopped: AtomicBool,
+}
+
+impl Lifecycle {
+    pub const fn new() -> Self {
+        Self {
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        !self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Stop taking events, then run `flush`. Returns false if we'd already
+    /// stopped, in which case nothing is flushed again.
+    pub fn shut_down<F>(&self, flush: F) -> bool
+    where
+        F: FnOnce(),
+    {
+        if self.stopped.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        flush();
+        true
+    }
+}
+
+/// True until `shutdown()` is called. The controller's dispatch path checks this.
+pub fn accepting_events() -> bool {
+    LIFECYCLE.running()
+}
+
+/// Stop taking events, flush what we have, and say goodbye in the log.
+pub fn shutdown() {
+    let flush = || {
+        flush_trace();
+        let summary = control::shutdown_summary();
+        log::info!("SoulsyHUD shutting down; {summary}.");
+        log::logger().flush();
+    };
+    if !LIFECYCLE.shut_down(flush) {
+        log::debug!("Shutdown requested again; nothing more to do.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{BufWriter, Write};
+
+    use super::*;
+    use crate::controller::facade::entry_to_show_in_slot;
+    use crate::plugin::HudElement;
+
+    #[test]
+    fn buffered_writes_land_before_we_stop() {
+        let path = std::env::temp_dir().join("SoulsyHUD_shutdown_flush.txt");
+        let mut writer = BufWriter::new(fs::File::create(&path).expect("temp dir is writable"));
+        writer
+            .write_all(b"dirty cycle data")
+            .expect("buffered write");
+        assert_eq!(fs::read(&path).expect("file exists").len(), 0);
+
+        let lifecycle = Lifecycle::new();
+        let flushed = lifecycle.shut_down(|| {
+            writer.flush().expect("flush to disk");
+        });
+        // The flush is done by the time shutdown returns; no waiting on a thread.
+        assert!(flushed);
+        assert_eq!(
+            fs::read_to_string(&path).expect("file exists"),
+            "dirty cycle data"
+        );
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn stopping_twice_is_harmless() {
+        let lifecycle = Lifecycle::new();
+        assert!(lifecycle.running());
+        assert!(lifecycle.shut_down(|| {}));
+        assert!(!lifecycle.running());
+        // The second call doesn't flush again.
+        assert!(!lifecycle.shut_down(|| panic!("flushed twice")));
+        assert!(!lifecycle.running());
+    }
+
+    #[test]
+    fn events_after_shutdown_do_nothing() {
+        shutdown();
+        shutdown();
+        assert!(!accepting_events());
+        assert!(entry_to_show_in_slot(HudElement::Right).name().is_empty());
+        // The controller's own entry points turn work away, whoever calls them.
+        assert_eq!(control::query(|_| 7), 0);
+        assert!(control::peek(|_| ()).is_none());
+    }
+}
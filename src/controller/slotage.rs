@@ -0,0 +1,122 @@
+//! How long ago each slot's content changed, for layouts that want to pop or
+//! flash a slot when something new lands in it. Only the game's equip events
+//! count: the HUD shows the next item as soon as the player presses a key, but
+//! the slot hasn't really changed until the game says the equip happened.
+//!
+//! While a save loads, the game replays the restored character's equipment.
+//! Those confirmations are recorded without a timestamp, so nothing animates
+//! just because the player loaded a game.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::plugin::HudElement;
+
+/// The age we report for slots that haven't changed since the save loaded.
+pub const NEVER_CHANGED: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+struct Confirmed {
+    form_spec: String,
+    when: Option<Instant>,
+}
+
+/// The confirmed content of each slot, and when it last changed.
+#[derive(Debug, Clone, Default)]
+pub struct SlotAges {
+    confirmed: HashMap<HudElement, Confirmed>,
+    settling: bool,
+}
+
+impl SlotAges {
+    /// A save is loading. Forget everything; confirmations that arrive before
+    /// `settled()` set up what's in each slot without counting as changes.
+    pub fn begin_settling(&mut self) {
+        self.confirmed.clear();
+        self.settling = true;
+    }
+
+    /// Loading is done. From now on, confirmations that change a slot count.
+    pub fn settled(&mut self) {
+        self.settling = false;
+    }
+
+    /// The game confirmed that this slot now holds this item; an empty spec
+    /// means the slot is empty. Returns true if that's a change we timed.
+    pub fn confirm(&mut self, slot: HudElement, form_spec: &str, now: Instant) -> bool {
+        let unchanged = self
+            .confirmed
+            .get(&slot)
+            .is_some_and(|xs| xs.form_spec == form_spec);
+        if unchanged {
+            return false;
+        }
+        let when = if self.settling { None } else { Some(now) };
+        self.confirmed.insert(
+            slot,
+            Confirmed {
+                form_spec: form_spec.to_string(),
+                when,
+            },
+        );
+        when.is_some()
+    }
+
+    /// Milliseconds since this slot's content last changed, or `NEVER_CHANGED`.
+    pub fn age_millis(&self, slot: &HudElement, now: Instant) -> u32 {
+        let Some(when) = self.confirmed.get(slot).and_then(|xs| xs.when) else {
+            return NEVER_CHANGED;
+        };
+        let millis = now.saturating_duration_since(when).as_millis();
+        u32::try_from(millis).unwrap_or(NEVER_CHANGED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn only_confirmed_changes_reset_the_age() {
+        let start = Instant::now();
+        let mut ages = SlotAges::default();
+        assert_eq!(ages.age_millis(&HudElement::Right, start), NEVER_CHANGED);
+
+        assert!(ages.confirm(HudElement::Right, "Skyrim.esm|0x12eb7", start));
+        let later = start + Duration::from_millis(250);
+        assert_eq!(ages.age_millis(&HudElement::Right, later), 250);
+
+        // The game confirming the same item again isn't a change.
+        assert!(!ages.confirm(HudElement::Right, "Skyrim.esm|0x12eb7", later));
+        assert_eq!(ages.age_millis(&HudElement::Right, later), 250);
+        // Nor does it touch the other hand.
+        assert_eq!(ages.age_millis(&HudElement::Left, later), NEVER_CHANGED);
+
+        // Emptying the hand is a change.
+        assert!(ages.confirm(HudElement::Right, "", later));
+        assert_eq!(ages.age_millis(&HudElement::Right, later), 0);
+    }
+
+    #[test]
+    fn loading_a_save_animates_nothing() {
+        let start = Instant::now();
+        let mut ages = SlotAges::default();
+        assert!(ages.confirm(HudElement::Left, "Skyrim.esm|0x1397e", start));
+
+        ages.begin_settling();
+        assert_eq!(ages.age_millis(&HudElement::Left, start), NEVER_CHANGED);
+        assert!(!ages.confirm(HudElement::Left, "Skyrim.esm|0x12eb6", start));
+        assert!(!ages.confirm(HudElement::Power, "Skyrim.esm|0x13e09", start));
+        ages.settled();
+
+        let later = start + Duration::from_secs(3);
+        assert_eq!(ages.age_millis(&HudElement::Left, later), NEVER_CHANGED);
+        assert_eq!(ages.age_millis(&HudElement::Power, later), NEVER_CHANGED);
+        // The restored item is what later equips are compared against.
+        assert!(!ages.confirm(HudElement::Left, "Skyrim.esm|0x12eb6", later));
+        assert!(ages.confirm(HudElement::Left, "Skyrim.esm|0x1397e", later));
+        assert_eq!(ages.age_millis(&HudElement::Left, later), 0);
+    }
+}
@@ -16,17 +16,63 @@ pub fn string_to_utf8(bytes_ffi: &CxxVector<u8>) -> String {
 /// Use this for null-terminated C strings.
 pub fn cstr_to_utf8(bytes_ffi: &CxxVector<u8>) -> String {
     let bytes: Vec<u8> = bytes_ffi.iter().copied().collect();
-    let bytes = if bytes.ends_with(&[0]) {
-        let chopped = bytes.len() - 1;
-        let mut tmp = bytes.clone();
-        tmp.truncate(chopped);
-        tmp
-    } else {
-        bytes
+    convert_to_utf8(chop_nul(bytes))
+}
+
+/// Use this for null-terminated C strings from the game when you know which
+/// Windows codepage they're in. C++ passes the process's ANSI codepage.
+pub fn cstr_to_utf8_with_codepage(bytes_ffi: &CxxVector<u8>, codepage: u32) -> String {
+    let bytes: Vec<u8> = bytes_ffi.iter().copied().collect();
+    decode_with_codepage(chop_nul(bytes), codepage)
+}
+
+fn chop_nul(mut bytes: Vec<u8>) -> Vec<u8> {
+    if bytes.ends_with(&[0]) {
+        bytes.truncate(bytes.len() - 1);
+    }
+    bytes
+}
+
+/// Decode game text using the codepage hint. Text that's already utf-8 is
+/// left alone, so decoding our own output again changes nothing. If the hint
+/// is unknown or the bytes don't fit it, we fall back to guessing.
+pub fn decode_with_codepage(bytes: Vec<u8>, codepage: u32) -> String {
+    let bytes = match String::from_utf8(bytes) {
+        Ok(valid) => return valid,
+        Err(e) => e.into_bytes(),
     };
+
+    if let Some(coder) = codepage_label(codepage).and_then(encoding_from_whatwg_label) {
+        if let Ok(decoded) = coder.decode(&bytes, DecoderTrap::Strict) {
+            return decoded;
+        }
+    }
+
     convert_to_utf8(bytes)
 }
 
+/// The encoding label for the Windows codepages Skyrim's languages use.
+fn codepage_label(codepage: u32) -> Option<&'static str> {
+    match codepage {
+        874 => Some("windows-874"),
+        932 => Some("shift_jis"),
+        936 => Some("gbk"),
+        949 => Some("euc-kr"),
+        950 => Some("big5"),
+        1250 => Some("windows-1250"),
+        1251 => Some("windows-1251"),
+        1252 => Some("windows-1252"),
+        1253 => Some("windows-1253"),
+        1254 => Some("windows-1254"),
+        1255 => Some("windows-1255"),
+        1256 => Some("windows-1256"),
+        1257 => Some("windows-1257"),
+        1258 => Some("windows-1258"),
+        65001 => Some("utf-8"),
+        _ => None,
+    }
+}
+
 /// Get a valid Rust representation of this Windows codepage string data by hook or by crook.
 pub fn convert_to_utf8(bytes: Vec<u8>) -> String {
     if bytes.is_empty() {
@@ -43,9 +89,170 @@ pub fn convert_to_utf8(bytes: Vec<u8>) -> String {
     String::from_utf8_lossy(&bytes).to_string()
 }
 
+/// Make a name safe to show the player and to hand to C++ every frame.
+/// Trims whitespace, drops control characters (embedded nulls included) and
+/// invisible direction marks, and clamps to `max_chars` characters, ending
+/// with an ellipsis if we had to cut. A `max_chars` of zero means no limit.
+pub fn sanitize_display_name(raw: &str, max_chars: usize) -> String {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| !c.is_control() && !is_direction_mark(*c))
+        .collect();
+    let trimmed = cleaned.trim();
+
+    if max_chars == 0 || trimmed.chars().count() <= max_chars {
+        return trimmed.to_string();
+    }
+
+    let keep = max_chars.saturating_sub(1);
+    let mut clamped: String = trimmed.chars().take(keep).collect();
+    clamped.truncate(clamped.trim_end().len());
+    clamped.push('…');
+    clamped
+}
+
+/// Unicode bidi formatting characters. They're invisible and can reorder the
+/// text around them, so they have no business in item names.
+fn is_direction_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200e}'
+            | '\u{200f}'
+            | '\u{061c}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{feff}'
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::controller::cycles::CycleData;
+    use crate::controller::keys::CycleSlot;
+    use crate::data::color::InvColor;
+    use crate::data::weapon::{WeaponEquipType, WeaponType};
+    use crate::data::{BaseType, HudItem};
+    use crate::images::icons::Icon;
+
+    const CP1251_NAME: &[u8] = include_bytes!("../../tests/fixtures/name-cp1251.txt");
+    const SHIFT_JIS_NAME: &[u8] = include_bytes!("../../tests/fixtures/name-shift-jis.txt");
+
+    #[test]
+    fn codepage_hints_decode_game_names() {
+        let russian = decode_with_codepage(CP1251_NAME.to_vec(), 1251);
+        assert_eq!(russian, "Железный меч");
+        let japanese = decode_with_codepage(SHIFT_JIS_NAME.to_vec(), 932);
+        assert_eq!(japanese, "鉄の剣");
+
+        // decoding our own output again is stable
+        assert_eq!(
+            decode_with_codepage(russian.clone().into_bytes(), 1251),
+            russian
+        );
+        assert_eq!(
+            decode_with_codepage(japanese.clone().into_bytes(), 932),
+            japanese
+        );
+
+        // ascii is the same in every codepage
+        assert_eq!(
+            decode_with_codepage(b"Glass Dagger".to_vec(), 1251),
+            "Glass Dagger"
+        );
+    }
+
+    #[test]
+    fn unhelpful_hints_still_give_valid_text() {
+        // an unknown codepage falls back to guessing
+        let guessed = decode_with_codepage(CP1251_NAME.to_vec(), 12345);
+        assert!(!guessed.is_empty());
+        // a wrong hint can't produce invalid utf-8, just the wrong letters
+        let wrong = decode_with_codepage(SHIFT_JIS_NAME.to_vec(), 65001);
+        assert!(!wrong.is_empty());
+        assert_eq!(decode_with_codepage(Vec::new(), 1251), "");
+    }
+
+    #[test]
+    fn identity_ignores_names() {
+        let kind = BaseType::Weapon(WeaponType::new(
+            Icon::WeaponSwordOneHanded,
+            InvColor::default(),
+            WeaponEquipType::EitherHand,
+        ));
+        let spec = "Skyrim.esm|0x12eb7".to_string();
+        let decoded = HudItem::preclassified(
+            decode_with_codepage(CP1251_NAME.to_vec(), 1251),
+            spec.clone(),
+            1,
+            kind.clone(),
+        );
+        let garbled = HudItem::preclassified(
+            String::from_utf8_lossy(CP1251_NAME).to_string(),
+            spec,
+            1,
+            kind,
+        );
+        assert_ne!(decoded.name(), garbled.name());
+
+        let mut cycle = CycleData::default();
+        cycle.add_item(CycleSlot::Right, &decoded);
+        assert!(cycle.includes(&CycleSlot::Right, &garbled));
+    }
+
+    #[test]
+    fn names_are_sanitized() {
+        assert_eq!(sanitize_display_name("  Iron Sword \t\n", 80), "Iron Sword");
+        assert_eq!(sanitize_display_name("Iron\0 Sword\0", 80), "Iron Sword");
+        assert_eq!(
+            sanitize_display_name("\u{202e}drowS norI\u{200f}", 80),
+            "drowS norI"
+        );
+        assert_eq!(sanitize_display_name("\0\0\0", 80), "");
+        assert_eq!(
+            sanitize_display_name("Zażółć gęślą jaźń", 80),
+            "Zażółć gęślą jaźń"
+        );
+    }
+
+    #[test]
+    fn long_names_are_clamped() {
+        let huge = "Potion of ".repeat(1000);
+        let clamped = sanitize_display_name(&huge, 40);
+        assert_eq!(clamped.chars().count(), 40);
+        assert!(clamped.ends_with('…'));
+
+        // multi-byte characters are never split
+        let wide = "おはよう".repeat(2500);
+        let clamped = sanitize_display_name(&wide, 5);
+        assert_eq!(clamped, "おはよう…");
+
+        // zero means unlimited
+        assert_eq!(sanitize_display_name(&huge, 0).len(), huge.trim().len());
+        // an exact fit is left alone
+        assert_eq!(sanitize_display_name("Iron Sword", 10), "Iron Sword");
+    }
+
+    #[test]
+    fn adversarial_input_stays_bounded() {
+        let mut bytes: Vec<u8> = Vec::new();
+        for i in 0..10_000u32 {
+            bytes.push((i % 256) as u8);
+        }
+        let lossy = String::from_utf8_lossy(&bytes).to_string();
+        let clamped = sanitize_display_name(&lossy, 64);
+        assert!(clamped.chars().count() <= 64);
+        assert!(!clamped.contains('\0'));
+        assert!(!clamped.chars().any(|c| c.is_control()));
+
+        let converted = convert_to_utf8(bytes);
+        let clamped = sanitize_display_name(&converted, 64);
+        assert!(clamped.chars().count() <= 64);
+
+        assert_eq!(sanitize_display_name("", 64), "");
+        assert_eq!(sanitize_display_name("x", 1), "x");
+        assert_eq!(sanitize_display_name("xy", 1), "…");
+    }
 
     #[test]
     fn utf8_data_is_untouched() {
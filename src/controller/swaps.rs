@@ -0,0 +1,94 @@
+//! Following reusable items across form swaps. A waterskin from a survival
+//! mod is a full form until the player drinks from it, when the mod takes the
+//! full one away and hands over an empty one. The two inventory events arrive
+//! in either order, a moment apart. We pair a loss with an arrival from the
+//! same keyword family so the utility cycle can keep the new form where the
+//! old one was, instead of pruning it.
+
+use std::time::{Duration, Instant};
+
+/// How far apart a loss and an arrival can be and still count as one swap.
+pub const SWAP_WINDOW: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone)]
+struct Sighting {
+    family: String,
+    form_spec: String,
+    when: Instant,
+}
+
+/// Losses and arrivals still waiting for their other half.
+#[derive(Debug, Clone, Default)]
+pub struct SwapTracker {
+    gone: Vec<Sighting>,
+    arrived: Vec<Sighting>,
+}
+
+impl SwapTracker {
+    /// The player has none of this item left. Returns the form it turned into,
+    /// if one from its family just arrived.
+    pub fn lost(&mut self, family: &str, form_spec: &str, now: Instant) -> Option<String> {
+        Self::pair(&mut self.arrived, &mut self.gone, family, form_spec, now)
+    }
+
+    /// The player gained some of this item. Returns the form it replaced, if
+    /// one from its family was just lost.
+    pub fn found(&mut self, family: &str, form_spec: &str, now: Instant) -> Option<String> {
+        Self::pair(&mut self.gone, &mut self.arrived, family, form_spec, now)
+    }
+
+    fn pair(
+        partners: &mut Vec<Sighting>,
+        waiting: &mut Vec<Sighting>,
+        family: &str,
+        form_spec: &str,
+        now: Instant,
+    ) -> Option<String> {
+        partners.retain(|xs| now.duration_since(xs.when) < SWAP_WINDOW);
+        waiting.retain(|xs| now.duration_since(xs.when) < SWAP_WINDOW && xs.form_spec != form_spec);
+        if let Some(index) = partners
+            .iter()
+            .position(|xs| xs.family == family && xs.form_spec != form_spec)
+        {
+            return Some(partners.remove(index).form_spec);
+        }
+        waiting.push(Sighting {
+            family: family.to_string(),
+            form_spec: form_spec.to_string(),
+            when: now,
+        });
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_loss_and_an_arrival_pair_up_in_either_order() {
+        let now = Instant::now();
+        let mut swaps = SwapTracker::default();
+        let family = "WaterskinFull:WaterskinEmpty";
+
+        assert_eq!(swaps.lost(family, "iNeed.esp|0x801", now), None);
+        assert_eq!(swaps.found("_WL_Lantern", "lantern.esp|0x802", now), None);
+        assert_eq!(
+            swaps.found(family, "iNeed.esp|0x803", now),
+            Some("iNeed.esp|0x801".to_string())
+        );
+        // Each half pairs only once.
+        assert_eq!(swaps.found(family, "iNeed.esp|0x804", now), None);
+        assert_eq!(
+            swaps.lost(family, "iNeed.esp|0x803", now),
+            Some("iNeed.esp|0x804".to_string())
+        );
+
+        // Too far apart to be the same swap.
+        assert_eq!(swaps.lost(family, "iNeed.esp|0x801", now), None);
+        assert_eq!(
+            swaps.found(family, "iNeed.esp|0x803", now + SWAP_WINDOW),
+            None
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Equip delay timers. The renderer ticks these every frame; when one runs out
+//! we equip whatever the player cycled to in that slot.
+//!
+//! A timer remembers only how long it has been running, not how long it will
+//! run. The delay is looked up from settings on every tick, so a delay changed
+//! in the MCM applies even to a timer that's already going.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use super::settings::settings;
+use crate::plugin::Action;
+
+/// There can be only one. Not public because we want access managed.
+static TIMERS: Lazy<Mutex<EquipTimers>> = Lazy::new(|| Mutex::new(EquipTimers::default()));
+
+/// Start a timer for this slot, replacing any that's already running.
+pub fn timer_started(which: Action) {
+    let mut timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    timers.start(which);
+}
+
+/// Stop the timer for this slot, if there is one. Returns true if any other
+/// timers are still running.
+pub fn timer_stopped(which: Action) -> bool {
+    let mut timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    timers.stop(&which);
+    !timers.is_empty()
+}
+
+/// Is the timer for this slot running?
+pub fn timer_running(which: &Action) -> bool {
+    let timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    timers.is_running(which)
+}
+
+/// How much of this slot's equip delay is left, from 1.0 just after the player
+/// cycled down to 0.0 as it runs out. 0.0 if the slot has no timer running.
+pub fn timer_fraction_left(which: &Action) -> f32 {
+    let timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    timers.fraction_left(which, settings().timer_duration_for(which))
+}
+
+/// Stop every timer. Returns the slots whose timers were running.
+pub fn cancel_all_timers() -> Vec<Action> {
+    let mut timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    timers.clear()
+}
+
+/// The slots with a timer running, oldest first.
+pub fn running_timers() -> Vec<Action> {
+    let timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    timers.running()
+}
+
+/// Advance all timers by `delta` seconds, and act on any that ran out.
+/// Returns true if any timers are still running.
+pub fn advance_timers(delta: f32) -> bool {
+    let expired = {
+        let mut timers = TIMERS
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+        if timers.is_empty() {
+            return false;
+        }
+        let config = settings();
+        timers.advance(delta, |which| config.timer_duration_for(which))
+    };
+    // The lock is released before this point, because acting on an expired
+    // timer can stop other timers.
+    for which in expired {
+        super::facade::timer_expired(which);
+    }
+    let timers = TIMERS
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire timers lock.");
+    !timers.is_empty()
+}
+
+/// The running timers and how long each has been running, in seconds.
+#[derive(Debug, Clone, Default)]
+pub struct EquipTimers {
+    running: Vec<(Action, f32)>,
+}
+
+impl EquipTimers {
+    pub fn start(&mut self, which: Action) {
+        self.stop(&which);
+        self.running.push((which, 0.0));
+    }
+
+    pub fn stop(&mut self, which: &Action) {
+        self.running.retain(|(action, _)| action != which);
+    }
+
+    /// Stop every timer, returning the slots that had one running.
+    pub fn clear(&mut self) -> Vec<Action> {
+        self.running.drain(..).map(|(which, _)| which).collect()
+    }
+
+    pub fn running(&self) -> Vec<Action> {
+        self.running.iter().map(|(which, _)| *which).collect()
+    }
+
+    pub fn is_running(&self, which: &Action) -> bool {
+        self.running.iter().any(|(action, _)| action == which)
+    }
+
+    /// How much of a delay of `delay_ms` this slot's timer has left to run.
+    pub fn fraction_left(&self, which: &Action, delay_ms: u32) -> f32 {
+        let Some((_, elapsed)) = self.running.iter().find(|(action, _)| action == which) else {
+            return 0.0;
+        };
+        if delay_ms == 0 {
+            return 0.0;
+        }
+        (1.0 - *elapsed * 1000.0 / delay_ms as f32).clamp(0.0, 1.0)
+    }
+
+    /// Advance every timer and remove the ones that have run for longer than
+    /// their delay, as given in milliseconds by `delay_ms`. Returns those.
+    pub fn advance<F>(&mut self, delta: f32, delay_ms: F) -> Vec<Action>
+    where
+        F: Fn(&Action) -> u32,
+    {
+        let mut expired = Vec::new();
+        self.running.retain_mut(|(which, elapsed)| {
+            *elapsed += delta;
+            if *elapsed * 1000.0 > delay_ms(which) as f32 {
+                expired.push(*which);
+                false
+            } else {
+                true
+            }
+        });
+        expired
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn delay_changes_apply_to_running_timers() {
+        let delay = Cell::new(750);
+        let mut timers = EquipTimers::default();
+        timers.start(Action::Right);
+        timers.start(Action::Left);
+
+        assert!(timers.advance(0.5, |_| delay.get()).is_empty());
+
+        // The player shortens the delay in the MCM while the timers run.
+        delay.set(400);
+        let expired = timers.advance(0.0, |_| delay.get());
+        assert_eq!(expired, vec![Action::Right, Action::Left]);
+        assert!(timers.is_empty());
+
+        // And lengthening it keeps a timer going past the old delay.
+        timers.start(Action::Power);
+        delay.set(2000);
+        assert!(timers.advance(1.0, |_| delay.get()).is_empty());
+        assert_eq!(timers.advance(1.1, |_| delay.get()), vec![Action::Power]);
+    }
+
+    #[test]
+    fn restarting_a_timer_resets_it() {
+        let mut timers = EquipTimers::default();
+        timers.start(Action::Utility);
+        assert!(timers.advance(0.6, |_| 750).is_empty());
+        timers.start(Action::Utility);
+        assert!(timers.advance(0.6, |_| 750).is_empty());
+        assert_eq!(timers.advance(0.2, |_| 750), vec![Action::Utility]);
+        timers.start(Action::Utility);
+        timers.stop(&Action::Utility);
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn slots_keep_their_own_timers() {
+        let mut timers = EquipTimers::default();
+        timers.start(Action::Right);
+        assert!(timers.advance(0.5, |_| 750).is_empty());
+        // Tapping the left hand several times leaves the right hand's timer be.
+        timers.start(Action::Left);
+        timers.start(Action::Left);
+        timers.start(Action::Left);
+        assert!(timers.is_running(&Action::Right));
+        assert_eq!(timers.advance(0.3, |_| 750), vec![Action::Right]);
+        // And the left hand expires once, however often it was tapped.
+        assert_eq!(timers.advance(0.5, |_| 750), vec![Action::Left]);
+        assert!(timers.advance(5.0, |_| 750).is_empty());
+    }
+
+    #[test]
+    fn highlights_shrink_as_the_delay_runs() {
+        let mut timers = EquipTimers::default();
+        assert_eq!(timers.fraction_left(&Action::Power, 1000), 0.0);
+        timers.start(Action::Power);
+        assert_eq!(timers.fraction_left(&Action::Power, 1000), 1.0);
+        timers.advance(0.25, |_| 1000);
+        assert_eq!(timers.fraction_left(&Action::Power, 1000), 0.75);
+        // A delay shortened under a running timer can't go below empty.
+        assert_eq!(timers.fraction_left(&Action::Power, 200), 0.0);
+        assert_eq!(timers.fraction_left(&Action::Power, 0), 0.0);
+        // Stopping the timer early, as Activate does, ends the highlight.
+        timers.stop(&Action::Power);
+        assert_eq!(timers.fraction_left(&Action::Power, 1000), 0.0);
+    }
+}
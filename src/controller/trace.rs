@@ -0,0 +1,572 @@
+//! Input traces, for reproducing bugs we can't make happen on demand. With
+//! `bRecordInputTrace` on, every event the game sends us through the bridge is
+//! appended to `SoulsyHUD_trace.jsonl` next to the log, one compact JSON object
+//! per line, stamped with milliseconds since recording began. A trace holds
+//! nothing the debug log doesn't already: key codes, form specs, and counts.
+//!
+//! The file is bounded. Once it passes `ROTATE_BYTES` it's moved aside to
+//! `SoulsyHUD_trace.jsonl.1`, replacing any older one, and a fresh file begins.
+//!
+//! `replay()` reads a trace back and feeds its events, in order, to anything
+//! that implements `TraceTarget`, reporting that target's state after each
+//! one. The live controller needs the game to answer its questions, so a
+//! replay target stands in for the game as well as the HUD.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+
+use super::keys::KeyState;
+use crate::plugin::Action;
+
+/// A trace file longer than this is rotated.
+pub const ROTATE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// The actions we run timers for, so a trace can name them.
+const TIMER_ACTIONS: [Action; 8] = [
+    Action::Power,
+    Action::Utility,
+    Action::Left,
+    Action::Right,
+    Action::LongPressPower,
+    Action::LongPressUtility,
+    Action::LongPressLeft,
+    Action::LongPressRight,
+];
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Where traces go, once the log has told us where it lives.
+static TRACE_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+static RECORDER: Lazy<Mutex<Option<TraceRecorder>>> = Lazy::new(|| Mutex::new(None));
+
+/// One event the game sent us.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A gameplay key event.
+    Key {
+        key: u32,
+        state: KeyState,
+        sequence: u64,
+    },
+    /// A key event while a menu was open.
+    MenuKey {
+        key: u32,
+        state: KeyState,
+        sequence: u64,
+    },
+    /// The player toggled an item in or out of a cycle from a menu.
+    Toggle { key: u32, form_spec: String },
+    /// The game equipped or unequipped something.
+    Equipped {
+        equipped: bool,
+        form_spec: String,
+        right: String,
+        left: String,
+    },
+    /// An item's count in the player's inventory changed.
+    Inventory { form_spec: String, count: u32 },
+    /// One of our timers ran out.
+    Timer { action: Action },
+    /// A save finished loading.
+    LoadComplete,
+}
+
+impl TraceEvent {
+    /// The event as one line of JSON, without the newline.
+    pub fn to_line(&self, millis: u128) -> String {
+        let body = match self {
+            TraceEvent::Key {
+                key,
+                state,
+                sequence,
+            } => format!(r#""ev":"key","key":{key},"state":"{state}","seq":{sequence}"#),
+            TraceEvent::MenuKey {
+                key,
+                state,
+                sequence,
+            } => format!(r#""ev":"menu_key","key":{key},"state":"{state}","seq":{sequence}"#),
+            TraceEvent::Toggle { key, form_spec } => {
+                format!(r#""ev":"toggle","key":{key},"spec":{}"#, quote(form_spec))
+            }
+            TraceEvent::Equipped {
+                equipped,
+                form_spec,
+                right,
+                left,
+            } => format!(
+                r#""ev":"equipped","equipped":{equipped},"spec":{},"right":{},"left":{}"#,
+                quote(form_spec),
+                quote(right),
+                quote(left)
+            ),
+            TraceEvent::Inventory { form_spec, count } => {
+                format!(
+                    r#""ev":"inventory","spec":{},"count":{count}"#,
+                    quote(form_spec)
+                )
+            }
+            TraceEvent::Timer { action } => format!(r#""ev":"timer","action":"{action:?}""#),
+            TraceEvent::LoadComplete => r#""ev":"load_complete""#.to_string(),
+        };
+        format!(r#"{{"ms":{millis},{body}}}"#)
+    }
+
+    /// Read back a line written by `to_line()`, with its timestamp.
+    pub fn from_line(line: &str) -> Option<(u64, TraceEvent)> {
+        let fields = parse_object(line)?;
+        let text = |name: &str| fields.get(name).cloned();
+        let number = |name: &str| fields.get(name).and_then(|v| v.parse::<u64>().ok());
+        let key = || number("key").and_then(|v| u32::try_from(v).ok());
+        let state = || match fields.get("state").map(String::as_str) {
+            Some("Up") => Some(KeyState::Up),
+            Some("Pressed") => Some(KeyState::Pressed),
+            Some("Down") => Some(KeyState::Down),
+            _ => None,
+        };
+
+        let event = match fields.get("ev")?.as_str() {
+            "key" => TraceEvent::Key {
+                key: key()?,
+                state: state()?,
+                sequence: number("seq")?,
+            },
+            "menu_key" => TraceEvent::MenuKey {
+                key: key()?,
+                state: state()?,
+                sequence: number("seq")?,
+            },
+            "toggle" => TraceEvent::Toggle {
+                key: key()?,
+                form_spec: text("spec")?,
+            },
+            "equipped" => TraceEvent::Equipped {
+                equipped: text("equipped")? == "true",
+                form_spec: text("spec")?,
+                right: text("right")?,
+                left: text("left")?,
+            },
+            "inventory" => TraceEvent::Inventory {
+                form_spec: text("spec")?,
+                count: u32::try_from(number("count")?).ok()?,
+            },
+            "timer" => {
+                let name = text("action")?;
+                let action = TIMER_ACTIONS
+                    .iter()
+                    .find(|action| format!("{action:?}") == name)?;
+                TraceEvent::Timer { action: *action }
+            }
+            "load_complete" => TraceEvent::LoadComplete,
+            _ => return None,
+        };
+        Some((number("ms")?, event))
+    }
+}
+
+/// Appends events to a trace file, rotating it when it grows too long.
+#[derive(Debug)]
+pub struct TraceRecorder {
+    path: PathBuf,
+    limit: u64,
+    out: Option<BufWriter<File>>,
+    written: u64,
+    start: Instant,
+}
+
+impl TraceRecorder {
+    pub fn new(path: &Path, limit: u64) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            limit,
+            out: None,
+            written: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// The file rotated traces are moved to.
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(".1");
+        PathBuf::from(name)
+    }
+
+    pub fn record(&mut self, event: &TraceEvent) -> std::io::Result<()> {
+        let mut line = event.to_line(self.start.elapsed().as_millis());
+        line.push('\n');
+        let len = line.len() as u64;
+        if self.written > 0 && self.written + len > self.limit {
+            self.rotate()?;
+        }
+        let out = match self.out.as_mut() {
+            Some(out) => out,
+            None => self.out.insert(BufWriter::new(File::create(&self.path)?)),
+        };
+        out.write_all(line.as_bytes())?;
+        self.written += len;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        match self.out.as_mut() {
+            Some(out) => out.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some(mut out) = self.out.take() {
+            out.flush()?;
+        }
+        std::fs::rename(&self.path, self.rotated_path())?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// The logger calls this with the log's path; traces are written beside it.
+pub fn set_trace_directory(log_path: &Path) {
+    *TRACE_PATH
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire trace path lock.") =
+        Some(log_path.with_file_name("SoulsyHUD_trace.jsonl"));
+}
+
+/// Start or stop recording, following the player's setting.
+pub fn set_recording(enabled: bool) {
+    let was = RECORDING.swap(enabled, Ordering::SeqCst);
+    if was == enabled {
+        return;
+    }
+    let mut recorder = RECORDER
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire trace recorder lock.");
+    if enabled {
+        let path = TRACE_PATH
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire trace path lock.")
+            .clone();
+        if let Some(path) = path {
+            log::info!("Recording an input trace; path='{}';", path.display());
+            *recorder = Some(TraceRecorder::new(&path, ROTATE_BYTES));
+        }
+    } else if let Some(mut stopped) = recorder.take() {
+        stopped.flush().ok();
+        log::info!("Stopped recording the input trace.");
+    }
+}
+
+/// Record an event if we're recording. The event is built only if it's needed.
+pub fn record<F>(event: F)
+where
+    F: FnOnce() -> TraceEvent,
+{
+    if !RECORDING.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut recorder = RECORDER
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire trace recorder lock.");
+    if let Some(recorder) = recorder.as_mut() {
+        if let Err(e) = recorder.record(&event()) {
+            log::warn!("Failed to write to the input trace; error={e:#}");
+        }
+    }
+}
+
+/// Write out whatever the trace is holding.
+pub fn flush_trace() {
+    if let Some(recorder) = RECORDER
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire trace recorder lock.")
+        .as_mut()
+    {
+        recorder.flush().ok();
+    }
+}
+
+/// Something a trace can be replayed into.
+pub trait TraceTarget {
+    fn apply(&mut self, event: &TraceEvent);
+    /// A summary of the target's state, to compare between runs.
+    fn describe(&self) -> String;
+}
+
+/// One replayed event and where it left the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayStep {
+    pub line: usize,
+    pub millis: u64,
+    pub event: TraceEvent,
+    pub state: String,
+}
+
+/// Feed a trace to the target, one event at a time. Lines we can't read are
+/// skipped with a note in the log, so a trace cut off mid-line still replays.
+pub fn replay<T: TraceTarget>(trace: &str, target: &mut T) -> Vec<ReplayStep> {
+    let mut steps = Vec::new();
+    for (idx, line) in trace.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((millis, event)) = TraceEvent::from_line(line) else {
+            log::warn!("Skipping unreadable trace line; line={};", idx + 1);
+            continue;
+        };
+        target.apply(&event);
+        steps.push(ReplayStep {
+            line: idx + 1,
+            millis,
+            event,
+            state: target.describe(),
+        });
+    }
+    steps
+}
+
+/// Quote a string for JSON.
+fn quote(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + 2);
+    out.push('"');
+    for c in input.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Parse one flat JSON object of the kind `to_line()` writes: string, number,
+/// and boolean values only. Values come back as their text, unquoted.
+fn parse_object(line: &str) -> Option<HashMap<String, String>> {
+    let mut chars = line.trim().chars().peekable();
+    let mut fields = HashMap::new();
+    if chars.next()? != '{' {
+        return None;
+    }
+    loop {
+        match chars.next()? {
+            '"' => {}
+            '}' if fields.is_empty() => return Some(fields),
+            _ => return None,
+        }
+        let name = parse_string(&mut chars)?;
+        if chars.next()? != ':' {
+            return None;
+        }
+        let value = if chars.peek() == Some(&'"') {
+            chars.next();
+            parse_string(&mut chars)?
+        } else {
+            let mut raw = String::new();
+            while let Some(c) = chars.next_if(|c| *c != ',' && *c != '}') {
+                raw.push(c);
+            }
+            raw
+        };
+        fields.insert(name, value);
+        match chars.next()? {
+            ',' => continue,
+            '}' => return Some(fields),
+            _ => return None,
+        }
+    }
+}
+
+/// Read the rest of a JSON string whose opening quote is already consumed.
+fn parse_string(chars: &mut impl Iterator<Item = char>) -> Option<String> {
+    let mut out = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(out),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'u' => {
+                    let hex: String = chars.take(4).collect();
+                    out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                c => out.push(c),
+            },
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::cycles::CycleData;
+    use crate::controller::keys::CycleSlot;
+    use crate::data::item_cache::ItemCache;
+
+    const SWORD: &str = "Skyrim.esm|0x12eb7";
+    const MACE: &str = "Skyrim.esm|0x13982";
+    const DAGGER: &str = "Skyrim.esm|0x1397e";
+    const POTION: &str = "Skyrim.esm|0x3eadd";
+
+    /// Stands in for the HUD and the game: the right-hand key advances the
+    /// right cycle when it goes up, and equip events put an item on top.
+    struct RightHand {
+        cycles: CycleData,
+        potions: u32,
+    }
+
+    impl RightHand {
+        fn new() -> Self {
+            let mut cache = ItemCache::default();
+            let mut cycles = CycleData::default();
+            for spec in [SWORD, MACE, DAGGER] {
+                cycles.add_item(CycleSlot::Right, &cache.get(&spec.to_string()));
+            }
+            Self { cycles, potions: 0 }
+        }
+    }
+
+    impl TraceTarget for RightHand {
+        fn apply(&mut self, event: &TraceEvent) {
+            match event {
+                TraceEvent::Key {
+                    key: 19,
+                    state: KeyState::Up,
+                    ..
+                } => {
+                    self.cycles.advance(&CycleSlot::Right, 1);
+                }
+                TraceEvent::Equipped {
+                    equipped: true,
+                    form_spec,
+                    ..
+                } => {
+                    self.cycles.set_top(&CycleSlot::Right, form_spec);
+                }
+                TraceEvent::Inventory { form_spec, count } if form_spec == POTION => {
+                    self.potions = *count;
+                }
+                _ => {}
+            }
+        }
+
+        fn describe(&self) -> String {
+            format!(
+                "right={:?}; potions={}",
+                self.cycles.get_top(&CycleSlot::Right),
+                self.potions
+            )
+        }
+    }
+
+    #[test]
+    fn a_recorded_session_replays_the_same() {
+        let session = vec![
+            TraceEvent::LoadComplete,
+            TraceEvent::Key {
+                key: 19,
+                state: KeyState::Down,
+                sequence: 1,
+            },
+            TraceEvent::Key {
+                key: 19,
+                state: KeyState::Up,
+                sequence: 2,
+            },
+            TraceEvent::Timer {
+                action: Action::Right,
+            },
+            TraceEvent::Equipped {
+                equipped: true,
+                form_spec: MACE.to_string(),
+                right: MACE.to_string(),
+                left: String::new(),
+            },
+            TraceEvent::MenuKey {
+                key: 19,
+                state: KeyState::Up,
+                sequence: 3,
+            },
+            TraceEvent::Toggle {
+                key: 19,
+                form_spec: "Mod \"Quoted\" Name.esp|0x801".to_string(),
+            },
+            TraceEvent::Inventory {
+                form_spec: POTION.to_string(),
+                count: 4,
+            },
+            TraceEvent::Key {
+                key: 19,
+                state: KeyState::Up,
+                sequence: 4,
+            },
+        ];
+
+        let path = std::env::temp_dir().join("SoulsyHUD_trace_session.jsonl");
+        let mut recorder = TraceRecorder::new(&path, ROTATE_BYTES);
+        let mut live = RightHand::new();
+        let mut live_states = Vec::new();
+        for event in session.iter() {
+            recorder.record(event).expect("trace is writable");
+            live.apply(event);
+            live_states.push(live.describe());
+        }
+        recorder.flush().expect("trace flushes");
+
+        let trace = std::fs::read_to_string(&path).expect("trace was written");
+        std::fs::remove_file(&path).ok();
+        let steps = replay(&trace, &mut RightHand::new());
+        let events: Vec<TraceEvent> = steps.iter().map(|s| s.event.clone()).collect();
+        let states: Vec<String> = steps.iter().map(|s| s.state.clone()).collect();
+        assert_eq!(events, session);
+        assert_eq!(states, live_states);
+        assert_eq!(
+            states.last().map(String::as_str),
+            Some(r#"right=Some("Skyrim.esm|0x1397e"); potions=4"#)
+        );
+    }
+
+    #[test]
+    fn traces_stay_bounded() {
+        let path = std::env::temp_dir().join("SoulsyHUD_trace_rotation.jsonl");
+        let mut recorder = TraceRecorder::new(&path, 256);
+        let rotated = recorder.rotated_path();
+        std::fs::remove_file(&rotated).ok();
+        let event = TraceEvent::Inventory {
+            form_spec: POTION.to_string(),
+            count: 1,
+        };
+        for _ in 0..20 {
+            recorder.record(&event).expect("trace is writable");
+        }
+        recorder.flush().expect("trace flushes");
+
+        let current = std::fs::metadata(&path).expect("trace exists").len();
+        let older = std::fs::metadata(&rotated).expect("trace rotated").len();
+        assert!(current <= 256);
+        assert!(older <= 256);
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(&rotated).ok();
+
+        // Junk lines are skipped, not fatal.
+        let steps = replay(
+            "not json\n{\"ms\":5,\"ev\":\"load_complete\"}\n{\"ms\":",
+            &mut RightHand::new(),
+        );
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].line, 2);
+    }
+}
@@ -0,0 +1,109 @@
+//! A short undo stack for cycle edits. Setting up cycles in a menu is fiddly
+//! and it's easy to remove the wrong thing, so we remember the last few
+//! additions and removals well enough to put things back exactly where they
+//! were. Not persisted: the stack is emptied when a save loads.
+//!
+//! There is no reordering operation for cycles, so adds and removes are the
+//! only edits we need to track.
+
+use std::collections::VecDeque;
+
+use super::keys::CycleSlot;
+
+/// How many edits we remember.
+const UNDO_DEPTH: usize = 20;
+
+/// One change to a cycle, with enough information to reverse it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CycleEdit {
+    /// The item was added to the cycle.
+    Added { slot: CycleSlot, form_spec: String },
+    /// The item was removed from the cycle. It sat just after `after`, or on
+    /// top if that's None. A neighbour survives the cycle turning; an index
+    /// wouldn't.
+    Removed {
+        slot: CycleSlot,
+        form_spec: String,
+        after: Option<String>,
+    },
+}
+
+impl CycleEdit {
+    pub fn slot(&self) -> &CycleSlot {
+        match self {
+            CycleEdit::Added { slot, .. } => slot,
+            CycleEdit::Removed { slot, .. } => slot,
+        }
+    }
+
+    pub fn form_spec(&self) -> &str {
+        match self {
+            CycleEdit::Added { form_spec, .. } => form_spec,
+            CycleEdit::Removed { form_spec, .. } => form_spec,
+        }
+    }
+}
+
+/// Most-recent-last list of edits, bounded to `UNDO_DEPTH`.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    edits: VecDeque<CycleEdit>,
+}
+
+impl UndoStack {
+    /// Remember an edit, forgetting the oldest one if we're full.
+    pub fn push(&mut self, edit: CycleEdit) {
+        if self.edits.len() >= UNDO_DEPTH {
+            self.edits.pop_front();
+        }
+        self.edits.push_back(edit);
+    }
+
+    /// Take the most recent edit, if there is one.
+    pub fn pop(&mut self) -> Option<CycleEdit> {
+        self.edits.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Forget everything.
+    pub fn clear(&mut self) {
+        self.edits.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn added(spec: &str) -> CycleEdit {
+        CycleEdit::Added {
+            slot: CycleSlot::Right,
+            form_spec: spec.to_string(),
+        }
+    }
+
+    #[test]
+    fn stack_is_bounded() {
+        let mut stack = UndoStack::default();
+        for i in 0..(UNDO_DEPTH + 5) {
+            stack.push(added(format!("item-{i}").as_str()));
+        }
+        assert_eq!(stack.len(), UNDO_DEPTH);
+        let latest = stack.pop().expect("there should be an edit to undo");
+        assert_eq!(latest.form_spec(), format!("item-{}", UNDO_DEPTH + 4));
+
+        let mut oldest = None;
+        while let Some(edit) = stack.pop() {
+            oldest = Some(edit);
+        }
+        assert_eq!(oldest, Some(added("item-5")));
+        assert!(stack.is_empty());
+    }
+}
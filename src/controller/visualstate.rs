@@ -0,0 +1,300 @@
+//! What the HUD is showing: the item in each slot, the cycle browser if one is
+//! open, and the lock hint. The renderer reads from this every frame, through
+//! the controller.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::browser::CycleBrowser;
+use super::keys::CycleSlot;
+use super::lockhint::LockHintTracker;
+use crate::data::HudItem;
+use crate::layouts::shared::DEFAULT_COOLDOWN_FORMAT;
+use crate::plugin::HudElement;
+
+/// How long a slot stays highlighted after a hotbar key picks its entry.
+pub const HOTBAR_FLASH: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct VisualState {
+    /// The items the HUD should show right now.
+    pub visible: HashMap<HudElement, HudItem>,
+    /// The cycle browser, if the player has one open.
+    pub browser: Option<CycleBrowser>,
+    /// Lockpicks and lock difficulty for a lock under the crosshair.
+    pub lock_hints: LockHintTracker,
+    /// The layout's pattern for shout recharge text. Cached so the render tick
+    /// doesn't have to look at the layout.
+    pub cooldown_format: String,
+    /// Slots whose item changed since the renderer last asked about them.
+    changed: HashSet<HudElement>,
+    /// The slot a hotbar key just filled, and when, so the renderer can
+    /// highlight it for a moment.
+    flash: Option<(HudElement, Instant)>,
+    /// Hands holding nothing at all. They show the unarmed entry, but nobody
+    /// chose it: the hand was emptied, say by a bound weapon running out.
+    empty_hands: HashSet<HudElement>,
+}
+
+impl Default for VisualState {
+    fn default() -> Self {
+        Self {
+            visible: HashMap::new(),
+            browser: None,
+            lock_hints: LockHintTracker::default(),
+            cooldown_format: DEFAULT_COOLDOWN_FORMAT.to_string(),
+            changed: HashSet::new(),
+            flash: None,
+            empty_hands: HashSet::new(),
+        }
+    }
+}
+
+impl VisualState {
+    /// The item showing in this slot, if any.
+    pub fn showing(&self, slot: &HudElement) -> Option<&HudItem> {
+        self.visible.get(slot)
+    }
+
+    /// Show this item in the slot. Returns true if it replaced a different
+    /// item; filling an empty slot for the first time doesn't count.
+    pub fn show(&mut self, slot: HudElement, item: HudItem) -> bool {
+        self.empty_hands.remove(&slot);
+        if let Some(replaced) = self.visible.insert(slot, item.clone()) {
+            let replaced = replaced != item;
+            if replaced {
+                self.changed.insert(slot);
+            }
+            replaced
+        } else {
+            self.changed.insert(slot);
+            false
+        }
+    }
+
+    /// Show this hand as holding nothing. Returns true if it wasn't empty
+    /// already, so the caller knows whether there's news to announce.
+    pub fn show_empty_hand(&mut self, slot: HudElement) -> bool {
+        let newly_empty = !self.empty_hands.contains(&slot);
+        self.show(slot, HudItem::make_unarmed_proxy());
+        self.empty_hands.insert(slot);
+        newly_empty
+    }
+
+    /// Is this hand holding nothing, as opposed to showing a chosen entry?
+    pub fn is_empty_hand(&self, slot: &HudElement) -> bool {
+        self.empty_hands.contains(slot)
+    }
+
+    /// Note that the item in this slot was changed in place, say its count.
+    pub fn touch(&mut self, slot: HudElement) {
+        self.changed.insert(slot);
+    }
+
+    /// Has this slot's item changed since the last time we were asked? Asking
+    /// clears the answer, so only the renderer should ask.
+    pub fn take_change(&mut self, slot: &HudElement) -> bool {
+        self.changed.remove(slot)
+    }
+
+    /// Highlight this slot for a moment, starting now.
+    pub fn flash(&mut self, slot: HudElement, now: Instant) {
+        self.flash = Some((slot, now));
+    }
+
+    /// How much of this slot's flash is left, from 1.0 down to 0.0.
+    pub fn flash_left(&self, slot: &HudElement, now: Instant) -> f32 {
+        match self.flash {
+            Some((flashing, since)) if flashing == *slot => {
+                let elapsed = now.saturating_duration_since(since);
+                1.0 - (elapsed.as_secs_f32() / HOTBAR_FLASH.as_secs_f32()).min(1.0)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Close the cycle browser if it's browsing this cycle.
+    pub fn close_browser_on(&mut self, which: &CycleSlot) {
+        if self.browser.as_ref().is_some_and(|b| b.slot() == which) {
+            self.browser = None;
+        }
+    }
+
+    /// Is anything here waiting to time out?
+    pub fn has_clock_work(&self) -> bool {
+        self.lock_hints.is_showing() || self.browser.is_some() || self.flash.is_some()
+    }
+
+    /// Let a stale lock hint and an idle cycle browser go.
+    pub fn expire(&mut self, now: Instant) {
+        if self.lock_hints.expire(now) {
+            log::trace!("The lock hint timed out.");
+            self.changed.insert(HudElement::Utility);
+        }
+        if self.browser.as_ref().is_some_and(|b| b.expired(now)) {
+            log::debug!("The cycle browser timed out.");
+            self.browser = None;
+        }
+        if self
+            .flash
+            .is_some_and(|(_, since)| now.saturating_duration_since(since) >= HOTBAR_FLASH)
+        {
+            self.flash = None;
+        }
+    }
+
+    /// Refresh the data the renderer draws that might have changed since the
+    /// last refresh. Returns the hands whose weapon enchantment ran dry during
+    /// this refresh.
+    pub fn refresh_items(&mut self) -> Vec<HudElement> {
+        // The only relevant items are shouts, left, and right hand.
+        if let Some(power) = self.visible.get_mut(&HudElement::Power) {
+            let before = power.clone();
+            power.refresh_extra_data();
+            power.refresh_cooldown_text(&self.cooldown_format);
+            if *power != before {
+                self.changed.insert(HudElement::Power);
+            }
+        }
+        let mut drained = Vec::new();
+        for hand in [HudElement::Left, HudElement::Right] {
+            if let Some(item) = self.visible.get_mut(&hand) {
+                let before = item.clone();
+                item.refresh_extra_data();
+                if *item != before {
+                    self.changed.insert(hand);
+                }
+                if item.is_drained() && !before.is_drained() {
+                    drained.push(hand);
+                }
+            }
+        }
+        drained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::item_cache::ItemCache;
+
+    #[test]
+    fn showing_an_item_reports_real_changes() {
+        let mut cache = ItemCache::default();
+        let sword = cache.get(&"Skyrim.esm|0x12eb7".to_string());
+        let mace = cache.get(&"Skyrim.esm|0x13982".to_string());
+
+        let mut visual = VisualState::default();
+        assert!(visual.showing(&HudElement::Right).is_none());
+        // Filling an empty slot isn't a change.
+        assert!(!visual.show(HudElement::Right, sword.clone()));
+        assert!(!visual.show(HudElement::Right, sword.clone()));
+        assert!(visual.show(HudElement::Right, mace.clone()));
+        assert_eq!(visual.showing(&HudElement::Right), Some(&mace));
+        assert!(visual.showing(&HudElement::Left).is_none());
+        assert_eq!(visual.cooldown_format, DEFAULT_COOLDOWN_FORMAT);
+
+        // Nothing to close is fine.
+        visual.close_browser_on(&CycleSlot::Right);
+        assert!(visual.browser.is_none());
+    }
+
+    #[test]
+    fn the_renderer_hears_about_each_change_once() {
+        let mut cache = ItemCache::default();
+        let sword = cache.get(&"Skyrim.esm|0x12eb7".to_string());
+        let mace = cache.get(&"Skyrim.esm|0x13982".to_string());
+
+        let mut visual = VisualState::default();
+        assert!(!visual.take_change(&HudElement::Right));
+        visual.show(HudElement::Right, sword.clone());
+        assert!(visual.take_change(&HudElement::Right));
+        assert!(!visual.take_change(&HudElement::Right));
+
+        // Showing the same thing again is no news.
+        visual.show(HudElement::Right, sword);
+        assert!(!visual.take_change(&HudElement::Right));
+        visual.show(HudElement::Right, mace);
+        visual.touch(HudElement::Utility);
+        assert!(visual.take_change(&HudElement::Utility));
+        assert!(visual.take_change(&HudElement::Right));
+        assert!(!visual.take_change(&HudElement::Left));
+    }
+
+    #[test]
+    fn hotbar_flashes_fade_and_go() {
+        let now = Instant::now();
+        let mut visual = VisualState::default();
+        assert_eq!(visual.flash_left(&HudElement::Right, now), 0.0);
+
+        visual.flash(HudElement::Right, now);
+        assert!(visual.has_clock_work());
+        assert_eq!(visual.flash_left(&HudElement::Right, now), 1.0);
+        assert_eq!(visual.flash_left(&HudElement::Left, now), 0.0);
+        let halfway = visual.flash_left(&HudElement::Right, now + HOTBAR_FLASH / 2);
+        assert!(halfway > 0.4 && halfway < 0.6);
+
+        visual.expire(now + HOTBAR_FLASH / 2);
+        assert!(visual.has_clock_work());
+        visual.expire(now + HOTBAR_FLASH);
+        assert!(!visual.has_clock_work());
+        assert_eq!(visual.flash_left(&HudElement::Right, now), 0.0);
+    }
+
+    #[test]
+    fn emptied_hands_are_their_own_state() {
+        let mut cache = ItemCache::default();
+        let sword = cache.get(&"Skyrim.esm|0x12eb7".to_string());
+        let unarmed = HudItem::make_unarmed_proxy();
+
+        let mut visual = VisualState::default();
+        visual.show(HudElement::Right, sword.clone());
+        assert!(!visual.is_empty_hand(&HudElement::Right));
+        assert!(visual.show_empty_hand(HudElement::Right));
+        assert!(visual.is_empty_hand(&HudElement::Right));
+        assert_eq!(visual.showing(&HudElement::Right), Some(&unarmed));
+        assert!(visual.take_change(&HudElement::Right));
+        // Emptying an empty hand is no news.
+        assert!(!visual.show_empty_hand(HudElement::Right));
+        assert!(!visual.is_empty_hand(&HudElement::Left));
+
+        // Choosing the unarmed entry from a cycle isn't an empty hand.
+        visual.show(HudElement::Right, unarmed);
+        assert!(!visual.is_empty_hand(&HudElement::Right));
+        assert!(visual.show_empty_hand(HudElement::Right));
+        visual.show(HudElement::Right, sword);
+        assert!(!visual.is_empty_hand(&HudElement::Right));
+    }
+
+    #[test]
+    fn idle_things_time_out_on_the_tick() {
+        use crate::controller::browser::BROWSER_TIMEOUT;
+        use crate::controller::lockhint::{LockLevel, LOCK_HINT_DURATION};
+
+        let now = Instant::now();
+        let mut visual = VisualState::default();
+        assert!(!visual.has_clock_work());
+
+        visual.lock_hints.show(LockLevel::from(2), 5, now);
+        visual.browser = CycleBrowser::open(
+            CycleSlot::Left,
+            vec!["Skyrim.esm|0x12eb7".to_string()],
+            None,
+            now,
+        );
+        assert!(visual.has_clock_work());
+        visual.expire(now);
+        assert!(visual.has_clock_work());
+        assert!(!visual.take_change(&HudElement::Utility));
+
+        // Frame rate doesn't matter; only how long it's been. The utility slot
+        // has to be redrawn without the hint.
+        visual.expire(now + LOCK_HINT_DURATION);
+        assert!(!visual.lock_hints.is_showing());
+        assert!(visual.take_change(&HudElement::Utility));
+        assert!(visual.browser.is_some());
+        visual.expire(now + BROWSER_TIMEOUT);
+        assert!(visual.browser.is_none());
+        assert!(!visual.has_clock_work());
+    }
+}
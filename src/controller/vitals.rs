@@ -0,0 +1,160 @@
+//! Whether the player is in any state to equip things. Equipping while the
+//! player is dead or bleeding out can crash the game, and follower-revival mods
+//! make bleedout a place the player spends real time. The renderer tells us
+//! when this changes; until it says otherwise, the player is alive and well.
+//!
+//! While the player is down, every equip and activation is refused, and the
+//! equip timers that were running are cancelled. Nothing is resumed when the
+//! player gets up again: the player cycles afresh if they still want to.
+//!
+//! Cutscenes, beast forms, and scripted scenes take the player's controls
+//! away instead. The renderer reports that too, and it's treated the same
+//! way, except that hotkeys go back to the game untouched while it lasts.
+//! Whatever the game put in the player's hands meanwhile is read back when
+//! control returns.
+
+/// What the game last told us about the player.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Vitals {
+    dead: bool,
+    bleeding_out: bool,
+    /// The game took away movement and activation, or the player's weapons.
+    controls_disabled: bool,
+}
+
+/// How a report changed the player's ability to act.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VitalsChange {
+    /// The player just went down.
+    Incapacitated,
+    /// The player is back on their feet.
+    Recovered,
+    /// No change that matters to us.
+    Unchanged,
+}
+
+impl Vitals {
+    pub fn update(&mut self, alive: bool, bleeding_out: bool) -> VitalsChange {
+        let could_act = self.can_act();
+        self.dead = !alive;
+        self.bleeding_out = bleeding_out;
+        self.change_from(could_act)
+    }
+
+    /// The game gave the player's controls back, or took them away. Either
+    /// flag being off is enough to stop us.
+    pub fn update_controls(&mut self, controls: bool, weapon_controls: bool) -> VitalsChange {
+        let could_act = self.can_act();
+        self.controls_disabled = !controls || !weapon_controls;
+        self.change_from(could_act)
+    }
+
+    fn change_from(&self, could_act: bool) -> VitalsChange {
+        match (could_act, self.can_act()) {
+            (true, false) => VitalsChange::Incapacitated,
+            (false, true) => VitalsChange::Recovered,
+            _ => VitalsChange::Unchanged,
+        }
+    }
+
+    pub fn can_act(&self) -> bool {
+        !self.dead && !self.bleeding_out && !self.controls_disabled
+    }
+
+    /// Has the game taken the player's controls away?
+    pub fn controls_disabled(&self) -> bool {
+        self.controls_disabled
+    }
+
+    /// May we do this now? Logs the refusal if not.
+    pub fn permit(&self, what: &str) -> bool {
+        if !self.can_act() {
+            log::debug!(
+                "Refusing to {what}; dead={}; bleeding_out={}; controls_disabled={};",
+                self.dead,
+                self.bleeding_out,
+                self.controls_disabled
+            );
+        }
+        self.can_act()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::timers::EquipTimers;
+    use crate::plugin::Action;
+
+    /// Tick the timers the way the renderer does and hand expired ones to a
+    /// stand-in for the bridge, which counts the equips that reach it.
+    fn tick(timers: &mut EquipTimers, vitals: &Vitals, seconds: f32) -> usize {
+        timers
+            .advance(seconds, |_| 750)
+            .into_iter()
+            .filter(|_| vitals.permit("equip on timer"))
+            .count()
+    }
+
+    #[test]
+    fn nothing_equips_while_the_player_is_down() {
+        let mut vitals = Vitals::default();
+        assert!(vitals.can_act());
+        let mut timers = EquipTimers::default();
+        timers.start(Action::Right);
+        timers.start(Action::Power);
+        assert_eq!(tick(&mut timers, &vitals, 0.5), 0);
+
+        // Bleedout cancels what's running.
+        assert_eq!(vitals.update(true, true), VitalsChange::Incapacitated);
+        assert_eq!(timers.clear(), vec![Action::Right, Action::Power]);
+        assert_eq!(tick(&mut timers, &vitals, 2.0), 0);
+
+        // A timer started while down still can't equip when it runs out.
+        timers.start(Action::Left);
+        assert_eq!(tick(&mut timers, &vitals, 2.0), 0);
+        assert!(timers.is_empty());
+
+        // Getting up again resumes nothing.
+        assert_eq!(vitals.update(true, false), VitalsChange::Recovered);
+        assert_eq!(tick(&mut timers, &vitals, 2.0), 0);
+        timers.start(Action::Left);
+        assert_eq!(tick(&mut timers, &vitals, 2.0), 1);
+    }
+
+    #[test]
+    fn death_counts_and_repeats_change_nothing() {
+        let mut vitals = Vitals::default();
+        assert_eq!(vitals.update(true, false), VitalsChange::Unchanged);
+        assert_eq!(vitals.update(false, false), VitalsChange::Incapacitated);
+        assert!(!vitals.permit("use an item"));
+        // Bleeding out on top of being dead changes nothing for us.
+        assert_eq!(vitals.update(false, true), VitalsChange::Unchanged);
+        assert_eq!(vitals.update(true, true), VitalsChange::Unchanged);
+        assert_eq!(vitals.update(true, false), VitalsChange::Recovered);
+        assert!(vitals.permit("use an item"));
+    }
+
+    #[test]
+    fn lost_controls_stop_equips_until_they_come_back() {
+        let mut vitals = Vitals::default();
+        assert!(!vitals.controls_disabled());
+        assert_eq!(
+            vitals.update_controls(true, false),
+            VitalsChange::Incapacitated
+        );
+        assert!(vitals.controls_disabled());
+        assert!(!vitals.permit("equip an item"));
+        assert_eq!(
+            vitals.update_controls(false, false),
+            VitalsChange::Unchanged
+        );
+
+        // Controls coming back while bleeding out isn't a recovery yet.
+        assert_eq!(vitals.update(true, true), VitalsChange::Unchanged);
+        assert_eq!(vitals.update_controls(true, true), VitalsChange::Unchanged);
+        assert!(!vitals.controls_disabled());
+        assert_eq!(vitals.update(true, false), VitalsChange::Recovered);
+        assert!(vitals.can_act());
+    }
+}
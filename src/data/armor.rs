@@ -121,7 +121,8 @@ pub enum ArmorWeight {
 }
 
 const WEIGHT_LIGHT: EnumSet<ArmorTag> = enum_set!(
-    ArmorTag::OCF_AccessoryShield_Light
+    ArmorTag::ArmorLight
+        | ArmorTag::OCF_AccessoryShield_Light
         | ArmorTag::OCF_ArmorBoots_Light
         | ArmorTag::OCF_ArmorCuirass_Light
         | ArmorTag::OCF_ArmorGauntlets_Light
@@ -129,7 +130,8 @@ const WEIGHT_LIGHT: EnumSet<ArmorTag> = enum_set!(
         | ArmorTag::OCF_ArmorShield_Light
 );
 const WEIGHT_HEAVY: EnumSet<ArmorTag> = enum_set!(
-    ArmorTag::OCF_ArmorBoots_Heavy
+    ArmorTag::ArmorHeavy
+        | ArmorTag::OCF_ArmorBoots_Heavy
         | ArmorTag::OCF_ArmorCuirass_Heavy
         | ArmorTag::OCF_ArmorGauntlets_Heavy
         | ArmorTag::OCF_ArmorHelmet_Heavy
@@ -153,6 +155,7 @@ const CLOAKS: EnumSet<ArmorTag> =
 
 const HANDS: EnumSet<ArmorTag> = enum_set!(
     ArmorTag::ClothingHands
+        | ArmorTag::ArmorGauntlets
         | ArmorTag::OCF_ArmorGauntlets_Heavy
         | ArmorTag::OCF_ArmorGauntlets_Light
         | ArmorTag::OCF_ArmorGauntlets_Medium
@@ -269,6 +272,7 @@ const MASKS: EnumSet<ArmorTag> = enum_set!(
 
 const BODY: EnumSet<ArmorTag> = enum_set!(
     ArmorTag::ClothingBody
+        | ArmorTag::ArmorCuirass
         | ArmorTag::ArmorClothing
         | ArmorTag::OCF_ArmorBodyPart
         | ArmorTag::OCF_ArmorTypeBody
@@ -316,6 +320,7 @@ const BODY: EnumSet<ArmorTag> = enum_set!(
 );
 const FEET: EnumSet<ArmorTag> = enum_set!(
     ArmorTag::ClothingFeet
+        | ArmorTag::ArmorBoots
         | ArmorTag::OCF_ArmorTypeFeet
         | ArmorTag::OCF_ArmorTypeFeet_Alt
         | ArmorTag::OCF_ArmorTypeFeet_Main
@@ -378,9 +383,14 @@ const SHIELDS: EnumSet<ArmorTag> = enum_set!(
 
 #[derive(Debug, EnumString, Hash, EnumSetType)]
 pub enum ArmorTag {
+    ArmorBoots,
     ArmorClothing,
     ArmorCrown,
+    ArmorCuirass,
+    ArmorGauntlets,
+    ArmorHeavy,
     ArmorHelmet,
+    ArmorLight,
     ArmorMaterialHide,
     ArmorQuiver,
     ArmorShield,
@@ -657,3 +667,38 @@ pub enum ArmorTag {
     WAF_FingerlessGauntletsBracers,
     WAF_SpikedGauntletGloves,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(keywords: &[&str]) -> ArmorType {
+        let keywords = keywords.iter().map(|xs| xs.to_string()).collect();
+        ArmorType::classify("Test Armor", keywords, false)
+    }
+
+    #[test]
+    fn vanilla_slot_and_weight_keywords_pick_icons() {
+        // What the plugin adds from the slot mask and weight class.
+        let cuirass = classify(&["ArmorCuirass", "ArmorHeavy"]);
+        assert_eq!(cuirass.icon(), &Icon::ArmorHeavy);
+        assert_eq!(
+            classify(&["ArmorGauntlets", "ArmorLight"]).icon(),
+            &Icon::ArmorLightHands
+        );
+        assert_eq!(classify(&["ArmorBoots"]).icon(), &Icon::ArmorClothingFeet);
+        assert_eq!(
+            classify(&["ArmorHelmet", "ArmorLight"]).icon(),
+            &Icon::ArmorLightHead
+        );
+        assert_eq!(classify(&["ClothingCirclet"]).icon(), &Icon::ArmorCirclet);
+
+        let light = classify(&["ArmorShield", "ArmorLight"]);
+        assert_eq!(light.icon(), &Icon::ArmorShieldLight);
+        assert!(!light.is_utility());
+        let heavy = classify(&["ArmorShield", "ArmorHeavy", "OCF_InvColorDaedric"]);
+        assert_eq!(heavy.icon(), &Icon::ArmorShieldHeavy);
+        assert_eq!(heavy.color(), InvColor::Daedric.color());
+        assert!(cuirass.is_utility());
+    }
+}
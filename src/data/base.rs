@@ -6,6 +6,7 @@ use super::color::InvColor;
 use super::food::FoodType;
 use super::potion::PotionType;
 use super::power::PowerType;
+use super::reusable::ReusableType;
 use super::shout::ShoutType;
 use super::spell::SpellType;
 use super::weapon::WeaponType;
@@ -26,6 +27,7 @@ pub enum BaseType {
     Potion(PotionType),
     PotionProxy(Proxy),
     Power(PowerType),
+    Reusable(ReusableType),
     Scroll(SpellType),
     Shout(ShoutType),
     Spell(SpellType),
@@ -46,6 +48,7 @@ impl std::fmt::Display for BaseType {
             BaseType::Potion(t) => write!(f, "{t}"),
             BaseType::PotionProxy(t) => write!(f, "{t}"),
             BaseType::Power(t) => write!(f, "{t}"),
+            BaseType::Reusable(t) => write!(f, "{t}"),
             BaseType::Scroll(t) => write!(f, "{t}"),
             BaseType::Shout(t) => write!(f, "{t}"),
             BaseType::Spell(t) => write!(f, "{t}"),
@@ -119,6 +122,13 @@ impl BaseType {
         keywords: Vec<String>,
         twohanded: bool,
     ) -> Self {
+        if matches!(
+            category,
+            ItemCategory::Armor | ItemCategory::Food | ItemCategory::Misc
+        ) && ReusableType::family_for(&keywords).is_some()
+        {
+            return Self::Reusable(ReusableType::classify(name, keywords, twohanded));
+        }
         match category {
             ItemCategory::Ammo => Self::Ammo(AmmoType::classify(name, keywords.clone(), twohanded)),
             ItemCategory::Armor => {
@@ -154,6 +164,7 @@ impl BaseType {
             BaseType::Potion(_) => true,
             BaseType::PotionProxy(_) => true,
             BaseType::Power(_) => false,
+            BaseType::Reusable(_) => true,
             BaseType::Scroll(_) => true,
             BaseType::Shout(_) => false,
             BaseType::Spell(_) => false,
@@ -173,6 +184,15 @@ impl BaseType {
         matches!(self, BaseType::Spell(_) | BaseType::Scroll(_))
     }
 
+    /// The keyword family of a reusable item, used to follow it across a
+    /// form swap. None for everything else.
+    pub fn reusable_family(&self) -> Option<&str> {
+        match self {
+            BaseType::Reusable(t) => Some(t.family()),
+            _ => None,
+        }
+    }
+
     pub fn is_potion(&self) -> bool {
         matches!(self, BaseType::Potion(_))
     }
@@ -198,6 +218,7 @@ impl BaseType {
             BaseType::Potion(_) => true,
             BaseType::PotionProxy(_) => true,
             BaseType::Power(_) => false,
+            BaseType::Reusable(_) => true,
             BaseType::Scroll(_) => false,
             BaseType::Shout(_) => false,
             BaseType::Spell(_) => false,
@@ -209,6 +230,18 @@ impl BaseType {
         matches!(self, BaseType::Weapon(_))
     }
 
+    pub fn is_tool(&self) -> bool {
+        matches!(self, BaseType::Weapon(t) if t.is_tool())
+    }
+
+    /// For bows and crossbows, whether they shoot bolts. None for everything else.
+    pub fn shoots_bolts(&self) -> Option<bool> {
+        match self {
+            BaseType::Weapon(t) => t.shoots_bolts(),
+            _ => None,
+        }
+    }
+
     pub fn is_one_handed(&self) -> bool {
         match self {
             BaseType::Weapon(t) => t.is_one_handed(),
@@ -239,9 +272,10 @@ impl BaseType {
             BaseType::Potion(_) => false,
             BaseType::PotionProxy(_) => false,
             BaseType::Power(_) => false,
-            BaseType::Scroll(t) => !t.is_two_handed(),
+            BaseType::Reusable(_) => false,
+            BaseType::Scroll(t) => t.left_hand_ok(),
             BaseType::Shout(_) => false,
-            BaseType::Spell(t) => !t.is_two_handed(),
+            BaseType::Spell(t) => t.left_hand_ok(),
             BaseType::Weapon(t) => t.left_hand_ok(),
         }
     }
@@ -259,9 +293,10 @@ impl BaseType {
             BaseType::Potion(_) => false,
             BaseType::PotionProxy(_) => false,
             BaseType::Power(_) => false,
-            BaseType::Scroll(_) => true,
+            BaseType::Reusable(_) => false,
+            BaseType::Scroll(t) => t.right_hand_ok(),
             BaseType::Shout(_) => false,
-            BaseType::Spell(_) => true,
+            BaseType::Spell(t) => t.right_hand_ok(),
             BaseType::Weapon(t) => t.right_hand_ok(),
         }
     }
@@ -281,6 +316,7 @@ impl HasIcon for BaseType {
             BaseType::Potion(t) => t.color(),
             BaseType::PotionProxy(t) => t.color(),
             BaseType::Power(t) => t.color(),
+            BaseType::Reusable(t) => t.color(),
             BaseType::Scroll(t) => t.color(),
             BaseType::Shout(t) => t.color(),
             BaseType::Spell(t) => t.color(),
@@ -301,6 +337,7 @@ impl HasIcon for BaseType {
             BaseType::Potion(t) => t.icon(),
             BaseType::PotionProxy(t) => t.icon(),
             BaseType::Power(t) => t.icon(),
+            BaseType::Reusable(t) => t.icon(),
             BaseType::Scroll(_) => &Icon::Scroll,
             BaseType::Shout(t) => t.icon(),
             BaseType::Spell(t) => t.icon(),
@@ -313,7 +350,7 @@ impl HasIcon for BaseType {
 mod tests {
     use super::*;
     use crate::data::color::{color_from_keywords, InvColor};
-    use crate::data::magic::{MagicCategory, SpellData};
+    use crate::data::magic::{MagicCategory, SpellData, SpellHand};
     use crate::data::weapon::{WeaponEquipType, WeaponType};
 
     #[test]
@@ -518,4 +555,62 @@ mod tests {
         ));
         assert!(master_spell.right_hand_ok());
     }
+
+    #[test]
+    fn spells_can_be_made_for_one_hand() {
+        let frostbite = SpellData::new(true, 43, false, 20, 25, 1);
+        let left = BaseType::Spell(SpellType::new(
+            frostbite.clone().with_hand(SpellHand::from(1)),
+            Vec::new(),
+        ));
+        assert!(left.left_hand_ok());
+        assert!(!left.right_hand_ok());
+        assert!(left.is_one_handed());
+
+        let right = BaseType::Scroll(SpellType::new(
+            frostbite.with_hand(SpellHand::from(2)),
+            Vec::new(),
+        ));
+        assert!(!right.left_hand_ok());
+        assert!(right.right_hand_ok());
+        assert_eq!(SpellHand::from(0), SpellHand::Either);
+    }
+
+    #[test]
+    fn spells_are_colored_by_damage_then_school() {
+        let fire = BaseType::Spell(SpellType::new(
+            SpellData::new(
+                true, 41, // ActorValue::ResistFire
+                false, 21, // School::Illusion
+                25, 1, // SpellArchetype::ValueModifier
+            ),
+            Vec::new(),
+        ));
+        assert_eq!(fire.color(), InvColor::Fire.color());
+
+        // No damage type, so the school decides.
+        let heal = BaseType::Spell(SpellType::new(
+            SpellData::new(
+                false, 0, // no resist
+                false, 22, // School::Restoration
+                25, 1, // SpellArchetype::ValueModifier
+            ),
+            Vec::new(),
+        ));
+        assert_eq!(heal.color(), InvColor::Green.color());
+
+        // A color keyword beats both.
+        let painted = BaseType::Spell(SpellType::new(
+            SpellData::new(true, 41, false, 21, 25, 1),
+            vec!["OCF_InvColorFrost".to_string()],
+        ));
+        assert_eq!(painted.color(), InvColor::Frost.color());
+
+        // Neither, and the spell keeps the default color.
+        let unclassified = BaseType::Spell(SpellType::new(
+            SpellData::new(false, 0, false, -1, 0, 1),
+            Vec::new(),
+        ));
+        assert_eq!(unclassified.color(), Color::default());
+    }
 }
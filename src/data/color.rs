@@ -3,6 +3,7 @@
 use eyre::{eyre, Result};
 use strum::{Display, EnumIter, EnumVariantNames, IntoEnumIterator};
 
+use crate::controller::settings::settings;
 use crate::plugin::Color;
 
 impl Color {
@@ -39,6 +40,7 @@ pub enum InvColor {
     Black,
     Blood,
     Blue,
+    Bone,
     Bound,
     Brown,
     Copper,
@@ -73,12 +75,15 @@ pub enum InvColor {
     Yellow,
 }
 
+/// The color an item's keywords ask for, unless the player would rather items
+/// were colored by type alone. Every item kind that carries keywords asks this
+/// first: a keyword color beats one derived from the item's type, which beats
+/// the default.
 pub fn color_from_keywords(keywords: &[String]) -> Option<InvColor> {
-    let color_keywords: Vec<InvColor> = keywords
-        .iter()
-        .filter_map(|xs| InvColor::try_from(xs.as_str()).ok())
-        .collect();
-    color_keywords.first().cloned()
+    if settings().ignore_keyword_colors() {
+        return None;
+    }
+    InvColor::from_keywords(keywords)
 }
 
 impl TryFrom<&str> for InvColor {
@@ -99,6 +104,15 @@ impl TryFrom<&str> for InvColor {
 }
 
 impl InvColor {
+    /// The first OCF color keyword in the list that names a color we know.
+    /// Other keywords are passed over, even ones that happen to be color names.
+    pub fn from_keywords(keywords: &[String]) -> Option<InvColor> {
+        keywords
+            .iter()
+            .filter(|xs| xs.starts_with("OCF_InvColor") || xs.starts_with("OCF_IconColor"))
+            .find_map(|xs| InvColor::try_from(xs.as_str()).ok())
+    }
+
     pub fn color(&self) -> Color {
         match *self {
             InvColor::Aedric => Color::default(), // TODO
@@ -106,6 +120,7 @@ impl InvColor {
             InvColor::Black => Color::rgb(0, 0, 0),
             InvColor::Blue => Color::rgb(59, 106, 249),
             InvColor::Blood => Color::rgb(138, 3, 3),
+            InvColor::Bone => Color::rgb(227, 218, 201),
             InvColor::Bound => Color::rgb(150, 200, 244),
             InvColor::Brown => Color::rgb(165, 42, 42),
             InvColor::Copper => Color::rgb(184, 115, 51),
@@ -158,6 +173,29 @@ mod tests {
         let color = InvColor::try_from("OCF_InvColorDaedric").expect("aedric is a valid color");
         assert_eq!(color, InvColor::Daedric);
     }
+
+    #[test]
+    fn keyword_colors_skip_what_they_dont_know() {
+        let keywords = |xs: &[&str]| xs.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(InvColor::from_keywords(&[]), None);
+        // Unknown colors, and color names that aren't OCF color keywords.
+        assert_eq!(
+            InvColor::from_keywords(&keywords(&["OCF_InvColorPlaid", "Fire", "WeapTypeSword"])),
+            None
+        );
+        assert_eq!(
+            InvColor::from_keywords(&keywords(&[
+                "OCF_InvColorPlaid",
+                "OCF_IconColorBone",
+                "OCF_InvColorGold"
+            ])),
+            Some(InvColor::Bone)
+        );
+        assert_eq!(
+            color_from_keywords(&keywords(&["OCF_InvColorFrost"])),
+            Some(InvColor::Frost)
+        );
+    }
 }
 
 #[cfg(test)]
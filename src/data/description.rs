@@ -0,0 +1,140 @@
+//! Short item descriptions for list views, where names alone can't tell five
+//! kinds of "Potion of Healing" apart. A description is built from what
+//! classification already worked out: the main effect and its strength for
+//! potions, the damage type and charge for weapons, the level and school for
+//! spells. Things we have nothing useful to say about get an empty string.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use strfmt::strfmt;
+
+use super::base::BaseType;
+use super::potion::PotionType;
+
+const FMT_RESTORES_HEALTH: &str = "$SoulsyHUD_desc_RestoresHealth";
+const FMT_RESTORES_MAGICKA: &str = "$SoulsyHUD_desc_RestoresMagicka";
+const FMT_RESTORES_STAMINA: &str = "$SoulsyHUD_desc_RestoresStamina";
+const FMT_RESIST: &str = "$SoulsyHUD_desc_Resist";
+const FMT_POISON: &str = "$SoulsyHUD_desc_Poison";
+const FMT_CHARGE: &str = "$SoulsyHUD_desc_Charge";
+const FMT_SPELL: &str = "$SoulsyHUD_desc_Spell";
+
+/// Describe an item of this kind. `magnitude` is the strength of a potion's
+/// main effect, 0 if unknown; `charge` is a weapon's enchantment charge as a
+/// percentage, if it has one. `translate` looks up a translation key, and
+/// gives back an empty string for keys it doesn't know.
+pub fn describe<F>(kind: &BaseType, magnitude: f32, charge: Option<f32>, translate: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let mut vars: HashMap<String, String> = HashMap::new();
+    vars.insert("magnitude".to_string(), format!("{}", magnitude.round()));
+
+    let key = match kind {
+        BaseType::Potion(_) if magnitude <= 0.0 => return String::new(),
+        BaseType::Potion(PotionType::Health) => FMT_RESTORES_HEALTH,
+        BaseType::Potion(PotionType::Magicka) => FMT_RESTORES_MAGICKA,
+        BaseType::Potion(PotionType::Stamina) => FMT_RESTORES_STAMINA,
+        BaseType::Potion(PotionType::Poison) => FMT_POISON,
+        BaseType::Potion(PotionType::Resist(element)) => {
+            vars.insert("element".to_string(), piece(&translate, element));
+            FMT_RESIST
+        }
+        BaseType::Spell(spell) | BaseType::Scroll(spell) => {
+            let data = spell.data();
+            vars.insert("level".to_string(), piece(&translate, &data.level));
+            vars.insert("school".to_string(), piece(&translate, &data.school));
+            FMT_SPELL
+        }
+        BaseType::Weapon(weapon) => {
+            let element = weapon.element().map(|xs| piece(&translate, &xs));
+            let charge = charge.map(|pct| {
+                vars.insert("charge".to_string(), format!("{}", pct.round()));
+                strfmt(&translate(FMT_CHARGE), &vars).unwrap_or_default()
+            });
+            let parts: Vec<String> = [element, charge].into_iter().flatten().collect();
+            return parts.join(", ");
+        }
+        _ => return String::new(),
+    };
+
+    strfmt(&translate(key), &vars).unwrap_or_default()
+}
+
+/// A word like a school or an element, translated if the translation files
+/// have it and as we name it otherwise.
+fn piece<F, T>(translate: &F, word: &T) -> String
+where
+    F: Fn(&str) -> String,
+    T: Display,
+{
+    let word = word.to_string();
+    let translated = translate(format!("$SoulsyHUD_desc_{word}").as_str());
+    if translated.is_empty() {
+        word
+    } else {
+        translated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::color::InvColor;
+    use crate::data::magic::{MagicCategory, SpellData};
+    use crate::data::spell::SpellType;
+    use crate::data::weapon::{WeaponEquipType, WeaponType};
+    use crate::images::icons::Icon;
+
+    fn english(key: &str) -> String {
+        match key {
+            FMT_RESTORES_HEALTH => "Restores {magnitude} health",
+            FMT_RESIST => "Resist {element} {magnitude}%",
+            FMT_CHARGE => "{charge}% charge",
+            FMT_SPELL => "{level} {school}",
+            "$SoulsyHUD_desc_adept" => "Adept",
+            "$SoulsyHUD_desc_destruction" => "Destruction",
+            _ => "",
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn descriptions_tell_lookalikes_apart() {
+        let healing = BaseType::Potion(PotionType::Health);
+        assert_eq!(
+            describe(&healing, 75.0, None, english),
+            "Restores 75 health"
+        );
+        assert_eq!(
+            describe(&healing, 25.4, None, english),
+            "Restores 25 health"
+        );
+        // Potions from before we knew magnitudes say nothing.
+        assert_eq!(describe(&healing, 0.0, None, english), "");
+
+        let resist = BaseType::Potion(PotionType::Resist(MagicCategory::Frost));
+        assert_eq!(describe(&resist, 40.0, None, english), "Resist Frost 40%");
+        // A missing format means no description, not a broken one.
+        let poison = BaseType::Potion(PotionType::Poison);
+        assert_eq!(describe(&poison, 10.0, None, english), "");
+
+        let data = SpellData::new(true, 41, false, 20, 50, 0);
+        let spell = BaseType::Spell(SpellType::new(data, Vec::new()));
+        assert_eq!(describe(&spell, 0.0, None, english), "Adept Destruction");
+
+        let sword = WeaponType::new(
+            Icon::WeaponSwordOneHanded,
+            InvColor::Frost,
+            WeaponEquipType::EitherHand,
+        );
+        let sword = BaseType::Weapon(sword);
+        assert_eq!(
+            describe(&sword, 0.0, Some(62.5), english),
+            "Frost, 63% charge"
+        );
+        assert_eq!(describe(&sword, 0.0, None, english), "Frost");
+        assert_eq!(describe(&BaseType::Book, 10.0, None, english), "");
+    }
+}
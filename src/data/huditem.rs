@@ -1,21 +1,33 @@
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
+use eyre::{eyre, Result};
 use strfmt::strfmt;
 
 use super::base::BaseType;
+use super::description::describe;
 use super::HasIcon;
+use crate::controller::control::translated_key;
+use crate::controller::settings::settings;
+use crate::controller::strings::sanitize_display_name;
 use crate::images::icons::Icon;
 #[cfg(not(test))]
 use crate::plugin::relevantExtraData;
 use crate::plugin::{Color, ItemCategory};
 
+/// More of one thing than anyone carries. A negative count from C++ turns into
+/// an unsigned number far above this.
+const MAX_SANE_COUNT: u32 = 10_000_000;
+
 /// A TESForm item that the player can use or equip, with the data
 /// that drives the HUD cached for fast access.
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct HudItem {
-    /// Name as utf8
+    /// Name as utf8, sanitized and clamped for display.
     name: String,
+    /// Name as the game gave it to us. C++ matches inventory entries by name.
+    raw_name: String,
     /// A string that can be turned back into form data; for serializing.
     form_string: String,
     /// An enum classifying this item for fast question-answering as well as icon selection.
@@ -30,6 +42,73 @@ pub struct HudItem {
     shout_cooldown: f32,
     /// Meter level, if relevant. As a percentage.
     meter_level: f32,
+    /// Shout recharge time as display text, cached between ticks.
+    cooldown: CooldownText,
+    /// Uses left today under the player's daily limits, if one applies.
+    uses_left: Option<u32>,
+    /// A spell the player knows but can't cast right now, because a
+    /// spell-preparation mod says it isn't prepared.
+    locked: bool,
+    /// For weapons the player switched between one- and two-handed grips, true
+    /// if held in both hands. None if the grip was never switched.
+    grip: Option<bool>,
+    /// How much one of this item weighs. Weightless things like spells are 0.
+    weight: f32,
+    /// The player's copy is tied to a quest, so using it up could break the quest.
+    quest_item: bool,
+    /// The strength of a potion's main effect, as the game reports it. 0 if unknown.
+    magnitude: f32,
+    /// A short description for list views, clamped like names are. Empty if
+    /// there's nothing useful to say.
+    description: String,
+    /// Lockpick count and lock difficulty, while the crosshair is on a lock.
+    lock_hint: Option<(u32, String)>,
+    /// Milliseconds since the game confirmed a change to the slot showing this
+    /// item. None if it hasn't changed since the save loaded.
+    change_age: Option<u32>,
+}
+
+/// Remaining shout recharge time, formatted for display. We hold onto the
+/// formatted text and only redo it when the whole number of seconds changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CooldownText {
+    seconds: Option<u32>,
+    pattern: String,
+    text: String,
+}
+
+impl CooldownText {
+    /// Update for the given remaining time. Returns true if the text changed.
+    pub fn update(&mut self, remaining: Option<f32>, pattern: &str) -> bool {
+        let seconds = remaining.filter(|xs| *xs > 0.0).map(|xs| xs.ceil() as u32);
+        if seconds == self.seconds && pattern == self.pattern {
+            return false;
+        }
+        self.seconds = seconds;
+        if self.pattern != pattern {
+            self.pattern = pattern.to_string();
+        }
+        self.text = match seconds {
+            Some(secs) => format_cooldown(secs, pattern),
+            None => String::new(),
+        };
+        true
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+}
+
+/// Format a count of seconds using a layout's cooldown pattern. The pattern
+/// `mm:ss` gives minutes and seconds; anything else has `{}` replaced with
+/// the number of seconds.
+pub fn format_cooldown(seconds: u32, pattern: &str) -> String {
+    if pattern == "mm:ss" {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        pattern.replace("{}", seconds.to_string().as_str())
+    }
 }
 
 /// This is the item extra data the hud cares about and displays (full name
@@ -125,6 +204,11 @@ impl RelevantExtraData {
     }
 }
 
+/// Names are sanitized once, here, so nothing downstream has to.
+fn display_name(raw: &str) -> String {
+    sanitize_display_name(raw, settings().max_name_length() as usize)
+}
+
 impl HudItem {
     pub fn from_keywords(
         category: ItemCategory,
@@ -137,31 +221,36 @@ impl HudItem {
         // log::trace!("calling BaseType::classify() with keywords={keywords:?};");
         let kind: BaseType = BaseType::classify(name.as_str(), category, keywords, twohanded);
         let mut result = Self {
-            name,
+            name: display_name(&name),
+            raw_name: name,
             form_string,
             count,
             kind,
             ..Default::default()
         };
         result.make_format_vars();
+        result.make_description();
         result
     }
 
     pub fn preclassified(name: String, form_string: String, count: u32, kind: BaseType) -> Self {
         let mut result = Self {
-            name,
+            name: display_name(&name),
+            raw_name: name,
             form_string,
             count,
             kind,
             ..Default::default()
         };
         result.make_format_vars();
+        result.make_description();
         result
     }
 
     pub fn for_equip_set(name: String, id: u32, icon: Icon) -> Self {
         let mut result = Self {
-            name,
+            name: display_name(&name),
+            raw_name: name,
             form_string: format!("equipset_{id}"),
             count: 1,
             kind: BaseType::Equipset(icon),
@@ -209,6 +298,12 @@ impl HudItem {
             "time_left".to_string(),
             format!("{:.0}", self.extra.time_left),
         );
+        vars.insert("cooldown".to_string(), self.cooldown.text().to_string());
+        let words = match &self.kind {
+            BaseType::Shout(shout) if shout.words() > 0 => shout.words().to_string(),
+            _ => String::new(),
+        };
+        vars.insert("words".to_string(), words);
         vars.insert(
             "meter_level".to_string(),
             format!("{:.0}", self.meter_level),
@@ -218,6 +313,24 @@ impl HudItem {
         } else {
             vars.insert("poison".to_string(), "".to_string());
         }
+        vars.insert(
+            "uses_left".to_string(),
+            self.uses_left.map(|xs| xs.to_string()).unwrap_or_default(),
+        );
+        let grip = match self.grip {
+            Some(true) => "2H",
+            Some(false) => "1H",
+            None => "",
+        };
+        vars.insert("grip".to_string(), grip.to_string());
+        let quest = if self.quest_item { "quest" } else { "" };
+        vars.insert("quest".to_string(), quest.to_string());
+        let (lockpicks, lock) = match &self.lock_hint {
+            Some((count, level)) => (count.to_string(), level.clone()),
+            None => (String::new(), String::new()),
+        };
+        vars.insert("lockpicks".to_string(), lockpicks);
+        vars.insert("lock".to_string(), lock);
         self.format_vars = vars;
     }
 
@@ -258,10 +371,40 @@ impl HudItem {
         self.form_string.clone()
     }
 
+    /// Check that an item handed to us from a menu is something we could put
+    /// in a cycle. Synthetic menu rows like crafting categories or SkyUI group
+    /// headers arrive half-filled: no form spec, no kind, a nonsense count.
+    pub fn validate(&self) -> Result<()> {
+        if self.form_string.trim().is_empty() {
+            return Err(eyre!("item has no form spec"));
+        }
+        if self.form_string.chars().any(|c| c.is_control()) {
+            return Err(eyre!("form spec contains control characters"));
+        }
+        if matches!(self.kind, BaseType::Empty) {
+            return Err(eyre!("item has no kind"));
+        }
+        if self.count > MAX_SANE_COUNT {
+            return Err(eyre!("item count {} is not believable", self.count));
+        }
+        Ok(())
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
 
+    /// Is this the same game form as the other item? Names don't count: two
+    /// mods can add items with the same name, and tempering renames an item.
+    pub fn same_form(&self, other: &HudItem) -> bool {
+        self.form_string == other.form_string
+    }
+
+    /// The unsanitized name, for matching against the game's inventory data.
+    pub fn raw_name(&self) -> String {
+        self.raw_name.clone()
+    }
+
     pub fn count(&self) -> u32 {
         self.count
     }
@@ -271,6 +414,104 @@ impl HudItem {
         self.make_format_vars();
     }
 
+    /// Set how many uses are left today under a daily limit; None for no limit.
+    pub fn set_uses_left(&mut self, v: Option<u32>) {
+        if self.uses_left != v {
+            self.uses_left = v;
+            self.make_format_vars();
+        }
+    }
+
+    /// Return true if this spell is known but can't be cast right now.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn set_locked(&mut self, v: bool) {
+        self.locked = v;
+    }
+
+    /// Return true if this is something counted that the player has none of.
+    /// It stays in its cycle, drawn faded, unless the player prunes.
+    pub fn is_spent(&self) -> bool {
+        self.count_matters() && self.count == 0
+    }
+
+    /// How long ago, in milliseconds, this item's slot last changed. Very large
+    /// if the slot hasn't changed since the save loaded.
+    pub fn change_age(&self) -> u32 {
+        self.change_age.unwrap_or(u32::MAX)
+    }
+
+    pub fn set_change_age(&mut self, v: Option<u32>) {
+        self.change_age = v;
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    /// The game says -1 for forms that have no weight at all; we say 0.
+    pub fn set_weight(&mut self, v: f32) {
+        self.weight = if v.is_finite() && v > 0.0 { v } else { 0.0 };
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.magnitude
+    }
+
+    pub fn set_magnitude(&mut self, v: f32) {
+        let v = if v.is_finite() && v > 0.0 { v } else { 0.0 };
+        if self.magnitude != v {
+            self.magnitude = v;
+            self.make_description();
+        }
+    }
+
+    /// A short description of this item for list views; may be empty.
+    pub fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn make_description(&mut self) {
+        let charge = if self.is_weapon() && self.extra.has_charge {
+            Some(self.meter_level)
+        } else {
+            None
+        };
+        let raw = describe(&self.kind, self.magnitude, charge, translated_key);
+        self.description =
+            sanitize_display_name(&raw, settings().max_description_length() as usize);
+    }
+
+    /// Return true if the player's copy of this item belongs to a quest.
+    pub fn is_quest_item(&self) -> bool {
+        self.quest_item
+    }
+
+    pub fn set_quest_item(&mut self, v: bool) {
+        if self.quest_item != v {
+            self.quest_item = v;
+            self.make_format_vars();
+        }
+    }
+
+    /// Show a lockpick count and lock difficulty with this item, or stop showing them.
+    pub fn set_lock_hint(&mut self, v: Option<(u32, String)>) {
+        if self.lock_hint != v {
+            self.lock_hint = v;
+            self.make_format_vars();
+        }
+    }
+
+    /// Set the grip the player chose for a switchable weapon; None for its usual grip.
+    pub fn set_grip(&mut self, v: Option<bool>) {
+        if self.grip != v {
+            self.grip = v;
+            self.make_format_vars();
+        }
+    }
+
     /// Return true if this item is poisoned.
     /// Does not update local flags; okay to use in tight loops.
     pub fn is_poisoned(&self) -> bool {
@@ -293,11 +534,27 @@ impl HudItem {
         self.meter_level
     }
 
+    /// How much of a shout's cooldown is left, from 1.0 down to 0.0. Always
+    /// 0.0 for anything that isn't a shout.
+    pub fn cooldown_fraction(&self) -> f32 {
+        if matches!(self.kind, BaseType::Shout(_)) && self.extra.has_time_left {
+            (self.meter_level / 100.0).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
     /// Return true if this item is enchanted.
     pub fn is_enchanted(&self) -> bool {
         self.extra.has_charge
     }
 
+    /// Return true if this is an enchanted weapon with no charge left.
+    /// Does not update local flags; okay to use in tight loops.
+    pub fn is_drained(&self) -> bool {
+        self.is_weapon() && self.extra.has_charge && self.extra.charge <= 0.0
+    }
+
     /// Get the charge level of this item's enchantment.
     /// Only meaningful for items like weapons.
     pub fn charge_level(&self) -> f32 {
@@ -314,7 +571,23 @@ impl HudItem {
         self.extra.time_left
     }
 
+    /// Recompute the shout recharge text from the most recent extra data.
+    /// Blank unless this is a shout that's recharging.
+    pub fn refresh_cooldown_text(&mut self, pattern: &str) {
+        let remaining = if matches!(self.kind, BaseType::Shout(_)) && self.extra.has_time_left {
+            Some(self.extra.time_left)
+        } else {
+            None
+        };
+        if self.cooldown.update(remaining, pattern) {
+            self.format_vars
+                .insert("cooldown".to_string(), self.cooldown.text().to_string());
+        }
+    }
+
     pub fn refresh_extra_data(&mut self) {
+        let old_charge = self.extra.has_charge.then_some(self.meter_level.round());
+
         #[cfg(test)]
         let extra = RelevantExtraData::randomize();
 
@@ -345,6 +618,11 @@ impl HudItem {
 
         self.extra = extra;
         self.make_format_vars();
+        // The charge is part of a weapon's description.
+        let charge = self.extra.has_charge.then_some(self.meter_level.round());
+        if self.is_weapon() && charge != old_charge {
+            self.make_description();
+        }
     }
 
     // We delegate everything to our object-kind. The goal is for most things
@@ -397,6 +675,16 @@ impl HudItem {
         self.kind.is_weapon()
     }
 
+    /// Delegated to item kind.
+    pub fn is_tool(&self) -> bool {
+        self.kind.is_tool()
+    }
+
+    /// Delegated to item kind.
+    pub fn shoots_bolts(&self) -> Option<bool> {
+        self.kind.shoots_bolts()
+    }
+
     /// Delegated to item kind.
     pub fn is_one_handed(&self) -> bool {
         self.kind.is_one_handed()
@@ -418,6 +706,14 @@ impl HudItem {
     }
 }
 
+/// Items hash by form spec alone, so two snapshots of one item with different
+/// counts or names land in the same bucket.
+impl Hash for HudItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.form_string.hash(state);
+    }
+}
+
 impl Display for HudItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.count_matters() {
@@ -440,3 +736,186 @@ impl Display for HudItem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_sanitized_once() {
+        let raw = format!("  Potion of\0 {}  ", "Very ".repeat(2000));
+        let item = HudItem::preclassified(
+            raw.clone(),
+            "Skyrim.esm|0x3eb42".to_string(),
+            1,
+            BaseType::Empty,
+        );
+        assert_eq!(item.raw_name(), raw);
+        assert!(item.name().starts_with("Potion of Very"));
+        assert!(item.name().chars().count() <= settings().max_name_length() as usize);
+        assert!(!item.name().contains('\0'));
+        assert!(item.fmtstr("{name}".to_string()).chars().count() <= 80);
+    }
+
+    #[test]
+    fn garbage_from_menus_is_rejected() {
+        let good = HudItem::preclassified(
+            "Potion of Minor Healing".to_string(),
+            "Skyrim.esm|0x3eb42".to_string(),
+            1,
+            BaseType::Potion(crate::data::potion::PotionType::Health),
+        );
+        assert!(good.validate().is_ok());
+        let mut zero = good.clone();
+        zero.set_count(0);
+        assert!(
+            zero.validate().is_ok(),
+            "spells and menu rows report a count of 0"
+        );
+
+        let specs = ["", "   ", "Skyrim.esm|\0x3eb42", "\u{1b}[31m"];
+        for spec in specs {
+            let mut item = good.clone();
+            item.form_string = spec.to_string();
+            assert!(item.validate().is_err(), "spec {spec:?} should be rejected");
+        }
+
+        let mut item = good.clone();
+        item.kind = BaseType::Empty;
+        assert!(item.validate().is_err());
+
+        for count in [MAX_SANE_COUNT + 1, -1i32 as u32, u32::MAX] {
+            let mut item = good.clone();
+            item.set_count(count);
+            assert!(item.validate().is_err(), "count {count} should be rejected");
+        }
+
+        // Everything wrong at once, including the name.
+        let zeroed = HudItem::preclassified(
+            "\0\0\0".to_string(),
+            String::new(),
+            u32::MAX,
+            BaseType::Empty,
+        );
+        assert!(zeroed.validate().is_err());
+        assert!(HudItem::default().validate().is_err());
+    }
+
+    #[test]
+    fn quest_items_are_marked_until_released() {
+        let spec = "Skyrim.esm|0x2ac6c".to_string();
+        let mut item = HudItem::preclassified(
+            "Elixir of the Dead".to_string(),
+            spec.clone(),
+            1,
+            BaseType::Potion(crate::data::potion::PotionType::Health),
+        );
+        assert_eq!(
+            item.fmtstr("{name}{quest}".to_string()),
+            "Elixir of the Dead"
+        );
+        item.set_quest_item(true);
+        assert!(item.is_quest_item());
+        assert_eq!(
+            item.fmtstr("{name} {quest}".to_string()),
+            "Elixir of the Dead quest"
+        );
+
+        // The quest finishes with it and the next inventory event says so.
+        let mut cache = crate::data::item_cache::ItemCache::new();
+        cache.record(item);
+        cache.update_quest_item(&spec, false);
+        let released = cache.get(&spec);
+        assert!(!released.is_quest_item());
+        assert_eq!(released.fmtstr("{quest}".to_string()), "");
+    }
+
+    #[test]
+    fn cooldown_patterns() {
+        assert_eq!(format_cooldown(42, "{}s"), "42s");
+        assert_eq!(format_cooldown(95, "mm:ss"), "1:35");
+        assert_eq!(format_cooldown(5, "mm:ss"), "0:05");
+        assert_eq!(format_cooldown(7, "{} seconds"), "7 seconds");
+    }
+
+    #[test]
+    fn cooldown_text_changes_only_on_whole_seconds() {
+        let mut cooldown = CooldownText::default();
+        assert!(cooldown.update(Some(44.7), "{}s"));
+        assert_eq!(cooldown.text(), "45s");
+        assert!(!cooldown.update(Some(44.2), "{}s"));
+        assert!(cooldown.update(Some(43.9), "{}s"));
+        assert_eq!(cooldown.text(), "44s");
+        assert!(cooldown.update(Some(43.9), "mm:ss"));
+        assert_eq!(cooldown.text(), "0:44");
+        assert!(cooldown.update(Some(0.0), "mm:ss"));
+        assert_eq!(cooldown.text(), "");
+        assert!(!cooldown.update(None, "mm:ss"));
+    }
+
+    #[test]
+    fn lock_hints_show_zero_lockpicks() {
+        let mut item = HudItem::preclassified(
+            "Potion of Minor Healing".to_string(),
+            "Skyrim.esm|0x3eb42".to_string(),
+            3,
+            BaseType::Potion(crate::data::potion::PotionType::Health),
+        );
+        assert_eq!(item.fmtstr("{lock}{lockpicks}".to_string()), "");
+        item.set_lock_hint(Some((0, "Hard".to_string())));
+        assert_eq!(
+            item.fmtstr("{count} {name} {lock} {lockpicks}".to_string()),
+            "3 Potion of Minor Healing Hard 0"
+        );
+        item.set_lock_hint(None);
+        assert_eq!(item.fmtstr("{lock}{lockpicks}".to_string()), "");
+    }
+
+    #[test]
+    fn running_out_marks_an_item_spent() {
+        let mut item = HudItem::preclassified(
+            "Potion of Minor Healing".to_string(),
+            "Skyrim.esm|0x3eb42".to_string(),
+            1,
+            BaseType::Potion(crate::data::potion::PotionType::Health),
+        );
+        assert!(!item.is_spent());
+        item.set_count(0);
+        assert!(item.is_spent());
+        assert_eq!(item.fmtstr("{count}".to_string()), "0");
+        item.set_count(7);
+        assert!(!item.is_spent());
+
+        // Counts don't matter for everything. Bare hands are never spent,
+        // even at a count of zero.
+        let mut unarmed = HudItem::make_unarmed_proxy();
+        assert_eq!(unarmed.count(), 1);
+        assert!(!unarmed.is_spent());
+        unarmed.set_count(0);
+        assert!(!unarmed.is_spent());
+    }
+
+    #[test]
+    fn only_enchanted_weapons_drain() {
+        let mut cache = crate::data::item_cache::ItemCache::default();
+        let mut sword = cache.get(&"Skyrim.esm|0x12eb7".to_string());
+        assert!(sword.is_weapon());
+        sword.extra = RelevantExtraData::new(true, 1500.0, 120.0, false, false, 0.0, 0.0);
+        assert!(sword.is_enchanted());
+        assert!(!sword.is_drained());
+        sword.extra.charge = 0.0;
+        assert!(sword.is_drained());
+        // No enchantment means nothing to drain.
+        sword.extra.has_charge = false;
+        assert!(!sword.is_drained());
+
+        let mut potion = HudItem::preclassified(
+            "Potion of Minor Healing".to_string(),
+            "Skyrim.esm|0x3eb42".to_string(),
+            1,
+            BaseType::Potion(crate::data::potion::PotionType::Health),
+        );
+        potion.extra = RelevantExtraData::new(true, 1500.0, 0.0, false, false, 0.0, 0.0);
+        assert!(!potion.is_drained());
+    }
+}
@@ -116,6 +116,30 @@ impl ItemCache {
         Some(item)
     }
 
+    /// Update the weight of a cached item. Weights can change under us when
+    /// survival or weight-tweaking mods are at work. If the item is not in the
+    /// cache, no action is taken.
+    pub fn update_weight(&mut self, form_spec: &str, weight: f32) {
+        if let Some(item) = self.lru.peek_mut(form_spec) {
+            item.set_weight(weight);
+        }
+    }
+
+    /// Update whether a cached item is tied to a quest. Quests hand items over
+    /// and take them back without the item itself changing. If the item is not
+    /// in the cache, no action is taken.
+    pub fn update_quest_item(&mut self, form_spec: &str, quest_item: bool) {
+        if let Some(item) = self.lru.peek_mut(form_spec) {
+            item.set_quest_item(quest_item);
+        }
+    }
+
+    /// The count the cache has for this item, without touching its place in
+    /// the cache. None if it isn't cached.
+    pub fn peek_count(&self, form_spec: &str) -> Option<u32> {
+        self.lru.peek(form_spec).map(|xs| xs.count())
+    }
+
     /// Update the count for a cached item. If the item is not in the
     /// cache, no action is taken.
     pub fn update_count(&mut self, form_spec: &str, new_count: u32) -> Option<&HudItem> {
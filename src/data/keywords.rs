@@ -10,6 +10,7 @@ use strum::{Display, EnumIter, IntoEnumIterator};
 use crate::images::Icon;
 
 use super::color::InvColor;
+use super::magic::MagicCategory;
 
 impl TryFrom<&str> for SpellKeywords {
     type Error = eyre::Error;
@@ -842,6 +843,126 @@ pub const COLOR_WIND: EnumSet<SpellKeywords> = enum_set!(
         | SpellKeywords::SpellDamage_Sonic
 );
 
+// Damage types. Blends are listed before the plain elements they contain, and
+// are checked first.
+
+pub const DAMAGE_ARCANEFIRE: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_ArcaneFire
+        | SpellKeywords::SpellDamage_ArcaneFireCloak
+        | SpellKeywords::SpellDamage_FireArcane
+        | SpellKeywords::SpellDamage_FireArcaneCloak
+);
+
+pub const DAMAGE_ASHFIRE: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_Ash
+        | SpellKeywords::SpellDamage_AshCloak
+        | SpellKeywords::SpellDamage_AshFire
+        | SpellKeywords::SpellDamage_AshFireCloak
+);
+
+pub const DAMAGE_FROSTFIRE: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_FireCold
+        | SpellKeywords::SpellDamage_FireColdCloak
+        | SpellKeywords::SpellDamage_FrostFire
+        | SpellKeywords::SpellDamage_FrostFireCloak
+);
+
+pub const DAMAGE_NECROTIC: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_Necrotic
+        | SpellKeywords::SpellDamage_NecroticCloak
+        | SpellKeywords::SpellDamage_NecroticFire
+        | SpellKeywords::SpellDamage_NecroticFireCloak
+);
+
+pub const DAMAGE_SHOCKARC: EnumSet<SpellKeywords> =
+    enum_set!(SpellKeywords::SpellDamage_ShockArc | SpellKeywords::SpellDamage_ShockArcCloak);
+
+pub const DAMAGE_ARCANE: EnumSet<SpellKeywords> =
+    enum_set!(SpellKeywords::SpellDamage_Arcane | SpellKeywords::SpellDamage_ArcaneCloak);
+
+pub const DAMAGE_ASTRAL: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_Astral
+        | SpellKeywords::SpellDamage_AstralCloak
+        | SpellKeywords::SpellDamage_HolyAstral
+        | SpellKeywords::SpellDamage_HolyAstralCloak
+);
+
+pub const DAMAGE_BLEED: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_Blood
+        | SpellKeywords::SpellDamage_BloodCloak
+        | SpellKeywords::SpellDamage_BloodShock
+        | SpellKeywords::SpellDamage_BloodShockCloak
+);
+
+pub const DAMAGE_DISEASE: EnumSet<SpellKeywords> =
+    enum_set!(SpellKeywords::SpellDamage_Disease | SpellKeywords::SpellDamage_DiseaseCloak);
+
+pub const DAMAGE_EARTH: EnumSet<SpellKeywords> =
+    enum_set!(SpellKeywords::SpellDamage_Earth | SpellKeywords::SpellDamage_EarthCloak);
+
+pub const DAMAGE_LUNAR: EnumSet<SpellKeywords> =
+    enum_set!(SpellKeywords::SpellDamage_HolyLunar | SpellKeywords::SpellDamage_HolyLunarCloak);
+
+pub const DAMAGE_POISON: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::SpellDamage_Poison
+        | SpellKeywords::SpellDamage_PoisonBug
+        | SpellKeywords::SpellDamage_PoisonBugCloak
+        | SpellKeywords::SpellDamage_PoisonCloak
+        | SpellKeywords::SpellDamage_PoisonDoomstone
+        | SpellKeywords::SpellDamage_PoisonEldritch
+        | SpellKeywords::SpellDamage_PoisonEldritchCloak
+);
+
+pub const DAMAGE_SHADOW: EnumSet<SpellKeywords> =
+    enum_set!(SpellKeywords::SpellDamage_Shadow | SpellKeywords::SpellDamage_ShadowCloak);
+
+pub const DAMAGE_SUN: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::MAG_MagicDamageSun
+        | SpellKeywords::SpellDamage_Holy
+        | SpellKeywords::SpellDamage_HolyCloak
+        | SpellKeywords::SpellDamage_Light
+        | SpellKeywords::SpellDamage_LightCloak
+);
+
+pub const DAMAGE_WATER: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::IconMagicWater
+        | SpellKeywords::IconWater
+        | SpellKeywords::SpellDamage_Steam
+        | SpellKeywords::SpellDamage_SteamCloak
+        | SpellKeywords::SpellDamage_Water
+        | SpellKeywords::SpellDamage_WaterCloak
+);
+
+pub const DAMAGE_WIND: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::IconMagicWind
+        | SpellKeywords::IconWind
+        | SpellKeywords::SpellDamage_Sonic
+        | SpellKeywords::SpellDamage_SonicCloak
+        | SpellKeywords::SpellDamage_Wind
+        | SpellKeywords::SpellDamage_WindCloak
+);
+
+pub const DAMAGE_FIRE: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::MagicDamageFire
+        | SpellKeywords::SpellDamage_Fire
+        | SpellKeywords::SpellDamage_FireCloak
+        | SpellKeywords::SpellDamage_FireCloakDunmer
+);
+
+pub const DAMAGE_FROST: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::MagicDamageFrost
+        | SpellKeywords::SpellDamage_Frost
+        | SpellKeywords::SpellDamage_FrostCloak
+);
+
+pub const DAMAGE_SHOCK: EnumSet<SpellKeywords> = enum_set!(
+    SpellKeywords::MagicDamageShock
+        | SpellKeywords::SpellDamage_Shock
+        | SpellKeywords::SpellDamage_ShockCloak
+        | SpellKeywords::SpellDamage_ShockStorm
+        | SpellKeywords::SpellDamage_ShockStormCloak
+);
+
 pub fn icon_for_tagset(tagset: &EnumSet<SpellKeywords>) -> Option<Icon> {
     if tagset.contains(SpellKeywords::Power_Bats) {
         Some(Icon::PowerBats)
@@ -1066,3 +1187,87 @@ pub fn color_for_tagset(tagset: &EnumSet<SpellKeywords>) -> Option<InvColor> {
         None
     }
 }
+
+/// The damage type keywords say a spell deals, if they say anything. Spell
+/// packs often leave the resist actor value unset, so this is checked first.
+pub fn damage_for_tagset(tagset: &EnumSet<SpellKeywords>) -> Option<MagicCategory> {
+    let table = [
+        (DAMAGE_ARCANEFIRE, MagicCategory::ArcaneFire),
+        (DAMAGE_ASHFIRE, MagicCategory::Ashfire),
+        (DAMAGE_FROSTFIRE, MagicCategory::FrostFire),
+        (DAMAGE_NECROTIC, MagicCategory::Necrotic),
+        (DAMAGE_SHOCKARC, MagicCategory::ShockArc),
+        (DAMAGE_ARCANE, MagicCategory::Arcane),
+        (DAMAGE_ASTRAL, MagicCategory::Astral),
+        (DAMAGE_BLEED, MagicCategory::Bleed),
+        (DAMAGE_DISEASE, MagicCategory::Disease),
+        (DAMAGE_EARTH, MagicCategory::Earth),
+        (DAMAGE_LUNAR, MagicCategory::Lunar),
+        (DAMAGE_POISON, MagicCategory::Poison),
+        (DAMAGE_SHADOW, MagicCategory::Shadow),
+        (DAMAGE_SUN, MagicCategory::Sun),
+        (DAMAGE_WATER, MagicCategory::Water),
+        (DAMAGE_WIND, MagicCategory::Wind),
+        (DAMAGE_FIRE, MagicCategory::Fire),
+        (DAMAGE_FROST, MagicCategory::Frost),
+        (DAMAGE_SHOCK, MagicCategory::Shock),
+    ];
+    table
+        .into_iter()
+        .find(|(keywords, _)| !tagset.is_disjoint(*keywords))
+        .map(|(_, damage)| damage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::strings_to_enumset;
+
+    fn damage_for(keywords: &[&str]) -> Option<MagicCategory> {
+        let tags: Vec<String> = keywords.iter().map(|xs| xs.to_string()).collect();
+        damage_for_tagset(&strings_to_enumset(&tags))
+    }
+
+    #[test]
+    fn spell_pack_keywords_give_damage_types() {
+        // Odin's Sun Fire, as patched by OCF.
+        assert_eq!(
+            damage_for(&[
+                "MagicDamageFire",
+                "MAG_MagicDamageSun",
+                "OCF_MgefSpellDamage_Light"
+            ]),
+            Some(MagicCategory::Sun)
+        );
+        // Apocalypse's Frostfire Cloak, which carries both elements.
+        assert_eq!(
+            damage_for(&[
+                "MagicCloak",
+                "MagicDamageFrost",
+                "OCF_MgefSpellDamage_FireColdCloak"
+            ]),
+            Some(MagicCategory::FrostFire)
+        );
+        // Odin's wind spells are tagged only with an icon keyword.
+        assert_eq!(
+            damage_for(&["IconWind", "MagicDamageResist"]),
+            Some(MagicCategory::Wind)
+        );
+        assert_eq!(
+            damage_for(&["OCF_MgefSpellDamage_PoisonBug"]),
+            Some(MagicCategory::Poison)
+        );
+        // Vanilla Firebolt.
+        assert_eq!(damage_for(&["MagicDamageFire"]), Some(MagicCategory::Fire));
+    }
+
+    #[test]
+    fn keywords_without_damage_leave_it_to_the_actor_value() {
+        assert_eq!(damage_for(&[]), None);
+        assert_eq!(
+            damage_for(&["MagicRestoreHealth", "Soulsy_SpellHeal_Self"]),
+            None
+        );
+        assert_eq!(damage_for(&["NotAKeyword_AtAll"]), None);
+    }
+}
@@ -1,14 +1,20 @@
+use std::str::FromStr;
+
 use strum::Display;
 
 use super::color::InvColor;
 use super::game_enums::{ActorValue, SpellArchetype};
+use super::HasIcon;
 use crate::images::icons::Icon;
+use crate::plugin::Color;
 
 #[derive(Default, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct SpellData {
     pub hostile: bool,
     pub damage: MagicCategory,
     pub twohanded: bool,
+    /// Which hand the game lets a one-handed spell go in.
+    pub hand: SpellHand,
     pub school: School,
     pub level: MagicSpellLevel,
     pub archetype: SpellArchetype,
@@ -40,12 +46,71 @@ impl SpellData {
         Self {
             hostile,
             twohanded,
+            hand: SpellHand::Either,
             school,
             archetype,
             level: level.into(),
             damage,
         }
     }
+
+    /// Restrict the spell to one hand, as its equip slot in the game does.
+    pub fn with_hand(mut self, hand: SpellHand) -> Self {
+        self.hand = hand;
+        self
+    }
+
+    /// The color from the spell's damage type, falling back to its school.
+    pub fn inv_color(&self) -> InvColor {
+        if !matches!(self.damage, MagicCategory::None) {
+            return self.damage.color();
+        }
+        match self.school {
+            // TODO identify common colors for magical schools
+            School::Alteration => InvColor::Eldritch,
+            School::Conjuration => InvColor::Silver,
+            School::Destruction => InvColor::Fire,
+            School::Illusion => InvColor::Blue,
+            School::Restoration => InvColor::Green,
+            School::None => InvColor::default(),
+        }
+    }
+}
+
+/// Spells without helpful keywords get an icon from what the spell does, then
+/// from its damage type, then from its school. A summon or bound weapon keeps
+/// its damage color, so a frost atronach is still drawn in frost blue.
+impl HasIcon for SpellData {
+    fn color(&self) -> Color {
+        self.inv_color().color()
+    }
+
+    fn icon(&self) -> &Icon {
+        match self.archetype {
+            SpellArchetype::BoundWeapon => &Icon::WeaponSwordOneHanded,
+            SpellArchetype::SummonCreature | SpellArchetype::CommandSummoned => &Icon::SpellSummon,
+            SpellArchetype::Reanimate => &Icon::SpellReanimate,
+            SpellArchetype::Cloak => &Icon::SpellCircle,
+            SpellArchetype::Paralysis => &Icon::SpellParalyze,
+            SpellArchetype::CureDisease
+            | SpellArchetype::CurePoison
+            | SpellArchetype::CureParalysis => &Icon::SpellCure,
+            SpellArchetype::SoulTrap => &Icon::SpellSoultrap,
+            SpellArchetype::Light => &Icon::SpellLight,
+            SpellArchetype::DetectLife => &Icon::SpellDetect,
+            _ => match self.damage.icon() {
+                Some(icon) => icon,
+                None => match self.school {
+                    School::Alteration => &Icon::Alteration,
+                    School::Conjuration => &Icon::Conjuration,
+                    School::Destruction => &Icon::Destruction,
+                    School::Illusion => &Icon::Illusion,
+                    School::Restoration => &Icon::Restoration,
+                    School::None => &Icon::IconDefault,
+                },
+            },
+        }
+    }
 }
 
 impl std::fmt::Display for SpellData {
@@ -53,11 +118,34 @@ impl std::fmt::Display for SpellData {
         write!(
             f,
             "school='{}'; hostile={}; damage-type={}; level={}; archetype={};",
-            self.school, self.level, self.hostile, self.damage, self.archetype
+            self.school, self.hostile, self.damage, self.level, self.archetype
         )
     }
 }
 
+/// The hand a spell's equip slot allows. Most spells go in either hand; a few
+/// mod spells are made for one hand only. Spells that take both hands are
+/// marked two-handed instead.
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+pub enum SpellHand {
+    #[default]
+    Either,
+    LeftOnly,
+    RightOnly,
+}
+
+/// The bridge hands us 1 for the left-hand slot, 2 for the right, and 0 for
+/// anything else.
+impl From<u8> for SpellHand {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => SpellHand::LeftOnly,
+            2 => SpellHand::RightOnly,
+            _ => SpellHand::Either,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Display, Hash, Eq, PartialEq)]
 pub enum MagicCategory {
     #[default]
@@ -113,24 +201,24 @@ impl MagicCategory {
         }
     }
 
-    pub fn icon(&self) -> Option<Icon> {
+    pub fn icon(&self) -> Option<&'static Icon> {
         match self {
-            MagicCategory::Arcane => Some(Icon::SpellStars),
-            MagicCategory::ShockArc => Some(Icon::SpellArclight),
-            MagicCategory::Astral => Some(Icon::SpellStars),
-            MagicCategory::Bleed => Some(Icon::SpellBleed),
-            MagicCategory::FrostFire => Some(Icon::SpellFire),
-            MagicCategory::Earth => Some(Icon::SpellEarth),
-            MagicCategory::Fire => Some(Icon::SpellFire),
-            MagicCategory::Frost => Some(Icon::SpellFrost),
-            MagicCategory::Lunar => Some(Icon::SpellMoon),
-            MagicCategory::Necrotic => Some(Icon::SpellDesecration),
-            MagicCategory::Poison => Some(Icon::SpellPoison),
-            MagicCategory::Shadow => Some(Icon::SpellShadow),
-            MagicCategory::Shock => Some(Icon::SpellShock),
-            MagicCategory::Sun => Some(Icon::SpellSun),
-            MagicCategory::Water => Some(Icon::SpellWater),
-            MagicCategory::Wind => Some(Icon::SpellWind),
+            MagicCategory::Arcane => Some(&Icon::SpellStars),
+            MagicCategory::ShockArc => Some(&Icon::SpellArclight),
+            MagicCategory::Astral => Some(&Icon::SpellStars),
+            MagicCategory::Bleed => Some(&Icon::SpellBleed),
+            MagicCategory::FrostFire => Some(&Icon::SpellFire),
+            MagicCategory::Earth => Some(&Icon::SpellEarth),
+            MagicCategory::Fire => Some(&Icon::SpellFire),
+            MagicCategory::Frost => Some(&Icon::SpellFrost),
+            MagicCategory::Lunar => Some(&Icon::SpellMoon),
+            MagicCategory::Necrotic => Some(&Icon::SpellDesecration),
+            MagicCategory::Poison => Some(&Icon::SpellPoison),
+            MagicCategory::Shadow => Some(&Icon::SpellShadow),
+            MagicCategory::Shock => Some(&Icon::SpellShock),
+            MagicCategory::Sun => Some(&Icon::SpellSun),
+            MagicCategory::Water => Some(&Icon::SpellWater),
+            MagicCategory::Wind => Some(&Icon::SpellWind),
             _ => None,
         }
     }
@@ -148,6 +236,7 @@ pub enum School {
     None,
 }
 
+/// The bridge hands us the skill actor value of the spell's costliest effect.
 impl From<i32> for School {
     fn from(value: i32) -> Self {
         match value {
@@ -156,20 +245,41 @@ impl From<i32> for School {
             20 => School::Destruction,
             21 => School::Illusion,
             22 => School::Restoration,
-            _ => School::None,
+            -1 => School::None,
+            _ => {
+                log::debug!("Magic effect skill {value} is not a school of magic; using none.");
+                School::None
+            }
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Hash, Display, PartialEq, Eq)]
+/// Editor names for the schools, as some keyword schemes spell them out.
+impl FromStr for School {
+    type Err = eyre::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "alteration" => Ok(School::Alteration),
+            "conjuration" => Ok(School::Conjuration),
+            "destruction" => Ok(School::Destruction),
+            "illusion" => Ok(School::Illusion),
+            "restoration" => Ok(School::Restoration),
+            _ => Err(eyre::eyre!("not a school of magic: '{value}'")),
+        }
+    }
+}
+
+/// Spell ranks, in order, so they compare the way players expect.
+#[derive(Debug, Default, Clone, Hash, Display, PartialEq, Eq, PartialOrd, Ord)]
 #[strum(serialize_all = "lowercase")]
 pub enum MagicSpellLevel {
     #[default]
     Novice,
     Apprentice,
     Adept,
-    Master,
     Expert,
+    Master,
 }
 
 impl From<u32> for MagicSpellLevel {
@@ -188,6 +298,20 @@ impl From<u32> for MagicSpellLevel {
     }
 }
 
+impl MagicSpellLevel {
+    /// How many rank pips to draw over a spell's icon: none for novice
+    /// spells, up to four for master.
+    pub fn rank_pips(&self) -> u8 {
+        match self {
+            MagicSpellLevel::Novice => 0,
+            MagicSpellLevel::Apprentice => 1,
+            MagicSpellLevel::Adept => 2,
+            MagicSpellLevel::Expert => 3,
+            MagicSpellLevel::Master => 4,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, Display, PartialEq, Eq)]
 pub enum CastingType {
     ConstantEffect,
@@ -227,3 +351,78 @@ impl From<u32> for Delivery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schools_come_from_skills_or_names() {
+        assert_eq!(School::from(18), School::Alteration);
+        assert_eq!(School::from(19), School::Conjuration);
+        assert_eq!(School::from(20), School::Destruction);
+        assert_eq!(School::from(21), School::Illusion);
+        assert_eq!(School::from(22), School::Restoration);
+        // No skill, a skill that isn't magic, and what some mod spells report.
+        assert_eq!(School::from(-1), School::None);
+        assert_eq!(School::from(17), School::None);
+        assert_eq!(School::from(255), School::None);
+
+        for school in [
+            School::Alteration,
+            School::Conjuration,
+            School::Destruction,
+            School::Illusion,
+            School::Restoration,
+        ] {
+            assert_eq!(school.to_string().parse::<School>().ok(), Some(school));
+        }
+        assert_eq!(
+            "Destruction".parse::<School>().ok(),
+            Some(School::Destruction)
+        );
+        assert_eq!(" Illusion ".parse::<School>().ok(), Some(School::Illusion));
+        assert!("Mysticism".parse::<School>().is_err());
+        assert!("".parse::<School>().is_err());
+    }
+
+    #[test]
+    fn spell_levels_follow_minimum_skill() {
+        assert_eq!(MagicSpellLevel::from(0), MagicSpellLevel::Novice);
+        assert_eq!(MagicSpellLevel::from(25), MagicSpellLevel::Apprentice);
+        assert_eq!(MagicSpellLevel::from(50), MagicSpellLevel::Adept);
+        assert_eq!(MagicSpellLevel::from(74), MagicSpellLevel::Adept);
+        assert_eq!(MagicSpellLevel::from(75), MagicSpellLevel::Expert);
+        assert_eq!(MagicSpellLevel::from(100), MagicSpellLevel::Master);
+        assert!(MagicSpellLevel::Expert < MagicSpellLevel::Master);
+        assert!(MagicSpellLevel::Novice < MagicSpellLevel::Apprentice);
+        assert_eq!(MagicSpellLevel::Master.rank_pips(), 4);
+        // The names used in descriptions haven't changed.
+        assert_eq!(MagicSpellLevel::Expert.to_string(), "expert");
+    }
+
+    #[test]
+    fn spell_icons_prefer_archetype_then_damage_then_school() {
+        // A frost atronach: summon icon, frost color.
+        let mut atronach = SpellData::new(false, 0, false, 19, 50, 18);
+        atronach.damage = MagicCategory::Frost;
+        assert_eq!(atronach.icon(), &Icon::SpellSummon);
+        assert_eq!(atronach.inv_color(), InvColor::Frost);
+
+        let bound = SpellData::new(false, 0, false, 19, 25, 17);
+        assert_eq!(bound.icon(), &Icon::WeaponSwordOneHanded);
+        assert_eq!(bound.inv_color(), InvColor::Silver);
+
+        // A plain damage spell takes the damage icon over the school's.
+        let firebolt = SpellData::new(true, 41, false, 20, 25, 0);
+        assert_eq!(firebolt.icon(), &Icon::SpellFire);
+        assert_eq!(firebolt.inv_color(), InvColor::Fire);
+
+        // With nothing else to go on, the school decides.
+        let muffle = SpellData::new(false, 0, false, 21, 25, 0);
+        assert_eq!(muffle.icon(), &Icon::Illusion);
+        let unknown = SpellData::new(false, 0, false, -1, 0, 1);
+        assert_eq!(unknown.icon(), &Icon::IconDefault);
+        assert_eq!(unknown.color(), Color::default());
+    }
+}
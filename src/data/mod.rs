@@ -6,6 +6,7 @@ pub mod ammo;
 pub mod armor;
 pub mod base;
 pub mod color;
+pub mod description;
 pub mod food;
 pub mod game_enums;
 pub mod huditem;
@@ -14,6 +15,7 @@ pub mod keywords;
 pub mod magic;
 pub mod potion;
 pub mod power;
+pub mod reusable;
 pub mod shout;
 pub mod spell;
 pub mod weapon;
@@ -24,6 +26,7 @@ use enumset::{EnumSet, EnumSetType};
 pub use self::base::{BaseType, Proxy};
 use self::color::*;
 pub use self::huditem::HudItem;
+use self::magic::SpellHand;
 use self::potion::PotionType;
 use self::power::PowerType;
 use self::shout::ShoutType;
@@ -56,11 +59,12 @@ pub fn hud_item_from_keywords(
 
 pub fn categorize_shout(
     keywords_ffi: &CxxVector<CxxString>,
+    words: u32,
     name: String,
     form_string: String,
 ) -> Box<HudItem> {
     let keywords: Vec<String> = keywords_ffi.iter().map(|xs| xs.to_string()).collect();
-    let kind = BaseType::Shout(ShoutType::new(keywords));
+    let kind = BaseType::Shout(ShoutType::new(keywords).with_words(words));
     let result = HudItem::preclassified(name, form_string, 1, kind);
     Box::new(result)
 }
@@ -69,11 +73,13 @@ pub fn fill_out_spell_data(
     hostile: bool,
     resist: i32,
     twohanded: bool,
+    hand: u8,
     school: i32,
     level: u32,
     archetype: i32,
 ) -> Box<SpellData> {
-    let result = SpellData::new(hostile, resist, twohanded, school, level, archetype);
+    let result = SpellData::new(hostile, resist, twohanded, school, level, archetype)
+        .with_hand(SpellHand::from(hand));
     Box::new(result)
 }
 
@@ -116,12 +122,15 @@ pub fn simple_from_formdata(kind: ItemCategory, name: String, form_string: Strin
 pub fn potion_from_formdata(
     is_poison: bool,
     effect: i32,
+    archetype: i32,
+    magnitude: f32,
     count: u32,
     name: String,
     form_string: String,
 ) -> Box<HudItem> {
-    let kind = PotionType::from_effect(is_poison, effect.into());
-    let result = HudItem::preclassified(name, form_string, count, BaseType::Potion(kind));
+    let kind = PotionType::from_effect(is_poison, effect.into(), archetype.into());
+    let mut result = HudItem::preclassified(name, form_string, count, BaseType::Potion(kind));
+    result.set_magnitude(magnitude);
     Box::new(result)
 }
 
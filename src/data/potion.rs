@@ -2,7 +2,7 @@
 
 use strum::Display;
 
-use super::game_enums::ActorValue;
+use super::game_enums::{ActorValue, SpellArchetype};
 use super::magic::MagicCategory;
 use super::{HasIcon, InvColor};
 use crate::images::icons::Icon;
@@ -15,16 +15,28 @@ pub enum PotionType {
     Default,
     Poison,
     Resist(MagicCategory),
+    /// Cures disease, poison, or paralysis.
+    Cure,
     Health,
     Magicka,
     Stamina,
 }
 
 impl PotionType {
-    pub fn from_effect(is_poison: bool, effect: ActorValue) -> Self {
+    pub fn from_effect(is_poison: bool, effect: ActorValue, archetype: SpellArchetype) -> Self {
         if is_poison {
             return PotionType::Poison;
         }
+        // Cure effects don't touch an actor value, so the archetype is all we have.
+        if matches!(
+            archetype,
+            SpellArchetype::CureDisease
+                | SpellArchetype::CurePoison
+                | SpellArchetype::CureParalysis
+                | SpellArchetype::CureAddiction
+        ) {
+            return PotionType::Cure;
+        }
 
         match effect {
             ActorValue::Health => PotionType::Health,
@@ -55,6 +67,7 @@ impl HasIcon for PotionType {
         match self {
             PotionType::Poison => InvColor::Poison.color(),
             PotionType::Resist(t) => t.color().color(),
+            PotionType::Cure => InvColor::Holy.color(),
             PotionType::Health => InvColor::Red.color(),
             PotionType::Magicka => InvColor::Blue.color(),
             PotionType::Stamina => InvColor::Green.color(),
@@ -75,6 +88,7 @@ impl HasIcon for PotionType {
                 MagicCategory::Sun => &Icon::PotionResistFire,
                 _ => &Icon::PotionResist,
             },
+            PotionType::Cure => &Icon::PotionResist,
             PotionType::Health => &Icon::PotionHealth,
             PotionType::Magicka => &Icon::PotionMagicka,
             PotionType::Stamina => &Icon::PotionStamina,
@@ -82,3 +96,38 @@ impl HasIcon for PotionType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn potions_are_classified_by_effect_then_archetype() {
+        let health =
+            PotionType::from_effect(false, ActorValue::Health, SpellArchetype::ValueModifier);
+        assert_eq!(health, PotionType::Health);
+        assert_eq!(health.icon(), &Icon::PotionHealth);
+        assert_eq!(
+            PotionType::from_effect(
+                false,
+                ActorValue::MagickaRate,
+                SpellArchetype::ValueModifier
+            ),
+            PotionType::Magicka
+        );
+
+        let cure = PotionType::from_effect(false, ActorValue::None, SpellArchetype::CureDisease);
+        assert_eq!(cure, PotionType::Cure);
+        assert_eq!(cure.color(), InvColor::Holy.color());
+
+        // Poison wins over anything the effect says.
+        let poison =
+            PotionType::from_effect(true, ActorValue::Health, SpellArchetype::ValueModifier);
+        assert_eq!(poison, PotionType::Poison);
+        assert_eq!(poison.icon(), &Icon::PotionPoison);
+        assert_eq!(
+            PotionType::from_effect(false, ActorValue::None, SpellArchetype::Script),
+            PotionType::Default
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Things the player uses over and over without using them up: wearable
+//! lanterns, waterskins, and the like from survival and camping mods. The game
+//! sees these as armor, food, or plain misc items; keywords tell us they're
+//! meant to be used from the utility slot.
+//!
+//! Some of these swap themselves for a different form when used, like a full
+//! waterskin that becomes an empty one. The keyword that marked the item is its
+//! family, and the inventory handler follows a swap between two items of the
+//! same family instead of pruning the one that went away.
+
+use super::color::InvColor;
+use super::{HasIcon, HasKeywords};
+use crate::controller::settings::settings;
+use crate::images::icons::Icon;
+use crate::plugin::Color;
+
+/// Keywords that mark reusable items no matter what the settings say.
+const BUILTIN_KEYWORDS: [&str; 2] = ["_WL_Lantern", "_SHWaterskin"];
+
+/// Icon selection, inventory color, and the keyword family for one item.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Default)]
+pub struct ReusableType {
+    icon: Icon,
+    color: InvColor,
+    family: String,
+}
+
+impl ReusableType {
+    /// The family an item with these keywords belongs to, if it's reusable.
+    /// A family is either a single keyword from the built-in list or from
+    /// `sReusableKeywords`, or a `full:empty` pair from `sReusableSwaps`.
+    pub fn family_for(keywords: &[String]) -> Option<String> {
+        let settings = settings();
+        family_from_lists(
+            keywords,
+            settings.reusable_keywords(),
+            settings.reusable_swaps(),
+        )
+    }
+
+    /// The keyword family, used to follow an item across a form swap.
+    pub fn family(&self) -> &str {
+        self.family.as_str()
+    }
+}
+
+fn family_from_lists(keywords: &[String], singles: &str, pairs: &str) -> Option<String> {
+    let has = |kwd: &str| keywords.iter().any(|xs| xs.as_str() == kwd);
+    let swap = pairs
+        .split(',')
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(full, empty)| (full.trim(), empty.trim()))
+        .find(|(full, empty)| has(full) || has(empty))
+        .map(|(full, empty)| format!("{full}:{empty}"));
+    swap.or_else(|| {
+        BUILTIN_KEYWORDS
+            .into_iter()
+            .chain(singles.split(',').map(|xs| xs.trim()))
+            .find(|kwd| !kwd.is_empty() && has(kwd))
+            .map(|kwd| kwd.to_string())
+    })
+}
+
+impl std::fmt::Display for ReusableType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Reusable: icon={}; color='{}'; family='{}';",
+            self.icon, self.color, self.family
+        )
+    }
+}
+
+impl HasIcon for ReusableType {
+    fn color(&self) -> Color {
+        self.color.color()
+    }
+
+    fn icon(&self) -> &Icon {
+        &self.icon
+    }
+}
+
+impl HasKeywords for ReusableType {
+    fn classify(name: &str, keywords: Vec<String>, _twohanded: bool) -> Self {
+        let color = super::color::color_from_keywords(&keywords).unwrap_or_default();
+        let family = Self::family_for(&keywords).unwrap_or_default();
+        // Mod keywords don't follow any scheme, so we go by what they say.
+        let lowered = format!("{} {}", family, name).to_lowercase();
+        let icon = if lowered.contains("lantern") {
+            Icon::MiscLantern
+        } else if lowered.contains("water") || lowered.contains("skin") {
+            Icon::DrinkWater
+        } else if lowered.contains("tent") {
+            Icon::MiscTent
+        } else if lowered.contains("campfire") {
+            Icon::MiscCampfire
+        } else if lowered.contains("lute") {
+            Icon::MiscLute
+        } else {
+            log::debug!("Falling back to the default icon for a reusable item: name='{name}'; keywords={keywords:?}");
+            Icon::IconDefault
+        };
+        Self {
+            icon,
+            color,
+            family,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_and_swap_pairs_make_families() {
+        let waterskin = vec!["VendorItemFood".to_string(), "WaterskinEmpty".to_string()];
+        let pairs = "WaterskinFull:WaterskinEmpty, FlaskLit:FlaskUnlit";
+        assert_eq!(
+            family_from_lists(&waterskin, "", pairs),
+            Some("WaterskinFull:WaterskinEmpty".to_string())
+        );
+        assert_eq!(family_from_lists(&waterskin, "", ""), None);
+        assert_eq!(
+            family_from_lists(&waterskin, "Bedroll, WaterskinEmpty", ""),
+            Some("WaterskinEmpty".to_string())
+        );
+
+        let lantern = vec!["_WL_Lantern".to_string(), "ArmorClothing".to_string()];
+        assert_eq!(
+            family_from_lists(&lantern, "", ""),
+            Some("_WL_Lantern".to_string())
+        );
+        let kind = ReusableType::classify("Travel Lantern", lantern, false);
+        assert_eq!(kind.icon(), &Icon::MiscLantern);
+        assert_eq!(kind.family(), "_WL_Lantern");
+    }
+}
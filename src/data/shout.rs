@@ -13,6 +13,8 @@ pub struct ShoutType {
     icon: Icon,
     color: InvColor,
     variant: ShoutVariant,
+    /// How many of the shout's words the player has unlocked, 0 if unknown.
+    words: u8,
 }
 
 impl Default for ShoutType {
@@ -21,6 +23,7 @@ impl Default for ShoutType {
             icon: Icon::Shout,
             color: InvColor::default(),
             variant: ShoutVariant::default(),
+            words: 0,
         }
     }
 }
@@ -80,6 +83,7 @@ impl ShoutType {
             icon,
             color,
             variant,
+            words: 0,
         }
     }
 
@@ -88,9 +92,21 @@ impl ShoutType {
             icon,
             color,
             variant,
+            words: 0,
         }
     }
 
+    /// Record how many words of power the player has unlocked. Shouts have
+    /// three at most.
+    pub fn with_words(mut self, words: u32) -> Self {
+        self.words = words.min(3) as u8;
+        self
+    }
+
+    pub fn words(&self) -> u8 {
+        self.words
+    }
+
     pub fn variant(&self) -> &ShoutVariant {
         &self.variant
     }
@@ -568,4 +584,15 @@ mod tests {
             .collect();
         assert!(unused.is_empty());
     }
+
+    #[test]
+    fn unlocked_words_are_capped_at_three() {
+        let shout = ShoutType::new(vec!["Shout_UnrelentingForce".to_string()]);
+        assert_eq!(shout.words(), 0);
+        let two = shout.clone().with_words(2);
+        assert_eq!(two.words(), 2);
+        // The same shout with more words unlocked is a different item to the HUD.
+        assert_ne!(two, shout);
+        assert_eq!(shout.with_words(7).words(), 3);
+    }
 }
@@ -15,7 +15,7 @@ use enumset::EnumSet;
 
 use super::color::{color_from_keywords, InvColor};
 use super::keywords::*;
-use super::magic::{School, SpellData};
+use super::magic::{MagicCategory, School, SpellData, SpellHand};
 use super::{strings_to_enumset, HasIcon};
 use crate::images::icons::Icon;
 use crate::plugin::Color;
@@ -28,27 +28,26 @@ pub struct SpellType {
 }
 
 impl SpellType {
-    pub fn new(data: SpellData, tags: Vec<String>) -> Self {
+    pub fn new(mut data: SpellData, tags: Vec<String>) -> Self {
         let tagset: EnumSet<SpellKeywords> = strings_to_enumset(&tags);
 
+        // Damage type. Keywords know better than the resist actor value,
+        // which mod spells often leave unset.
+        if let Some(damage) = damage_for_tagset(&tagset) {
+            data.damage = damage;
+        }
+
         // Icons. We look to see if the keywords contain any of the words that
         // match certain known icon art sets. If we have a specific icon for
         // a spell type, e.g. cloak spells, we use that. We then try to use an
         // icon for a mod spell pack, e.g., constellation. If all else fails,
-        // we use the icon for the magic school.
+        // the spell data picks one from its archetype, damage type, or school.
         let icon = if let Some(icon) = icon_for_tagset(&tagset) {
             icon
         } else {
-            log::debug!("Falling back to magic school for spell; data: {data:?}");
+            log::debug!("Falling back to spell data for icon; data: {data:?}");
             log::debug!("    keywords: {tags:?}");
-            match data.school {
-                School::Alteration => Icon::Alteration,
-                School::Conjuration => Icon::Conjuration,
-                School::Destruction => Icon::Destruction,
-                School::Illusion => Icon::Illusion,
-                School::Restoration => Icon::Restoration,
-                School::None => Icon::IconDefault,
-            }
+            data.icon().clone()
         };
 
         // Colors. We base this on damage type, mostly, but first we look to see
@@ -58,27 +57,31 @@ impl SpellType {
         } else if let Some(c) = color_for_tagset(&tagset) {
             c
         } else {
-            match data.school {
-                // TODO identify common colors for magical schools
-                School::Alteration => InvColor::Eldritch,
-                School::Conjuration => InvColor::Silver,
-                School::Destruction => InvColor::Fire,
-                School::Illusion => InvColor::Blue,
-                School::Restoration => InvColor::Green,
-                School::None => {
-                    log::debug!("no color specified for spell; keywords={tags:?};");
-                    InvColor::default()
-                }
+            if matches!(data.school, School::None) && matches!(data.damage, MagicCategory::None) {
+                log::debug!("no color specified for spell; keywords={tags:?};");
             }
+            data.inv_color()
         };
 
         Self { icon, color, data }
     }
 
+    pub fn data(&self) -> &SpellData {
+        &self.data
+    }
+
     pub fn is_two_handed(&self) -> bool {
         self.data.twohanded
     }
 
+    pub fn left_hand_ok(&self) -> bool {
+        !self.data.twohanded && self.data.hand != SpellHand::RightOnly
+    }
+
+    pub fn right_hand_ok(&self) -> bool {
+        self.data.hand != SpellHand::LeftOnly
+    }
+
     pub fn icon_fallback(&self) -> Icon {
         match self.data.school {
             School::Alteration => Icon::Alteration,
@@ -12,6 +12,7 @@ use enumset::{enum_set, EnumSet, EnumSetType};
 use strum::EnumString;
 
 use super::color::InvColor;
+use super::magic::MagicCategory;
 use super::{strings_to_enumset, HasIcon, HasKeywords};
 use crate::images::icons::Icon;
 use crate::plugin::Color;
@@ -76,6 +77,37 @@ impl WeaponType {
     pub fn is_two_handed(&self) -> bool {
         matches!(self.equiptype, WeaponEquipType::TwoHanded)
     }
+
+    /// Bows and crossbows need ammo. Returns whether this weapon shoots bolts,
+    /// or None if it shoots nothing.
+    pub fn shoots_bolts(&self) -> Option<bool> {
+        match self.icon {
+            Icon::WeaponBow => Some(false),
+            Icon::WeaponCrossbow => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Gathering tools: pickaxes, wood axes, and fishing rods.
+    /// The damage type this weapon's enchantment deals, going by its color
+    /// keywords. None for weapons whose color says nothing about damage.
+    pub fn element(&self) -> Option<MagicCategory> {
+        match self.color {
+            InvColor::Fire | InvColor::FireVolcanic => Some(MagicCategory::Fire),
+            InvColor::Frost => Some(MagicCategory::Frost),
+            InvColor::Shock | InvColor::ShockArc => Some(MagicCategory::Shock),
+            InvColor::Poison => Some(MagicCategory::Poison),
+            InvColor::Holy | InvColor::Sun => Some(MagicCategory::Sun),
+            _ => None,
+        }
+    }
+
+    pub fn is_tool(&self) -> bool {
+        matches!(
+            self.icon,
+            Icon::ToolPickaxe | Icon::WeaponWoodAxe | Icon::ToolFishingRod
+        )
+    }
 }
 
 impl HasKeywords for WeaponType {
@@ -197,7 +229,8 @@ const BATTLEAXES: EnumSet<WeaponTag> = enum_set!(
         | WeaponTag::OCF_WeapTypeWarpick2H
 );
 const BOWS: EnumSet<WeaponTag> = enum_set!(
-    WeaponTag::OCF_WeapTypeBlowgun2H
+    WeaponTag::WeapTypeBow
+        | WeaponTag::OCF_WeapTypeBlowgun2H
         | WeaponTag::OCF_WeapTypeBow
         | WeaponTag::OCF_WeapTypeBow2H
         | WeaponTag::OCF_WeapTypeGun
@@ -211,7 +244,8 @@ const CROSSBOWS: EnumSet<WeaponTag> = enum_set!(
         | WeaponTag::OCF_WeapTypeCrossbow2H
 );
 const DAGGERS: EnumSet<WeaponTag> = enum_set!(
-    WeaponTag::OCF_WeapTypeChakram1H
+    WeaponTag::WeapTypeDagger
+        | WeaponTag::OCF_WeapTypeChakram1H
         | WeaponTag::OCF_WeapTypeCutlery1H
         | WeaponTag::OCF_WeapTypeDagger1H
         | WeaponTag::OCF_WeapTypeKunai1H
@@ -226,7 +260,8 @@ const DAGGERS: EnumSet<WeaponTag> = enum_set!(
         | WeaponTag::OCF_WeapTypeTwinDagger1H
 );
 const GREATSWORDS: EnumSet<WeaponTag> = enum_set!(
-    WeaponTag::OCF_WeapTypeMassiveSword2H
+    WeaponTag::WeapTypeGreatsword
+        | WeaponTag::OCF_WeapTypeMassiveSword2H
         | WeaponTag::OCF_WeapTypeSaber2H
         | WeaponTag::OCF_WeapTypeScimitar2H
         | WeaponTag::OCF_WeapTypeTwinblade2H
@@ -283,7 +318,8 @@ const LANCES: EnumSet<WeaponTag> = enum_set!(
         | WeaponTag::OCF_WeapTypeTrident2H
 );
 const MACES: EnumSet<WeaponTag> = enum_set!(
-    WeaponTag::OCF_WeapTypeBoomerang1H
+    WeaponTag::WeapTypeMace
+        | WeaponTag::OCF_WeapTypeBoomerang1H
         | WeaponTag::OCF_WeapTypeCestus1H
         | WeaponTag::OCF_WeapTypeClub1H
         | WeaponTag::OCF_WeapTypeMace1H
@@ -312,7 +348,8 @@ const RAPIERS: EnumSet<WeaponTag> =
     enum_set!(WeaponTag::OCF_WeapTypeRapier1H | WeaponTag::OCF_WeapTypeRapier2H);
 
 const SWORDS: EnumSet<WeaponTag> = enum_set!(
-    WeaponTag::OCF_WeapTypeSaber1H
+    WeaponTag::WeapTypeSword
+        | WeaponTag::OCF_WeapTypeSaber1H
         | WeaponTag::OCF_WeapTypeScimitar1H
         | WeaponTag::OCF_WeapTypeSword1H
         | WeaponTag::OCF_WeapTypeTwinblade1H
@@ -322,7 +359,8 @@ const STAVES: EnumSet<WeaponTag> =
     enum_set!(WeaponTag::Staff | WeaponTag::WeapTypeStaff | WeaponTag::OCF_WeapTypeBlankStaff);
 
 const WARAXES: EnumSet<WeaponTag> = enum_set!(
-    WeaponTag::OCF_WeapTypeCleaver1H
+    WeaponTag::WeapTypeWarAxe
+        | WeaponTag::OCF_WeapTypeCleaver1H
         | WeaponTag::OCF_WeapTypeCrescent1H
         | WeaponTag::OCF_WeapTypeHandBlade1H
         | WeaponTag::OCF_WeapTypeHatchet1H
@@ -557,4 +595,66 @@ mod tests {
         assert_eq!(result.color, InvColor::Fire);
         assert_eq!(result.icon, Icon::WeaponSwordTwoHanded);
     }
+
+    #[test]
+    fn animation_type_and_enchantment_fill_in_missing_keywords() {
+        // A mod dagger with no type keyword, enchanted with frost damage. The
+        // plugin adds keywords from its animation type and enchantment.
+        let input = vec![
+            "Weapon".to_string(),
+            "WeapTypeDagger".to_string(),
+            "OCF_InvColorFrost".to_string(),
+        ];
+        let result = WeaponType::classify("Icy Shiv", input, false);
+        assert_eq!(result.icon, Icon::WeaponDagger);
+        assert_eq!(result.element(), Some(MagicCategory::Frost));
+
+        // A color keyword the weapon already had wins over the enchantment's.
+        let input = vec![
+            "OCF_WeapTypeKatana1H".to_string(),
+            "OCF_InvColorBlood".to_string(),
+            "OCF_InvColorFire".to_string(),
+        ];
+        let result = WeaponType::classify("Bloody Katana", input, false);
+        assert_eq!(result.icon, Icon::WeaponKatana);
+        assert_eq!(result.color, InvColor::Blood);
+
+        // Mod weapon types come before the animation type's vanilla keyword.
+        let input = vec![
+            "OCF_WeapTypeWhip1H".to_string(),
+            "WeapTypeSword".to_string(),
+        ];
+        let result = WeaponType::classify("Whip", input, false);
+        assert_eq!(result.icon, Icon::WeaponWhip);
+
+        // Every keyword the plugin can add from an animation type means something.
+        for (keyword, icon) in [
+            ("WeapTypeSword", Icon::WeaponSwordOneHanded),
+            ("WeapTypeWarAxe", Icon::WeaponAxeOneHanded),
+            ("WeapTypeMace", Icon::WeaponMace),
+            ("WeapTypeGreatsword", Icon::WeaponSwordTwoHanded),
+            ("WeapTypeBattleaxe", Icon::WeaponAxeTwoHanded),
+            ("WeapTypeBow", Icon::WeaponBow),
+            ("WeapTypeStaff", Icon::WeaponStaff),
+            ("WeapTypeCrossbow", Icon::WeaponCrossbow),
+        ] {
+            let result = WeaponType::classify("Plain", vec![keyword.to_string()], false);
+            assert_eq!(result.icon, icon, "{keyword}");
+        }
+    }
+
+    #[test]
+    fn tools_are_detected() {
+        let input = vec!["OCF_WeapTypePickaxe1H".to_string(), "Weapon".to_string()];
+        let result = WeaponType::classify("Pickaxe", input, false);
+        assert!(result.is_tool());
+
+        let input = vec!["OCF_WeapTypeWoodaxe1H".to_string()];
+        let result = WeaponType::classify("Woodcutter's Axe", input, false);
+        assert!(result.is_tool());
+
+        let input = vec!["OCF_WeapTypeLongsword2H".to_string()];
+        let result = WeaponType::classify("Greatsword", input, true);
+        assert!(!result.is_tool());
+    }
 }
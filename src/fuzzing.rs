@@ -0,0 +1,102 @@
+//! Seeded mutation fuzzing for the parsers that read files players edit by
+//! hand: the settings ini and the layout toml. We can't link a cargo-fuzz
+//! target against a staticlib, so this does the same job inside `cargo test`.
+//! Seeds come from the test fixtures and shipped layouts; each is mangled
+//! many times over with a fixed rng seed, so any failure reproduces exactly.
+//! Set `SOULSY_FUZZ_ITERATIONS` to fuzz harder than the default.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// No mutated input grows past this, so no single case can eat memory.
+pub const MAX_INPUT_BYTES: usize = 64 * 1024;
+
+/// Fragments that parsers find interesting.
+#[rustfmt::skip]
+const TOKENS: &[&str] = &[
+    "=", "[", "]", "[[", "]]", "\"", "'", "\\", "\n", "\r\n", ";", "#", "{", "}", ",", ".", "-",
+    "0", "-1", "nan", "NaN", "inf", "-inf", "1e39", "4294967296", "99999999999999999999",
+    "true", "\u{0}", "\u{feff}", "ÿ", "日本", "🗡",
+];
+
+/// How many mutants to try per seed.
+pub fn iterations() -> usize {
+    std::env::var("SOULSY_FUZZ_ITERATIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(200)
+}
+
+/// A repeatable rng for a fuzz run.
+pub fn rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Read every file with this extension in the given directories.
+pub fn seeds(dirs: &[&str], extension: &str) -> Vec<(String, String)> {
+    let mut found = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Ok(bytes) = std::fs::read(&path) {
+                found.push((
+                    path.to_string_lossy().into_owned(),
+                    String::from_utf8_lossy(&bytes).into_owned(),
+                ));
+            }
+        }
+    }
+    found
+}
+
+/// Mangle the input a few times over.
+pub fn mutate(input: &str, rng: &mut StdRng) -> String {
+    let mut bytes = input.as_bytes().to_vec();
+    for _ in 0..rng.gen_range(1..=4) {
+        mutate_once(&mut bytes, rng);
+    }
+    bytes.truncate(MAX_INPUT_BYTES);
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn mutate_once(bytes: &mut Vec<u8>, rng: &mut StdRng) {
+    let len = bytes.len();
+    let at = if len == 0 { 0 } else { rng.gen_range(0..len) };
+    match rng.gen_range(0..6) {
+        0 if len > 0 => bytes[at] = rng.gen(),
+        1 => {
+            let token = TOKENS[rng.gen_range(0..TOKENS.len())];
+            bytes.splice(at..at, token.bytes());
+        }
+        2 if len > 0 => {
+            let end = (at + rng.gen_range(1..=64)).min(len);
+            bytes.drain(at..end);
+        }
+        3 if len > 0 => {
+            let end = (at + rng.gen_range(1..=256)).min(len);
+            let chunk = bytes[at..end].to_vec();
+            let to = rng.gen_range(0..=len);
+            bytes.splice(to..to, chunk);
+        }
+        4 => bytes.truncate(at),
+        _ => {
+            // Swap a line for another, which keeps the syntax mostly intact
+            // while putting keys in the wrong sections.
+            let mut lines: Vec<&[u8]> = bytes.split(|b| *b == b'\n').collect();
+            if lines.len() > 1 {
+                let a = rng.gen_range(0..lines.len());
+                let b = rng.gen_range(0..lines.len());
+                lines.swap(a, b);
+                let swapped = lines.join(&b'\n');
+                *bytes = swapped;
+            }
+        }
+    }
+}
@@ -2,11 +2,77 @@
 
 #include "gear.h"
 #include "helpers.h"
+#include "player.h"
+#include "shouts.h"
 
 #include "lib.rs.h"
 
 namespace equippable
 {
+	// Mod weapons often leave off the vanilla type keyword. The animation type
+	// is always set, so it stands in for the keyword the weapon lacks.
+	const char* keywordForAnimationType(RE::WEAPON_TYPE animType)
+	{
+		switch (animType)
+		{
+			case RE::WEAPON_TYPE::kHandToHandMelee: return "HandToHandMelee";
+			case RE::WEAPON_TYPE::kOneHandSword: return "WeapTypeSword";
+			case RE::WEAPON_TYPE::kOneHandDagger: return "WeapTypeDagger";
+			case RE::WEAPON_TYPE::kOneHandAxe: return "WeapTypeWarAxe";
+			case RE::WEAPON_TYPE::kOneHandMace: return "WeapTypeMace";
+			case RE::WEAPON_TYPE::kTwoHandSword: return "WeapTypeGreatsword";
+			case RE::WEAPON_TYPE::kTwoHandAxe: return "WeapTypeBattleaxe";
+			case RE::WEAPON_TYPE::kBow: return "WeapTypeBow";
+			case RE::WEAPON_TYPE::kStaff: return "WeapTypeStaff";
+			case RE::WEAPON_TYPE::kCrossbow: return "WeapTypeCrossbow";
+			default: return nullptr;
+		}
+	}
+
+	// The color keyword for the damage an enchantment deals, if it deals any.
+	const char* colorKeywordForEnchantment(const RE::TESObjectWEAP* weapon)
+	{
+		const auto* enchantment = weapon->formEnchanting;
+		if (!enchantment) { return nullptr; }
+		const auto* costliest = enchantment->GetCostliestEffectItem();
+		if (!costliest || !costliest->baseEffect) { return nullptr; }
+
+		switch (costliest->baseEffect->data.resistVariable)
+		{
+			case RE::ActorValue::kResistFire: return "OCF_InvColorFire";
+			case RE::ActorValue::kResistFrost: return "OCF_InvColorFrost";
+			case RE::ActorValue::kResistShock: return "OCF_InvColorShock";
+			case RE::ActorValue::kPoisonResist: return "OCF_InvColorPoison";
+			default: return nullptr;
+		}
+	}
+
+	// Vanilla and many mod armors only say what slot they fill and how heavy they
+	// are through their form data, not keywords. These stand in for the keywords.
+	const char* keywordForArmorWeight(const RE::TESObjectARMO* armor)
+	{
+		switch (armor->GetArmorType())
+		{
+			case RE::BIPED_MODEL::ArmorType::kLightArmor: return "ArmorLight";
+			case RE::BIPED_MODEL::ArmorType::kHeavyArmor: return "ArmorHeavy";
+			default: return nullptr;
+		}
+	}
+
+	const char* keywordForArmorSlot(const RE::TESObjectARMO* armor)
+	{
+		using Slot = RE::BGSBipedObjectForm::BipedObjectSlot;
+		if (armor->HasPartOf(Slot::kShield)) { return "ArmorShield"; }
+		if (armor->HasPartOf(Slot::kBody)) { return "ArmorCuirass"; }
+		if (armor->HasPartOf(Slot::kHead) || armor->HasPartOf(Slot::kHair)) { return "ArmorHelmet"; }
+		if (armor->HasPartOf(Slot::kHands)) { return "ArmorGauntlets"; }
+		if (armor->HasPartOf(Slot::kFeet)) { return "ArmorBoots"; }
+		if (armor->HasPartOf(Slot::kAmulet)) { return "ClothingNecklace"; }
+		if (armor->HasPartOf(Slot::kRing)) { return "ClothingRing"; }
+		if (armor->HasPartOf(Slot::kCirclet)) { return "ClothingCirclet"; }
+		return nullptr;
+	}
+
 	bool requiresTwoHands(RE::TESForm*& item_form)
 	{
 		if (!item_form) { return false; }
@@ -55,7 +121,19 @@ namespace equippable
 		return RE::ActorValue::kNone;
 	}
 
-	rust::Box<SpellData> fillOutSpellData(bool twoHanded, int32_t skill_level, const RE::EffectSetting* effect)
+	uint8_t handRestriction(RE::TESForm*& item_form)
+	{
+		if (!item_form) { return 0; }
+		const auto* equipType = item_form->As<RE::BGSEquipType>();
+		if (!equipType) { return 0; }
+		const auto* slot = equipType->GetEquipSlot();
+		if (slot && slot == gear::left_hand_equip_slot()) { return 1; }
+		if (slot && slot == gear::right_hand_equip_slot()) { return 2; }
+		return 0;
+	}
+
+	rust::Box<SpellData>
+		fillOutSpellData(bool twoHanded, uint8_t hand, int32_t skill_level, const RE::EffectSetting* effect)
 	{
 		auto isHostile = effect->IsHostile();
 		auto archetype = effect->data.archetype;
@@ -65,6 +143,7 @@ namespace equippable
 		rust::Box<SpellData> data = fill_out_spell_data(isHostile,
 			static_cast<std::underlying_type_t<RE::ActorValue>>(resist),
 			twoHanded,
+			hand,
 			static_cast<std::underlying_type_t<RE::ActorValue>>(school),
 			skill_level,
 			static_cast<std::underlying_type_t<RE::EffectSetting::Archetype>>(archetype));
@@ -75,6 +154,19 @@ namespace equippable
 	{
 		if (!form) { return empty_huditem(); }
 
+		auto item = categorizeForm(form);
+		RE::TESBoundObject* boundObject = nullptr;
+		RE::ExtraDataList* extraData    = nullptr;
+		gear::boundObjectForForm(form, boundObject, extraData);
+		item->set_weight(boundObject ? boundObject->GetWeight() : form->GetWeight());
+		item->set_quest_item(player::isQuestItem(form));
+		return item;
+	}
+
+	rust::Box<HudItem> categorizeForm(RE::TESForm* form)
+	{
+		if (!form) { return empty_huditem(); }
+
 		RE::TESBoundObject* boundObject = nullptr;
 		RE::ExtraDataList* extraData    = nullptr;
 		const auto count                = gear::boundObjectForForm(form, boundObject, extraData);
@@ -83,6 +175,7 @@ namespace equippable
 		std::string formSpec =
 			boundObject ? helpers::makeFormSpecString(boundObject) : helpers::makeFormSpecString(form);
 		bool twoHanded = requiresTwoHands(form);
+		uint8_t hand   = handRestriction(form);
 
 		KeywordAccumulator::clear();
 
@@ -97,7 +190,8 @@ namespace equippable
 
 			spell->ForEachKeyword(KeywordAccumulator::collect);
 			auto& keywords = KeywordAccumulator::mKeywords;
-			return categorize_shout(*keywords, std::move(safename), formSpec);
+			const auto words = shouts::unlockedWordCount(shout);
+			return categorize_shout(*keywords, words, std::move(safename), formSpec);
 		}
 
 		if (form->Is(RE::FormType::Spell))
@@ -135,7 +229,7 @@ namespace equippable
 					effect->ForEachKeyword(KeywordAccumulator::collect);
 					auto& keywords          = KeywordAccumulator::mKeywords;
 					auto skill_level        = effect->GetMinimumSkillLevel();
-					auto data               = fillOutSpellData(twoHanded, skill_level, effect);
+					auto data               = fillOutSpellData(twoHanded, hand, skill_level, effect);
 					rust::Box<HudItem> item = magic_from_spelldata(
 						ItemCategory::Spell, std::move(data), *keywords, std::move(safename), formSpec, 1);
 					return item;
@@ -164,6 +258,13 @@ namespace equippable
 				weapon->ForEachKeyword(KeywordAccumulator::collect);
 				auto& keywords = KeywordAccumulator::mKeywords;
 				if (weapon->IsBound()) { keywords->push_back(std::string("OCF_InvColorBound")); }
+				// These go last so that real keywords win over them.
+				const bool typed = std::ranges::any_of(
+					*keywords, [](const std::string& kwd) { return kwd.starts_with("WeapType"); });
+				const auto* animKeyword = keywordForAnimationType(weapon->GetWeaponType());
+				if (!typed && animKeyword) { keywords->push_back(std::string(animKeyword)); }
+				const auto* tint = colorKeywordForEnchantment(weapon);
+				if (tint) { keywords->push_back(std::string(tint)); }
 				rust::Box<HudItem> item = hud_item_from_keywords(
 					ItemCategory::Weapon, *keywords, std::move(safename), formSpec, count, twoHanded);
 
@@ -177,6 +278,11 @@ namespace equippable
 			const auto* armor = form->As<RE::TESObjectARMO>();
 			armor->ForEachKeyword(KeywordAccumulator::collect);
 			auto& keywords = KeywordAccumulator::mKeywords;
+			// As with weapons, these go last so the armor's own keywords win.
+			const auto* slotKeyword = keywordForArmorSlot(armor);
+			if (slotKeyword) { keywords->push_back(std::string(slotKeyword)); }
+			const auto* weightKeyword = keywordForArmorWeight(armor);
+			if (weightKeyword) { keywords->push_back(std::string(weightKeyword)); }
 			rust::Box<HudItem> item =
 				hud_item_from_keywords(ItemCategory::Armor, *keywords, std::move(safename), formSpec, count, false);
 
@@ -213,7 +319,7 @@ namespace equippable
 				auto& keywords  = KeywordAccumulator::mKeywords;
 				auto skillLevel = effect->GetMinimumSkillLevel();
 
-				auto data               = fillOutSpellData(twoHanded, skillLevel, effect);
+				auto data               = fillOutSpellData(twoHanded, hand, skillLevel, effect);
 				rust::Box<HudItem> item = magic_from_spelldata(
 					ItemCategory::Scroll, std::move(data), *keywords, std::move(safename), formSpec, count);
 				return item;
@@ -236,10 +342,13 @@ namespace equippable
 			else
 			{
 				rlog::trace("making HudItem for potion: '{}'"sv, safename);
-				const auto* effect      = alchemy_potion->GetCostliestEffectItem()->baseEffect;
+				const auto* costliest   = alchemy_potion->GetCostliestEffectItem();
+				const auto* effect      = costliest->baseEffect;
 				auto actor_value        = effect->data.primaryAV;
 				rust::Box<HudItem> item = potion_from_formdata(alchemy_potion->IsPoison(),
 					static_cast<int32_t>(actor_value),
+					static_cast<std::underlying_type_t<RE::EffectSetting::Archetype>>(effect->data.archetype),
+					costliest->GetMagnitude(),
 					count,
 					std::move(safename),
 					formSpec);
@@ -254,9 +363,22 @@ namespace equippable
 			return item;
 		}
 
+		// Most misc items are junk as far as the HUD is concerned, but camping and
+		// survival mods hang their reusable tools on this form type. Rust decides.
+		if (form->Is(RE::FormType::Misc))
+		{
+			rlog::trace("making HudItem for misc item: '{}';"sv, safename);
+			const auto* misc = form->As<RE::TESObjectMISC>();
+			misc->ForEachKeyword(KeywordAccumulator::collect);
+			auto& keywords = KeywordAccumulator::mKeywords;
+			rust::Box<HudItem> item =
+				hud_item_from_keywords(ItemCategory::Misc, *keywords, std::move(safename), formSpec, count, false);
+			return item;
+		}
+
 		const auto formtype    = form->GetFormType();
 		const auto formtypestr = RE::FormTypeToString(formtype);
-		rlog::debug("hudItemFromForm() fell all the way through; type={}; name='{}'; formspec='{}';",
+		rlog::debug("categorizeForm() fell all the way through; type={}; name='{}'; formspec='{}';",
 			formtypestr,
 			safename,
 			formSpec);
@@ -29,9 +29,13 @@ inline const std::set<RE::FormType> RELEVANT_FORMTYPES_INVENTORY{
 namespace equippable
 {
 	rust::Box<HudItem> hudItemFromForm(RE::TESForm* form);
-	rust::Box<SpellData> fillOutSpellData(bool two_handed, int32_t skill_level, const RE::EffectSetting* effect);
+	rust::Box<HudItem> categorizeForm(RE::TESForm* form);
+	rust::Box<SpellData>
+		fillOutSpellData(bool two_handed, uint8_t hand, int32_t skill_level, const RE::EffectSetting* effect);
 
 	bool requiresTwoHands(RE::TESForm*& form);
+	// 1 if the game only lets this item go in the left hand, 2 for the right, 0 otherwise.
+	uint8_t handRestriction(RE::TESForm*& form);
 	RE::ActorValue getPotionEffect(RE::TESForm* form, bool filter);
 
 	struct KeywordAccumulator
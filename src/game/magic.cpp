@@ -45,7 +45,7 @@ namespace game
 					rlog::warn(
 						"power/shout {} is equipped, will only cast spell in elden mode if shout slot is empty. return."sv,
 						helpers::nameAsUtf8(selected_power));
-					RE::DebugNotification("Shout Slot not Empty, Skipping Spellcast");
+					helpers::notifyPlayer(std::string(helpers::lookupTranslation("$SoulsyHUD_ShoutSlotBusy_Msg")));
 					return;
 				}
 			}
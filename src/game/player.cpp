@@ -38,6 +38,55 @@ namespace player
 		return false;
 	}
 
+	bool isPlayerAttacking()
+	{
+		auto* the_player = RE::PlayerCharacter::GetSingleton();
+		if (!the_player) { return false; }
+		const auto* state = the_player->AsActorState();
+		if (!state) { return false; }
+		return state->GetAttackState() != RE::ATTACK_STATE_ENUM::kNone;
+	}
+
+	void reportPlayerVitals()
+	{
+		// Start out alive, as the Rust side does, so we only speak up on a change.
+		static bool lastAlive     = true;
+		static bool lastBleeding  = false;
+		static bool lastStaggered = false;
+		static bool lastControls  = true;
+		static bool lastWeapons   = true;
+
+		auto* the_player = RE::PlayerCharacter::GetSingleton();
+		if (!the_player) { return; }
+
+		// Cutscenes and scripted scenes take controls away; beast forms have no use for weapons.
+		if (const auto* controlMap = RE::ControlMap::GetSingleton())
+		{
+			const bool controls = controlMap->IsMovementControlsEnabled() && controlMap->IsActivateControlsEnabled();
+			const bool weapons  = controlMap->IsFightingControlsEnabled() && !isWerewolf();
+			if (controls != lastControls || weapons != lastWeapons)
+			{
+				lastControls = controls;
+				lastWeapons  = weapons;
+				player_controls_changed(controls, weapons);
+			}
+		}
+		const auto* state   = the_player->AsActorState();
+		const bool alive    = !the_player->IsDead();
+		const bool bleeding = state && state->IsBleedingOut();
+
+		// Being knocked about is only news when it starts.
+		const bool staggered = (state && state->actorState2.staggered) || the_player->IsInRagdollState();
+		if (staggered && !lastStaggered) { player_staggered(); }
+		lastStaggered = staggered;
+
+		if (alive == lastAlive && bleeding == lastBleeding) { return; }
+
+		lastAlive    = alive;
+		lastBleeding = bleeding;
+		player_vitals_changed(alive, bleeding);
+	}
+
 	bool useCGOAltGrip()
 	{
 		bool useAltGrip = false;
@@ -140,6 +189,29 @@ namespace player
 		return std::move(*specs);
 	}
 
+	rust::Vec<rust::String> getAmmoInventoryFor(bool bolts)
+	{
+		auto player    = RE::PlayerCharacter::GetSingleton();
+		auto ammoTypes = getInventoryForType(player, RE::FormType::Ammo);
+
+		std::vector<std::pair<RE::TESAmmo*, int32_t>> counted;
+		for (const auto& [item, inv_data] : ammoTypes)
+		{
+			const auto& [num_items, entry] = inv_data;
+			auto* ammo                     = item->As<RE::TESAmmo>();
+			if (ammo && (num_items > 0) && (ammo->IsBolt() == bolts)) { counted.push_back({ ammo, num_items }); }
+		}
+		std::stable_sort(
+			counted.begin(), counted.end(), [](const auto& left, const auto& right) { return left.second > right.second; });
+
+		rust::Vec<rust::String> specs;
+		for (const auto& [ammo, count] : counted)
+		{
+			specs.push_back(rust::String(helpers::makeFormSpecString(ammo->As<RE::TESForm>())));
+		}
+		return specs;
+	}
+
 	bool hasRangedEquipped()
 	{
 		auto player    = RE::PlayerCharacter::GetSingleton();
@@ -225,6 +297,54 @@ namespace player
 		utility::consumePotion(form, player);
 	}
 
+	void useReusableItem(const std::string& form_spec, const std::string& nameToMatch)
+	{
+		auto* form = helpers::formSpecToFormItem(form_spec);
+		if (!form) { return; }
+		auto* player = RE::PlayerCharacter::GetSingleton();
+		if (form->IsArmor())
+		{
+			utility::toggleArmorByForm(form, player, nameToMatch);
+			return;
+		}
+		if (form->Is(RE::FormType::AlchemyItem))
+		{
+			utility::consumePotion(form, player);
+			return;
+		}
+
+		// Misc items can't really be equipped, but asking is what the inventory
+		// menu does, and it's the event the mods owning these items listen for.
+		RE::TESBoundObject* obj      = nullptr;
+		RE::ExtraDataList* extraData = nullptr;
+		auto remaining               = gear::boundObjectForForm(form, obj, extraData);
+		if (!obj || remaining == 0)
+		{
+			rlog::warn("Couldn't find the reusable item in inventory; form_spec='{}';"sv, form_spec);
+			helpers::honk();
+			return;
+		}
+		auto* task = SKSE::GetTaskInterface();
+		if (!task) { return; }
+		task->AddTask([=]() { RE::ActorEquipManager::GetSingleton()->EquipObject(player, obj, extraData); });
+	}
+
+	void castSpellOnSelf(const std::string& form_spec)
+	{
+		auto* form = helpers::formSpecToFormItem(form_spec);
+		if (!form) { return; }
+		auto* spell = form->As<RE::SpellItem>();
+		if (!spell)
+		{
+			rlog::warn("form spec '{}' is not a spell; not casting it."sv, form_spec);
+			return;
+		}
+		auto* player = RE::PlayerCharacter::GetSingleton();
+		auto* caster = player->GetMagicCaster(RE::MagicSystem::CastingSource::kInstant);
+		if (!caster) { return; }
+		caster->CastSpellImmediate(spell, false, player, 1.0f, false, 0.0f, player);
+	}
+
 	std::map<RE::TESBoundObject*, std::pair<int, std::unique_ptr<RE::InventoryEntryData>>>
 		getInventoryForType(RE::PlayerCharacter*& a_player, RE::FormType a_type)
 	{
@@ -238,6 +358,80 @@ namespace player
 		return getInventoryCountByForm(form);
 	}
 
+	bool isFavorited(const std::string& form_spec)
+	{
+		auto* form = helpers::formSpecToFormItem(form_spec);
+		if (!form) { return false; }
+
+		if (form->Is(RE::FormType::Spell) || form->Is(RE::FormType::Shout))
+		{
+			auto* favorites = RE::MagicFavorites::GetSingleton();
+			if (!favorites) { return false; }
+			for (auto* fave : favorites->spells)
+			{
+				if (fave && fave->GetFormID() == form->GetFormID()) { return true; }
+			}
+			return false;
+		}
+
+		auto* player   = RE::PlayerCharacter::GetSingleton();
+		auto inventory = player->GetInventory(
+			[form](const RE::TESBoundObject& a_object) { return a_object.GetFormID() == form->GetFormID(); });
+		for (const auto& [item, inv_data] : inventory)
+		{
+			const auto& [num_items, entry] = inv_data;
+			if (entry && entry->IsFavorited()) { return true; }
+		}
+		return false;
+	}
+
+	rust::Vec<rust::String> favoritedItems()
+	{
+		rust::Vec<rust::String> specs;
+		if (auto* favorites = RE::MagicFavorites::GetSingleton())
+		{
+			for (auto* fave : favorites->spells)
+			{
+				if (fave) { specs.push_back(rust::String(helpers::makeFormSpecString(fave))); }
+			}
+		}
+
+		auto* player   = RE::PlayerCharacter::GetSingleton();
+		auto inventory = player->GetInventory();
+		for (const auto& [item, inv_data] : inventory)
+		{
+			const auto& [num_items, entry] = inv_data;
+			if (item && num_items > 0 && entry && entry->IsFavorited())
+			{
+				specs.push_back(rust::String(helpers::makeFormSpecString(item)));
+			}
+		}
+		return specs;
+	}
+
+	bool isQuestItem(const RE::TESForm* form)
+	{
+		if (!form) { return false; }
+		auto* player = RE::PlayerCharacter::GetSingleton();
+		if (!player) { return false; }
+
+		auto inventory = player->GetInventory(
+			[form](const RE::TESBoundObject& a_object) { return a_object.GetFormID() == form->GetFormID(); });
+		for (const auto& [item, inv_data] : inventory)
+		{
+			const auto& [num_items, entry] = inv_data;
+			if (entry && entry->IsQuestObject()) { return true; }
+		}
+		return false;
+	}
+
+	float gameDaysPassed()
+	{
+		auto* calendar = RE::Calendar::GetSingleton();
+		if (!calendar) { return 0.0f; }
+		return calendar->GetDaysPassed();
+	}
+
 	uint32_t getInventoryCountByForm(const RE::TESForm* form)
 	{
 		uint32_t count = 0;
@@ -286,6 +480,27 @@ namespace player
 		return has_it;
 	}
 
+	// Spells a spell-preparation mod has told us are known but not prepared.
+	static std::unordered_set<std::string> UNPREPARED_SPELLS;
+	static std::mutex UNPREPARED_LOCK;
+
+	bool isSpellCastable(const std::string& form_spec)
+	{
+		auto* form = helpers::formSpecToFormItem(form_spec);
+		if (!form || !form->Is(RE::FormType::Spell)) { return true; }
+
+		std::lock_guard<std::mutex> guard(UNPREPARED_LOCK);
+		return !UNPREPARED_SPELLS.contains(form_spec);
+	}
+
+	void setSpellPrepared(const std::string& form_spec, bool prepared)
+	{
+		std::lock_guard<std::mutex> guard(UNPREPARED_LOCK);
+		if (form_spec.empty()) { UNPREPARED_SPELLS.clear(); }
+		else if (prepared) { UNPREPARED_SPELLS.erase(form_spec); }
+		else { UNPREPARED_SPELLS.insert(form_spec); }
+	}
+
 	void reequipHand(Action which, const std::string& form_spec, const std::string& nameToMatch)
 	{
 		auto* form = helpers::formSpecToFormItem(form_spec);
@@ -339,6 +554,17 @@ namespace player
 	uint32_t healthPotionCount() { return potionCountByActorValue(RE::ActorValue::kHealth); }
 	uint32_t magickaPotionCount() { return potionCountByActorValue(RE::ActorValue::kMagicka); }
 
+	uint32_t lockpickCount()
+	{
+		auto* the_player = RE::PlayerCharacter::GetSingleton();
+		auto* lockpick   = RE::TESForm::LookupByID<RE::TESObjectMISC>(0x0000000A);
+		if (!the_player || !lockpick) return 0;
+
+		auto counts = the_player->GetInventoryCounts([lockpick](RE::TESBoundObject& obj) { return &obj == lockpick; });
+		const auto found = counts.find(lockpick);
+		return found == counts.end() ? 0 : static_cast<uint32_t>(found->second);
+	}
+
 	void chooseStaminaPotion() { utility::consumeBestOption(RE::ActorValue::kStamina); }
 	void chooseHealthPotion() { utility::consumeBestOption(RE::ActorValue::kHealth); }
 	void chooseMagickaPotion() { utility::consumeBestOption(RE::ActorValue::kMagicka); }
@@ -371,6 +597,18 @@ namespace player
 		return std::move(data);
 	}
 
+	CarryWeight getCarryWeight()
+	{
+		auto* the_player = RE::PlayerCharacter::GetSingleton();
+		if (!the_player) { return CarryWeight{ .current = 0.0f, .max = 0.0f }; }
+
+		auto* values = the_player->AsActorValueOwner();
+		return CarryWeight{
+			.current = values->GetActorValue(RE::ActorValue::kInventoryWeight),
+			.max     = values->GetActorValue(RE::ActorValue::kCarryWeight),
+		};
+	}
+
 	void unequipSlotByShift(uint8_t shift)
 	{
 		auto slot        = static_cast<RE::BGSBipedObjectForm::BipedObjectSlot>(1 << shift);
@@ -21,15 +21,20 @@ namespace player
 	rust::String specEquippedPower();
 	rust::String specEquippedAmmo();
 	rust::Vec<rust::String> getAmmoInventory();
+	rust::Vec<rust::String> getAmmoInventoryFor(bool bolts);
 	bool compare(RE::TESAmmo* left, RE::TESAmmo* right);
 
 	rust::Box<EquippedData> getEquippedItems();
+	CarryWeight getCarryWeight();
 
 	bool isInCombat();
 	bool weaponsAreDrawn();
 	bool hasRangedEquipped();
 	bool isVampireLord();
 	bool isWerewolf();
+	bool isPlayerAttacking();
+	// Tell the controller if the player has died, gone into bleedout, or recovered.
+	void reportPlayerVitals();
 
 	void unequipSlot(Action slot);
 	void unequipShout();
@@ -43,10 +48,20 @@ namespace player
 	void unequipSlotByShift(uint8_t shift);
 
 	void consumePotion(const std::string& form_spec);
+	void useReusableItem(const std::string& form_spec, const std::string& nameToMatch);
+	void castSpellOnSelf(const std::string& form_spec);
 
 	bool hasItemOrSpell(const std::string& form_spec);
+	bool isSpellCastable(const std::string& form_spec);
+	// Record what a spell-preparation mod reports. An empty spec forgets every report.
+	void setSpellPrepared(const std::string& form_spec, bool prepared);
 	uint32_t itemCount(const std::string& form_spec);
+	bool isFavorited(const std::string& form_spec);
+	rust::Vec<rust::String> favoritedItems();
+	bool isQuestItem(const RE::TESForm* form);
+	float gameDaysPassed();
 	uint32_t staminaPotionCount();
+	uint32_t lockpickCount();
 	uint32_t healthPotionCount();
 	uint32_t magickaPotionCount();
 
@@ -36,6 +36,19 @@ namespace shouts
 		func(a_vm, a_stack_id, a_actor, a_shout);
 	}
 
+	uint32_t unlockedWordCount(const RE::TESShout* shout)
+	{
+		// The same form flag SKSE's Game.IsWordUnlocked() checks.
+		constexpr uint32_t WORD_UNLOCKED = 0x10000;
+
+		uint32_t count = 0;
+		for (const auto& variation : shout->variations)
+		{
+			if (variation.word && (variation.word->formFlags & WORD_UNLOCKED) != 0) { count++; }
+		}
+		return count;
+	}
+
 	void unequipShoutSlot(RE::PlayerCharacter*& player)
 	{
 		auto* selected_power = player->GetActorRuntimeData().selectedPower;
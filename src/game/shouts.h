@@ -4,6 +4,7 @@ namespace shouts
 {
 	bool has_shout(RE::Actor* a_actor, RE::TESShout* a_shout);
 	void equipShoutByForm(RE::TESForm* a_form, RE::PlayerCharacter*& a_player);
+	uint32_t unlockedWordCount(const RE::TESShout* a_shout);
 	void unequipShoutSlot(RE::PlayerCharacter*& a_player);
 
 	// Implementation details.
@@ -0,0 +1,139 @@
+//! Stand-ins for the game calls the key handler and the equip and inventory
+//! handlers can reach, so tests can drive the controller from a key press or a
+//! game event. The C++ half of the bridge isn't linked
+//! into `cargo test`; these fill the symbols it would have provided. Calls a
+//! test can reach answer like an idle game. The rest return values through
+//! pointers we'd have to fill, so they abort rather than hand back garbage.
+//! Prefer a `#[cfg(test)]` wrapper next to the caller for one-off calls; this
+//! is for the equip paths, which reach too many of them for that.
+
+#![allow(non_snake_case)]
+
+use crate::plugin::Action;
+
+type CxxString = u8;
+
+fn unreachable_in_tests(name: &str) -> ! {
+    panic!("{name}() needs the game; no test should reach it");
+}
+
+#[export_name = "ui$cxxbridge1$showBriefly"]
+extern "C" fn showBriefly() -> bool {
+    false
+}
+
+#[export_name = "ui$cxxbridge1$startTimer"]
+extern "C" fn startTimer(_which: Action) {}
+
+#[export_name = "ui$cxxbridge1$stopTimer"]
+extern "C" fn stopTimer(_which: Action) {}
+
+#[export_name = "ui$cxxbridge1$startAlphaTransition"]
+extern "C" fn startAlphaTransition(_fade_in: bool, _alpha: f32) {}
+
+#[export_name = "player$cxxbridge1$hasRangedEquipped"]
+extern "C" fn hasRangedEquipped() -> bool {
+    false
+}
+
+#[export_name = "player$cxxbridge1$unequipSlot"]
+extern "C" fn unequipSlot(_which: Action) {}
+
+#[export_name = "player$cxxbridge1$unequipSlotByShift"]
+extern "C" fn unequipSlotByShift(_shift: u8) {}
+
+#[export_name = "player$cxxbridge1$chooseHealthPotion"]
+extern "C" fn chooseHealthPotion() {}
+
+#[export_name = "player$cxxbridge1$chooseMagickaPotion"]
+extern "C" fn chooseMagickaPotion() {}
+
+#[export_name = "player$cxxbridge1$chooseStaminaPotion"]
+extern "C" fn chooseStaminaPotion() {}
+
+#[export_name = "player$cxxbridge1$castSpellOnSelf"]
+extern "C" fn castSpellOnSelf(_form_spec: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$consumePotion"]
+extern "C" fn consumePotion(_form_spec: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$equipAmmo"]
+extern "C" fn equipAmmo(_form_spec: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$equipShout"]
+extern "C" fn equipShout(_form_spec: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$equipWeapon"]
+extern "C" fn equipWeapon(_form_spec: *const CxxString, _which: Action, _name: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$equipArmor"]
+extern "C" fn equipArmor(_form_spec: *const CxxString, _name: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$toggleArmor"]
+extern "C" fn toggleArmor(_form_spec: *const CxxString, _name: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$useReusableItem"]
+extern "C" fn useReusableItem(_form_spec: *const CxxString, _name: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$reequipHand"]
+extern "C" fn reequipHand(_which: Action, _form_spec: *const CxxString, _name: *const CxxString) {}
+
+#[export_name = "player$cxxbridge1$healthPotionCount"]
+extern "C" fn healthPotionCount() -> u32 {
+    0
+}
+
+#[export_name = "player$cxxbridge1$magickaPotionCount"]
+extern "C" fn magickaPotionCount() -> u32 {
+    0
+}
+
+#[export_name = "player$cxxbridge1$staminaPotionCount"]
+extern "C" fn staminaPotionCount() -> u32 {
+    0
+}
+
+#[export_name = "player$cxxbridge1$isVampireLord"]
+extern "C" fn isVampireLord() -> bool {
+    false
+}
+
+#[export_name = "player$cxxbridge1$isWerewolf"]
+extern "C" fn isWerewolf() -> bool {
+    false
+}
+
+#[export_name = "player$cxxbridge1$getAmmoInventory"]
+extern "C" fn getAmmoInventory() {
+    unreachable_in_tests("getAmmoInventory");
+}
+
+#[export_name = "player$cxxbridge1$getAmmoInventoryFor"]
+extern "C" fn getAmmoInventoryFor() {
+    unreachable_in_tests("getAmmoInventoryFor");
+}
+
+#[export_name = "player$cxxbridge1$getCarryWeight"]
+extern "C" fn getCarryWeight() {
+    unreachable_in_tests("getCarryWeight");
+}
+
+#[export_name = "player$cxxbridge1$getEquippedItems"]
+extern "C" fn getEquippedItems() {
+    unreachable_in_tests("getEquippedItems");
+}
+
+#[export_name = "player$cxxbridge1$specEquippedAmmo"]
+extern "C" fn specEquippedAmmo() {
+    unreachable_in_tests("specEquippedAmmo");
+}
+
+#[export_name = "player$cxxbridge1$specEquippedLeft"]
+extern "C" fn specEquippedLeft() {
+    unreachable_in_tests("specEquippedLeft");
+}
+
+#[export_name = "player$cxxbridge1$specEquippedRight"]
+extern "C" fn specEquippedRight() {
+    unreachable_in_tests("specEquippedRight");
+}
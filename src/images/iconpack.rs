@@ -0,0 +1,162 @@
+//! Icon pack manifests. An icon pack can drop an `icon_pack.toml` into the
+//! icons directory to say which icons it provides, by their file names without
+//! the `.svg`. When a manifest is present, icons outside the core set are used
+//! only if the pack declares them; the rest fall back as usual. Declared icons
+//! are used even when we're in compatibility mode for an older resource bundle.
+//!
+//! A declaration never stands in for the file: an icon whose svg is missing
+//! falls back whatever the manifest says. With no manifest, or one we can't
+//! read, we go by which files exist, as we always have. The directory is
+//! scanned once and again on each settings refresh, not probed per icon.
+//!
+//! ```toml
+//! name = "SoulsyHUD icon pack"
+//! version = "1.0.0"
+//! provides = ["weapon_katana", "shout_cyclone"]
+//! ```
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use super::icons::Icon;
+use super::svg::{icon_map, rescan_icon_files, ICON_SVG_PATH};
+
+/// The name of the manifest file in the icons directory.
+pub const MANIFEST_FILE: &str = "icon_pack.toml";
+
+static ICON_PACK: Lazy<Mutex<Option<IconPack>>> =
+    Lazy::new(|| Mutex::new(IconPack::read(Path::new(ICON_SVG_PATH))));
+
+/// The manifest as written on disk.
+#[derive(Debug, Clone, Deserialize)]
+struct Manifest {
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    provides: Vec<String>,
+}
+
+/// What an installed icon pack says about itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IconPack {
+    pub name: String,
+    pub version: String,
+    declared: HashSet<Icon>,
+}
+
+impl IconPack {
+    /// Parse a manifest. Icon names we don't know are skipped with a note in
+    /// the log, so a pack made for a newer HUD still works with this one.
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let manifest: Manifest = toml::from_str(text)?;
+        let mut declared = HashSet::new();
+        for key in manifest.provides.iter() {
+            match Icon::from_str(key.trim_end_matches(".svg")) {
+                Ok(icon) => {
+                    declared.insert(icon);
+                }
+                Err(_) => log::info!(
+                    "Icon pack '{}' declares an icon we don't know; key={key};",
+                    manifest.name
+                ),
+            }
+        }
+        Ok(Self {
+            name: manifest.name,
+            version: manifest.version,
+            declared,
+        })
+    }
+
+    /// Read the manifest in this icons directory, if there is one we can use.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let text = std::fs::read_to_string(&path).ok()?;
+        match Self::parse(&text) {
+            Ok(pack) => {
+                log::info!(
+                    "Found icon pack '{}' version {} declaring {} icons.",
+                    pack.name,
+                    pack.version,
+                    pack.declared.len()
+                );
+                Some(pack)
+            }
+            Err(e) => {
+                log::warn!(
+                    "Ignoring unreadable icon pack manifest; path='{}'; error={e:#}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Does this pack say it provides this icon?
+    pub fn provides(&self, icon: &Icon) -> bool {
+        self.declared.contains(icon)
+    }
+}
+
+/// The manifest for the installed icon pack, if any.
+pub fn icon_pack() -> Option<IconPack> {
+    ICON_PACK
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire icon pack lock.")
+        .clone()
+}
+
+/// Read the manifest and the icons directory again, in case the player swapped
+/// icon packs or added icons, and forget the icons we resolved before.
+pub fn refresh_icon_pack() {
+    let pack = IconPack::read(Path::new(ICON_SVG_PATH));
+    let files_changed = rescan_icon_files();
+    let mut current = ICON_PACK
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire icon pack lock.");
+    if *current != pack || files_changed {
+        *current = pack;
+        icon_map().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_declare_icons() {
+        let pack = IconPack::parse(include_str!(
+            "../../tests/fixtures/icon-packs/extended/icon_pack.toml"
+        ))
+        .expect("the fixture manifest parses");
+        assert_eq!(pack.name, "Extended weapon icons");
+        assert_eq!(pack.version, "2.1.0");
+        assert!(pack.provides(&Icon::WeaponKatana));
+        assert!(pack.provides(&Icon::ShoutCyclone));
+        assert!(!pack.provides(&Icon::WeaponGun));
+
+        // Names we don't know are skipped; the rest of the manifest stands.
+        let pack =
+            IconPack::parse("name = \"x\"\nprovides = [\"weapon_lightsaber\", \"shout_cyclone\"]")
+                .expect("unknown icon names aren't an error");
+        assert!(pack.provides(&Icon::ShoutCyclone));
+    }
+
+    #[test]
+    fn bad_manifests_are_ignored() {
+        assert!(IconPack::parse(include_str!(
+            "../../tests/fixtures/icon-packs/malformed/icon_pack.toml"
+        ))
+        .is_err());
+        assert!(IconPack::read(Path::new("tests/fixtures/icon-packs/malformed")).is_none());
+        assert!(IconPack::read(Path::new("tests/fixtures/icon-packs/none")).is_none());
+        assert!(IconPack::read(Path::new("tests/fixtures/icon-packs/extended")).is_some());
+    }
+}
@@ -20,6 +20,8 @@
 //! submodule. Ammo, Armor, Food (Drink also categorized here), Potion, Power,
 //! Shout, Spell, Weapon. This could be tidier.
 
+use std::fmt::Display as FmtDisplay;
+
 use strum::{Display, EnumString, EnumVariantNames};
 
 /// The Icon enum. Each variant maps to a known icon type.
@@ -541,6 +543,70 @@ impl Icon {
     }
 }
 
+/// The resource bundle version this build of the plugin expects. The bundle
+/// ships a `version.txt` in its resources folder that C++ hands to us at startup.
+pub const EXPECTED_RESOURCE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A resource bundle version: major, minor, patch. Bundles from before we
+/// started versioning them read as 0.0.0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ResourceVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl ResourceVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse a version string like `0.16.6`. Missing parts count as zero;
+    /// anything after the patch number (a build or beta tag) is ignored.
+    pub fn parse(input: &str) -> Option<Self> {
+        let trimmed = input.trim().trim_start_matches('v');
+        if trimmed.is_empty() {
+            return None;
+        }
+        let mut parts = trimmed.split(['.', '-', '+']);
+        let major = parts.next()?.parse::<u32>().ok()?;
+        let minor = parts.next().map_or(Some(0), |xs| xs.parse::<u32>().ok())?;
+        let patch = parts.next().map_or(Some(0), |xs| xs.parse::<u32>().ok())?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl FmtDisplay for ResourceVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// How the installed resource bundle compares to the one we expect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionCheck {
+    Equal,
+    Older(ResourceVersion),
+    Newer(ResourceVersion),
+}
+
+/// Compare the bundle version we found to the one we expect. A version we
+/// can't read is treated as older than anything, because bundles didn't
+/// always carry a version.
+pub fn compare_resource_versions(found: &str, expected: &str) -> VersionCheck {
+    let expected = ResourceVersion::parse(expected).unwrap_or_default();
+    let found = ResourceVersion::parse(found).unwrap_or_default();
+    match found.cmp(&expected) {
+        std::cmp::Ordering::Equal => VersionCheck::Equal,
+        std::cmp::Ordering::Less => VersionCheck::Older(found),
+        std::cmp::Ordering::Greater => VersionCheck::Newer(found),
+    }
+}
+
 /// For tests, pick a random icon to use for randomly-generated items.
 #[cfg(test)]
 pub fn random_icon() -> Icon {
@@ -557,7 +623,6 @@ pub fn random_icon() -> Icon {
 }
 
 /// Check if an icon is in the core set.
-#[cfg(test)]
 pub fn is_in_core_set(icon: &Icon) -> bool {
     matches!(
         icon,
@@ -666,6 +731,34 @@ mod tests {
         assert!(bad_fallback.is_empty());
     }
 
+    #[test]
+    fn resource_versions_compare() {
+        assert_eq!(
+            compare_resource_versions("0.16.6", "0.16.6"),
+            VersionCheck::Equal
+        );
+        assert_eq!(
+            compare_resource_versions("0.16.6\r\n", "0.16.6"),
+            VersionCheck::Equal
+        );
+        assert_eq!(
+            compare_resource_versions("0.14.2", "0.16.6"),
+            VersionCheck::Older(ResourceVersion::new(0, 14, 2))
+        );
+        assert_eq!(
+            compare_resource_versions("0.17.0-beta", "0.16.6"),
+            VersionCheck::Newer(ResourceVersion::new(0, 17, 0))
+        );
+        assert_eq!(
+            compare_resource_versions("", "0.16.6"),
+            VersionCheck::Older(ResourceVersion::default())
+        );
+        assert_eq!(
+            compare_resource_versions("banana", "0.16.6"),
+            VersionCheck::Older(ResourceVersion::default())
+        );
+    }
+
     #[test]
     fn soulsy_pack_complete() {
         let icon_paths = [
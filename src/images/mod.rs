@@ -1,6 +1,8 @@
 //! A smaller sub-module that handles icon and image data. This module has
 //! the functions for loading and rasterizing SVGs.
+pub mod iconpack;
 pub mod icons;
 pub mod svg;
+pub use iconpack::refresh_icon_pack;
 pub use icons::*;
 pub use svg::*;
@@ -4,8 +4,8 @@
 //! animation. This module also maintains a mapping of icon key to the icon file
 //! found for that path after fallbacks, so icon data is loaded at most once.
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Mutex;
 
@@ -14,10 +14,98 @@ use once_cell::sync::Lazy;
 use resvg::usvg::TreeParsing;
 use resvg::*;
 
-use super::icons::Icon;
+use super::iconpack::{icon_pack, IconPack};
+use super::icons::{
+    compare_resource_versions, is_in_core_set, Icon, ResourceVersion, VersionCheck,
+    EXPECTED_RESOURCE_VERSION,
+};
 use crate::plugin::LoadedImage;
 
 static ICON_MAP: Lazy<Mutex<HashMap<Icon, Icon>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// The svg files in the icons directory, as of the last scan.
+static ICON_FILES: Lazy<Mutex<IconFiles>> =
+    Lazy::new(|| Mutex::new(IconFiles::scan(Path::new(ICON_SVG_PATH))));
+
+/// Set if the installed resource bundle is older than this plugin expects. We
+/// then use only core-set icons and icons an icon pack's manifest declares,
+/// and only if their files are there. Nothing records which icons each old
+/// bundle shipped, so the files and the manifests are all we go by.
+static COMPAT_BUNDLE: Lazy<Mutex<Option<ResourceVersion>>> = Lazy::new(|| Mutex::new(None));
+
+/// A mismatch message waiting for the player to be in a game to see it.
+static MISMATCH_NOTICE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+fn compat_bundle() -> Option<ResourceVersion> {
+    *COMPAT_BUNDLE
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire compat bundle lock.")
+}
+
+/// C++ calls this at startup with the contents of the resource bundle's
+/// version file. On a mismatch we complain loudly and, if the bundle is
+/// older than we are, switch icon lookups into compatibility mode.
+pub fn resource_version_handshake(found: String) {
+    let found = found.trim().to_string();
+    let check = compare_resource_versions(&found, EXPECTED_RESOURCE_VERSION);
+    let shown = if found.is_empty() {
+        "unknown"
+    } else {
+        found.as_str()
+    };
+
+    let compat = match check {
+        VersionCheck::Equal => {
+            log::info!("Resource bundle version {found} matches the plugin.");
+            return;
+        }
+        VersionCheck::Older(v) => {
+            log::error!("!!! The SoulsyHUD resources are version {shown} but the plugin is version {EXPECTED_RESOURCE_VERSION}.");
+            log::error!("!!! Using only core icons and declared icon-pack icons until then. Please reinstall the mod to fix this.");
+            Some(v)
+        }
+        VersionCheck::Newer(_) => {
+            log::error!("!!! The SoulsyHUD resources are version {shown} but the plugin is version {EXPECTED_RESOURCE_VERSION}.");
+            log::error!(
+                "!!! The plugin is older than its resources. Please reinstall the mod to fix this."
+            );
+            None
+        }
+    };
+
+    *COMPAT_BUNDLE
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire compat bundle lock.") = compat;
+    // Forget anything resolved before we knew.
+    icon_map().clear();
+
+    let mut vars = HashMap::new();
+    vars.insert("found".to_string(), shown.to_string());
+    vars.insert(
+        "expected".to_string(),
+        EXPECTED_RESOURCE_VERSION.to_string(),
+    );
+    let format = crate::control::translated_key(FMT_RESOURCE_MISMATCH);
+    if let Ok(msg) = strfmt::strfmt(&format, &vars) {
+        *MISMATCH_NOTICE
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire mismatch notice lock.") =
+            Some(msg);
+    }
+}
+
+/// Tell the player about a resource version mismatch. Does nothing after the
+/// first time, or if there was no mismatch.
+pub fn notify_resource_mismatch_once() {
+    let notice = MISMATCH_NOTICE
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire mismatch notice lock.")
+        .take();
+    if let Some(msg) = notice {
+        crate::control::notify(&msg);
+    }
+}
+
+const FMT_RESOURCE_MISMATCH: &str = "$SoulsyHUD_fmt_ResourceMismatch";
 
 /// Get the lock for the icon map for safe use.
 pub fn icon_map() -> std::sync::MutexGuard<'static, HashMap<Icon, Icon>> {
@@ -26,11 +114,85 @@ pub fn icon_map() -> std::sync::MutexGuard<'static, HashMap<Icon, Icon>> {
         .expect("Unrecoverable runtime problem: cannot acquire icon hashmap lock. Exiting.")
 }
 
+fn icon_files() -> std::sync::MutexGuard<'static, IconFiles> {
+    ICON_FILES
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire icon files lock. Exiting.")
+}
+
+/// Look at the icons directory again. Returns true if the svg files in it
+/// changed since the last look, in which case resolved icons are stale.
+pub fn rescan_icon_files() -> bool {
+    let fresh = IconFiles::scan(Path::new(ICON_SVG_PATH));
+    let mut files = icon_files();
+    if files.present == fresh.present {
+        return false;
+    }
+    log::info!(
+        "The icons directory changed; now {} svg files.",
+        fresh.present.len()
+    );
+    *files = fresh;
+    true
+}
+
+/// Which svg files an icons directory holds. Scanned once so resolving an
+/// icon doesn't go to the disk, and so each missing file is logged once per
+/// scan rather than every time something asks for it.
+#[derive(Debug, Clone, Default)]
+pub struct IconFiles {
+    dir: PathBuf,
+    present: HashSet<String>,
+    reported: HashSet<Icon>,
+}
+
+impl IconFiles {
+    pub fn scan(dir: &Path) -> Self {
+        let present = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.ends_with(".svg"))
+                .collect(),
+            Err(e) => {
+                log::warn!(
+                    "Unable to read the icons directory; path='{}'; error={e:#}",
+                    dir.display()
+                );
+                HashSet::new()
+            }
+        };
+        Self {
+            dir: dir.to_path_buf(),
+            present,
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Is this icon's svg in the directory?
+    pub fn has(&self, icon: &Icon) -> bool {
+        self.present.contains(&icon.icon_file())
+    }
+
+    /// Log a missing file, unless we already have since the last scan.
+    /// Returns true if this is the first we've heard of it.
+    fn report_missing(&mut self, icon: &Icon) -> bool {
+        let first = self.reported.insert(icon.clone());
+        if first {
+            log::info!(
+                "NOTE: icon file is missing; using a fallback; path='{}';",
+                self.dir.join(icon.icon_file()).display()
+            );
+        }
+        first
+    }
+}
+
 /// Path for icons relative to the game dir.
 #[cfg(not(test))]
-const ICON_SVG_PATH: &str = "data/SKSE/plugins/resources/icons/";
+pub const ICON_SVG_PATH: &str = "data/SKSE/plugins/resources/icons/";
 #[cfg(test)]
-const ICON_SVG_PATH: &str = "installer/core/SKSE/plugins/resources/icons/";
+pub const ICON_SVG_PATH: &str = "installer/core/SKSE/plugins/resources/icons/";
 
 /// C++ should call this before trying to load any icon data.
 pub fn get_icon_key(name: String) -> String {
@@ -76,35 +238,68 @@ pub fn load_icon(icon: &Icon, maxdim: u32) -> Result<LoadedImage> {
 /// This allows us to load fallbacks once and hold at most one copy
 /// of that texture data in memory.
 pub fn key_for_icon(icon: &Icon) -> Icon {
-    let mut mapping = icon_map();
-    if let Some(result) = mapping.get(icon) {
+    if let Some(result) = icon_map().get(icon) {
         return result.clone();
     }
+    let pack = icon_pack();
+    let resolved = resolve_icon(icon, &mut icon_files(), compat_bundle(), pack.as_ref());
+    icon_map().insert(icon.clone(), resolved.clone());
+    resolved
+}
 
-    let first_path = icon_to_path(icon);
-    if first_path.exists() {
-        mapping.insert(icon.clone(), icon.clone());
-        icon.clone()
-    } else {
-        log::info!("NOTE: icon pack does not include '{icon}.svg'; using generic icon.");
-        let fb = icon.fallback();
-        if icon_to_path(&fb).exists() {
-            mapping.insert(icon.clone(), fb.clone());
-            fb
-        } else {
-            log::warn!(
-                "Fallback icon {fb} load failed! path='{}';",
-                icon_to_path(&fb).display()
-            );
-            mapping.insert(icon.clone(), Icon::IconDefault);
-            Icon::IconDefault
+/// Can we draw this icon from these files? Its file has to be there. Past
+/// that, core-set icons are always fine, and an icon pack's manifest decides
+/// for the rest. With no manifest we take any icon whose file is there, unless
+/// the resource bundle is older than we expect.
+fn icon_usable(
+    candidate: &Icon,
+    files: &IconFiles,
+    compat: Option<ResourceVersion>,
+    pack: Option<&IconPack>,
+) -> bool {
+    if !files.has(candidate) {
+        return false;
+    }
+    match pack {
+        Some(pack) if pack.provides(candidate) => true,
+        Some(_) => is_in_core_set(candidate),
+        None => compat.is_none() || is_in_core_set(candidate),
+    }
+}
+
+/// Find the icon to draw for this one: itself if we can, otherwise the first
+/// usable icon down its chain of fallbacks, otherwise the default icon.
+fn resolve_icon(
+    icon: &Icon,
+    files: &mut IconFiles,
+    compat: Option<ResourceVersion>,
+    pack: Option<&IconPack>,
+) -> Icon {
+    let mut tried: Vec<Icon> = Vec::new();
+    let mut candidate = icon.clone();
+    while candidate != Icon::IconDefault && !tried.contains(&candidate) {
+        if !files.has(&candidate) {
+            files.report_missing(&candidate);
+        } else if icon_usable(&candidate, files, compat, pack) {
+            return candidate;
         }
+        let next = candidate.fallback();
+        tried.push(candidate);
+        candidate = next;
+    }
+    if !files.has(&Icon::IconDefault) {
+        files.report_missing(&Icon::IconDefault);
     }
+    Icon::IconDefault
 }
 
 /// Turn an icon into a full path to its svg.
 fn icon_to_path(icon: &Icon) -> PathBuf {
-    [ICON_SVG_PATH, icon.icon_file().as_str()].iter().collect()
+    icon_in(Path::new(ICON_SVG_PATH), icon)
+}
+
+fn icon_in(dir: &Path, icon: &Icon) -> PathBuf {
+    dir.join(icon.icon_file())
 }
 
 /// Internal shared implementation: do the real work.
@@ -188,4 +383,98 @@ mod tests {
             loaded.width as usize * loaded.height as usize * 4
         );
     }
+
+    #[test]
+    fn manifests_choose_extended_icons() {
+        let dir = Path::new("installer/icon-pack-soulsy");
+        let mut files = IconFiles::scan(dir);
+        let pack = IconPack::read(Path::new("tests/fixtures/icon-packs/extended"))
+            .expect("the fixture manifest parses");
+        let old_bundle = Some(ResourceVersion::new(0, 13, 0));
+
+        // Without a manifest, an older bundle means no extended icons at all.
+        assert_eq!(
+            resolve_icon(&Icon::ShoutCyclone, &mut files, old_bundle, None),
+            Icon::Shout
+        );
+        // Core icons whose files are there stay, however old the bundle.
+        assert_eq!(
+            resolve_icon(&Icon::WeaponKatana, &mut files, old_bundle, None),
+            Icon::WeaponKatana
+        );
+        // With one, the icons it declares come back.
+        assert_eq!(
+            resolve_icon(&Icon::ShoutCyclone, &mut files, old_bundle, Some(&pack)),
+            Icon::ShoutCyclone
+        );
+        assert_eq!(
+            resolve_icon(&Icon::WeaponKatana, &mut files, old_bundle, Some(&pack)),
+            Icon::WeaponKatana
+        );
+        // Icons it doesn't declare fall back, even though the files are there.
+        assert!(icon_in(dir, &Icon::WeaponGun).exists());
+        assert_eq!(
+            resolve_icon(&Icon::WeaponGun, &mut files, None, Some(&pack)),
+            Icon::WeaponGun.fallback()
+        );
+        // Core icons don't need declaring.
+        assert_eq!(
+            resolve_icon(&Icon::Shout, &mut files, None, Some(&pack)),
+            Icon::Shout
+        );
+    }
+
+    #[test]
+    fn declarations_cannot_conjure_files() {
+        let dir = Path::new("installer/icon-pack-soulsy");
+        let mut files = IconFiles::scan(dir);
+        let pack = IconPack::read(Path::new("tests/fixtures/icon-packs/extended"))
+            .expect("the fixture manifest parses");
+        assert!(pack.provides(&Icon::AmmoSlingshot));
+        assert!(!icon_in(dir, &Icon::AmmoSlingshot).exists());
+        assert_eq!(
+            resolve_icon(&Icon::AmmoSlingshot, &mut files, None, Some(&pack)),
+            Icon::AmmoArrow
+        );
+
+        // A malformed manifest is as good as none: existence probing decides.
+        let broken = IconPack::read(Path::new("tests/fixtures/icon-packs/malformed"));
+        assert!(broken.is_none());
+        assert_eq!(
+            resolve_icon(&Icon::WeaponGun, &mut files, None, broken.as_ref()),
+            Icon::WeaponGun
+        );
+    }
+
+    #[test]
+    fn missing_files_walk_the_chain_and_are_noted_once() {
+        let dir = std::env::temp_dir().join("SoulsyHUD_icon_scan");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("can make a scratch icons dir");
+        std::fs::write(dir.join(Icon::IconDefault.icon_file()), "<svg/>").expect("write");
+
+        let mut files = IconFiles::scan(&dir);
+        assert!(files.has(&Icon::IconDefault));
+        assert!(!files.has(&Icon::Destruction));
+        assert_eq!(
+            resolve_icon(&Icon::Destruction, &mut files, None, None),
+            Icon::IconDefault
+        );
+        // Both the icon and its fallback were reported, and won't be again.
+        assert!(!files.report_missing(&Icon::Destruction));
+        assert!(!files.report_missing(&Icon::Scroll));
+
+        // A file installed since the last scan is found on the next one.
+        std::fs::write(dir.join(Icon::Scroll.icon_file()), "<svg/>").expect("write");
+        assert!(!files.has(&Icon::Scroll));
+        let mut files = IconFiles::scan(&dir);
+        assert_eq!(
+            resolve_icon(&Icon::Destruction, &mut files, None, None),
+            Icon::Scroll
+        );
+        // A fresh scan notes a still-missing file again, but only once.
+        assert!(!files.report_missing(&Icon::Destruction));
+        assert!(files.report_missing(&Icon::PotionResistFire));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
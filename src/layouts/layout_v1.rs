@@ -8,7 +8,8 @@
 use eyre::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::layouts::shared::NamedAnchor;
+use crate::layouts::screen_size;
+use crate::layouts::shared::{NamedAnchor, DEFAULT_COOLDOWN_FORMAT};
 use crate::plugin::{
     Align, Color, HudElement, LayoutFlattened, MeterKind, Point, SlotFlattened, TextFlattened,
 };
@@ -136,17 +137,60 @@ impl HudLayout1 {
     }
 
     pub fn anchor_point(&self) -> Point {
+        self.anchor_point_for(&screen_size())
+    }
+
+    /// Where this layout goes on a screen of the given size.
+    pub fn anchor_point_for(&self, screen: &Point) -> Point {
         super::anchor_point(
             self.global_scale,
             &self.size,
             &self.anchor_name,
             self.anchor.as_ref(),
+            screen,
         )
     }
 
-    fn flatten(&self, slot: &SlotLayout) -> SlotFlattened {
+    /// Compute absolute geometry for every element on a screen of the given size.
+    pub fn flatten_for(&self, screen: &Point) -> LayoutFlattened {
+        let anchor = self.anchor_point_for(screen);
+        let slots = self
+            .layouts
+            .iter()
+            .map(|xs| self.flatten_slot(xs, &anchor))
+            .collect();
+        let factor = self.scale_for_display();
+
+        LayoutFlattened {
+            global_scale: factor,
+            anchor,
+            size: self.size.scale(factor),
+            bg_size: Point {
+                x: self.size.x * factor,
+                y: self.size.y * factor,
+            },
+            bg_color: self.bg_color.clone(),
+            bg_image: "hud_bg.svg".to_string(),
+            hide_ammo_when_irrelevant: self.hide_ammo_when_irrelevant,
+            hide_left_when_irrelevant: self.hide_left_when_irrelevant,
+            font: self.font.clone(),
+            font_size: self.font_size * factor,
+            // glyphs requested
+            chinese_full_glyphs: self.chinese_full_glyphs,
+            simplified_chinese_glyphs: self.simplified_chinese_glyphs,
+            cyrillic_glyphs: self.cyrillic_glyphs,
+            japanese_glyphs: self.japanese_glyphs,
+            korean_glyphs: self.korean_glyphs,
+            thai_glyphs: self.thai_glyphs,
+            vietnamese_glyphs: self.vietnamese_glyphs,
+            cooldown_format: DEFAULT_COOLDOWN_FORMAT.to_string(),
+            // layout slots
+            slots,
+        }
+    }
+
+    fn flatten_slot(&self, slot: &SlotLayout, anchor: &Point) -> SlotFlattened {
         let factor = self.scale_for_display();
-        let anchor = self.anchor_point();
         let center = anchor.translate(&slot.offset.scale(factor));
 
         let mut text = Vec::new();
@@ -227,34 +271,7 @@ impl HudLayout1 {
 
 impl From<&HudLayout1> for LayoutFlattened {
     fn from(v: &HudLayout1) -> Self {
-        let slots = v.layouts.iter().map(|xs| v.flatten(xs)).collect();
-        let factor = v.scale_for_display();
-
-        LayoutFlattened {
-            global_scale: factor,
-            anchor: v.anchor_point(),
-            size: v.size.scale(factor),
-            bg_size: Point {
-                x: v.size.x * factor,
-                y: v.size.y * factor,
-            },
-            bg_color: v.bg_color.clone(),
-            bg_image: "hud_bg.svg".to_string(),
-            hide_ammo_when_irrelevant: v.hide_ammo_when_irrelevant,
-            hide_left_when_irrelevant: v.hide_left_when_irrelevant,
-            font: v.font.clone(),
-            font_size: v.font_size * factor,
-            // glyphs requested
-            chinese_full_glyphs: v.chinese_full_glyphs,
-            simplified_chinese_glyphs: v.simplified_chinese_glyphs,
-            cyrillic_glyphs: v.cyrillic_glyphs,
-            japanese_glyphs: v.japanese_glyphs,
-            korean_glyphs: v.korean_glyphs,
-            thai_glyphs: v.thai_glyphs,
-            vietnamese_glyphs: v.vietnamese_glyphs,
-            // layout slots
-            slots,
-        }
+        v.flatten_for(&screen_size())
     }
 }
 
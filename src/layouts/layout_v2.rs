@@ -8,6 +8,7 @@ use eyre::{Context, Result};
 use serde::de::{Deserializer, Error};
 use serde::{Deserialize, Serialize};
 
+use super::screen_size;
 use super::shared::*;
 use crate::plugin::{
     Align, Color, HudElement, LayoutFlattened, MeterKind, Point, SlotFlattened, TextFlattened,
@@ -66,6 +67,9 @@ pub struct HudLayout2 {
     /// Whether to build glyphs for Vietnamese text display.
     #[serde(default)]
     vietnamese_glyphs: bool,
+    /// How to format shout recharge time for the `{cooldown}` text variable.
+    #[serde(default)]
+    cooldown_format: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -109,22 +113,74 @@ impl HudLayout2 {
     }
 
     pub fn anchor_point(&self) -> Point {
+        self.anchor_point_for(&screen_size())
+    }
+
+    /// Where this layout goes on a screen of the given size.
+    pub fn anchor_point_for(&self, screen: &Point) -> Point {
         super::anchor_point(
             self.scale_for_display(),
             &self.size,
             &self.anchor_name,
             self.anchor.as_ref(),
+            screen,
         )
     }
 
-    fn flatten_slot(&self, slot: &SlotElement, element: HudElement) -> SlotFlattened {
+    /// Compute absolute geometry for every element on a screen of the given size.
+    pub fn flatten_for(&self, screen: &Point) -> LayoutFlattened {
+        let anchor = self.anchor_point_for(screen);
+        let mut slots = vec![
+            self.flatten_slot(&self.power, HudElement::Power, &anchor),
+            self.flatten_slot(&self.utility, HudElement::Utility, &anchor),
+            self.flatten_slot(&self.left, HudElement::Left, &anchor),
+            self.flatten_slot(&self.right, HudElement::Right, &anchor),
+            self.flatten_slot(&self.ammo, HudElement::Ammo, &anchor),
+        ];
+        if let Some(equipset) = self.equipset.as_ref() {
+            slots.push(self.flatten_slot(equipset, HudElement::EquipSet, &anchor));
+        }
+        let bg = self.background.clone().unwrap_or_default();
+        let scale = self.scale_for_display();
+
+        LayoutFlattened {
+            global_scale: scale,
+            anchor,
+            size: self.size.scale(scale),
+            bg_size: bg.size.scale(scale),
+            bg_color: bg.color.clone(),
+            bg_image: bg.svg.clone(),
+            hide_ammo_when_irrelevant: self.hide_ammo_when_irrelevant,
+            hide_left_when_irrelevant: self.hide_left_when_irrelevant,
+            font: self.font.clone(),
+            font_size: self.font_size * scale,
+            chinese_full_glyphs: self.chinese_full_glyphs,
+            simplified_chinese_glyphs: self.simplified_chinese_glyphs,
+            cyrillic_glyphs: self.cyrillic_glyphs,
+            japanese_glyphs: self.japanese_glyphs,
+            korean_glyphs: self.korean_glyphs,
+            thai_glyphs: self.thai_glyphs,
+            vietnamese_glyphs: self.vietnamese_glyphs,
+            cooldown_format: self
+                .cooldown_format
+                .clone()
+                .unwrap_or_else(|| DEFAULT_COOLDOWN_FORMAT.to_string()),
+            slots,
+        }
+    }
+
+    fn flatten_slot(
+        &self,
+        slot: &SlotElement,
+        element: HudElement,
+        anchor: &Point,
+    ) -> SlotFlattened {
         let scale = self.scale_for_display();
 
         let bg = slot.background.clone().unwrap_or_default();
         let hotkey = slot.hotkey.clone().unwrap_or_default();
         let hkbg = hotkey.background.unwrap_or_default();
 
-        let anchor = self.anchor_point();
         let center = anchor.translate(&slot.offset.scale(scale));
         let text = slot
             .text
@@ -487,39 +543,7 @@ impl Default for PoisonElement {
 
 impl From<&HudLayout2> for LayoutFlattened {
     fn from(v: &HudLayout2) -> Self {
-        let mut slots = vec![
-            v.flatten_slot(&v.power, HudElement::Power),
-            v.flatten_slot(&v.utility, HudElement::Utility),
-            v.flatten_slot(&v.left, HudElement::Left),
-            v.flatten_slot(&v.right, HudElement::Right),
-            v.flatten_slot(&v.ammo, HudElement::Ammo),
-        ];
-        if let Some(equipset) = v.equipset.as_ref() {
-            slots.push(v.flatten_slot(equipset, HudElement::EquipSet));
-        }
-        let bg = v.background.clone().unwrap_or_default();
-        let scale = v.scale_for_display();
-
-        LayoutFlattened {
-            global_scale: scale,
-            anchor: v.anchor_point(),
-            size: v.size.scale(scale),
-            bg_size: bg.size.scale(scale),
-            bg_color: bg.color.clone(),
-            bg_image: bg.svg.clone(),
-            hide_ammo_when_irrelevant: v.hide_ammo_when_irrelevant,
-            hide_left_when_irrelevant: v.hide_left_when_irrelevant,
-            font: v.font.clone(),
-            font_size: v.font_size * scale,
-            chinese_full_glyphs: v.chinese_full_glyphs,
-            simplified_chinese_glyphs: v.simplified_chinese_glyphs,
-            cyrillic_glyphs: v.cyrillic_glyphs,
-            japanese_glyphs: v.japanese_glyphs,
-            korean_glyphs: v.korean_glyphs,
-            thai_glyphs: v.thai_glyphs,
-            vietnamese_glyphs: v.vietnamese_glyphs,
-            slots,
-        }
+        v.flatten_for(&screen_size())
     }
 }
 
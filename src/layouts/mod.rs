@@ -17,13 +17,16 @@ use serde::{Deserialize, Serialize};
 use self::shared::NamedAnchor;
 use crate::control::notify;
 use crate::controller::control::translated_key;
-use crate::controller::user_settings;
 use crate::plugin::{LayoutFlattened, Point};
 
-static LAYOUT_PATH: &str = "./data/SKSE/Plugins/SoulsyHUD_Layout.toml";
+pub static LAYOUT_PATH: &str = "./data/SKSE/Plugins/SoulsyHUD_Layout.toml";
+
+/// No real layout file comes anywhere near this size.
+const MAX_LAYOUT_BYTES: usize = 1024 * 1024;
 
 /// There can be only one. Not public because we want access managed.
-static LAYOUT: Lazy<Mutex<LayoutFlattened>> = Lazy::new(|| Mutex::new(Layout::initialize()));
+static LAYOUT: Lazy<Mutex<LayoutCache>> =
+    Lazy::new(|| Mutex::new(LayoutCache::new(Layout::initialize())));
 
 /// Lazy parsing of the compile-time include of the default layout, as a fallback.
 static DEFAULT_LAYOUT: Lazy<HudLayout2> = Lazy::new(HudLayout2::fallback);
@@ -33,7 +36,63 @@ pub fn hud_layout() -> LayoutFlattened {
     let layout = LAYOUT
         .lock()
         .expect("Unrecoverable runtime problem: cannot acquire layout lock.");
-    layout.clone()
+    layout.flattened.clone()
+}
+
+/// A number that changes whenever the flattened layout does. The renderer
+/// calls this every frame and fetches the layout again only when it changes.
+/// This is also where we notice that the screen size has changed.
+pub fn layout_serial() -> u32 {
+    let mut layout = LAYOUT
+        .lock()
+        .expect("Unrecoverable runtime problem: cannot acquire layout lock.");
+    let screen = screen_size();
+    if screen != layout.screen {
+        log::info!(
+            "Screen size changed to {}x{}; recomputing layout geometry.",
+            screen.x,
+            screen.y
+        );
+        layout.reflatten(screen);
+    }
+    layout.serial
+}
+
+/// The parsed layout and the geometry we computed from it. The geometry
+/// depends on the screen size and on the scale and anchor settings, so it's
+/// computed again only when one of those changes or the layout is reloaded.
+struct LayoutCache {
+    source: Layout,
+    screen: Point,
+    flattened: LayoutFlattened,
+    serial: u32,
+}
+
+impl LayoutCache {
+    fn new(source: Layout) -> Self {
+        let screen = screen_size();
+        let flattened = source.flatten_for(&screen);
+        Self {
+            source,
+            screen,
+            flattened,
+            serial: 1,
+        }
+    }
+
+    fn reflatten(&mut self, screen: Point) {
+        self.flattened = self.source.flatten_for(&screen);
+        self.screen = screen;
+        self.serial = self.serial.wrapping_add(1).max(1);
+    }
+}
+
+/// The current screen size, in pixels.
+pub fn screen_size() -> Point {
+    Point {
+        x: displayWidth(),
+        y: displayHeight(),
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -51,15 +110,14 @@ impl Default for Layout {
 
 impl Layout {
     /// Read the layout at startup, falling back if necessary.
-    pub fn initialize() -> LayoutFlattened {
-        let layout = match Layout::read_from_file(LAYOUT_PATH) {
+    pub fn initialize() -> Layout {
+        match Layout::read_from_file(LAYOUT_PATH) {
             Ok(v) => v,
             Err(e) => {
                 log::warn!("Problem reading the enabled layout file! {e:#}");
                 Layout::default()
             }
-        };
-        layout.flatten()
+        }
     }
 
     /// Read the layout from disk to pick up any changes to the file.
@@ -69,7 +127,9 @@ impl Layout {
                 let mut hudl = LAYOUT
                     .lock()
                     .expect("Unrecoverable runtime problem: cannot acquire layout lock.");
-                *hudl = v.flatten();
+                hudl.source = v;
+                let screen = screen_size();
+                hudl.reflatten(screen);
             }
             Err(e) => {
                 log::warn!("{e:#}");
@@ -92,21 +152,15 @@ impl Layout {
 
         let buf = fs::read_to_string(path)
             .wrap_err_with(|| format!("Unable to read the layout file: {}", pathstr))?;
-        match toml::from_str::<Layout>(&buf) {
+        match Layout::parse(&buf) {
             Ok(v) => {
                 // could notify here if we wanted with $SoulsyHUD_Layout_Refreshed_Msg
                 Ok(v)
             }
-            Err(_) => {
+            Err(e) => {
                 let msg = translated_key("$SoulsyHUD_Layout_Failed_Msg");
                 notify(&msg);
-                // We know these are both errors or we wouldn't be here.
-                let v1err = HudLayout1::read_from_file(pathstr)
-                    .expect_err("Layout parsing failed but v1 succeeded? WAT.");
-                eprintln!("{v1err:#}");
-                let v2err = HudLayout2::read_from_file(pathstr)
-                    .expect_err("Layout parsing failed but v2 succeeded? WAT.");
-                eprintln!("{v2err:#}");
+                log::debug!("{e:#}");
                 Err(eyre!(
                     "The toml file at '{}' can't be parsed as a SoulsyHUD layout.",
                     pathstr
@@ -115,14 +169,52 @@ impl Layout {
         }
     }
 
+    /// Parse layout text in either schema version.
+    pub fn parse(buf: &str) -> Result<Self> {
+        if buf.len() > MAX_LAYOUT_BYTES {
+            return Err(eyre!(
+                "The layout is {} bytes, which is far too big to be real.",
+                buf.len()
+            ));
+        }
+        match toml::from_str::<Layout>(buf) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                // The untagged error says only that neither schema matched, so
+                // we ask each schema for its own complaint.
+                if let Err(e) = toml::from_str::<HudLayout1>(buf) {
+                    log::warn!("The layout isn't a valid v1 layout: {e:#}");
+                }
+                if let Err(e) = toml::from_str::<HudLayout2>(buf) {
+                    log::warn!("The layout isn't a valid v2 layout: {e:#}");
+                }
+                Err(eyre!("The text can't be parsed as a SoulsyHUD layout."))
+            }
+        }
+    }
+
+    /// Recompute the layout geometry without re-reading the file. Call this
+    /// when settings the geometry depends on have changed.
+    pub fn refresh_geometry() {
+        let mut hudl = LAYOUT
+            .lock()
+            .expect("Unrecoverable runtime problem: cannot acquire layout lock.");
+        let screen = screen_size();
+        hudl.reflatten(screen);
+    }
+
     /// Convert the editable human-facing layout format to the format used by
     /// the renderer. This process scales all sizes and translates all locations
     /// from relative to absolute in screen space.
     pub fn flatten(&self) -> LayoutFlattened {
+        self.flatten_for(&screen_size())
+    }
+
+    /// Flatten for a screen of the given size.
+    pub fn flatten_for(&self, screen: &Point) -> LayoutFlattened {
         match self {
-            // *v dereference the ref-to-box, **v unbox, &**v borrow
-            Layout::Version1(v) => LayoutFlattened::from(&**v),
-            Layout::Version2(v) => LayoutFlattened::from(&**v),
+            Layout::Version1(v) => v.flatten_for(screen),
+            Layout::Version2(v) => v.flatten_for(screen),
         }
     }
 
@@ -142,19 +234,19 @@ pub fn anchor_point(
     size: &Point,
     anchor_name: &NamedAnchor,
     maybe_anchor: Option<&Point>,
+    screen: &Point,
 ) -> Point {
     // If we read a named anchor point, turn it into pixels.
     // The anchor point is the location of the hud CENTER, so we offset.
-    let config = *user_settings();
-    let screen_width = displayWidth();
-    let screen_height = displayHeight();
+    let screen_width = screen.x;
+    let screen_height = screen.y;
 
     let width = size.x * global_scale;
     let height = size.y * global_scale;
 
-    let user_pref_anchor = config.anchor_loc();
-    let anchor_to_use = if !matches!(user_pref_anchor, &NamedAnchor::None) {
-        user_pref_anchor
+    let user_pref_anchor = preferred_anchor();
+    let anchor_to_use = if !matches!(user_pref_anchor, NamedAnchor::None) {
+        &user_pref_anchor
     } else {
         anchor_name
     };
@@ -253,9 +345,28 @@ fn displayHeight() -> f32 {
     1440.0
 }
 
+/// The anchor the player chose in the settings, overriding the layout's own.
+#[cfg(not(test))]
+fn preferred_anchor() -> NamedAnchor {
+    crate::controller::user_settings().anchor_loc().clone()
+}
+
+// Tests choose their anchor per thread, so a test that overrides it doesn't
+// move the HUD for every other test running alongside it.
+#[cfg(test)]
+thread_local! {
+    static PREFERRED_ANCHOR: std::cell::RefCell<NamedAnchor> = const { std::cell::RefCell::new(NamedAnchor::None) };
+}
+
+#[cfg(test)]
+fn preferred_anchor() -> NamedAnchor {
+    PREFERRED_ANCHOR.with(|xs| xs.borrow().clone())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fuzzing;
 
     #[test]
     fn point_functions_behave() {
@@ -307,6 +418,93 @@ mod tests {
         assert_eq!(power1.hotkey_center, power2.hotkey_center);
     }
 
+    #[test]
+    fn geometry_depends_on_screen_size() {
+        let layout = Layout::read_from_file("tests/fixtures/anchor-point.toml")
+            .expect("this test fixture exists and is valid");
+        let ultrawide = layout.flatten_for(&Point {
+            x: 3440.0,
+            y: 1440.0,
+        });
+        let hd = layout.flatten_for(&Point {
+            x: 1920.0,
+            y: 1080.0,
+        });
+
+        // An anchor given as a point doesn't move with the screen.
+        assert_eq!(
+            ultrawide.anchor,
+            Point {
+                x: 150.0,
+                y: 1290.0
+            }
+        );
+        assert_eq!(hd.anchor, ultrawide.anchor);
+
+        // A named anchor does. This one is bottom left.
+        let named = Layout::read_from_file("tests/fixtures/named-anchor.toml")
+            .expect("this test fixture exists and is valid");
+        let ultrawide = named.flatten_for(&Point {
+            x: 3440.0,
+            y: 1440.0,
+        });
+        let hd = named.flatten_for(&Point {
+            x: 1920.0,
+            y: 1080.0,
+        });
+        assert_eq!(
+            ultrawide.anchor,
+            Point {
+                x: 150.0,
+                y: 1290.0
+            }
+        );
+        assert_eq!(hd.anchor, Point { x: 150.0, y: 930.0 });
+        assert_eq!(ultrawide.size, hd.size);
+        assert_eq!(ultrawide.slots.len(), hd.slots.len());
+
+        let power_wide = ultrawide.slots.first().expect("the power slot is first");
+        let power_hd = hd.slots.first().expect("the power slot is first");
+        assert_eq!(
+            power_wide.center,
+            Point {
+                x: 150.0,
+                y: 1207.0
+            }
+        );
+        assert_eq!(power_hd.center, Point { x: 150.0, y: 847.0 });
+
+        // Every element keeps its place relative to the anchor.
+        for (wide, narrow) in ultrawide.slots.iter().zip(hd.slots.iter()) {
+            assert_eq!(wide.element, narrow.element);
+            assert_eq!(
+                wide.center.translate(&ultrawide.anchor.scale(-1.0)),
+                narrow.center.translate(&hd.anchor.scale(-1.0))
+            );
+            assert_eq!(wide.icon_size, narrow.icon_size);
+        }
+    }
+
+    #[test]
+    fn geometry_is_cached() {
+        let mut cache = LayoutCache::new(Layout::default());
+        assert_eq!(cache.serial, 1);
+        assert_eq!(cache.screen, screen_size());
+        assert_eq!(
+            cache.flattened.anchor,
+            Point {
+                x: 150.0,
+                y: 1290.0
+            }
+        );
+        cache.reflatten(Point {
+            x: 1920.0,
+            y: 1080.0,
+        });
+        assert_eq!(cache.serial, 2);
+        assert_eq!(cache.flattened.anchor, Point { x: 150.0, y: 930.0 });
+    }
+
     #[test]
     fn default_layout_exists() {
         let fpath = std::path::Path::new(
@@ -348,7 +546,11 @@ mod tests {
     #[test]
     fn anchor_points_respect_settings() {
         // override the defaults with what we need for this test
-        let _ = crate::controller::UserSettings::refresh_with("tests/fixtures/test-settings.ini");
+        let mut config = crate::controller::UserSettings::default();
+        config
+            .read_from_file("tests/fixtures/test-settings.ini")
+            .expect("the test settings fixture is readable");
+        PREFERRED_ANCHOR.with(|xs| *xs.borrow_mut() = config.anchor_loc().clone());
 
         let named = Layout::read_from_file("tests/fixtures/named-anchor.toml")
             .expect("this test fixture exists and is valid");
@@ -366,4 +568,56 @@ mod tests {
         let flattened = named.flatten();
         assert_eq!(flattened.anchor, relocated);
     }
+
+    /// Serialize, parse what we wrote, and serialize again. The two strings
+    /// must match, or a layout we write out won't read back as itself.
+    fn assert_round_trip(layout: &Layout, context: &str) {
+        let once = toml::to_string(layout)
+            .unwrap_or_else(|e| panic!("{context}: a parsed layout can't be written: {e:#}"));
+        let reparsed = Layout::parse(&once)
+            .unwrap_or_else(|e| panic!("{context}: a written layout can't be read: {e:#}"));
+        let twice = toml::to_string(&reparsed).expect("this layout was written once already");
+        assert_eq!(once, twice, "{context}");
+    }
+
+    #[test]
+    fn fuzzed_layouts_are_survivable() {
+        let seeds = fuzzing::seeds(
+            &[
+                "tests/fixtures",
+                "tests/fixtures/fuzz",
+                "layouts/curvy",
+                "layouts/hexagons",
+                "installer/core/SKSE/plugins/soulsy_layouts",
+            ],
+            "toml",
+        );
+        let screen = screen_size();
+
+        let mut valid_seeds = 0;
+        for (path, seed) in seeds.iter() {
+            if let Ok(layout) = Layout::parse(seed) {
+                valid_seeds += 1;
+                layout.flatten_for(&screen);
+                assert_round_trip(&layout, path);
+            }
+        }
+        assert!(valid_seeds > 0, "the layout seed corpus is missing");
+
+        let mut rng = fuzzing::rng(0x1a7007);
+        for (path, seed) in seeds.iter() {
+            for _ in 0..fuzzing::iterations() {
+                let input = fuzzing::mutate(seed, &mut rng);
+                let Ok(layout) = Layout::parse(&input) else {
+                    continue;
+                };
+                layout.flatten_for(&screen);
+                layout.flatten_for(&Point::origin());
+                assert_round_trip(&layout, &format!("{path}: {input:?}"));
+            }
+        }
+
+        let oversized = format!("# {}", "x".repeat(MAX_LAYOUT_BYTES));
+        assert!(Layout::parse(&oversized).is_err());
+    }
 }
@@ -8,6 +8,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::plugin::{Action, Align, HudElement, MeterKind};
 
+/// Shout recharge time is shown as a number of seconds unless a layout says otherwise.
+pub const DEFAULT_COOLDOWN_FORMAT: &str = "{}s";
+
 // ---------- Align
 
 impl Default for Align {
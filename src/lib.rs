@@ -9,14 +9,18 @@
 
 pub mod controller;
 pub mod data;
+#[cfg(test)]
+mod fuzzing;
+#[cfg(test)]
+mod gamestubs;
 pub mod images;
 pub mod layouts;
 
 use controller::*;
 use data::huditem::{empty_extra_data, HudItem, RelevantExtraData};
 use data::{SpellData, *};
-use images::{get_icon_key, rasterize_by_path, rasterize_icon};
-use layouts::hud_layout;
+use images::{get_icon_key, rasterize_by_path, rasterize_icon, resource_version_handshake};
+use layouts::{hud_layout, layout_serial};
 
 /// Rust defines the bridge between it and C++ in the `plugin` mod, using the
 /// affordances of the `cxx` crate. At build time `cxx_build` generates the
@@ -115,6 +119,8 @@ pub mod plugin {
         /// The color to draw the HUD bg image with; if zero will not be drawn.
         bg_color: Color,
         bg_image: String,
+        /// How to show shout recharge time: `{}s`, `mm:ss`, and so on.
+        cooldown_format: String,
         /// One slot layout for each element. This wants to be map, not a vec,
         /// but the map types are not shareable.
         slots: Vec<SlotFlattened>,
@@ -213,6 +219,20 @@ pub mod plugin {
         LongPressPower,
         /// Long press the powers/shouts key.
         LongPressUtility,
+        /// Move the cycle browser's selection up.
+        BrowseUp,
+        /// Move the cycle browser's selection down.
+        BrowseDown,
+        /// Pick the cycle browser's selection.
+        BrowseConfirm,
+        /// Close the cycle browser without picking anything.
+        BrowseCancel,
+        /// The ammo cycle hotkey, if the player gave ammo a key of its own.
+        Ammo,
+        /// Swap the left hand back to what it held before.
+        LeftPrevious,
+        /// Swap the right hand back to what it held before.
+        RightPrevious,
     }
 
     /// A high-level item category, used to jump-start item categorization via keywords & form data.
@@ -233,15 +253,16 @@ pub mod plugin {
         Spell,
         Torch,
         Weapon,
+        Misc,
         Empty,
     }
 
     /// What Rust did with a key event, so the C++ caller can present UI.
     ///
     /// This struct passes data from controller to C++ to signal if it should
-    /// start or stop a timer, and if so which timer. For complicated reasons,
-    /// timers on the Rust side are impractical (they need to be async) and so I
-    /// am doing them on the  C++ side.
+    /// start or stop a timer, and if so which timer. The timers themselves are
+    /// kept on the Rust side, in `timers.rs`; the C++ side only ticks them and
+    /// handles slow motion, and `timer_expired()` is called when one runs out.
     #[derive(Debug, Clone, PartialEq, Eq)]
     struct KeyEventResponse {
         /// Did we handle this keypress?
@@ -250,6 +271,104 @@ pub mod plugin {
         start_timer: Action,
         /// Do we need to stop a timer?
         stop_timer: Action,
+        /// Did cycling come back around past the start of the cycle?
+        wrapped: bool,
+        /// What the press did, so the UI can pick a sound or effect for it.
+        outcome: KeyOutcome,
+        /// The HUD slot the outcome happened in; `None` if it wasn't about a slot.
+        slot: HudElement,
+        /// The name of the item involved, if there was one.
+        item_name: String,
+    }
+
+    /// What a key press ended up doing, as reported to the UI layer.
+    #[derive(Debug, Clone, Hash)]
+    enum KeyOutcome {
+        /// Nothing the player would notice.
+        Nothing,
+        /// A cycle moved on to the named item.
+        CycleAdvanced,
+        /// The player tried to cycle or use a slot that had nothing to offer.
+        CycleEmpty,
+        /// Something went into a hand, or came out of it, right away.
+        ItemEquipped,
+        /// A utility item was used or consumed.
+        ItemConsumed,
+        /// The HUD was shown or hidden.
+        HudToggled,
+    }
+
+    /// What the controller did with a menu hotkey press on an item.
+    #[derive(Debug, Clone, Hash)]
+    enum MenuEventResponse {
+        Okay,
+        Unhandled,
+        Error,
+        ItemAdded,
+        ItemRemoved,
+        ItemInappropriate,
+        /// The item is equipped, and the player asked us not to take equipped
+        /// items out of their cycles.
+        ItemEquipped,
+        TooManyItems,
+        /// The menu handed us something that isn't a real item, like a category
+        /// header. Nothing was changed, and the menu should have the key press.
+        InvalidItem,
+        /// A toggle in both hand cycles at once came out differently for each
+        /// hand, say added to one and already in the other.
+        HandsDiffer,
+    }
+
+    /// A destructive action held until the player confirms it. The token is
+    /// handed back to `confirm_action()` to go ahead; 0 means nothing to confirm.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ConfirmationRequest {
+        token: u32,
+        /// What will happen, for the player to read.
+        summary: String,
+    }
+
+    /// What the cycle browser shows. The renderer draws one page of entry
+    /// names and highlights the selection.
+    #[derive(Debug, Clone, PartialEq)]
+    struct CycleBrowserView {
+        /// False if no browser is open; nothing else means anything then.
+        open: bool,
+        /// The slot whose cycle is being browsed.
+        slot: HudElement,
+        /// Names of the entries on the current page, in cycle order.
+        names: Vec<String>,
+        /// Short descriptions of the same entries; any may be empty.
+        descriptions: Vec<String>,
+        /// The selection's index on the current page.
+        selected: u32,
+        /// The current page, counting from 0.
+        page: u32,
+        page_count: u32,
+        /// How many entries the whole cycle has.
+        total: u32,
+    }
+
+    /// One entry in a cycle, for pages and widgets that show a whole cycle.
+    #[derive(Debug, Clone, PartialEq)]
+    struct CycleEntryView {
+        form_spec: String,
+        name: String,
+        /// A short description; may be empty.
+        description: String,
+        icon_key: String,
+        /// How many the player has, for items where that matters; 0 otherwise.
+        count: u32,
+        /// True for the entry the HUD is showing in the slot.
+        showing: bool,
+    }
+
+    /// The player's carry weight: what they have now and the most they can
+    /// carry without being overencumbered.
+    #[derive(Debug, Clone, PartialEq)]
+    struct CarryWeight {
+        current: f32,
+        max: f32,
     }
 
     /// What the player has equipped, and which armor slots are empty.
@@ -293,6 +412,9 @@ pub mod plugin {
         fn string_to_utf8(bytes: &CxxVector<u8>) -> String;
         /// Decode a null-terminated C string from whatever it is to utf-8.
         fn cstr_to_utf8(bytes_ffi: &CxxVector<u8>) -> String;
+        /// Decode a null-terminated C string using a Windows codepage hint,
+        /// guessing only if the hint doesn't work.
+        fn cstr_to_utf8_with_codepage(bytes_ffi: &CxxVector<u8>, codepage: u32) -> String;
 
         /// If we're registered with the trainwreck crash logger, and we're in
         /// the process of crashing, try to provide info for the Trainwreck section.
@@ -301,6 +423,10 @@ pub mod plugin {
         /// Trigger rust to read config, figure out what the player has equipped,
         /// and figure out what it should draw.
         fn initialize_hud();
+        /// The game finished loading; replay any events held during the load.
+        fn handle_load_complete();
+        /// The game is exiting. Flush what we hold and stop taking events.
+        fn shutdown();
         /// Get cycle data for cosave.
         fn serialize_cycles() -> Vec<u8>;
         /// Serialization format version.
@@ -309,6 +435,8 @@ pub mod plugin {
         fn cycle_loaded_from_cosave(bytes: &CxxVector<u8>, version: u32);
         /// On save load or death restore, wipe the hud item cache.
         fn clear_cache();
+        /// Callback from C++ when SKSE reverts game state, before a load or a new game.
+        fn cosave_reverted();
         /// Refresh the enchant charge / time remaining / poisoned status of all visible items.
         fn refresh_hud_items();
 
@@ -320,8 +448,14 @@ pub mod plugin {
         fn equip_delay_ms(self: &UserSettings) -> u32;
         /// Get whether the HUD should control its own visibility.
         fn autofade(self: &UserSettings) -> bool;
+        /// Whether auto-fade wants the HUD faded out, given the player's state.
+        fn should_fade_out(self: &UserSettings, in_combat: bool, weapons_drawn: bool) -> bool;
+        /// How long to wait before fading out, in milliseconds.
+        fn fade_delay(self: &UserSettings) -> u32;
         /// Get the hotkey for a specific action.
         fn hotkey_for(self: &UserSettings, action: HudElement) -> u32;
+        /// Get the hotkey to show for an action while a gamepad is in use.
+        fn gamepad_hotkey_for(self: &UserSettings, action: HudElement) -> u32;
         /// Get which kind of controller to draw shortcuts for: PS5 or Xbox.
         fn controller_kind(self: &UserSettings) -> u32;
         /// If we should enter slow motion while cycling.
@@ -345,8 +479,13 @@ pub mod plugin {
 
         /// After an MCM-managed change, re-read our .ini file.
         fn refresh_user_settings();
+        /// Change an equip delay from the MCM, saving it and using it right away.
+        /// Slots are 0-3 for power, utility, left, and right; -1 is the shared delay.
+        fn set_equip_delay(which: i32, millis: u32) -> bool;
         /// Fetch a read-only copy of our current layout.
         fn hud_layout() -> LayoutFlattened;
+        /// Changes whenever the layout does; fetch the layout again when it changes.
+        fn layout_serial() -> u32;
 
         /// Cached data for items displayed in cycles. This is opaque to C++.
         type HudItem;
@@ -366,10 +505,24 @@ pub mod plugin {
         fn fmtstr(self: &HudItem, format: String) -> String;
         /// Check if this item is poisoned.
         fn is_poisoned(self: &HudItem) -> bool;
+        /// Check if this is a spell the player knows but can't cast yet.
+        fn is_locked(self: &HudItem) -> bool;
+        /// Check if this is a consumable or other counted item the player has run out of.
+        fn is_spent(self: &HudItem) -> bool;
+        /// Check if this is an enchanted weapon whose charge has run out.
+        fn is_drained(self: &HudItem) -> bool;
+        /// Milliseconds since this item's slot last changed, for layout animations.
+        fn change_age(self: &HudItem) -> u32;
+        /// Record how much one of this item weighs. Set when the item is built.
+        fn set_weight(self: &mut HudItem, weight: f32);
+        /// Record whether the player's copy of this item belongs to a quest.
+        fn set_quest_item(self: &mut HudItem, quest_item: bool);
         /// Check if this item needs a meter drawn.
         fn show_meter(self: &HudItem) -> bool;
         /// Get the meter level as a percentage of full/complete.
         fn meter_level(self: &HudItem) -> f32;
+        /// How much of a shout's cooldown is left, from 1.0 down to 0.0.
+        fn cooldown_fraction(self: &HudItem) -> f32;
 
         /// See src/data/magic.rs for this struct. It's used to classify spells.
         type SpellData;
@@ -377,6 +530,7 @@ pub mod plugin {
             hostile: bool,
             resist: i32,
             twohanded: bool,
+            hand: u8,
             school: i32,
             level: u32,
             archetype: i32,
@@ -389,8 +543,11 @@ pub mod plugin {
             form_string: String,
             count: u32,
         ) -> Box<HudItem>;
+        /// Build a HUD item for a shout from its keywords and how many of its words
+        /// the player has unlocked.
         fn categorize_shout(
             keywords: &CxxVector<CxxString>,
+            words: u32,
             name: String,
             form_string: String,
         ) -> Box<HudItem>;
@@ -404,10 +561,13 @@ pub mod plugin {
             count: u32,
             twohanded: bool,
         ) -> Box<HudItem>;
-        /// Build a HUD item for a potion from its major effect and a hint about whether it's poison or not.
+        /// Build a HUD item for a potion from its major effect, that effect's archetype and
+        /// magnitude, and a hint about whether it's poison or not.
         fn potion_from_formdata(
             is_poison: bool,
             effect: i32,
+            archetype: i32,
+            magnitude: f32,
             count: u32,
             name: String,
             form_string: String,
@@ -435,6 +595,8 @@ pub mod plugin {
             time_left: f32,
         ) -> Box<RelevantExtraData>;
 
+        /// Compare the installed resource bundle's version to the one we expect.
+        fn resource_version_handshake(found: String);
         /// Call this to get the fallback-aware key for an icon.
         fn get_icon_key(name: String) -> String;
         /// Load a rasterized image for an icon given its key.
@@ -445,34 +607,115 @@ pub mod plugin {
         // These are called by plugin hooks and sinks.
 
         /// Handle an incoming key press event, responding with how it was handled.
-        fn handle_key_event(key: u32, button: &ButtonEvent) -> KeyEventResponse;
+        /// The sequence number identifies the input batch the event arrived in.
+        fn handle_key_event(key: u32, button: &ButtonEvent, sequence: u64) -> KeyEventResponse;
         /// Handle an in-menu event (which adds/removes items) from the game.
-        fn handle_menu_event(key: u32, button: &ButtonEvent) -> bool;
-        /// Toggle a menu item in the given cycle.
-        fn toggle_item(key: u32, item: Box<HudItem>);
+        fn handle_menu_event(key: u32, button: &ButtonEvent, sequence: u64) -> bool;
+        /// A spell-preparation mod says which spells are prepared has changed.
+        fn spell_preparation_changed();
+        /// Toggle a menu item in the given cycle, responding with what we did.
+        fn toggle_item(key: u32, item: Box<HudItem>) -> MenuEventResponse;
         /// Get the item readied in the given slot, if any.
         fn entry_to_show_in_slot(slot: HudElement) -> Box<HudItem>;
+        /// Has the item in this slot changed since this was last asked? The
+        /// renderer can skip per-item work when it hasn't.
+        fn huditem_changed(slot: HudElement) -> bool;
+        /// The player died, went into bleedout, or got back up. Until this is
+        /// called, the player is assumed alive and on their feet.
+        fn player_vitals_changed(alive: bool, bleeding_out: bool);
+        /// The game disabled or re-enabled the player's controls, as it does for
+        /// cutscenes and beast forms. Until this is called, controls are assumed enabled.
+        fn player_controls_changed(controls: bool, weapon_controls: bool);
+        /// The player was just staggered or knocked into ragdoll. Pending equips are cancelled.
+        fn player_staggered();
+        /// The game opened a menu. Cycle keys do nothing until it closes.
+        fn menu_opened(name: &str);
+        /// The game closed a menu.
+        fn menu_closed(name: &str);
+        /// The crosshair is on a locked object. The level is the game's lock level.
+        fn on_crosshair_lock(level: u8);
+        /// The crosshair moved off a locked object, or onto nothing.
+        fn on_crosshair_clear();
+        /// Open the cycle browser on this slot's cycle. False if there's nothing to browse.
+        fn open_cycle_browser(slot: HudElement) -> bool;
+        /// What the cycle browser is showing, for the renderer.
+        fn cycle_browser_view() -> CycleBrowserView;
         /// A cycle delay timer has expired. Time to equip!
         fn timer_expired(slot: Action);
+        /// Start tracking a timer for this action, replacing any already running.
+        fn timer_started(which: Action);
+        /// Stop tracking a timer. Returns true if other timers are still running.
+        fn timer_stopped(which: Action) -> bool;
+        /// Once a frame: advance all timers, handling any that expire, and let the
+        /// controller do anything waiting on the clock. Returns true if any timers
+        /// are still running.
+        fn tick(delta: f32) -> bool;
+        /// True while this slot's equip timer runs, so the renderer can highlight it.
+        fn is_slot_highlighted(slot: Action) -> bool;
+        /// How much of the slot's equip delay is left, from 1.0 down to 0.0.
+        fn slot_highlight_fraction(slot: Action) -> f32;
         /// Handle equipment-changed events from the game.
         fn handle_item_equipped(
             equipped: bool,
             form_spec: &String,
             worn_right: &String,
             worn_left: &String,
-        ) -> bool;
-        /// Handle inventory-count changed events from the game.
-        fn handle_inventory_changed(form_spec: &String, count: u32);
+        );
+        /// Handle inventory-count changed events from the game. The weight is for one item.
+        fn handle_inventory_changed(form_spec: &String, count: u32, weight: f32, quest_item: bool);
         /// Favoriting & unfavoriting.
         fn handle_favorite_event(_button: &ButtonEvent, is_favorite: bool, _item: Box<HudItem>);
         /// Handle CGO switching grip mode.
         fn handle_grip_change(use_alt_grip: bool);
-        /// Clear all cycles on player request.
-        fn clear_cycles();
+        /// Handle a grip-switching mod changing the grip of the weapon in one hand.
+        fn handle_hand_grip_change(hand: Action, two_handed: bool);
+        /// Ask to clear all cycles. Nothing happens until the token is confirmed. Used in MCM.
+        fn request_clear_cycles() -> ConfirmationRequest;
+        /// Carry out the destructive action the token was issued for, if the token is
+        /// still good. Returns a message for the player, empty if nothing happened.
+        fn confirm_action(token: u32) -> String;
         /// Get the names of the entries in the given cycle as a vec of strings. Used in MCM.
         fn get_cycle_names(which: i32) -> Vec<String>;
+        /// Get short descriptions of the entries in the given cycle, lined up with
+        /// the names. An entry with nothing to say has an empty string. Used in MCM.
+        fn get_cycle_descriptions(which: i32) -> Vec<String>;
         /// Get a list of form spec strings for the given cycle. Used in MCM.
         fn get_cycle_formids(which: i32) -> Vec<String>;
+        /// Get a copy of everything in the cycle for this slot, top entry first.
+        /// Empty for anything that isn't a cycle slot.
+        fn cycle_contents(slot: Action) -> Vec<CycleEntryView>;
+        /// Flip the tool flag on the item at this index in the given cycle. Used in MCM.
+        fn toggle_tool_flag(which: i32, index: i32) -> bool;
+        /// Move the entry at one index in the given cycle to another. The top entry stays
+        /// on top. Returns the entry's new index, or -1 if nothing moved. Used in MCM.
+        fn move_cycle_entry(which: i32, from: i32, to: i32) -> i32;
+        /// Remove the entry at this index from the given cycle. Used in MCM.
+        fn remove_cycle_entry(which: i32, index: i32) -> bool;
+        /// Add the item with this form spec to a cycle, for other mods' scripts.
+        /// Cycles are numbered as for the MCM. Adding an item already there succeeds.
+        fn add_to_cycle(which: i32, form_spec: &str, quiet: bool) -> bool;
+        /// Remove the item with this form spec from a cycle, for other mods' scripts.
+        fn remove_from_cycle(which: i32, form_spec: &str, quiet: bool) -> bool;
+        /// Undo the most recent cycle edit. Returns a message for the player, empty if nothing changed.
+        fn undo_last_cycle_edit() -> String;
+        /// Add everything the player has favorited to the cycles. Returns a message for the player.
+        fn import_favorites() -> String;
+        /// Write the cycles to a toml file players can edit and share. Tells the player how it went.
+        fn export_cycles(path: &str) -> bool;
+        /// Read cycles from a toml file, replacing the current cycles or adding to them. A file
+        /// that can't be parsed changes nothing. Tells the player how many entries it accepted.
+        fn import_cycles(path: &str, replace: bool) -> bool;
+        /// Log the cycles, equipped items, running timers, and recent key events. Does
+        /// nothing and returns false unless debug mode is on. Used in MCM.
+        fn dump_state() -> bool;
+        /// Check the install for common problems. The first entry is ok, warn, or error;
+        /// the rest are findings, worst first. Used in MCM.
+        fn run_diagnostics() -> Vec<String>;
+        /// Names of the cycle entries unused for more than this many game days. Used in MCM.
+        fn preview_prune(days: u32) -> Vec<String>;
+        /// Ask to remove the entries `preview_prune()` lists. Nothing happens until the
+        /// token is confirmed. Used in MCM.
+        fn request_prune(days: u32) -> ConfirmationRequest;
         /// Get equip set names in order by id. Used in MCM.
         fn get_equipset_names() -> Vec<String>;
         /// Get equip set ids. Used in MCM.
@@ -483,6 +726,8 @@ pub mod plugin {
         fn handle_create_equipset(name: String) -> bool;
         /// Save an equipment set. Used in MCM.
         fn handle_update_equipset(id: u32) -> bool;
+        /// Create a new equipment set that also holds the hands, power, and utility item.
+        fn save_current_as_set(name: String) -> bool;
         /// Rename an equipment set. Used in MCM.
         fn handle_rename_equipset(id: u32, name: String) -> bool;
         /// Remove an equipment set. Used in MCM.
@@ -536,6 +781,8 @@ pub mod plugin {
         fn honk();
         /// Make a full HUD-drawing-ready item from a form spec string.
         fn formSpecToHudItem(form_spec: &CxxString) -> Box<HudItem>;
+        /// Does the game know a form with this spec? False if its plugin isn't loaded.
+        fn formSpecExists(form_spec: &CxxString) -> bool;
         /// Is this item poisoned?
         fn isPoisonedByFormSpec(form_spec: &CxxString) -> bool;
         /// Does this item have fuel or an enchantment charge level?
@@ -557,8 +804,8 @@ pub mod plugin {
         fn displayWidth() -> f32;
         fn displayHeight() -> f32;
 
-        /// Start the named timer. Duration is looked up from settings.
-        fn startTimer(which: Action, duration: u32);
+        /// Start the named timer. Its duration is looked up from settings on every tick.
+        fn startTimer(which: Action);
         /// Stop the named timer.
         fn stopTimer(which: Action);
         /// Show the hud very briefly on a cycle change. Returns true if the HUD was invisible before.
@@ -592,15 +839,23 @@ pub mod plugin {
 
         /// Check if the player still has items from this form in their inventory.
         fn hasItemOrSpell(form_spec: &CxxString) -> bool;
+        /// Check if the player can cast this known spell right now. Spells a
+        /// spell-preparation mod reports as unprepared can't be cast.
+        fn isSpellCastable(form_spec: &CxxString) -> bool;
 
         /// Does the player have a bow or crossbow equipped?
         fn hasRangedEquipped() -> bool;
         /// Get a vec of form specs for all relevant ammo in the player's inventory.
         /// The vec is sorted by damage.
         fn getAmmoInventory() -> Vec<String>;
+        /// Form specs for the bolts (or arrows) in the player's inventory,
+        /// the one the player has the most of first.
+        fn getAmmoInventoryFor(bolts: bool) -> Vec<String>;
 
         /// Get a list of form specs for all equipped armor. Used to build an equipset.
         fn getEquippedItems() -> Box<EquippedData>;
+        /// How much the player is carrying and how much they can carry.
+        fn getCarryWeight() -> CarryWeight;
 
         /// Unequip the relevant slot.
         fn unequipSlot(which: Action);
@@ -623,6 +878,11 @@ pub mod plugin {
         fn equipAmmo(form_spec: &CxxString);
         /// Potions great and small.
         fn consumePotion(form_spec: &CxxString);
+        /// Use a reusable item the way the inventory menu would, without
+        /// spending it.
+        fn useReusableItem(form_spec: &CxxString, name: &CxxString);
+        /// Cast the spell with this form spec on the player.
+        fn castSpellOnSelf(form_spec: &CxxString);
         /// Choose and then consume the best potion for the given stat.
         fn chooseMagickaPotion();
         /// Choose a life. Choose a job. Choose a career. Choose a family.
@@ -635,14 +895,24 @@ pub mod plugin {
         fn healthPotionCount() -> u32;
         /// How many restore magicka potions the player has in inventory. For grouped potions.
         fn magickaPotionCount() -> u32;
+        /// How many lockpicks the player is carrying.
+        fn lockpickCount() -> u32;
         /// Get a count for items with this form spec.
         fn itemCount(form_spec: &CxxString) -> u32;
+        /// Has the player marked this item or spell as a favorite?
+        fn isFavorited(form_spec: &CxxString) -> bool;
+        /// Form specs for everything the player has favorited, spells and shouts first.
+        fn favoritedItems() -> Vec<String>;
+        /// How many game days have passed since the start of this playthrough.
+        fn gameDaysPassed() -> f32;
         /// Is the player using CGO's alt-grip mode? (Always false if not using CGO or compatible mod.)
         fn useCGOAltGrip() -> bool;
         /// Is the player a vampire lord?
         fn isVampireLord() -> bool;
         /// Is the player a werewolf?
         fn isWerewolf() -> bool;
+        /// Is the player in the middle of an attack? False if we can't tell.
+        fn isPlayerAttacking() -> bool;
 
     }
 }
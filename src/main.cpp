@@ -1,3 +1,6 @@
+#include <cstdlib>
+#include <fstream>
+
 #include "SKSE/Interfaces.h"
 #include "cosave.h"
 #include "inventory.h"
@@ -36,6 +39,16 @@ void init_logger()
 	}
 }
 
+// Read the version of the resource bundle we were installed with. Empty if the
+// bundle is too old to have a version file.
+std::string readResourceVersion()
+{
+	std::ifstream file("./data/SKSE/plugins/resources/version.txt");
+	std::string version;
+	if (file.is_open()) { std::getline(file, version); }
+	return version;
+}
+
 // Our handler for plugin-level SKSE messages.
 // We care about new game, game loaded, and data loaded messages.
 void message_callback(SKSE::MessagingInterface::Message* msg)
@@ -46,6 +59,7 @@ void message_callback(SKSE::MessagingInterface::Message* msg)
 			if (ui::ui_renderer::d_3d_init_hook::initialized)
 			{
 				rlog::info("SKSE data loaded message received; about to install hooks."sv);
+				resource_version_handshake(readResourceVersion());
 				ui::ui_renderer::preloadImages();
 				MenuHook::install();
 				PlayerHook::install();
@@ -58,6 +72,7 @@ void message_callback(SKSE::MessagingInterface::Message* msg)
 		case SKSE::MessagingInterface::kNewGame:
 			rlog::trace("SKSE kNewGame message received: type={};"sv, static_cast<uint32_t>(msg->type));
 			initialize_hud();
+			handle_load_complete();
 			break;
 		case SKSE::MessagingInterface::kPostPostLoad:
 			rlog::debug("Registering with Trainwreck if it's found...");
@@ -94,6 +109,10 @@ EXTERN_C [[maybe_unused]] __declspec(dllexport) bool SKSEAPI SKSEPlugin_Load(con
 	}
 
 	g_message->RegisterListener(message_callback);
+	// SKSE never unloads plugins, so the closest thing we get to an unload
+	// hook is process exit. This runs under the loader lock, so shutdown()
+	// flushes right here and starts no threads.
+	std::atexit([] { shutdown(); });
 
 	rlog::info("{} load successful."sv, Version::PROJECT);
 	return true;
@@ -62,5 +62,5 @@ namespace cosave
 		}
 	}
 
-	void revertHandler(SKSE::SerializationInterface*) { clear_cache(); }
+	void revertHandler(SKSE::SerializationInterface*) { cosave_reverted(); }
 }
@@ -88,5 +88,5 @@ void PlayerHook::notifyInventoryChanged(RE::TESForm* item_form)
 
 	auto count              = player::getInventoryCountByForm(item_form);
 	std::string form_string = helpers::makeFormSpecString(item_form);
-	handle_inventory_changed(form_string, count);
+	handle_inventory_changed(form_string, count, item_form->GetWeight(), player::isQuestItem(item_form));
 }
@@ -7,7 +7,11 @@ namespace keycodes
 		uint32_t key = button->idCode;
 		switch (button->device.get())
 		{
-			case RE::INPUT_DEVICE::kMouse: key += kMouseOffset; break;
+			case RE::INPUT_DEVICE::kMouse:
+				// Mouse codes have only the ten slots below the gamepad's; anything
+				// past them would read as a gamepad button.
+				key = key < kGamepadOffset - kMouseOffset ? key + kMouseOffset : kInvalid;
+				break;
 			case RE::INPUT_DEVICE::kKeyboard: key += kKeyboardOffset; break;
 			case RE::INPUT_DEVICE::kGamepad: key = gamepadIndex(static_cast<RE::BSWin32GamepadDevice::Key>(key)); break;
 			case RE::INPUT_DEVICE::kNone:
@@ -50,4 +54,21 @@ namespace keycodes
 
 		return index != kInvalid ? index + kGamepadOffset : kInvalid;
 	}
+
+	uint64_t batchSequence(const RE::InputEvent* head, BatchReader reader)
+	{
+		static const RE::InputEvent* lastHead = nullptr;
+		static uint64_t sequence              = 0;
+		static uint8_t readers                = 0;
+
+		const auto bit = static_cast<uint8_t>(reader);
+		if (head != lastHead || (readers & bit) != 0)
+		{
+			lastHead = head;
+			readers  = 0;
+			sequence++;
+		}
+		readers |= bit;
+		return sequence;
+	}
 }
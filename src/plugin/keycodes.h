@@ -3,7 +3,9 @@
 namespace keycodes
 {
 	// This namespace has convenience functions for getting usable keycodes
-	// out of game button events.
+	// out of game button events. Each device gets its own range of codes, so
+	// mouse button 4 and keyboard scancode 4 never look alike on the Rust side.
+	// Keep these offsets in step with src/controller/keycodes.rs.
 
 	enum : uint32_t
 	{
@@ -17,4 +19,18 @@ namespace keycodes
 
 	uint32_t gamepadIndex(RE::BSWin32GamepadDevice::Key key);
 
+	// Who is asking for a batch sequence number.
+	enum class BatchReader : uint8_t
+	{
+		kMenu     = 1 << 0,
+		kGameplay = 1 << 1
+	};
+
+	// Both the menu hook and the gameplay sink see the same batch of input events.
+	// This hands out one sequence number per batch so the controller can tell them apart.
+	// The number only ever goes up: a new batch starts when the list changes or when a
+	// reader comes back for a second look, because the game reuses its list from frame
+	// to frame and the head pointer alone can't tell two frames apart.
+	uint64_t batchSequence(const RE::InputEvent* head, BatchReader reader);
+
 }  // keycodes
@@ -53,6 +53,7 @@ RE::BSEventNotifyControl MenuHook::process_event(RE::InputEvent** eventPtr,
 	// TODO consider treating the favorites menu completely differently.
 	if (eventPtr && *eventPtr)
 	{
+		const uint64_t sequence = keycodes::batchSequence(*eventPtr, keycodes::BatchReader::kMenu);
 		for (auto* event = *eventPtr; event; event = event->next)
 		{
 			if (event->eventType != RE::INPUT_EVENT_TYPE::kButton || !event->HasIDCode()) { continue; }
@@ -92,7 +93,7 @@ RE::BSEventNotifyControl MenuHook::process_event(RE::InputEvent** eventPtr,
 
 			// We send all key events to this handler because it needs to track modifiers.
 			// It returns true if we should act on this event.
-			if (!handle_menu_event(key, *button)) { continue; }
+			if (!handle_menu_event(key, *button, sequence)) { continue; }
 
 			MenuSelection* selection = nullptr;
 			auto menu_form           = MenuSelection::getSelectionFromMenu(ui, selection);
@@ -101,8 +102,10 @@ RE::BSEventNotifyControl MenuHook::process_event(RE::InputEvent** eventPtr,
 			auto* item_form = selection->form;
 			if (!item_form) { continue; }
 
-			auto entry = equippable::hudItemFromForm(item_form);
-			toggle_item(key, std::move(entry));
+			auto entry    = equippable::hudItemFromForm(item_form);
+			auto response = toggle_item(key, std::move(entry));
+			// Category headers and the like aren't items; the menu keeps the key press.
+			if (response == MenuEventResponse::InvalidItem) { continue; }
 
 			// We know this event was something we acted on. We suppress it so downstream
 			// handlers do not also act upon it by doing something annoying like changing what's
@@ -8,7 +8,8 @@
 
 namespace papyrus
 {
-	static const char* MCM_NAME = "SoulsyHUD_MCM";
+	static const char* MCM_NAME    = "SoulsyHUD_MCM";
+	static const char* GLOBAL_NAME = "SoulsyHUD";
 
 	void registerPapyrusFunctions()
 	{
@@ -19,7 +20,8 @@ namespace papyrus
 	bool Register(RE::BSScript::IVirtualMachine* a_vm)
 	{
 		a_vm->RegisterFunction("OnConfigClose", MCM_NAME, handleConfigClose);
-		a_vm->RegisterFunction("ClearCycles", MCM_NAME, handleClearCycles);
+		a_vm->RegisterFunction("RequestClearCycles", MCM_NAME, requestClearCycles);
+		a_vm->RegisterFunction("ConfirmAction", MCM_NAME, confirmAction);
 		a_vm->RegisterFunction("GetResolutionWidth", MCM_NAME, get_resolution_width);
 		a_vm->RegisterFunction("GetResolutionHeight", MCM_NAME, get_resolution_height);
 
@@ -36,18 +38,49 @@ namespace papyrus
 		a_vm->RegisterFunction("StringToInt", MCM_NAME, stringToInt);
 
 		a_vm->RegisterFunction("GetCycleNames", MCM_NAME, getCycleNames);
+		a_vm->RegisterFunction("GetCycleDescriptions", MCM_NAME, getCycleDescriptions);
 		a_vm->RegisterFunction("GetCycleFormIDs", MCM_NAME, getCycleFormIDs);
+		a_vm->RegisterFunction("ToggleToolFlag", MCM_NAME, toggleToolFlag);
+		a_vm->RegisterFunction("MoveCycleEntry", MCM_NAME, moveCycleEntry);
+		a_vm->RegisterFunction("RemoveCycleEntry", MCM_NAME, removeCycleEntry);
+		a_vm->RegisterFunction("UndoLastCycleEdit", MCM_NAME, undoLastCycleEdit);
+		a_vm->RegisterFunction("ImportFavorites", MCM_NAME, importFavorites);
+		a_vm->RegisterFunction("DumpState", MCM_NAME, dumpState);
+		a_vm->RegisterFunction("RunDiagnostics", MCM_NAME, runDiagnostics);
+		a_vm->RegisterFunction("PreviewPrune", MCM_NAME, previewPrune);
+		a_vm->RegisterFunction("RequestPrune", MCM_NAME, requestPrune);
+		a_vm->RegisterFunction("SetEquipDelay", MCM_NAME, setEquipDelay);
 
 		a_vm->RegisterFunction("GetResolutionWidth", MCM_NAME, get_resolution_width);
 		a_vm->RegisterFunction("GetResolutionHeight", MCM_NAME, get_resolution_height);
 
 		rlog::info("Registered papyrus functions for the MCM; classname='{}'."sv, MCM_NAME);
+
+		a_vm->RegisterFunction("AddToCycle", GLOBAL_NAME, addToCycle);
+		a_vm->RegisterFunction("RemoveFromCycle", GLOBAL_NAME, removeFromCycle);
+		a_vm->RegisterFunction("SaveCurrentAsSet", GLOBAL_NAME, saveCurrentAsSet);
+		a_vm->RegisterFunction("ExportCycles", GLOBAL_NAME, exportCycles);
+		a_vm->RegisterFunction("ImportCycles", GLOBAL_NAME, importCycles);
+		rlog::info("Registered global papyrus functions; classname='{}'."sv, GLOBAL_NAME);
 		return true;
 	}
 
 	void handleConfigClose(RE::TESQuest*) { refresh_user_settings(); }
 
-	void handleClearCycles(RE::TESQuest*) { clear_cycles(); }
+	RE::BSTArray<RE::BSFixedString> confirmationForPapyrus(const ConfirmationRequest& request)
+	{
+		auto array = RE::BSTArray<RE::BSFixedString>();
+		array.push_back(std::to_string(request.token));
+		array.push_back(std::string(request.summary));
+		return array;
+	}
+
+	RE::BSTArray<RE::BSFixedString> requestClearCycles(RE::TESQuest*)
+	{
+		return confirmationForPapyrus(request_clear_cycles());
+	}
+
+	RE::BSFixedString confirmAction(RE::TESQuest*, uint32_t token) { return std::string(confirm_action(token)); }
 
 	RE::BSTArray<RE::BSFixedString> getEquipSetNames(RE::TESQuest*)
 	{
@@ -83,7 +116,7 @@ namespace papyrus
 
 	int findSelectedSetByName(RE::TESQuest*, RE::BSFixedString name)
 	{
-		return look_up_equipset_by_name(std::string(name));
+		return look_up_equipset_by_name(helpers::stringAsUtf8(std::string(name)));
 	}
 
 	RE::BSTArray<RE::BSFixedString> getEquipSetItemNames(RE::TESQuest*, uint32_t id)
@@ -102,13 +135,13 @@ namespace papyrus
 
 	bool handleCreateEquipSet(RE::TESQuest*, RE::BSFixedString fixed)
 	{
-		auto name = std::string(fixed);
+		auto name = helpers::stringAsUtf8(std::string(fixed));
 		return handle_create_equipset(name);
 	}
 
 	bool handleRenameEquipSet(RE::TESQuest*, uint32_t id, RE::BSFixedString fixed)
 	{
-		auto name = std::string(fixed);
+		auto name = helpers::stringAsUtf8(std::string(fixed));
 		rlog::debug("handleRenameEquipSet(): id={}; new name='{}';", id, name);
 		return handle_rename_equipset(id, name);
 	}
@@ -127,6 +160,16 @@ namespace papyrus
 		return array;
 	}
 
+	RE::BSTArray<RE::BSFixedString> getCycleDescriptions(RE::TESQuest*, int inWhich)
+	{
+		int which                            = std::clamp(inWhich, 0, 3);
+		rust::Vec<rust::String> descriptions = get_cycle_descriptions(which);
+		auto array                           = RE::BSTArray<RE::BSFixedString>();
+		for (auto description : descriptions) { array.push_back(std::string(description)); }
+
+		return array;
+	}
+
 	RE::BSTArray<RE::BSFixedString> getCycleFormIDs(RE::TESQuest*, int inWhich)
 	{
 		int which                   = std::clamp(inWhich, 0, 3);
@@ -137,6 +180,92 @@ namespace papyrus
 		return array;
 	}
 
+	bool toggleToolFlag(RE::TESQuest*, int inWhich, int index)
+	{
+		int which = std::clamp(inWhich, 0, 3);
+		return toggle_tool_flag(which, index);
+	}
+
+	int moveCycleEntry(RE::TESQuest*, int inWhich, int from, int to)
+	{
+		int which = std::clamp(inWhich, 0, 3);
+		return move_cycle_entry(which, from, to);
+	}
+
+	bool removeCycleEntry(RE::TESQuest*, int inWhich, int index)
+	{
+		int which = std::clamp(inWhich, 0, 3);
+		return remove_cycle_entry(which, index);
+	}
+
+	RE::BSFixedString undoLastCycleEdit(RE::TESQuest*)
+	{
+		auto message = std::string(undo_last_cycle_edit());
+		return RE::BSFixedString(message);
+	}
+
+	RE::BSFixedString importFavorites(RE::TESQuest*)
+	{
+		auto message = std::string(import_favorites());
+		return RE::BSFixedString(message);
+	}
+
+	bool dumpState(RE::TESQuest*) { return dump_state(); }
+
+	bool setEquipDelay(RE::TESQuest*, int which, int millis)
+	{
+		return set_equip_delay(std::clamp(which, -1, 3), static_cast<uint32_t>(std::max(millis, 0)));
+	}
+
+	bool addToCycle(RE::StaticFunctionTag*, int which, RE::BSFixedString formSpec, bool quiet)
+	{
+		return add_to_cycle(which, std::string(formSpec), quiet);
+	}
+
+	bool removeFromCycle(RE::StaticFunctionTag*, int which, RE::BSFixedString formSpec, bool quiet)
+	{
+		return remove_from_cycle(which, std::string(formSpec), quiet);
+	}
+
+	bool saveCurrentAsSet(RE::StaticFunctionTag*, RE::BSFixedString fixed)
+	{
+		auto name = helpers::stringAsUtf8(std::string(fixed));
+		return save_current_as_set(name);
+	}
+
+	bool exportCycles(RE::StaticFunctionTag*, RE::BSFixedString path)
+	{
+		return export_cycles(helpers::stringAsUtf8(std::string(path)));
+	}
+
+	bool importCycles(RE::StaticFunctionTag*, RE::BSFixedString path, bool replace)
+	{
+		return import_cycles(helpers::stringAsUtf8(std::string(path)), replace);
+	}
+
+	RE::BSTArray<RE::BSFixedString> runDiagnostics(RE::TESQuest*)
+	{
+		auto findings = run_diagnostics();
+		auto array    = RE::BSTArray<RE::BSFixedString>();
+		for (auto finding : findings) { array.push_back(std::string(finding)); }
+
+		return array;
+	}
+
+	RE::BSTArray<RE::BSFixedString> previewPrune(RE::TESQuest*, uint32_t days)
+	{
+		auto names = preview_prune(days);
+		auto array = RE::BSTArray<RE::BSFixedString>();
+		for (auto name : names) { array.push_back(std::string(name)); }
+
+		return array;
+	}
+
+	RE::BSTArray<RE::BSFixedString> requestPrune(RE::TESQuest*, uint32_t days)
+	{
+		return confirmationForPapyrus(request_prune(days));
+	}
+
 	RE::BSFixedString get_resolution_width(RE::TESQuest*)
 	{
 		return fmt::format(FMT_STRING("{:.2f}"), ui::resolutionWidth());
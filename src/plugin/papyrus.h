@@ -3,10 +3,31 @@
 namespace papyrus
 {
 	void handleConfigClose(RE::TESQuest*);
-	void handleClearCycles(RE::TESQuest*);
+	// Destructive actions come back as [token, summary] and wait for ConfirmAction(token).
+	RE::BSTArray<RE::BSFixedString> requestClearCycles(RE::TESQuest*);
+	RE::BSFixedString confirmAction(RE::TESQuest*, uint32_t token);
 
 	RE::BSTArray<RE::BSFixedString> getCycleNames(RE::TESQuest*, int which);
+	RE::BSTArray<RE::BSFixedString> getCycleDescriptions(RE::TESQuest*, int which);
 	RE::BSTArray<RE::BSFixedString> getCycleFormIDs(RE::TESQuest*, int which);
+	bool toggleToolFlag(RE::TESQuest*, int which, int index);
+	int moveCycleEntry(RE::TESQuest*, int which, int from, int to);
+	bool removeCycleEntry(RE::TESQuest*, int which, int index);
+	RE::BSFixedString undoLastCycleEdit(RE::TESQuest*);
+	RE::BSFixedString importFavorites(RE::TESQuest*);
+	bool dumpState(RE::TESQuest*);
+	RE::BSTArray<RE::BSFixedString> runDiagnostics(RE::TESQuest*);
+	RE::BSTArray<RE::BSFixedString> previewPrune(RE::TESQuest*, uint32_t days);
+	RE::BSTArray<RE::BSFixedString> requestPrune(RE::TESQuest*, uint32_t days);
+	bool setEquipDelay(RE::TESQuest*, int which, int millis);
+
+	// Global functions for other mods' scripts.
+	bool addToCycle(RE::StaticFunctionTag*, int which, RE::BSFixedString formSpec, bool quiet);
+	bool removeFromCycle(RE::StaticFunctionTag*, int which, RE::BSFixedString formSpec, bool quiet);
+	bool saveCurrentAsSet(RE::StaticFunctionTag*, RE::BSFixedString name);
+	// Also meant for the console: cgf "SoulsyHUD.ExportCycles" "path".
+	bool exportCycles(RE::StaticFunctionTag*, RE::BSFixedString path);
+	bool importCycles(RE::StaticFunctionTag*, RE::BSFixedString path, bool replace);
 
 	RE::BSFixedString get_resolution_width(RE::TESQuest*);
 	RE::BSFixedString get_resolution_height(RE::TESQuest*);
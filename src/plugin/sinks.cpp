@@ -22,8 +22,8 @@ void registerAllListeners()
 	// scriptEventSourceHolder->GetEventSource<RE::TESHitEvent>()->AddEventSink(listener);
 	// rlog::info("    hit events: {}"sv, typeid(RE::TESHitEvent).name());
 
-	// RE::UI::GetSingleton()->AddEventSink<RE::MenuOpenCloseEvent>(listener);
-	//rlog::info("    menu open/close events: {}"sv, typeid(RE::MenuOpenCloseEvent).name());
+	RE::UI::GetSingleton()->AddEventSink<RE::MenuOpenCloseEvent>(listener);
+	rlog::info("    menu open/close events: {}"sv, typeid(RE::MenuOpenCloseEvent).name());
 
 	RE::BSInputDeviceManager::GetSingleton()->AddEventSink(listener);
 	rlog::info("    player input events."sv);
@@ -32,6 +32,12 @@ void registerAllListeners()
 	auto okay    = player->AddAnimationGraphEventSink(listener);
 	if (okay) { rlog::info("    animation graph events to get grip changes."); }
 
+	SKSE::GetModCallbackEventSource()->AddEventSink(listener);
+	rlog::info("    mod events from spell-preparation and grip-switching mods."sv);
+
+	SKSE::GetCrosshairRefEventSource()->AddEventSink(listener);
+	rlog::info("    crosshair target changes, for lock hints."sv);
+
 	//scriptEventSourceHolder->GetEventSource<RE::TESMagicEffectApplyEvent>()->AddEventSink(listener);
 	//scriptEventSourceHolder->GetEventSource<RE::TESActiveEffectApplyRemoveEvent>()->AddEventSink(listener);
 	//rlog::info("    magic effects come and go, talking of Michelangelo."sv);
@@ -99,8 +105,16 @@ RE::BSEventNotifyControl TheListener::ProcessEvent(const RE::TESHitEvent* event,
 RE::BSEventNotifyControl TheListener::ProcessEvent(const RE::MenuOpenCloseEvent* event,
 	[[maybe_unused]] RE::BSTEventSource<RE::MenuOpenCloseEvent>* source)
 {
-	// TODO; just logging for now
-	rlog::info("menu event: '{}' {}", event->menuName, event->opening ? "opened" : "closed");
+	if (!event) { return RE::BSEventNotifyControl::kContinue; }
+	rlog::trace("menu event: '{}' {}", event->menuName, event->opening ? "opened" : "closed");
+	if (event->opening)
+	{
+		// Overlays like the HUD itself stay up during gameplay. Only menus that
+		// take the game away from the player count.
+		auto menu = RE::UI::GetSingleton()->GetMenu(event->menuName);
+		if (menu && (menu->PausesGame() || menu->UsesCursor())) { menu_opened(event->menuName.c_str()); }
+	}
+	else { menu_closed(event->menuName.c_str()); }
 	return RE::BSEventNotifyControl::kContinue;
 }
 
@@ -112,6 +126,8 @@ RE::BSEventNotifyControl TheListener::ProcessEvent(RE::InputEvent* const* event_
 
 	if (helpers::ignoreKeyEvents()) { return RE::BSEventNotifyControl::kContinue; }
 
+	const uint64_t sequence = keycodes::batchSequence(*event_list, keycodes::BatchReader::kGameplay);
+
 	// We might get a list of events to handle.
 	for (auto* event = *event_list; event; event = event->next)
 	{
@@ -127,7 +143,7 @@ RE::BSEventNotifyControl TheListener::ProcessEvent(RE::InputEvent* const* event_
 
 		// We need to be a little bit stateful to handle modifier keys, because we don't
 		// get chording events, so all the logic is now in the controller.
-		const KeyEventResponse response = handle_key_event(key, *button);
+		const KeyEventResponse response = handle_key_event(key, *button, sequence);
 		if (!response.handled) { continue; }
 		//rlog::info("mod handled key: {}", key);
 
@@ -140,9 +156,17 @@ RE::BSEventNotifyControl TheListener::ProcessEvent(RE::InputEvent* const* event_
 		if (response.start_timer != Action::None)
 		{
 			// rlog::trace("hysteresis timer START; slot={}"sv, static_cast<uint8_t>(response.start_timer));
-			auto settings = user_settings();
-			auto duration = settings->equip_delay_ms();
-			ui::startTimer(response.start_timer, duration);
+			ui::startTimer(response.start_timer);
+		}
+
+		// A different sound when the cycle comes back around to its start.
+		if (response.wrapped) { helpers::chime(); }
+		if (response.outcome != KeyOutcome::Nothing)
+		{
+			rlog::trace("key outcome={}; slot={}; item='{}';"sv,
+				static_cast<uint8_t>(response.outcome),
+				static_cast<uint8_t>(response.slot),
+				std::string(response.item_name));
 		}
 
 		// Now wipe out the event data so nothing else acts on it.
@@ -238,3 +262,44 @@ RE::BSEventNotifyControl TheListener::ProcessEvent(const RE::TESActiveEffectAppl
 
 	return RE::BSEventNotifyControl::kContinue;
 }
+
+// Other mods talk to us with mod events.
+// Spell-preparation mods: SendModEvent("SoulsyHUD_SpellPrepared", "Plugin.esp|0x1234", 1.0).
+// A numArg of 0 means the spell is known but not prepared. An empty string forgets every report.
+// Grip-switching mods: SendModEvent("SoulsyHUD_GripChanged", "right", 1.0). A numArg of 1
+// means the weapon in that hand is now held in both hands.
+RE::BSEventNotifyControl TheListener::ProcessEvent(const SKSE::ModCallbackEvent* event,
+	[[maybe_unused]] RE::BSTEventSource<SKSE::ModCallbackEvent>* source)
+{
+	if (!event) { return RE::BSEventNotifyControl::kContinue; }
+
+	if (event->eventName == "SoulsyHUD_SpellPrepared")
+	{
+		const auto form_spec = std::string(event->strArg.c_str());
+		player::setSpellPrepared(form_spec, event->numArg != 0.0f);
+		spell_preparation_changed();
+	}
+	else if (event->eventName == "SoulsyHUD_GripChanged")
+	{
+		const auto hand = std::string(event->strArg.c_str());
+		if (hand == "left" || hand == "right")
+		{
+			handle_hand_grip_change(hand == "left" ? Action::Left : Action::Right, event->numArg != 0.0f);
+		}
+	}
+
+	return RE::BSEventNotifyControl::kContinue;
+}
+
+RE::BSEventNotifyControl TheListener::ProcessEvent(const SKSE::CrosshairRefEvent* event,
+	[[maybe_unused]] RE::BSTEventSource<SKSE::CrosshairRefEvent>* source)
+{
+	const auto target = event ? event->crosshairRef : nullptr;
+	if (target && target->IsLocked())
+	{
+		on_crosshair_lock(static_cast<uint8_t>(target->GetLockLevel()));
+	}
+	else { on_crosshair_clear(); }
+
+	return RE::BSEventNotifyControl::kContinue;
+}
@@ -16,6 +16,8 @@ class TheListener final
 	, public RE::BSTEventSink<RE::TESHitEvent>
 	, public RE::BSTEventSink<RE::TESMagicEffectApplyEvent>
 	, public RE::BSTEventSink<RE::TESActiveEffectApplyRemoveEvent>
+	, public RE::BSTEventSink<SKSE::ModCallbackEvent>
+	, public RE::BSTEventSink<SKSE::CrosshairRefEvent>
 {
 	using event_result = RE::BSEventNotifyControl;
 
@@ -51,6 +53,12 @@ protected:
 	RE::BSEventNotifyControl ProcessEvent(const RE::TESActiveEffectApplyRemoveEvent* event,
 		RE::BSTEventSource<RE::TESActiveEffectApplyRemoveEvent>* source) override;
 
+	RE::BSEventNotifyControl ProcessEvent(const SKSE::ModCallbackEvent* event,
+		RE::BSTEventSource<SKSE::ModCallbackEvent>* source) override;
+
+	RE::BSEventNotifyControl ProcessEvent(const SKSE::CrosshairRefEvent* event,
+		RE::BSTEventSource<SKSE::CrosshairRefEvent>* source) override;
+
 private:
 	TheListener()           = default;
 	~TheListener() override = default;
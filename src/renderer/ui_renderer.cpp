@@ -17,7 +17,6 @@ namespace ui
 	static std::map<animation_type, std::vector<TextureData>> animation_frame_map = {};
 	static std::vector<std::pair<animation_type, std::unique_ptr<Animation>>> animation_list;
 
-	static std::map<uint8_t, float> cycle_timers = {};
 
 	static std::map<uint32_t, TextureData> key_struct;
 	static std::map<uint32_t, TextureData> default_key_struct;
@@ -551,9 +550,58 @@ namespace ui
 			im_color);
 	}
 
+	// The layout only changes when the file is reloaded, settings change, or
+	// the screen size changes. We keep a copy and refetch it when it does.
+	static LayoutFlattened cachedLayout;
+	static uint32_t cachedLayoutSerial = 0;  // serials start at 1
+
+	const LayoutFlattened& currentLayout()
+	{
+		const auto serial = layout_serial();
+		if (serial != cachedLayoutSerial)
+		{
+			cachedLayout       = hud_layout();
+			cachedLayoutSerial = serial;
+		}
+		return cachedLayout;
+	}
+
+	// Same for the item in each slot: the controller tells us when one changes,
+	// so we only copy it across the bridge then.
+	static std::map<uint8_t, rust::Box<HudItem>> cachedEntries;
+
+	const HudItem& entryForSlot(HudElement element)
+	{
+		const auto changed = huditem_changed(element);
+		auto found         = cachedEntries.find(element.repr);
+		if (found == cachedEntries.end())
+		{
+			found = cachedEntries.emplace(element.repr, entry_to_show_in_slot(element)).first;
+		}
+		else if (changed)
+		{
+			found->second = entry_to_show_in_slot(element);
+		}
+		return *found->second;
+	}
+
+	// The equip timer that belongs to each HUD slot, for highlighting.
+	Action timerActionForElement(HudElement element)
+	{
+		switch (element)
+		{
+			case HudElement::Power: return Action::Power;
+			case HudElement::Utility: return Action::Utility;
+			case HudElement::Left: return Action::Left;
+			case HudElement::Right: return Action::Right;
+			case HudElement::EquipSet: return Action::Equipment;
+			default: return Action::None;
+		}
+	}
+
 	void drawAllSlots()
 	{
-		auto topLayout          = hud_layout();
+		const auto& topLayout   = currentLayout();
 		auto anchor             = topLayout.anchor;
 		auto hudsize            = topLayout.bg_size;
 		bool rangedEquipped     = player::hasRangedEquipped();
@@ -592,17 +640,18 @@ namespace ui
 				continue;
 			}
 
-			rust::Box<HudItem> entry = entry_to_show_in_slot(slotLayout.element);
-			if ((slotLayout.element == HudElement::EquipSet) && entry->name().empty())
+			const auto& entry = entryForSlot(slotLayout.element);
+			if ((slotLayout.element == HudElement::EquipSet) && entry.name().empty())
 			{
 				// Do nothing for empty equipsets. TODO draw as empty slot
 				continue;
 			}
 
-			auto entry_name        = std::string(entry->name());
-			const auto hotkey      = settings->hotkey_for(slotLayout.element);
+			auto entry_name        = std::string(entry.name());
+			const auto hotkey      = helpers::gamepadInUse() ? settings->gamepad_hotkey_for(slotLayout.element) :
+			                                                   settings->hotkey_for(slotLayout.element);
 			const auto slot_center = ImVec2(slotLayout.center.x, slotLayout.center.y);
-			const bool skipItem    = (entry_name.empty() && entry->icon_key().empty()) || entry->form_string().empty();
+			const bool skipItem    = (entry_name.empty() && entry.icon_key().empty()) || entry.form_string().empty();
 
 			const auto slotbg = std::string(slotLayout.bg_image);
 			if (slotLayout.bg_color.a > 0 && ui_renderer::lazyLoadHudImage(slotbg))
@@ -610,13 +659,38 @@ namespace ui
 				const auto [texture, width, height] = HUD_IMAGES_MAP[slotbg];
 				const auto size                     = ImVec2(slotLayout.bg_size.x, slotLayout.bg_size.y);
 				drawElement(texture, slot_center, size, 0.f, slotLayout.bg_color);
+
+				// A slot waiting to equip, or just picked by a hotbar key, flashes its
+				// background, fading as the delay or the flash runs out.
+				const auto timerAction = timerActionForElement(slotLayout.element);
+				if (timerAction != Action::None && is_slot_highlighted(timerAction))
+				{
+					auto flash = slotLayout.bg_color;
+					flash.a    = static_cast<uint8_t>(flash.a * slot_highlight_fraction(timerAction));
+					drawElement(texture, slot_center, size, 0.f, flash);
+				}
 			}
 
 			// now draw the icon over the background...
 			if (slotLayout.icon_color.a > 0 && !skipItem)
 			{
-				const auto iconColor = colorizeIcons ? entry->color() : slotLayout.icon_color;
-				auto iconkey         = std::string(entry->icon_key());
+				auto iconColor = colorizeIcons ? entry.color() : slotLayout.icon_color;
+				// Spells the player hasn't prepared yet are drawn faded, and so
+				// are items the player has run out of and weapons with drained enchantments.
+				if (entry.is_locked() || entry.is_spent() || entry.is_drained())
+				{
+					iconColor.a = static_cast<uint8_t>(iconColor.a / 3);
+				}
+				// A recharging shout is grayed out, brightening as the cooldown runs down.
+				if (const auto cooldown = entry.cooldown_fraction(); cooldown > 0.0f)
+				{
+					const auto gray = static_cast<uint8_t>((iconColor.r + iconColor.g + iconColor.b) / 3);
+					iconColor.r     = static_cast<uint8_t>(std::lerp(iconColor.r, gray, cooldown));
+					iconColor.g     = static_cast<uint8_t>(std::lerp(iconColor.g, gray, cooldown));
+					iconColor.b     = static_cast<uint8_t>(std::lerp(iconColor.b, gray, cooldown));
+					iconColor.a     = static_cast<uint8_t>(iconColor.a * (1.0f - 0.5f * cooldown));
+				}
+				auto iconkey         = std::string(entry.icon_key());
 				if (ui_renderer::lazyLoadIcon(iconkey))
 				{
 					const auto [texture, width, height] = ICON_MAP[iconkey];
@@ -637,7 +711,7 @@ namespace ui
 				{
 					if (label.color.a == 0) { continue; }
 					const auto textPos = ImVec2(label.anchor.x, label.anchor.y);
-					auto entrytxt      = std::string(entry->fmtstr(label.contents));
+					auto entrytxt      = std::string(entry.fmtstr(label.contents));
 					if (!entrytxt.empty()) { drawText(entrytxt, textPos, &label); }
 				}
 			}
@@ -662,15 +736,15 @@ namespace ui
 			}
 
 			// Charge/fuel meter.
-			if (slotLayout.meter_kind != MeterKind::None && entry->show_meter())
+			if (slotLayout.meter_kind != MeterKind::None && entry.show_meter())
 			{
-				auto level = entry->meter_level();
+				auto level = entry.meter_level();
 				if (slotLayout.meter_kind == MeterKind::CircleArc) { drawMeterCircleArc(level, slotLayout); }
 				else if (slotLayout.meter_kind == MeterKind::Rectangular) { drawMeterRectangular(level, slotLayout); }
 			}
 
 			// Finally, the poisoned indicator.
-			if (slotLayout.poison_color.a > 0 && entry->is_poisoned())
+			if (slotLayout.poison_color.a > 0 && entry.is_poisoned())
 			{
 				const auto poison_img = std::string(slotLayout.poison_image);
 				if (ui_renderer::lazyLoadHudImage(poison_img))
@@ -895,6 +969,12 @@ namespace ui
 	// Returns true if the HUD was invisible and we began showing it.
 	bool showBriefly()
 	{
+		// Still fully visible and waiting to fade? The player is busy with us, so wait anew.
+		if (gIsFading && !doFadeIn && gHudAlpha >= gMaxAlpha && delayBeforeFadeout > 0.0f)
+		{
+			const auto settings = user_settings();
+			delayBeforeFadeout  = std::max(FADEOUT_HYSTERESIS, static_cast<float>(settings->fade_delay()) / 1000.0f);
+		}
 		if (gDoingBriefPeek || gHudAlpha >= gMaxAlpha || (doFadeIn == true && gHudAlpha > gMinAlpha)) { return false; }
 
 		gDoingBriefPeek = true;
@@ -929,10 +1009,14 @@ namespace ui
 		// The game will report that the player has sheathed weapons when
 		// the player has merely equipped something new. So we give it some
 		// time to decide that the weapons are truly gone. This number is the
-		// how long we'll wait before actually fading.
-		if (!doFadeIn) { delayBeforeFadeout = FADEOUT_HYSTERESIS; }
+		// how long we'll wait before actually fading. The player can ask us to
+		// wait longer.
+		auto settings = user_settings();
+		if (!doFadeIn)
+		{
+			delayBeforeFadeout = std::max(FADEOUT_HYSTERESIS, static_cast<float>(settings->fade_delay()) / 1000.0f);
+		}
 
-		auto settings   = user_settings();
 		float fade_time = static_cast<float>(settings->fade_time()) / 1000.0f;
 		if (gDoingBriefPeek)
 		{
@@ -1038,41 +1122,24 @@ namespace ui
 	}
 
 	// We implement timers using UI ticks. We don't need them to be
-	// particularly accurate, just good-feeling to humans. Because we only
-	// manage timers here, this is the right decision point for going into
-	// and out of slow motion.
+	// particularly accurate, just good-feeling to humans. The timers
+	// themselves live on the Rust side, which looks up their durations
+	// from settings as they run, and the same tick lets the controller
+	// expire anything else that's been waiting. This is still the right
+	// decision point for going into and out of slow motion.
 	void advanceTimers(float delta)
 	{
-		std::vector<uint8_t> to_remove;
-		std::map<uint8_t, float>::iterator iter;
-		for (iter = cycle_timers.begin(); iter != cycle_timers.end(); ++iter)
-		{
-			auto which     = iter->first;
-			auto remaining = iter->second;
-
-			remaining -= delta;
-			// rlog::trace("timer decremented; timer={}; delta={}; remaining={};"sv, which, delta, remaining);
-			if (remaining < 0.0f)
-			{
-				to_remove.push_back(which);
-				auto action = static_cast<Action>(which);
-				timer_expired(action);
-			}
-			else { cycle_timers[which] = remaining; }
-		}
-
-		for (const auto& xs : to_remove) { cycle_timers.erase(xs); }
-		if (cycle_timers.size() == 0) { helpers::exitSlowMotion(); }
+		// Check on the player first, so a timer can't fire in bleedout.
+		player::reportPlayerVitals();
+		if (!tick(delta)) { helpers::exitSlowMotion(); }
 	}
 
-	void startTimer(Action which, uint32_t duration)
+	void startTimer(Action which)
 	{
 		// We replace any existing timer for this slot.
-		// All incoming durations are in milliseconds, but our time deltas
-		// are floats where whole numbers are seconds. So we divide.
 		const auto settings = user_settings();
-		cycle_timers.insert_or_assign(static_cast<uint8_t>(which), static_cast<float>(duration) / 1000.0f);
-		rlog::debug("Started equip delay timer; which={}; duration={} ms;"sv, static_cast<uint8_t>(which), duration);
+		timer_started(which);
+		rlog::debug("Started equip delay timer; which={};"sv, static_cast<uint8_t>(which));
 		// TODO do not start slomo for long-presses???
 		if (settings->cycling_slows_time() && RE::PlayerCharacter::GetSingleton()->IsInCombat())
 		{
@@ -1083,7 +1150,6 @@ namespace ui
 	// remove timer from the map if it exists
 	void stopTimer(Action which)
 	{
-		cycle_timers.erase(static_cast<uint8_t>(which));
-		if (cycle_timers.size() == 0) { helpers::exitSlowMotion(); }
+		if (!timer_stopped(which)) { helpers::exitSlowMotion(); }
 	}
 }
@@ -29,7 +29,7 @@ namespace ui
 	void setMaxAlpha(float max);
 	void setMinAlpha(float min);
 
-	void startTimer(Action which, uint32_t duration);
+	void startTimer(Action which);
 	void stopTimer(Action which);
 	void advanceTimers(float delta);
 	void advanceTransition(float delta);
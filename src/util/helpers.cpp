@@ -29,6 +29,18 @@ namespace helpers
 		}
 	}
 
+	// play a softer sound than honk() for a cycle wrapping around to its start
+	void chime()
+	{
+		auto* the_player = RE::PlayerCharacter::GetSingleton();
+		auto* form       = RE::TESForm::LookupByID(0x00057f93);  // UIMenuInactiveSD
+		if (form)
+		{
+			auto* sound = form->As<RE::BGSSoundDescriptorForm>();
+			if (sound) { utility::playSound(sound->soundDescriptor, the_player); }
+		}
+	}
+
 	// How you know I've been replaced by a pod person: if I ever declare that
 	// I love dealing with strings in systems programming languages.
 
@@ -38,7 +50,7 @@ namespace helpers
 		// It is called by bound object finder functions.
 		auto name     = form->GetName();  // this use is required
 		auto chonker  = helpers::chars_to_vec(name);
-		auto safename = std::string(cstr_to_utf8(chonker));
+		auto safename = std::string(cstr_to_utf8_with_codepage(chonker, GetACP()));
 		return safename;
 	}
 
@@ -47,10 +59,17 @@ namespace helpers
 		// Do not call this from bound object finder functions.
 		auto name     = gear::displayName(form);
 		auto chonker  = helpers::chars_to_vec(name);
-		auto safename = std::string(cstr_to_utf8(chonker));
+		auto safename = std::string(cstr_to_utf8_with_codepage(chonker, GetACP()));
 		return safename;
 	}
 
+	std::string stringAsUtf8(const std::string& input)
+	{
+		// Strings from Papyrus are in the game's codepage, not utf-8.
+		auto chonker = helpers::chars_to_vec(input.c_str());
+		return std::string(cstr_to_utf8_with_codepage(chonker, GetACP()));
+	}
+
 	std::vector<uint8_t> chars_to_vec(const char* input)
 	{
 		if (!input) { return std::move(std::vector<uint8_t>()); }
@@ -149,13 +168,14 @@ namespace helpers
 
 	bool hudShouldAutoFadeOut()
 	{
-		if (!user_settings()->autofade()) { return false; }
+		const auto settings = user_settings();
+		if (!settings->autofade()) { return false; }
 
 		const auto player       = RE::PlayerCharacter::GetSingleton();
 		const bool inCombat     = player->IsInCombat();
 		const auto weaponsDrawn = player->AsActorState()->IsWeaponDrawn();
 
-		return !inCombat && !weaponsDrawn;
+		return settings->should_fade_out(inCombat, weaponsDrawn);
 	}
 
 	void notifyPlayer(const std::string& message)
@@ -239,6 +259,8 @@ namespace helpers
 		return form;
 	}
 
+	bool formSpecExists(const std::string& spec) { return formSpecToFormItem(spec) != nullptr; }
+
 	rust::Box<HudItem> formSpecToHudItem(const std::string& spec)
 	{
 		if (spec.empty())
@@ -10,12 +10,15 @@
 namespace helpers
 {
 	RE::TESForm* formSpecToFormItem(const std::string& spec);
+	bool formSpecExists(const std::string& spec);
 	rust::Box<HudItem> formSpecToHudItem(const std::string& spec);
 	std::string makeFormSpecString(RE::TESForm* form);
 	// uint32_t getSelectedFormFromMenu(RE::UI*& a_ui);
 
 	// play failure sound
 	void honk();
+	// play the sound for a cycle coming back around
+	void chime();
 
 	void notifyPlayer(const std::string& message);
 	rust::String lookupTranslation(const std::string& key);
@@ -40,6 +43,7 @@ namespace helpers
 
 	std::string nameAsUtf8(const RE::TESForm* form);
 	std::string displayNameAsUtf8(const RE::TESForm* form);
+	std::string stringAsUtf8(const std::string& input);
 	std::string vec_to_stdstring(rust::Vec<uint8_t> input);
 	std::vector<uint8_t> chars_to_vec(const char* input);
 